@@ -1,3 +1,4 @@
+use borsh::{BorshDeserialize, BorshSerialize};
 use kaspa_hashes::Hash;
 use serde::{Deserialize, Serialize};
 
@@ -5,13 +6,13 @@ use crate::tx::TransactionId;
 
 pub type AcceptanceData = Vec<MergesetBlockAcceptanceData>;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct MergesetBlockAcceptanceData {
     pub block_hash: Hash,
     pub accepted_transactions: Vec<AcceptedTxEntry>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct AcceptedTxEntry {
     pub transaction_id: TransactionId,
     pub index_within_block: u32,
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use crate::{coinbase::MinerData, header::Header, tx::Transaction};
+use borsh::{BorshDeserialize, BorshSerialize};
 use kaspa_hashes::Hash;
 
 /// A mutable block structure where header and transactions within can still be mutated.
@@ -27,7 +28,7 @@ impl MutableBlock {
 /// A block structure where the inner header and transactions are wrapped by Arcs for
 /// cheap cloning and for cross-thread safety and immutability. Note: no need to wrap
 /// this struct with an additional Arc.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
 pub struct Block {
     pub header: Arc<Header>,
     pub transactions: Arc<Vec<Transaction>>,
@@ -35,6 +35,9 @@ pub struct Config {
     /// Enable RPC commands which affect the state of the node
     pub unsafe_rpc: bool,
 
+    /// Deliver only the newest of the queued "latest value wins" notifications, like the DAA score, to the RPC
+    pub coalesce_score_notifications: bool,
+
     /// Allow the node to accept blocks from RPC while not synced
     /// (this flag is mainly used for testing)
     // TODO: add and handle a matching kaspad command argument
@@ -51,6 +54,7 @@ impl Config {
             process_genesis: true,
             utxoindex: false,
             unsafe_rpc: false,
+            coalesce_score_notifications: false,
             allow_submit_block_when_not_synced: false,
             user_agent_comments: Default::default(),
         }
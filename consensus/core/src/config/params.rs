@@ -22,6 +22,8 @@ pub struct Params {
     pub max_difficulty_f64: f64,
     /// Size of window that is inspected to calculate the required difficulty of each block
     pub difficulty_window_size: usize,
+    /// Minimal size of the difficulty window accepted for estimating the network hashrate
+    pub min_difficulty_window_size: usize,
     pub mergeset_size_limit: u64,
     pub merge_depth: u64,
     pub finality_depth: u64,
@@ -135,6 +137,7 @@ pub const MAINNET_PARAMS: Params = Params {
     max_difficulty: DIFFICULTY_MAX,
     max_difficulty_f64: DIFFICULTY_MAX_AS_F64,
     difficulty_window_size: 2641,
+    min_difficulty_window_size: 1000,
     mergeset_size_limit: (DEFAULT_GHOSTDAG_K as u64) * 10,
     merge_depth: 3600,
     finality_depth: 86400,
@@ -186,6 +189,7 @@ pub const TESTNET_PARAMS: Params = Params {
     max_difficulty: DIFFICULTY_MAX,
     max_difficulty_f64: DIFFICULTY_MAX_AS_F64,
     difficulty_window_size: 2641,
+    min_difficulty_window_size: 1000,
     mergeset_size_limit: (DEFAULT_GHOSTDAG_K as u64) * 10,
     merge_depth: 3600,
     finality_depth: 86400,
@@ -233,6 +237,7 @@ pub const SIMNET_PARAMS: Params = Params {
     max_difficulty: DIFFICULTY_MAX,
     max_difficulty_f64: DIFFICULTY_MAX_AS_F64,
     difficulty_window_size: 2641,
+    min_difficulty_window_size: 1000,
     mergeset_size_limit: (DEFAULT_GHOSTDAG_K as u64) * 10,
     merge_depth: 3600,
     finality_depth: 86400,
@@ -280,6 +285,7 @@ pub const DEVNET_PARAMS: Params = Params {
     max_difficulty: DIFFICULTY_MAX,
     max_difficulty_f64: DIFFICULTY_MAX_AS_F64,
     difficulty_window_size: 2641,
+    min_difficulty_window_size: 1000,
     mergeset_size_limit: (DEFAULT_GHOSTDAG_K as u64) * 10,
     merge_depth: 3600,
     finality_depth: 86400,
@@ -2,7 +2,7 @@ use std::fmt::Display;
 
 use crate::{
     constants,
-    errors::{coinbase::CoinbaseError, tx::TxRuleError},
+    errors::{coinbase::CoinbaseError, difficulty::DifficultyError, tx::TxRuleError},
     tx::{TransactionId, TransactionOutpoint},
     BlueWorkType,
 };
@@ -67,6 +67,9 @@ pub enum RuleError {
     #[error("block difficulty of {0} is not the expected value of {1}")]
     UnexpectedDifficulty(u32, u32),
 
+    #[error("block difficulty could not be calculated: {0}")]
+    BadDifficultyCalculation(DifficultyError),
+
     #[error("block timestamp of {0} is not after expected {1}")]
     TimeTooOld(u64, u64),
 
@@ -5,8 +5,17 @@ pub enum DifficultyError {
     #[error("under min allowed window size ({0} < {1})")]
     UnderMinWindowSizeAllowed(usize, usize),
 
+    #[error("the difficulty window holds no block")]
+    EmptyWindow,
+
     #[error("min window timestamp is equal to the max window timestamp")]
     EmptyTimestampRange,
+
+    #[error("max window timestamp {max} is earlier than the min window timestamp {min}")]
+    NonMonotonicTimestamps { min: u64, max: u64 },
+
+    #[error("difficulty target overflowed during calculation")]
+    TargetOverflow,
 }
 
 pub type DifficultyResult<T> = std::result::Result<T, DifficultyError>;
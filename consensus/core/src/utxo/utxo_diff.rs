@@ -3,6 +3,7 @@ use super::{
     utxo_error::{UtxoAlgebraError, UtxoResult},
 };
 use crate::tx::{TransactionOutpoint, UtxoEntry, VerifiableTransaction};
+use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use std::collections::hash_map::Entry::Vacant;
 
@@ -11,7 +12,7 @@ pub trait ImmutableUtxoDiff {
     fn removed(&self) -> &UtxoCollection;
 }
 
-#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct UtxoDiff {
     pub add: UtxoCollection,
     pub remove: UtxoCollection,
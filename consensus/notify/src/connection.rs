@@ -1,3 +1,281 @@
 use crate::notification::Notification;
+use async_channel::{Receiver, RecvError, Sender, TryRecvError};
+use futures::Stream;
+use kaspa_notify::{
+    connection::{Connection, Unchanged},
+    error::Error,
+    events::EventArray,
+    notification::Notification as NotificationTrait,
+};
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
 
-pub type ConsensusChannelConnection = kaspa_notify::connection::ChannelConnection<Notification>;
+/// Newest pending value of each coalescable event type, shared by a [`CoalescingChannelConnection`] and its
+/// [`CoalescingReceiver`]
+#[derive(Debug, Default)]
+struct Slots {
+    latest: Mutex<EventArray<Option<Notification>>>,
+}
+
+/// The channel connection of the listeners of a [`ConsensusNotifier`](crate::notifier::ConsensusNotifier), able to
+/// coalesce "latest value wins" notifications.
+///
+/// When coalescing is enabled, a coalescable notification (see [`Notification::is_coalescable`]) is stored in the
+/// slot of its event type and only the first one of a run enters the channel, as a ticket. The
+/// [`CoalescingReceiver`] swaps each ticket for the newest value of its slot, so a slow listener only receives the
+/// newest value. Other notifications are never dropped and their order is preserved. The sending side never reads
+/// from the channel.
+#[derive(Clone, Debug)]
+pub struct CoalescingChannelConnection {
+    sender: Sender<Notification>,
+    slots: Arc<Slots>,
+    coalesce: bool,
+}
+
+impl CoalescingChannelConnection {
+    /// Creates a connection sending to `sender`, along with the receiver to drain in place of `receiver`
+    pub fn new(sender: Sender<Notification>, receiver: Receiver<Notification>, coalesce: bool) -> (Self, CoalescingReceiver) {
+        let slots = Arc::new(Slots::default());
+        (Self { sender, slots: slots.clone(), coalesce }, CoalescingReceiver { receiver, slots, coalesce })
+    }
+
+    /// Creates a connection never coalescing, whose notifications can be received straight from the channel of `sender`
+    pub fn without_coalescing(sender: Sender<Notification>) -> Self {
+        Self { sender, slots: Default::default(), coalesce: false }
+    }
+
+    pub fn is_coalescing(&self) -> bool {
+        self.coalesce
+    }
+
+    fn try_send(&self, notification: Notification) -> Result<(), Error> {
+        Ok(self.sender.try_send(notification)?)
+    }
+
+    fn send_coalesced(&self, notification: Notification) -> Result<(), Error> {
+        let mut latest = self.slots.latest.lock().unwrap();
+        let slot = &mut latest[notification.event_type()];
+        if slot.replace(notification.clone()).is_some() {
+            // A ticket of this event type is already queued and will be swapped for the new value
+            return Ok(());
+        }
+        self.try_send(notification).map_err(|err| {
+            // No ticket will ever claim the value
+            *slot = None;
+            err
+        })
+    }
+}
+
+impl Connection for CoalescingChannelConnection {
+    type Notification = Notification;
+    type Message = Notification;
+    type Encoding = Unchanged;
+    type Error = Error;
+
+    fn encoding(&self) -> Self::Encoding {
+        Unchanged::Clone
+    }
+
+    fn into_message(notification: &Self::Notification, _: &Self::Encoding) -> Self::Message {
+        notification.clone()
+    }
+
+    fn send(&self, message: Self::Message) -> Result<(), Self::Error> {
+        if self.is_closed() {
+            return Err(Error::ConnectionClosed);
+        }
+        match self.coalesce && message.is_coalescable() {
+            true => self.send_coalesced(message),
+            false => self.try_send(message),
+        }
+    }
+
+    fn close(&self) -> bool {
+        self.sender.close()
+    }
+
+    fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+}
+
+/// Receiving end of a [`CoalescingChannelConnection`]
+#[derive(Clone, Debug)]
+pub struct CoalescingReceiver {
+    receiver: Receiver<Notification>,
+    slots: Arc<Slots>,
+    coalesce: bool,
+}
+
+impl CoalescingReceiver {
+    pub async fn recv(&self) -> Result<Notification, RecvError> {
+        self.receiver.recv().await.map(|notification| self.resolve(notification))
+    }
+
+    pub fn try_recv(&self) -> Result<Notification, TryRecvError> {
+        self.receiver.try_recv().map(|notification| self.resolve(notification))
+    }
+
+    /// Number of notifications queued, a run of coalesced notifications counting as one
+    pub fn len(&self) -> usize {
+        self.receiver.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.receiver.is_empty()
+    }
+
+    /// Swaps a ticket for the newest value of its slot
+    fn resolve(&self, notification: Notification) -> Notification {
+        if !self.coalesce || !notification.is_coalescable() {
+            return notification;
+        }
+        self.slots.latest.lock().unwrap()[notification.event_type()].take().unwrap_or(notification)
+    }
+}
+
+impl Stream for CoalescingReceiver {
+    type Item = Notification;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_next(cx).map(|notification| notification.map(|notification| self.resolve(notification)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notification::{
+        PruningPointUtxoSetOverrideNotification, SinkBlueScoreChangedNotification, VirtualDaaScoreChangedNotification,
+    };
+    use crate::notifier::ConsensusNotifier;
+    use async_channel::unbounded;
+    use futures::StreamExt;
+    use kaspa_notify::{
+        events::EVENT_TYPE_ARRAY,
+        notifier::Notify,
+        scope::{PruningPointUtxoSetOverrideScope, Scope, VirtualDaaScoreChangedScope},
+    };
+
+    fn flood(connection: &CoalescingChannelConnection) {
+        for i in 0..100 {
+            connection.send(Notification::VirtualDaaScoreChanged(VirtualDaaScoreChangedNotification::new(i))).unwrap();
+            connection.send(Notification::SinkBlueScoreChanged(SinkBlueScoreChangedNotification::new(i * 2))).unwrap();
+            if i % 25 == 0 {
+                connection.send(Notification::PruningPointUtxoSetOverride(PruningPointUtxoSetOverrideNotification::new())).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_coalescing_delivers_latest_score() {
+        let (sender, receiver) = unbounded();
+        let (connection, receiver) = CoalescingChannelConnection::new(sender, receiver, true);
+        flood(&connection);
+
+        let received = std::iter::from_fn(|| receiver.try_recv().ok()).collect::<Vec<_>>();
+        let daa_scores = received
+            .iter()
+            .filter_map(|x| match x {
+                Notification::VirtualDaaScoreChanged(payload) => Some(payload.virtual_daa_score),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let blue_scores = received
+            .iter()
+            .filter_map(|x| match x {
+                Notification::SinkBlueScoreChanged(payload) => Some(payload.sink_blue_score),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let overrides = received.iter().filter(|x| matches!(x, Notification::PruningPointUtxoSetOverride(_))).count();
+
+        assert_eq!(daa_scores, vec![99], "only the newest DAA score should be delivered");
+        assert_eq!(blue_scores, vec![198], "only the newest blue score should be delivered");
+        assert_eq!(overrides, 4, "non-coalescable notifications must all be delivered");
+        assert!(receiver.is_empty());
+    }
+
+    #[test]
+    fn test_coalescing_keeps_drained_values() {
+        let (sender, receiver) = unbounded();
+        let (connection, receiver) = CoalescingChannelConnection::new(sender, receiver, true);
+        let daa_score = |notification: Notification| match notification {
+            Notification::VirtualDaaScoreChanged(payload) => payload.virtual_daa_score,
+            _ => panic!("expected a VirtualDaaScoreChanged notification"),
+        };
+
+        // A consumer keeping up receives every value, in order
+        for i in 0..3 {
+            connection.send(Notification::VirtualDaaScoreChanged(VirtualDaaScoreChangedNotification::new(i))).unwrap();
+            assert_eq!(daa_score(receiver.try_recv().unwrap()), i);
+        }
+
+        // Values sent while the consumer lags are coalesced at the position of the first of them
+        connection.send(Notification::VirtualDaaScoreChanged(VirtualDaaScoreChangedNotification::new(3))).unwrap();
+        connection.send(Notification::PruningPointUtxoSetOverride(PruningPointUtxoSetOverrideNotification::new())).unwrap();
+        connection.send(Notification::VirtualDaaScoreChanged(VirtualDaaScoreChangedNotification::new(4))).unwrap();
+        assert_eq!(receiver.len(), 2);
+        assert_eq!(daa_score(receiver.try_recv().unwrap()), 4);
+        assert!(matches!(receiver.try_recv().unwrap(), Notification::PruningPointUtxoSetOverride(_)));
+        assert!(receiver.try_recv().is_err());
+
+        // A failed send leaves no value behind
+        connection.close();
+        assert!(connection.send(Notification::VirtualDaaScoreChanged(VirtualDaaScoreChangedNotification::new(5))).is_err());
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_coalescing_listener_of_notifier() {
+        let notifier = Arc::new(ConsensusNotifier::new(EVENT_TYPE_ARRAY[..].into(), vec![], vec![], 1, "test"));
+        notifier.clone().start();
+
+        // Both listeners are subscribed to the DAA scores and the overrides, only the first one coalescing
+        let receivers = [true, false].map(|coalesce| {
+            let (sender, receiver) = unbounded();
+            let (connection, receiver) = CoalescingChannelConnection::new(sender, receiver, coalesce);
+            let id = notifier.register_new_listener(connection);
+            notifier.try_start_notify(id, Scope::VirtualDaaScoreChanged(VirtualDaaScoreChangedScope {})).unwrap();
+            notifier.try_start_notify(id, Scope::PruningPointUtxoSetOverride(PruningPointUtxoSetOverrideScope {})).unwrap();
+            receiver
+        });
+
+        for i in 0..100 {
+            notifier.notify(Notification::VirtualDaaScoreChanged(VirtualDaaScoreChangedNotification::new(i))).unwrap();
+        }
+        // Sent last, the override tells when all the DAA scores are delivered
+        notifier.notify(Notification::PruningPointUtxoSetOverride(PruningPointUtxoSetOverrideNotification::new())).unwrap();
+
+        let daa_scores = |mut receiver: CoalescingReceiver| async move {
+            let mut daa_scores = vec![];
+            while let Some(notification) = receiver.next().await {
+                match notification {
+                    Notification::VirtualDaaScoreChanged(payload) => daa_scores.push(payload.virtual_daa_score),
+                    Notification::PruningPointUtxoSetOverride(_) => break,
+                    notification => panic!("unexpected notification {notification:?}"),
+                }
+            }
+            daa_scores
+        };
+        let [coalescing, plain] = receivers;
+        let daa_scores = [daa_scores(coalescing).await, daa_scores(plain).await];
+        assert_eq!(daa_scores[0].last(), Some(&99));
+        assert!(daa_scores[0].len() < 100, "the DAA scores queued for the coalescing listener should be coalesced");
+        assert_eq!(daa_scores[1], (0..100).collect::<Vec<_>>(), "every DAA score should be delivered to the other listener");
+
+        notifier.stop().await.unwrap();
+    }
+
+    #[test]
+    fn test_no_coalescing_delivers_all() {
+        let (sender, receiver) = unbounded();
+        let (connection, receiver) = CoalescingChannelConnection::new(sender, receiver, false);
+        flood(&connection);
+        assert_eq!(receiver.len(), 204);
+    }
+}
@@ -1,5 +1,13 @@
+use borsh::{BorshDeserialize, BorshSerialize};
 use derive_more::Display;
-use kaspa_consensus_core::{acceptance_data::AcceptanceData, block::Block, utxo::utxo_diff::UtxoDiff};
+use kaspa_consensus_core::{
+    acceptance_data::AcceptanceData,
+    block::Block,
+    tx::{ScriptPublicKey, TransactionOutpoint, UtxoEntry},
+    utxo::{utxo_collection::UtxoCollection, utxo_diff::UtxoDiff},
+    BlueWorkType,
+};
+use kaspa_core::time::unix_now;
 use kaspa_hashes::Hash;
 use kaspa_notify::{
     events::EventType,
@@ -10,18 +18,18 @@ use kaspa_notify::{
         Single,
     },
 };
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 full_featured! {
-#[derive(Clone, Debug, Display)]
+#[derive(Clone, Debug, Display, BorshSerialize, BorshDeserialize)]
 pub enum Notification {
     #[display(fmt = "BlockAdded notification: block hash {}", "_0.block.header.hash")]
     BlockAdded(BlockAddedNotification),
 
-    #[display(fmt = "VirtualChainChanged notification: {} removed blocks, {} added blocks, {} accepted transactions", "_0.removed_chain_block_hashes.len()", "_0.added_chain_block_hashes.len()", "_0.added_chain_blocks_acceptance_data.len()")]
+    #[display(fmt = "VirtualChainChanged notification: {} removed blocks (reorg depth {}), {} added blocks, {} accepted transactions", "_0.removed_chain_block_hashes.len()", "_0.reorg_depth", "_0.added_chain_block_hashes.len()", "_0.added_chain_blocks_acceptance_data.len()")]
     VirtualChainChanged(VirtualChainChangedNotification),
 
-    #[display(fmt = "FinalityConflict notification: violating block hash {}", "_0.violating_block_hash")]
+    #[display(fmt = "FinalityConflict notification ({} severity): violating block hash {}, competing tip {}, blue work difference {}", "_0.severity", "_0.violating_block_hash", "_0.competing_tip_hash", "_0.blue_work_difference")]
     FinalityConflict(FinalityConflictNotification),
 
     #[display(fmt = "FinalityConflict notification: violating block hash {}", "_0.finality_block_hash")]
@@ -41,13 +49,124 @@ pub enum Notification {
 
     #[display(fmt = "NewBlockTemplate notification")]
     NewBlockTemplate(NewBlockTemplateNotification),
+
+    #[display(fmt = "MempoolSizeChanged notification: {} transactions, total mass {}", "_0.transaction_count", "_0.total_mass")]
+    MempoolSizeChanged(MempoolSizeChangedNotification),
 }
 }
 
+impl Notification {
+    /// Unix time in milliseconds at which the notification was generated
+    pub fn emitted_at(&self) -> u64 {
+        match self {
+            Notification::BlockAdded(payload) => payload.emitted_at,
+            Notification::VirtualChainChanged(payload) => payload.emitted_at,
+            Notification::FinalityConflict(payload) => payload.emitted_at,
+            Notification::FinalityConflictResolved(payload) => payload.emitted_at,
+            Notification::UtxosChanged(payload) => payload.emitted_at,
+            Notification::SinkBlueScoreChanged(payload) => payload.emitted_at,
+            Notification::VirtualDaaScoreChanged(payload) => payload.emitted_at,
+            Notification::PruningPointUtxoSetOverride(payload) => payload.emitted_at,
+            Notification::NewBlockTemplate(payload) => payload.emitted_at,
+            Notification::MempoolSizeChanged(payload) => payload.emitted_at,
+        }
+    }
+
+    /// Returns the hashes of the blocks the notification is about:
+    /// - the added block for `BlockAdded`,
+    /// - the added then the removed chain blocks for `VirtualChainChanged`,
+    /// - the violating block for `FinalityConflict` and the finality block for `FinalityConflictResolved`,
+    /// - the virtual parents for `UtxosChanged`.
+    ///
+    /// The other notifications concern no particular block and return no hash.
+    pub fn block_hashes(&self) -> Vec<Hash> {
+        match self {
+            Notification::BlockAdded(payload) => vec![payload.block.hash()],
+            Notification::VirtualChainChanged(payload) => {
+                payload.added_chain_block_hashes.iter().chain(payload.removed_chain_block_hashes.iter()).copied().collect()
+            }
+            Notification::FinalityConflict(payload) => vec![payload.violating_block_hash],
+            Notification::FinalityConflictResolved(payload) => vec![payload.finality_block_hash],
+            Notification::UtxosChanged(payload) => payload.virtual_parents.as_ref().clone(),
+            Notification::SinkBlueScoreChanged(_)
+            | Notification::VirtualDaaScoreChanged(_)
+            | Notification::PruningPointUtxoSetOverride(_)
+            | Notification::NewBlockTemplate(_)
+            | Notification::MempoolSizeChanged(_) => vec![],
+        }
+    }
+
+    /// Returns the blue score the notification refers to: the blue score of the added block for `BlockAdded`
+    /// and the sink blue score for `SinkBlueScoreChanged`.
+    ///
+    /// The other notifications carry no meaningful blue score and return `None`.
+    pub fn blue_score(&self) -> Option<u64> {
+        match self {
+            Notification::BlockAdded(payload) => Some(payload.block.header.blue_score),
+            Notification::SinkBlueScoreChanged(payload) => Some(payload.sink_blue_score),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` for "latest value wins" notifications, for which only the newest
+    /// queued instance is relevant to a listener
+    pub fn is_coalescable(&self) -> bool {
+        matches!(
+            self,
+            Notification::VirtualDaaScoreChanged(_) | Notification::SinkBlueScoreChanged(_) | Notification::MempoolSizeChanged(_)
+        )
+    }
+
+    /// Returns a one-line summary of the notification made of space separated `key=value` fields, starting with
+    /// `event=<event type>` and ending with `emitted_at=<unix time in milliseconds>`.
+    ///
+    /// Unlike the human readable `Display`, the format is stable and meant to be parsed by log processors.
+    /// Vectors are summarized by their length.
+    pub fn summary(&self) -> String {
+        let fields = match self {
+            Notification::BlockAdded(payload) => {
+                format!("hash={} blue_score={}", payload.block.header.hash, payload.block.header.blue_score)
+            }
+            Notification::VirtualChainChanged(payload) => format!(
+                "added_blocks={} removed_blocks={} accepted_blocks={} reorg_depth={}",
+                payload.added_chain_block_hashes.len(),
+                payload.removed_chain_block_hashes.len(),
+                payload.added_chain_blocks_acceptance_data.len(),
+                payload.reorg_depth
+            ),
+            Notification::FinalityConflict(payload) => format!(
+                "violating_block_hash={} competing_tip_hash={} blue_work_difference={} severity={}",
+                payload.violating_block_hash, payload.competing_tip_hash, payload.blue_work_difference, payload.severity
+            ),
+            Notification::FinalityConflictResolved(payload) => format!("finality_block_hash={}", payload.finality_block_hash),
+            Notification::UtxosChanged(payload) => format!(
+                "added_utxos={} removed_utxos={} virtual_parents={} addresses={} more={}",
+                payload.accumulated_utxo_diff.add.len(),
+                payload.accumulated_utxo_diff.remove.len(),
+                payload.virtual_parents.len(),
+                payload.address_changes.len(),
+                payload.more
+            ),
+            Notification::SinkBlueScoreChanged(payload) => format!("sink_blue_score={}", payload.sink_blue_score),
+            Notification::VirtualDaaScoreChanged(payload) => format!("virtual_daa_score={}", payload.virtual_daa_score),
+            Notification::PruningPointUtxoSetOverride(_) | Notification::NewBlockTemplate(_) => String::new(),
+            Notification::MempoolSizeChanged(payload) => {
+                format!("transaction_count={} total_mass={}", payload.transaction_count, payload.total_mass)
+            }
+        };
+        let event = format!("event={:?}", EventType::from(self));
+        let emitted_at = format!("emitted_at={}", self.emitted_at());
+        [event, fields, emitted_at].into_iter().filter(|field| !field.is_empty()).collect::<Vec<_>>().join(" ")
+    }
+}
+
 impl NotificationTrait for Notification {
     fn apply_overall_subscription(&self, subscription: &OverallSubscription) -> Option<Self> {
         match subscription.active() {
-            true => Some(self.clone()),
+            true => match (subscription.min_blue_score(), self.blue_score()) {
+                (Some(min_blue_score), Some(blue_score)) if blue_score < min_blue_score => None,
+                _ => Some(self.clone()),
+            },
             false => None,
         }
     }
@@ -63,6 +182,8 @@ impl NotificationTrait for Notification {
                             removed_chain_block_hashes: payload.removed_chain_block_hashes.clone(),
                             added_chain_block_hashes: payload.added_chain_block_hashes.clone(),
                             added_chain_blocks_acceptance_data: Arc::new(vec![]),
+                            reorg_depth: payload.reorg_depth,
+                            emitted_at: payload.emitted_at,
                         }));
                     }
                 }
@@ -72,33 +193,52 @@ impl NotificationTrait for Notification {
         }
     }
 
-    fn apply_utxos_changed_subscription(&self, _subscription: &UtxosChangedSubscription) -> Option<Self> {
-        // No effort is made here to apply the subscription addresses.
-        // This will be achieved farther along the notification backbone.
-        Some(self.clone())
+    fn apply_utxos_changed_subscription(&self, subscription: &UtxosChangedSubscription) -> Option<Self> {
+        match subscription.active() {
+            true => {
+                let Self::UtxosChanged(notification) = self else { return None };
+                notification.apply_utxos_changed_subscription(subscription).map(Self::UtxosChanged)
+            }
+            false => None,
+        }
     }
 
     fn event_type(&self) -> EventType {
         self.into()
     }
+
+    fn paginate(&self, page_size: usize) -> Option<Vec<Self>> {
+        match self {
+            Notification::UtxosChanged(payload) => {
+                payload.paginate(page_size).map(|pages| pages.into_iter().map(Notification::UtxosChanged).collect())
+            }
+            _ => None,
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
 pub struct BlockAddedNotification {
     pub block: Block,
+    /// Unix time in milliseconds at which the notification was generated
+    pub emitted_at: u64,
 }
 
 impl BlockAddedNotification {
     pub fn new(block: Block) -> Self {
-        Self { block }
+        Self { block, emitted_at: unix_now() }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
 pub struct VirtualChainChangedNotification {
     pub added_chain_block_hashes: Arc<Vec<Hash>>,
     pub removed_chain_block_hashes: Arc<Vec<Hash>>,
     pub added_chain_blocks_acceptance_data: Arc<Vec<Arc<AcceptanceData>>>,
+    /// Depth of the reorg, ie. the number of removed chain blocks
+    pub reorg_depth: u64,
+    /// Unix time in milliseconds at which the notification was generated
+    pub emitted_at: u64,
 }
 impl VirtualChainChangedNotification {
     pub fn new(
@@ -106,69 +246,809 @@ impl VirtualChainChangedNotification {
         removed_chain_block_hashes: Arc<Vec<Hash>>,
         added_chain_blocks_acceptance_data: Arc<Vec<Arc<AcceptanceData>>>,
     ) -> Self {
-        Self { added_chain_block_hashes, removed_chain_block_hashes, added_chain_blocks_acceptance_data }
+        let reorg_depth = removed_chain_block_hashes.len() as u64;
+        Self {
+            added_chain_block_hashes,
+            removed_chain_block_hashes,
+            added_chain_blocks_acceptance_data,
+            reorg_depth,
+            emitted_at: unix_now(),
+        }
+    }
+}
+
+/// Assessment of a finality conflict, allowing monitoring tools to alert appropriately
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Display, BorshSerialize, BorshDeserialize)]
+pub enum FinalityConflictSeverity {
+    /// A stale block with no realistic chance of overtaking the selected chain
+    #[default]
+    #[display(fmt = "low")]
+    Low,
+    #[display(fmt = "medium")]
+    Medium,
+    /// A competing chain closing in on the selected chain, possibly a reorg attempt
+    #[display(fmt = "high")]
+    High,
+}
+
+impl FinalityConflictSeverity {
+    /// Assesses a conflict from the blue work of the violating block, of the competing tip and of the finality point:
+    /// a violating block outweighing the tip is [`High`](Self::High), one closer to the tip than half the blue work
+    /// accumulated since the finality point is [`Medium`](Self::Medium), any other is [`Low`](Self::Low).
+    pub fn assess(
+        violating_blue_work: BlueWorkType,
+        competing_tip_blue_work: BlueWorkType,
+        finality_point_blue_work: BlueWorkType,
+    ) -> Self {
+        if violating_blue_work >= competing_tip_blue_work {
+            return Self::High;
+        }
+        let finality_window = competing_tip_blue_work - finality_point_blue_work.min(competing_tip_blue_work);
+        match competing_tip_blue_work - violating_blue_work < finality_window / 2 {
+            true => Self::Medium,
+            false => Self::Low,
+        }
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, BorshSerialize, BorshDeserialize)]
 pub struct FinalityConflictNotification {
     pub violating_block_hash: Hash,
+    /// Tip of the selected chain the violating block competes with
+    pub competing_tip_hash: Hash,
+    /// Blue work difference between the competing tip and the violating block
+    pub blue_work_difference: BlueWorkType,
+    pub severity: FinalityConflictSeverity,
+    /// Unix time in milliseconds at which the notification was generated
+    pub emitted_at: u64,
 }
 
 impl FinalityConflictNotification {
-    pub fn new(violating_block_hash: Hash) -> Self {
-        Self { violating_block_hash }
+    pub fn new(
+        violating_block_hash: Hash,
+        competing_tip_hash: Hash,
+        blue_work_difference: BlueWorkType,
+        severity: FinalityConflictSeverity,
+    ) -> Self {
+        Self { violating_block_hash, competing_tip_hash, blue_work_difference, severity, emitted_at: unix_now() }
+    }
+
+    /// Creates the notification of a block violating finality while competing with the tip of the selected chain,
+    /// see [`FinalityConflictSeverity::assess`]
+    pub fn assess(
+        violating_block_hash: Hash,
+        violating_blue_work: BlueWorkType,
+        competing_tip_hash: Hash,
+        competing_tip_blue_work: BlueWorkType,
+        finality_point_blue_work: BlueWorkType,
+    ) -> Self {
+        let blue_work_difference = match violating_blue_work > competing_tip_blue_work {
+            true => violating_blue_work - competing_tip_blue_work,
+            false => competing_tip_blue_work - violating_blue_work,
+        };
+        let severity = FinalityConflictSeverity::assess(violating_blue_work, competing_tip_blue_work, finality_point_blue_work);
+        Self::new(violating_block_hash, competing_tip_hash, blue_work_difference, severity)
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, BorshSerialize, BorshDeserialize)]
 pub struct FinalityConflictResolvedNotification {
     pub finality_block_hash: Hash,
+    /// Unix time in milliseconds at which the notification was generated
+    pub emitted_at: u64,
 }
 
 impl FinalityConflictResolvedNotification {
     pub fn new(finality_block_hash: Hash) -> Self {
-        Self { finality_block_hash }
+        Self { finality_block_hash, emitted_at: unix_now() }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
 pub struct UtxosChangedNotification {
     /// Accumulated UTXO diff between the last virtual state and the current virtual state
     pub accumulated_utxo_diff: Arc<UtxoDiff>,
     pub virtual_parents: Arc<Vec<Hash>>,
+    /// UTXO entries of `accumulated_utxo_diff` grouped by subscribed address.
+    ///
+    /// Only filled when the notification is filtered by an address subscription,
+    /// empty for wildcard subscribers. The addresses are ordered by script public key
+    /// and their entries by outpoint, so that equal diffs always yield equal changes.
+    pub address_changes: Arc<Vec<AddressUtxoChanges>>,
+    /// Unix time in milliseconds at which the notification was generated
+    pub emitted_at: u64,
+    /// `true` if the notification is a page of a larger diff and more pages of the same diff follow,
+    /// see [`UtxosChangedNotification::paginate`]
+    pub more: bool,
 }
 
 impl UtxosChangedNotification {
     pub fn new(accumulated_utxo_diff: Arc<UtxoDiff>, virtual_parents: Arc<Vec<Hash>>) -> Self {
-        Self { accumulated_utxo_diff, virtual_parents }
+        Self { accumulated_utxo_diff, virtual_parents, address_changes: Arc::new(vec![]), emitted_at: unix_now(), more: false }
+    }
+
+    /// Splits the accumulated diff into pages of at most `page_size` added and removed entries, returning `None` if the
+    /// diff fits in a single page.
+    ///
+    /// The added entries are paginated first, then the removed ones. Every page but the last has `more` set, and holds
+    /// the address changes of its own entries only.
+    pub fn paginate(&self, page_size: usize) -> Option<Vec<Self>> {
+        let diff = &self.accumulated_utxo_diff;
+        if page_size == 0 || diff.add.len() + diff.remove.len() <= page_size {
+            return None;
+        }
+        let entries = diff
+            .add
+            .iter()
+            .map(|(outpoint, entry)| (true, outpoint, entry))
+            .chain(diff.remove.iter().map(|(outpoint, entry)| (false, outpoint, entry)))
+            .collect::<Vec<_>>();
+        let mut pages = entries
+            .chunks(page_size)
+            .map(|chunk| {
+                let mut add = UtxoCollection::new();
+                let mut remove = UtxoCollection::new();
+                for &(added, outpoint, entry) in chunk {
+                    match added {
+                        true => add.insert(*outpoint, entry.clone()),
+                        false => remove.insert(*outpoint, entry.clone()),
+                    };
+                }
+                let address_changes = self
+                    .address_changes
+                    .iter()
+                    .filter_map(|changes| {
+                        let page_changes = AddressUtxoChanges {
+                            script_public_key: changes.script_public_key.clone(),
+                            added: changes.added.iter().filter(|(outpoint, _)| add.contains_key(outpoint)).cloned().collect(),
+                            removed: changes.removed.iter().filter(|(outpoint, _)| remove.contains_key(outpoint)).cloned().collect(),
+                        };
+                        (!page_changes.added.is_empty() || !page_changes.removed.is_empty()).then_some(page_changes)
+                    })
+                    .collect::<Vec<_>>();
+                Self {
+                    accumulated_utxo_diff: Arc::new(UtxoDiff::new(add, remove)),
+                    virtual_parents: self.virtual_parents.clone(),
+                    address_changes: Arc::new(address_changes),
+                    emitted_at: self.emitted_at,
+                    more: true,
+                }
+            })
+            .collect::<Vec<_>>();
+        if let Some(last) = pages.last_mut() {
+            last.more = false;
+        }
+        Some(pages)
+    }
+
+    pub(crate) fn apply_utxos_changed_subscription(&self, subscription: &UtxosChangedSubscription) -> Option<Self> {
+        if subscription.to_all() {
+            Some(self.clone())
+        } else {
+            let mut address_changes: HashMap<ScriptPublicKey, AddressUtxoChanges> = HashMap::new();
+            let add = Self::filter_utxo_collection(&self.accumulated_utxo_diff.add, subscription, |outpoint, entry| {
+                Self::address_entry(&mut address_changes, entry).added.push((*outpoint, entry.clone()))
+            });
+            let remove = Self::filter_utxo_collection(&self.accumulated_utxo_diff.remove, subscription, |outpoint, entry| {
+                Self::address_entry(&mut address_changes, entry).removed.push((*outpoint, entry.clone()))
+            });
+            if add.is_empty() && remove.is_empty() {
+                None
+            } else {
+                Some(Self {
+                    accumulated_utxo_diff: Arc::new(UtxoDiff::new(add, remove)),
+                    virtual_parents: self.virtual_parents.clone(),
+                    address_changes: Arc::new(Self::sorted_address_changes(address_changes)),
+                    emitted_at: self.emitted_at,
+                    more: self.more,
+                })
+            }
+        }
+    }
+
+    fn filter_utxo_collection(
+        collection: &UtxoCollection,
+        subscription: &UtxosChangedSubscription,
+        mut on_match: impl FnMut(&TransactionOutpoint, &UtxoEntry),
+    ) -> UtxoCollection {
+        collection
+            .iter()
+            .filter(|(_, entry)| subscription.addresses().contains_key(&entry.script_public_key))
+            .map(|(outpoint, entry)| {
+                on_match(outpoint, entry);
+                (*outpoint, entry.clone())
+            })
+            .collect()
+    }
+
+    /// Orders the changes by script public key and their entries by outpoint, regardless of the iteration order of the
+    /// diff they are drawn from
+    fn sorted_address_changes(address_changes: HashMap<ScriptPublicKey, AddressUtxoChanges>) -> Vec<AddressUtxoChanges> {
+        let mut address_changes = address_changes.into_values().collect::<Vec<_>>();
+        address_changes.sort_by(|a, b| {
+            (a.script_public_key.version(), a.script_public_key.script())
+                .cmp(&(b.script_public_key.version(), b.script_public_key.script()))
+        });
+        for changes in address_changes.iter_mut() {
+            changes.added.sort_by_key(|(outpoint, _)| *outpoint);
+            changes.removed.sort_by_key(|(outpoint, _)| *outpoint);
+        }
+        address_changes
+    }
+
+    fn address_entry<'a>(
+        address_changes: &'a mut HashMap<ScriptPublicKey, AddressUtxoChanges>,
+        entry: &UtxoEntry,
+    ) -> &'a mut AddressUtxoChanges {
+        address_changes
+            .entry(entry.script_public_key.clone())
+            .or_insert_with(|| AddressUtxoChanges::new(entry.script_public_key.clone()))
+    }
+}
+
+/// UTXO entries added and removed for a single address, identified by its script public key
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct AddressUtxoChanges {
+    pub script_public_key: ScriptPublicKey,
+    pub added: Vec<(TransactionOutpoint, UtxoEntry)>,
+    pub removed: Vec<(TransactionOutpoint, UtxoEntry)>,
+}
+
+impl AddressUtxoChanges {
+    pub fn new(script_public_key: ScriptPublicKey) -> Self {
+        Self { script_public_key, added: vec![], removed: vec![] }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
 pub struct SinkBlueScoreChangedNotification {
     pub sink_blue_score: u64,
+    /// Unix time in milliseconds at which the notification was generated
+    pub emitted_at: u64,
 }
 
 impl SinkBlueScoreChangedNotification {
     pub fn new(sink_blue_score: u64) -> Self {
-        Self { sink_blue_score }
+        Self { sink_blue_score, emitted_at: unix_now() }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
 pub struct VirtualDaaScoreChangedNotification {
     pub virtual_daa_score: u64,
+    /// Unix time in milliseconds at which the notification was generated
+    pub emitted_at: u64,
 }
 
 impl VirtualDaaScoreChangedNotification {
     pub fn new(virtual_daa_score: u64) -> Self {
-        Self { virtual_daa_score }
+        Self { virtual_daa_score, emitted_at: unix_now() }
+    }
+}
+
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct PruningPointUtxoSetOverrideNotification {
+    /// Unix time in milliseconds at which the notification was generated
+    pub emitted_at: u64,
+}
+
+impl PruningPointUtxoSetOverrideNotification {
+    pub fn new() -> Self {
+        Self { emitted_at: unix_now() }
+    }
+}
+
+impl Default for PruningPointUtxoSetOverrideNotification {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct NewBlockTemplateNotification {
+    /// Unix time in milliseconds at which the notification was generated
+    pub emitted_at: u64,
+}
+
+impl NewBlockTemplateNotification {
+    pub fn new() -> Self {
+        Self { emitted_at: unix_now() }
+    }
+}
+
+impl Default for NewBlockTemplateNotification {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct MempoolSizeChangedNotification {
+    /// Number of transactions currently held in the mempool
+    pub transaction_count: u64,
+    /// Sum of the mass of all transactions currently held in the mempool
+    pub total_mass: u64,
+    /// Unix time in milliseconds at which the notification was generated
+    pub emitted_at: u64,
+}
+
+impl MempoolSizeChangedNotification {
+    pub fn new(transaction_count: u64, total_mass: u64) -> Self {
+        Self { transaction_count, total_mass, emitted_at: unix_now() }
     }
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct PruningPointUtxoSetOverrideNotification {}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kaspa_consensus_core::acceptance_data::{AcceptedTxEntry, MergesetBlockAcceptanceData};
+    use kaspa_notify::address::{test_helpers::get_3_addresses, UtxoAddress};
+
+    fn utxos_changed(added: &[u64], removed: &[u64], scripts: &[ScriptPublicKey]) -> Notification {
+        let collection = |words: &[u64]| -> UtxoCollection {
+            words
+                .iter()
+                .zip(scripts.iter().cycle())
+                .map(|(word, script)| {
+                    (TransactionOutpoint::new(Hash::from_u64_word(*word), 0), UtxoEntry::new(*word, script.clone(), 0, false))
+                })
+                .collect()
+        };
+        let diff = UtxoDiff::new(collection(added), collection(removed));
+        Notification::UtxosChanged(UtxosChangedNotification::new(Arc::new(diff), Arc::new(vec![])))
+    }
+
+    fn unwrap_utxos_changed(notification: Notification) -> UtxosChangedNotification {
+        match notification {
+            Notification::UtxosChanged(payload) => payload,
+            _ => panic!("expected a UtxosChanged notification"),
+        }
+    }
+
+    #[test]
+    fn test_sink_blue_score_changed() {
+        let notification = Notification::SinkBlueScoreChanged(SinkBlueScoreChangedNotification::new(42));
+        assert_eq!(notification.event_type(), EventType::SinkBlueScoreChanged);
+        assert!(notification.to_string().contains("42"));
+
+        let subscription = OverallSubscription::new(EventType::SinkBlueScoreChanged, true);
+        match notification.apply_overall_subscription(&subscription) {
+            Some(Notification::SinkBlueScoreChanged(payload)) => assert_eq!(payload.sink_blue_score, 42),
+            _ => panic!("expected a SinkBlueScoreChanged notification"),
+        }
+        let subscription = OverallSubscription::new(EventType::SinkBlueScoreChanged, false);
+        assert!(notification.apply_overall_subscription(&subscription).is_none());
+    }
+
+    #[test]
+    fn test_min_blue_score() {
+        let block_added = |blue_score| {
+            let mut header = kaspa_consensus_core::header::Header::from_precomputed_hash(Hash::from_u64_word(blue_score), vec![]);
+            header.blue_score = blue_score;
+            Notification::BlockAdded(BlockAddedNotification::new(Block::from_header(header)))
+        };
+        let subscription = OverallSubscription::new(EventType::BlockAdded, true).with_min_blue_score(100);
+        let passed = [50, 99, 100, 101, 1_000]
+            .into_iter()
+            .filter_map(|blue_score| block_added(blue_score).apply_overall_subscription(&subscription))
+            .map(|notification| notification.blue_score().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(passed, vec![100, 101, 1_000], "only the blocks at or above the floor should pass");
+
+        let subscription = OverallSubscription::new(EventType::SinkBlueScoreChanged, true).with_min_blue_score(100);
+        let sink_blue_score_changed =
+            |blue_score| Notification::SinkBlueScoreChanged(SinkBlueScoreChangedNotification::new(blue_score));
+        assert!(sink_blue_score_changed(99).apply_overall_subscription(&subscription).is_none());
+        assert!(sink_blue_score_changed(100).apply_overall_subscription(&subscription).is_some());
+
+        // Events with no blue score pass through unchanged
+        let subscription = OverallSubscription::new(EventType::VirtualDaaScoreChanged, true).with_min_blue_score(100);
+        let notification = Notification::VirtualDaaScoreChanged(VirtualDaaScoreChangedNotification::new(1));
+        assert_eq!(notification.blue_score(), None);
+        assert!(notification.apply_overall_subscription(&subscription).is_some());
+
+        // The floor does not activate an inactive subscription
+        let subscription = OverallSubscription::new(EventType::BlockAdded, false).with_min_blue_score(0);
+        assert!(block_added(1).apply_overall_subscription(&subscription).is_none());
+    }
+
+    #[test]
+    fn test_mempool_size_changed() {
+        let notification = Notification::MempoolSizeChanged(MempoolSizeChangedNotification::new(17, 34_000));
+        assert_eq!(notification.event_type(), EventType::MempoolSizeChanged);
+        assert!(notification.is_coalescable());
+
+        let subscription = OverallSubscription::new(EventType::MempoolSizeChanged, true);
+        match notification.apply_overall_subscription(&subscription) {
+            Some(Notification::MempoolSizeChanged(payload)) => {
+                assert_eq!(payload.transaction_count, 17);
+                assert_eq!(payload.total_mass, 34_000);
+            }
+            _ => panic!("expected a MempoolSizeChanged notification"),
+        }
+        let subscription = OverallSubscription::new(EventType::MempoolSizeChanged, false);
+        assert!(notification.apply_overall_subscription(&subscription).is_none());
+    }
+
+    #[test]
+    fn test_emitted_at() {
+        const EMITTED_AT: u64 = 1_680_000_000_123;
+        let mut payload = VirtualDaaScoreChangedNotification::new(11);
+        assert!(payload.emitted_at > 0);
+        payload.emitted_at = EMITTED_AT;
+        let notification = Notification::VirtualDaaScoreChanged(payload);
+        assert_eq!(notification.emitted_at(), EMITTED_AT);
+        assert!(!notification.to_string().contains(&EMITTED_AT.to_string()));
+
+        let subscription = OverallSubscription::new(EventType::VirtualDaaScoreChanged, true);
+        let filtered = notification.apply_overall_subscription(&subscription).unwrap();
+        assert_eq!(filtered.emitted_at(), EMITTED_AT);
+        assert_eq!(filtered.clone().emitted_at(), EMITTED_AT);
+
+        // Filtered UTXO notifications keep the original timestamp as well
+        let scripts = get_3_addresses(false).into_iter().map(|x| UtxoAddress::from(x).to_script_public_key()).collect::<Vec<_>>();
+        let Notification::UtxosChanged(mut payload) = utxos_changed(&[1, 2], &[], &scripts) else { unreachable!() };
+        payload.emitted_at = EMITTED_AT;
+        let subscription = UtxosChangedSubscription::new(true, vec![get_3_addresses(false)[0].clone()]);
+        let filtered = Notification::UtxosChanged(payload).apply_utxos_changed_subscription(&subscription).unwrap();
+        assert_eq!(filtered.emitted_at(), EMITTED_AT);
+    }
+
+    #[test]
+    fn test_virtual_chain_changed_reorg_depth() {
+        let hashes = |words: std::ops::Range<u64>| Arc::new(words.map(Hash::from_u64_word).collect::<Vec<_>>());
+        let notification = VirtualChainChangedNotification::new(hashes(0..2), hashes(10..13), Arc::new(vec![]));
+        assert_eq!(notification.reorg_depth, 3);
+        assert_eq!(notification.reorg_depth, notification.removed_chain_block_hashes.len() as u64);
+
+        let notification = Notification::VirtualChainChanged(notification);
+        assert!(notification.to_string().contains("reorg depth 3"));
+
+        // The depth is preserved when the subscription drops the acceptance data
+        let notification =
+            Notification::VirtualChainChanged(VirtualChainChangedNotification::new(hashes(0..1), hashes(10..11), Arc::new(vec![])));
+        let subscription = VirtualChainChangedSubscription::new(true, false);
+        match notification.apply_virtual_chain_changed_subscription(&subscription) {
+            Some(Notification::VirtualChainChanged(payload)) => assert_eq!(payload.reorg_depth, 1),
+            _ => panic!("expected a VirtualChainChanged notification"),
+        }
+    }
+
+    #[test]
+    fn test_apply_utxos_changed_subscription() {
+        let addresses = get_3_addresses(false);
+        let scripts = addresses.iter().map(|x| UtxoAddress::from(x.clone()).to_script_public_key()).collect::<Vec<_>>();
+
+        // Outpoints 1 & 4 pay to address 0, 2 & 5 to address 1, 3 to address 2
+        let notification = utxos_changed(&[1, 2, 3], &[4, 5], &scripts);
+
+        // Matching address
+        let subscription = UtxosChangedSubscription::new(true, vec![addresses[0].clone()]);
+        let filtered = unwrap_utxos_changed(notification.apply_utxos_changed_subscription(&subscription).unwrap());
+        assert_eq!(filtered.accumulated_utxo_diff.add.len(), 1);
+        assert_eq!(filtered.accumulated_utxo_diff.remove.len(), 1);
+        assert!(filtered.accumulated_utxo_diff.add.contains_key(&TransactionOutpoint::new(Hash::from_u64_word(1), 0)));
+        assert!(filtered.accumulated_utxo_diff.remove.contains_key(&TransactionOutpoint::new(Hash::from_u64_word(4), 0)));
+
+        // Per-address entries match the filtered diff
+        assert_eq!(filtered.address_changes.len(), 1);
+        let changes = &filtered.address_changes[0];
+        assert_eq!(changes.script_public_key, scripts[0]);
+        assert_eq!(changes.added.len(), filtered.accumulated_utxo_diff.add.len());
+        assert_eq!(changes.removed.len(), filtered.accumulated_utxo_diff.remove.len());
+        for (outpoint, entry) in changes.added.iter() {
+            assert_eq!(filtered.accumulated_utxo_diff.add.get(outpoint), Some(entry));
+        }
+        for (outpoint, entry) in changes.removed.iter() {
+            assert_eq!(filtered.accumulated_utxo_diff.remove.get(outpoint), Some(entry));
+        }
+
+        // Several matching addresses, ordered by script public key
+        let subscription = UtxosChangedSubscription::new(true, vec![addresses[0].clone(), addresses[1].clone()]);
+        let filtered = unwrap_utxos_changed(notification.apply_utxos_changed_subscription(&subscription).unwrap());
+        assert_eq!(filtered.address_changes.len(), 2);
+        let mut expected = scripts[..2].to_vec();
+        expected.sort_by(|a, b| a.script().cmp(b.script()));
+        assert_eq!(filtered.address_changes.iter().map(|changes| changes.script_public_key.clone()).collect::<Vec<_>>(), expected);
+        for changes in filtered.address_changes.iter() {
+            assert!(scripts[..2].contains(&changes.script_public_key));
+            assert_eq!(changes.added.len(), 1);
+            assert_eq!(changes.removed.len(), 1);
+            assert!(changes
+                .added
+                .iter()
+                .chain(changes.removed.iter())
+                .all(|(_, entry)| entry.script_public_key == changes.script_public_key));
+        }
+
+        // Entries ordered by outpoint
+        let notification = utxos_changed(&[6, 2, 9, 4, 1], &[], &scripts[..1]);
+        let subscription = UtxosChangedSubscription::new(true, vec![addresses[0].clone()]);
+        let filtered = unwrap_utxos_changed(notification.apply_utxos_changed_subscription(&subscription).unwrap());
+        let outpoints = filtered.address_changes[0].added.iter().map(|(outpoint, _)| *outpoint).collect::<Vec<_>>();
+        assert_eq!(outpoints, [1, 2, 4, 6, 9].map(|word| TransactionOutpoint::new(Hash::from_u64_word(word), 0)));
 
-#[derive(Debug, Clone)]
-pub struct NewBlockTemplateNotification {}
+        // Non-matching address
+        let notification = utxos_changed(&[1], &[2], &scripts[..2]);
+        let subscription = UtxosChangedSubscription::new(true, vec![addresses[2].clone()]);
+        assert!(notification.apply_utxos_changed_subscription(&subscription).is_none());
+
+        // Wildcard
+        let notification = utxos_changed(&[1, 2, 3], &[4, 5], &scripts);
+        let subscription = UtxosChangedSubscription::new(true, vec![]);
+        let filtered = unwrap_utxos_changed(notification.apply_utxos_changed_subscription(&subscription).unwrap());
+        assert_eq!(filtered.accumulated_utxo_diff.add.len(), 3);
+        assert_eq!(filtered.accumulated_utxo_diff.remove.len(), 2);
+        assert!(filtered.address_changes.is_empty());
+
+        // Inactive subscription
+        let subscription = UtxosChangedSubscription::new(false, vec![]);
+        assert!(notification.apply_utxos_changed_subscription(&subscription).is_none());
+    }
+
+    #[test]
+    fn test_utxos_changed_pagination() {
+        const PAGE_SIZE: usize = 10;
+        let addresses = get_3_addresses(false);
+        let scripts = addresses.iter().map(|x| UtxoAddress::from(x.clone()).to_script_public_key()).collect::<Vec<_>>();
+        let notification = utxos_changed(&(1..=25).collect::<Vec<_>>(), &(101..=112).collect::<Vec<_>>(), &scripts);
+        let original = unwrap_utxos_changed(notification.clone());
+
+        let pages = notification.paginate(PAGE_SIZE).expect("a diff of 37 entries should be paginated").into_iter();
+        let pages = pages.map(unwrap_utxos_changed).collect::<Vec<_>>();
+        assert_eq!(pages.len(), 4);
+        assert_eq!(pages.iter().map(|page| page.more).collect::<Vec<_>>(), vec![true, true, true, false]);
+        let mut add = UtxoCollection::new();
+        let mut remove = UtxoCollection::new();
+        for page in pages.iter() {
+            let diff = &page.accumulated_utxo_diff;
+            assert!(diff.add.len() + diff.remove.len() <= PAGE_SIZE, "a page should hold at most {PAGE_SIZE} entries");
+            assert_eq!(page.emitted_at, original.emitted_at);
+            add.extend(diff.add.iter().map(|(outpoint, entry)| (*outpoint, entry.clone())));
+            remove.extend(diff.remove.iter().map(|(outpoint, entry)| (*outpoint, entry.clone())));
+        }
+        assert_eq!(add, original.accumulated_utxo_diff.add, "the pages should total the original added entries");
+        assert_eq!(remove, original.accumulated_utxo_diff.remove, "the pages should total the original removed entries");
+
+        // The address changes of a filtered notification are split along with its diff
+        let subscription = UtxosChangedSubscription::new(true, vec![addresses[0].clone()]);
+        let filtered = notification.apply_utxos_changed_subscription(&subscription).unwrap();
+        for page in filtered.paginate(4).unwrap().into_iter().map(unwrap_utxos_changed) {
+            let diff = &page.accumulated_utxo_diff;
+            assert_eq!(page.address_changes.len(), 1);
+            assert_eq!(page.address_changes[0].added.len(), diff.add.len());
+            assert_eq!(page.address_changes[0].removed.len(), diff.remove.len());
+        }
+
+        assert!(notification.paginate(37).is_none(), "a diff fitting a single page should not be paginated");
+        assert!(notification.paginate(0).is_none());
+        assert!(Notification::SinkBlueScoreChanged(SinkBlueScoreChangedNotification::new(5)).paginate(1).is_none());
+    }
+
+    #[test]
+    fn test_finality_conflict() {
+        let payload = FinalityConflictNotification::new(
+            Hash::from_u64_word(1),
+            Hash::from_u64_word(2),
+            BlueWorkType::from_u64(42),
+            FinalityConflictSeverity::High,
+        );
+        assert_eq!(payload.competing_tip_hash, Hash::from_u64_word(2));
+        assert_eq!(payload.blue_work_difference, BlueWorkType::from_u64(42));
+        let notification = Notification::FinalityConflict(payload);
+        assert_eq!(notification.event_type(), EventType::FinalityConflict);
+        let display = notification.to_string();
+        assert!(display.contains("high severity"), "severity missing from `{display}`");
+        assert!(display.contains(&Hash::from_u64_word(2).to_string()), "competing tip missing from `{display}`");
+
+        match round_trip(&notification) {
+            Notification::FinalityConflict(decoded) => {
+                assert_eq!(decoded.violating_block_hash, Hash::from_u64_word(1));
+                assert_eq!(decoded.competing_tip_hash, Hash::from_u64_word(2));
+                assert_eq!(decoded.blue_work_difference, BlueWorkType::from_u64(42));
+                assert_eq!(decoded.severity, FinalityConflictSeverity::High);
+            }
+            _ => panic!("expected a FinalityConflict notification"),
+        }
+        assert_eq!(FinalityConflictSeverity::default(), FinalityConflictSeverity::Low);
+
+        // Finality point at 1000 and competing tip at 2000 blue work
+        let assess = |violating: u64| {
+            FinalityConflictNotification::assess(
+                Hash::from_u64_word(1),
+                violating.into(),
+                Hash::from_u64_word(2),
+                2_000u64.into(),
+                1_000u64.into(),
+            )
+        };
+        let payload = assess(2_300);
+        assert_eq!((payload.severity, payload.blue_work_difference), (FinalityConflictSeverity::High, BlueWorkType::from_u64(300)));
+        assert_eq!(assess(1_600).severity, FinalityConflictSeverity::Medium);
+        let payload = assess(1_200);
+        assert_eq!((payload.severity, payload.blue_work_difference), (FinalityConflictSeverity::Low, BlueWorkType::from_u64(800)));
+    }
+
+    #[test]
+    fn test_block_hashes() {
+        let hash = Hash::from_u64_word;
+        let hashes = |words: std::ops::Range<u64>| Arc::new(words.map(Hash::from_u64_word).collect::<Vec<_>>());
+        let utxos_changed = Notification::UtxosChanged(UtxosChangedNotification::new(Arc::new(UtxoDiff::default()), hashes(20..22)));
+
+        let tests = vec![
+            (
+                Notification::BlockAdded(BlockAddedNotification::new(Block::from_precomputed_hash(hash(1), vec![hash(2)]))),
+                vec![hash(1)],
+            ),
+            (
+                Notification::VirtualChainChanged(VirtualChainChangedNotification::new(
+                    hashes(3..5),
+                    hashes(10..12),
+                    Arc::new(vec![]),
+                )),
+                vec![hash(3), hash(4), hash(10), hash(11)],
+            ),
+            (
+                Notification::FinalityConflict(FinalityConflictNotification::new(
+                    hash(5),
+                    hash(15),
+                    100u64.into(),
+                    FinalityConflictSeverity::Low,
+                )),
+                vec![hash(5)],
+            ),
+            (Notification::FinalityConflictResolved(FinalityConflictResolvedNotification::new(hash(6))), vec![hash(6)]),
+            (utxos_changed, vec![hash(20), hash(21)]),
+            (Notification::SinkBlueScoreChanged(SinkBlueScoreChangedNotification::new(5)), vec![]),
+            (Notification::VirtualDaaScoreChanged(VirtualDaaScoreChangedNotification::new(6)), vec![]),
+            (Notification::PruningPointUtxoSetOverride(PruningPointUtxoSetOverrideNotification::new()), vec![]),
+            (Notification::NewBlockTemplate(NewBlockTemplateNotification::new()), vec![]),
+            (Notification::MempoolSizeChanged(MempoolSizeChangedNotification::new(7, 8)), vec![]),
+        ];
+        for (notification, expected) in tests {
+            assert_eq!(notification.block_hashes(), expected, "wrong block hashes for {notification}");
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let hash = Hash::from_u64_word;
+        let hashes = |words: std::ops::Range<u64>| Arc::new(words.map(Hash::from_u64_word).collect::<Vec<_>>());
+        let scripts = get_3_addresses(false).into_iter().map(|x| UtxoAddress::from(x).to_script_public_key()).collect::<Vec<_>>();
+
+        let tests = vec![
+            (
+                Notification::BlockAdded(BlockAddedNotification::new(Block::from_precomputed_hash(hash(1), vec![hash(2)]))),
+                vec!["event=BlockAdded", "hash=", "blue_score="],
+            ),
+            (
+                Notification::VirtualChainChanged(VirtualChainChangedNotification::new(
+                    hashes(3..5),
+                    hashes(10..13),
+                    Arc::new(vec![]),
+                )),
+                vec!["event=VirtualChainChanged", "added_blocks=2", "removed_blocks=3", "accepted_blocks=0", "reorg_depth=3"],
+            ),
+            (
+                Notification::FinalityConflict(FinalityConflictNotification::new(
+                    hash(5),
+                    hash(15),
+                    100u64.into(),
+                    FinalityConflictSeverity::High,
+                )),
+                vec![
+                    "event=FinalityConflict",
+                    "violating_block_hash=",
+                    "competing_tip_hash=",
+                    "blue_work_difference=",
+                    "severity=high",
+                ],
+            ),
+            (
+                Notification::FinalityConflictResolved(FinalityConflictResolvedNotification::new(hash(6))),
+                vec!["event=FinalityConflictResolved", "finality_block_hash="],
+            ),
+            (
+                utxos_changed(&[1, 2, 3], &[4, 5], &scripts),
+                vec!["event=UtxosChanged", "added_utxos=3", "removed_utxos=2", "virtual_parents=0", "addresses=0", "more=false"],
+            ),
+            (
+                Notification::SinkBlueScoreChanged(SinkBlueScoreChangedNotification::new(5)),
+                vec!["event=SinkBlueScoreChanged", "sink_blue_score=5"],
+            ),
+            (
+                Notification::VirtualDaaScoreChanged(VirtualDaaScoreChangedNotification::new(6)),
+                vec!["event=VirtualDaaScoreChanged", "virtual_daa_score=6"],
+            ),
+            (
+                Notification::PruningPointUtxoSetOverride(PruningPointUtxoSetOverrideNotification::new()),
+                vec!["event=PruningPointUtxoSetOverride"],
+            ),
+            (Notification::NewBlockTemplate(NewBlockTemplateNotification::new()), vec!["event=NewBlockTemplate"]),
+            (
+                Notification::MempoolSizeChanged(MempoolSizeChangedNotification::new(7, 8)),
+                vec!["event=MempoolSizeChanged", "transaction_count=7", "total_mass=8"],
+            ),
+        ];
+        for (notification, expected) in tests {
+            let summary = notification.summary();
+            assert!(!summary.contains('\n'), "the summary of {notification} should fit on one line");
+            assert!(summary.starts_with(expected[0]), "the summary {summary} should start with the event type");
+            assert!(summary.ends_with(&format!("emitted_at={}", notification.emitted_at())));
+            let fields = summary.split(' ').collect::<Vec<_>>();
+            assert!(fields.iter().all(|field| field.split_once('=').is_some()), "every field of {summary} should be a key=value pair");
+            for key in expected {
+                assert!(fields.iter().any(|field| field.starts_with(key)), "the summary {summary} should contain {key}");
+            }
+        }
+    }
+
+    fn round_trip(notification: &Notification) -> Notification {
+        let bytes = notification.try_to_vec().unwrap();
+        let decoded = Notification::try_from_slice(&bytes).unwrap();
+        assert_eq!(bytes, decoded.try_to_vec().unwrap(), "re-encoding the decoded {} should yield the same bytes", notification);
+        decoded
+    }
+
+    #[test]
+    fn test_borsh_round_trip() {
+        let hashes = |words: std::ops::Range<u64>| Arc::new(words.map(Hash::from_u64_word).collect::<Vec<_>>());
+        let scripts = get_3_addresses(false).into_iter().map(|x| UtxoAddress::from(x).to_script_public_key()).collect::<Vec<_>>();
+        let acceptance_data = Arc::new(vec![Arc::new(vec![MergesetBlockAcceptanceData {
+            block_hash: Hash::from_u64_word(7),
+            accepted_transactions: vec![AcceptedTxEntry { transaction_id: Hash::from_u64_word(8), index_within_block: 3 }],
+        }])]);
+
+        let notifications = vec![
+            Notification::BlockAdded(BlockAddedNotification::new(Block::from_precomputed_hash(
+                Hash::from_u64_word(1),
+                vec![Hash::from_u64_word(2)],
+            ))),
+            Notification::VirtualChainChanged(VirtualChainChangedNotification::new(hashes(0..2), hashes(10..13), acceptance_data)),
+            Notification::FinalityConflict(FinalityConflictNotification::new(
+                Hash::from_u64_word(3),
+                Hash::from_u64_word(13),
+                1_000u64.into(),
+                FinalityConflictSeverity::Medium,
+            )),
+            Notification::FinalityConflictResolved(FinalityConflictResolvedNotification::new(Hash::from_u64_word(4))),
+            utxos_changed(&[1, 2, 3], &[4, 5], &scripts),
+            Notification::SinkBlueScoreChanged(SinkBlueScoreChangedNotification::new(5)),
+            Notification::VirtualDaaScoreChanged(VirtualDaaScoreChangedNotification::new(6)),
+            Notification::PruningPointUtxoSetOverride(PruningPointUtxoSetOverrideNotification::new()),
+            Notification::NewBlockTemplate(NewBlockTemplateNotification::new()),
+            Notification::MempoolSizeChanged(MempoolSizeChangedNotification::new(7, 8)),
+        ];
+
+        for notification in notifications.iter() {
+            let decoded = round_trip(notification);
+            assert_eq!(notification.event_type(), decoded.event_type());
+            match (notification, &decoded) {
+                (Notification::BlockAdded(a), Notification::BlockAdded(b)) => {
+                    assert_eq!(a.block.hash(), b.block.hash());
+                    assert_eq!(a.block.header.direct_parents(), b.block.header.direct_parents());
+                }
+                (Notification::VirtualChainChanged(a), Notification::VirtualChainChanged(b)) => {
+                    assert_eq!(a.added_chain_block_hashes, b.added_chain_block_hashes);
+                    assert_eq!(a.removed_chain_block_hashes, b.removed_chain_block_hashes);
+                    assert_eq!(a.reorg_depth, b.reorg_depth);
+                    assert_eq!(b.added_chain_blocks_acceptance_data[0][0].accepted_transactions[0].index_within_block, 3);
+                }
+                (Notification::UtxosChanged(a), Notification::UtxosChanged(b)) => {
+                    assert_eq!(a.accumulated_utxo_diff, b.accumulated_utxo_diff);
+                    assert_eq!(a.virtual_parents, b.virtual_parents);
+                }
+                (Notification::SinkBlueScoreChanged(a), Notification::SinkBlueScoreChanged(b)) => {
+                    assert_eq!(a.sink_blue_score, b.sink_blue_score)
+                }
+                (Notification::VirtualDaaScoreChanged(a), Notification::VirtualDaaScoreChanged(b)) => {
+                    assert_eq!(a.virtual_daa_score, b.virtual_daa_score)
+                }
+                (Notification::MempoolSizeChanged(a), Notification::MempoolSizeChanged(b)) => {
+                    assert_eq!((a.transaction_count, a.total_mass), (b.transaction_count, b.total_mass))
+                }
+                _ => {}
+            }
+        }
+    }
+}
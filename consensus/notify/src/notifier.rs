@@ -1,4 +1,4 @@
-use crate::notification::Notification;
-use kaspa_notify::{connection::ChannelConnection, notifier::Notifier};
+use crate::{connection::CoalescingChannelConnection, notification::Notification};
+use kaspa_notify::notifier::Notifier;
 
-pub type ConsensusNotifier = Notifier<Notification, ChannelConnection<Notification>>;
+pub type ConsensusNotifier = Notifier<Notification, CoalescingChannelConnection>;
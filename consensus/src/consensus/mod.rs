@@ -273,7 +273,8 @@ impl Consensus {
             params.genesis.bits,
             params.difficulty_window_size,
             params.target_time_per_block,
-        );
+        )
+        .with_min_window_size(params.min_difficulty_window_size);
         let depth_manager = BlockDepthManager::new(
             params.merge_depth,
             params.finality_depth,
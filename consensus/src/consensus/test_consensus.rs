@@ -100,7 +100,7 @@ impl TestConsensus {
             .consensus
             .difficulty_manager
             .calc_daa_score_and_non_daa_mergeset_blocks(&mut window.iter().map(|item| item.0.hash), &ghostdag_data);
-        header.bits = self.consensus.difficulty_manager.calculate_difficulty_bits(&window);
+        header.bits = self.consensus.difficulty_manager.calculate_difficulty_bits(&window).unwrap();
         header.daa_score = daa_score;
         header.timestamp = self.consensus.past_median_time_manager.calc_past_median_time(&ghostdag_data).unwrap().0 + 1;
         header.blue_score = ghostdag_data.blue_score;
@@ -36,7 +36,7 @@ impl HeaderProcessor {
 
         ctx.mergeset_non_daa = Some(mergeset_non_daa);
 
-        let expected_bits = self.difficulty_manager.calculate_difficulty_bits(&window);
+        let expected_bits = self.difficulty_manager.calculate_difficulty_bits(&window).map_err(RuleError::BadDifficultyCalculation)?;
         if header.bits != expected_bits {
             return Err(RuleError::UnexpectedDifficulty(header.bits, expected_bits));
         }
@@ -62,8 +62,8 @@ use kaspa_consensus_core::{
 };
 use kaspa_consensus_notify::{
     notification::{
-        Notification, SinkBlueScoreChangedNotification, UtxosChangedNotification, VirtualChainChangedNotification,
-        VirtualDaaScoreChangedNotification,
+        FinalityConflictNotification, Notification, SinkBlueScoreChangedNotification, UtxosChangedNotification,
+        VirtualChainChangedNotification, VirtualDaaScoreChangedNotification,
     },
     root::ConsensusNotificationRoot,
 };
@@ -451,7 +451,7 @@ impl VirtualStateProcessor {
         let (virtual_daa_score, mergeset_non_daa) = self
             .difficulty_manager
             .calc_daa_score_and_non_daa_mergeset_blocks(&mut window.iter().map(|item| item.0.hash), &virtual_ghostdag_data);
-        let virtual_bits = self.difficulty_manager.calculate_difficulty_bits(&window);
+        let virtual_bits = self.difficulty_manager.calculate_difficulty_bits(&window).map_err(RuleError::BadDifficultyCalculation)?;
         let virtual_past_median_time = self.past_median_time_manager.calc_past_median_time(&virtual_ghostdag_data)?.0;
 
         // Calc virtual UTXO state relative to selected parent
@@ -530,7 +530,7 @@ impl VirtualStateProcessor {
         // since we check that every pushed block is not in the past of current heap
         // (and it can't be in the future by induction)
         loop {
-            let candidate = heap.pop().expect("valid sink must exist").hash;
+            let SortableBlock { hash: candidate, blue_work: candidate_blue_work } = heap.pop().expect("valid sink must exist");
             if self.reachability_service.is_chain_ancestor_of(finality_point, candidate) {
                 diff_point = self.calculate_utxo_state_relatively(stores, diff, diff_point, candidate);
                 if diff_point == candidate {
@@ -542,6 +542,16 @@ impl VirtualStateProcessor {
             } else if finality_point != pruning_point {
                 // `finality_point == pruning_point` indicates we are at IBD start hence no warning required
                 warn!("Finality Violation Detected. Block {} violates finality and is ignored from Virtual chain.", candidate);
+                // The previous sink is the tip of the selected chain the violating block competes with
+                let notification = FinalityConflictNotification::assess(
+                    candidate,
+                    candidate_blue_work,
+                    prev_sink,
+                    self.ghostdag_store.get_blue_work(prev_sink).unwrap(),
+                    self.ghostdag_store.get_blue_work(finality_point).unwrap(),
+                );
+                // TODO: handle notify return error
+                let _ = self.notification_root.notify(Notification::FinalityConflict(notification));
             }
             for parent in self.relations_service.get_parents(candidate).unwrap().iter().copied() {
                 if !self.reachability_service.is_dag_ancestor_of_any(parent, &mut heap.iter().map(|sb| sb.hash)) {
@@ -1,24 +1,28 @@
-use crate::model::stores::{block_window_cache::BlockWindowHeap, ghostdag::GhostdagData, headers::HeaderStoreReader};
+use crate::{
+    model::stores::{block_window_cache::BlockWindowHeap, ghostdag::GhostdagData, headers::HeaderStoreReader},
+    params::Params,
+};
 use kaspa_consensus_core::{
     errors::difficulty::{DifficultyError, DifficultyResult},
     BlockHashSet, BlueWorkType,
 };
 use kaspa_hashes::Hash;
 use kaspa_math::{Uint256, Uint320};
-use std::{
-    cmp::{max, Ordering},
-    sync::Arc,
-};
+use std::{cmp::Ordering, sync::Arc};
 
 use super::ghostdag::ordering::SortableBlock;
 use itertools::Itertools;
 
+/// Default minimal window size required for estimating the network hashrate, matching mainnet behavior
+pub const DEFAULT_MIN_WINDOW_SIZE: usize = 1000;
+
 #[derive(Clone)]
 pub struct DifficultyManager<T: HeaderStoreReader> {
     headers_store: Arc<T>,
     genesis_bits: u32,
     difficulty_adjustment_window_size: usize,
     target_time_per_block: u64,
+    min_window_size: usize,
 }
 
 impl<T: HeaderStoreReader> DifficultyManager<T> {
@@ -28,7 +32,24 @@ impl<T: HeaderStoreReader> DifficultyManager<T> {
         difficulty_adjustment_window_size: usize,
         target_time_per_block: u64,
     ) -> Self {
-        Self { headers_store, difficulty_adjustment_window_size, genesis_bits, target_time_per_block }
+        Self {
+            headers_store,
+            difficulty_adjustment_window_size,
+            genesis_bits,
+            target_time_per_block,
+            min_window_size: DEFAULT_MIN_WINDOW_SIZE,
+        }
+    }
+
+    /// Overrides the minimal window size accepted by [`Self::estimate_network_hashes_per_second`],
+    /// allowing networks such as devnet or simnet to work with shorter windows.
+    pub fn with_min_window_size(mut self, min_window_size: usize) -> Self {
+        self.min_window_size = min_window_size;
+        self
+    }
+
+    pub fn min_window_size(&self) -> usize {
+        self.min_window_size
     }
 
     pub fn calc_daa_score_and_non_daa_mergeset_blocks(
@@ -54,12 +75,12 @@ impl<T: HeaderStoreReader> DifficultyManager<T> {
             .collect()
     }
 
-    pub fn calculate_difficulty_bits(&self, window: &BlockWindowHeap) -> u32 {
+    pub fn calculate_difficulty_bits(&self, window: &BlockWindowHeap) -> DifficultyResult<u32> {
         let mut difficulty_blocks = self.get_difficulty_blocks(window);
 
         // Until there are enough blocks for a full block window the difficulty should remain constant.
         if difficulty_blocks.len() < self.difficulty_adjustment_window_size {
-            return self.genesis_bits;
+            return Ok(self.genesis_bits);
         }
 
         let (min_ts_index, max_ts_index) = difficulty_blocks.iter().position_minmax().into_option().unwrap();
@@ -70,22 +91,14 @@ impl<T: HeaderStoreReader> DifficultyManager<T> {
         // We remove the minimal block because we want the average target for the internal window.
         difficulty_blocks.swap_remove(min_ts_index);
 
-        // We need Uint320 to avoid overflow when summing and multiplying by the window size.
-        // TODO: Try to see if we can use U256 instead, by modifying the algorithm.
-        let difficulty_blocks_len = difficulty_blocks.len();
-        let targets_sum: Uint320 =
-            difficulty_blocks.into_iter().map(|diff_block| Uint320::from(Uint256::from_compact_target_bits(diff_block.bits))).sum();
-        let average_target = targets_sum / (difficulty_blocks_len as u64);
-        let new_target = average_target * max(max_ts - min_ts, 1) / self.target_time_per_block / difficulty_blocks_len as u64;
-        Uint256::try_from(new_target).expect("Expected target should be less than 2^256").compact_target_bits()
+        let targets = difficulty_blocks.into_iter().map(|diff_block| Uint256::from_compact_target_bits(diff_block.bits)).collect_vec();
+        Ok(calc_new_target(&targets, min_ts, max_ts, self.target_time_per_block)?.compact_target_bits())
     }
 
     pub fn estimate_network_hashes_per_second(&self, window: &BlockWindowHeap) -> DifficultyResult<u64> {
-        // TODO: perhaps move this const
-        const MIN_WINDOW_SIZE: usize = 1000;
         let window_size = window.len();
-        if window_size < MIN_WINDOW_SIZE {
-            return Err(DifficultyError::UnderMinWindowSizeAllowed(window_size, MIN_WINDOW_SIZE));
+        if window_size < self.min_window_size {
+            return Err(DifficultyError::UnderMinWindowSizeAllowed(window_size, self.min_window_size));
         }
         // return 0 if no blocks had been mined yet
         if window.is_empty() {
@@ -94,10 +107,7 @@ impl<T: HeaderStoreReader> DifficultyManager<T> {
 
         let difficulty_blocks = self.get_difficulty_blocks(window);
         let (min_ts, max_ts) = difficulty_blocks.iter().map(|x| x.timestamp).minmax().into_option().unwrap();
-        if min_ts == max_ts {
-            return Err(DifficultyError::EmptyTimestampRange);
-        }
-        let window_duration = (max_ts - min_ts) / 1000; // Divided by 1000 to convert milliseconds to seconds
+        let window_duration = timestamps_span(min_ts, max_ts)? / 1000; // Divided by 1000 to convert milliseconds to seconds
         if window_duration == 0 {
             return Ok(0);
         }
@@ -109,6 +119,71 @@ impl<T: HeaderStoreReader> DifficultyManager<T> {
     }
 }
 
+/// Computes the target required by a full difficulty window, as done by consensus for a new block.
+///
+/// `window` lists the `(timestamp, target)` pairs of the window blocks in ascending blue work order, which resolves
+/// ties between equal minimal timestamps the same way consensus does. Consensus stores the compact form of the
+/// returned target, see [`Uint256::compact_target_bits`].
+///
+/// Returns [`DifficultyError::EmptyWindow`] for an empty window and [`DifficultyError::UnderMinWindowSizeAllowed`]
+/// for a window shorter than `params.difficulty_window_size`, for which consensus keeps the genesis difficulty instead.
+pub fn calculate_difficulty(window: &[(u64, Uint256)], params: &Params) -> DifficultyResult<Uint256> {
+    if window.is_empty() {
+        return Err(DifficultyError::EmptyWindow);
+    }
+    // At least two blocks are required since the block with the minimal timestamp is left out of the average target
+    let min_window_size = params.difficulty_window_size.max(2);
+    if window.len() < min_window_size {
+        return Err(DifficultyError::UnderMinWindowSizeAllowed(window.len(), min_window_size));
+    }
+
+    let (min_ts_index, max_ts_index) = window.iter().map(|(timestamp, _)| timestamp).position_minmax().into_option().unwrap();
+    let (min_ts, max_ts) = (window[min_ts_index].0, window[max_ts_index].0);
+    let targets = window.iter().enumerate().filter(|(i, _)| *i != min_ts_index).map(|(_, (_, target))| target);
+    calc_new_target(targets, min_ts, max_ts, params.target_time_per_block)
+}
+
+/// Returns the span between the min and max window timestamps, rejecting empty and reversed ranges.
+fn timestamps_span(min_ts: u64, max_ts: u64) -> DifficultyResult<u64> {
+    match max_ts.cmp(&min_ts) {
+        Ordering::Greater => Ok(max_ts - min_ts),
+        Ordering::Equal => Err(DifficultyError::EmptyTimestampRange),
+        Ordering::Less => Err(DifficultyError::NonMonotonicTimestamps { min: min_ts, max: max_ts }),
+    }
+}
+
+/// Computes the new target from the targets of the window blocks and the window timestamps span.
+///
+/// Corrupt or adversarial headers may drive the intermediate values beyond their bounds,
+/// in which case [`DifficultyError::TargetOverflow`] is returned.
+fn calc_new_target<'a>(
+    targets: impl IntoIterator<Item = &'a Uint256>,
+    min_ts: u64,
+    max_ts: u64,
+    target_time_per_block: u64,
+) -> DifficultyResult<Uint256> {
+    // We need Uint320 to avoid overflow when summing and multiplying by the window size.
+    // TODO: Try to see if we can use U256 instead, by modifying the algorithm.
+    let (targets_sum, targets_len) = targets.into_iter().try_fold((Uint320::ZERO, 0u64), |(sum, len), target| {
+        match sum.overflowing_add(Uint320::from(*target)) {
+            (sum, false) => Ok((sum, len + 1)),
+            (_, true) => Err(DifficultyError::TargetOverflow),
+        }
+    })?;
+    let average_target = targets_sum / targets_len;
+    // An empty range is clamped to a span of 1 so that identical timestamps still produce a target
+    let span = match timestamps_span(min_ts, max_ts) {
+        Err(DifficultyError::EmptyTimestampRange) => 1,
+        span => span?,
+    };
+    let (scaled_target, overflow) = average_target.overflowing_mul_u64(span);
+    if overflow {
+        return Err(DifficultyError::TargetOverflow);
+    }
+    let new_target = scaled_target / target_time_per_block / targets_len;
+    Uint256::try_from(new_target).map_err(|_| DifficultyError::TargetOverflow)
+}
+
 pub fn calc_work(bits: u32) -> BlueWorkType {
     let target = Uint256::from_compact_target_bits(bits);
     // Source: https://github.com/bitcoin/bitcoin/blob/2e34374bf3e12b37b0c66824a6c998073cdfab01/src/chain.cpp#L131
@@ -146,3 +221,149 @@ impl Ord for DifficultyBlock {
         self.timestamp.cmp(&other.timestamp).then_with(|| self.sortable_block.cmp(&other.sortable_block))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        model::stores::headers::{DbHeadersStore, HeaderStore},
+        params::MAINNET_PARAMS,
+    };
+    use kaspa_consensus_core::header::Header;
+    use kaspa_database::utils::create_temp_db;
+    use std::cmp::Reverse;
+
+    #[test]
+    fn test_calc_new_target() {
+        let target = Uint256::from_compact_target_bits(0x1e7fffff);
+        let targets = vec![target; 10];
+        // A window spanning exactly the expected time keeps the target unchanged
+        assert_eq!(calc_new_target(&targets, 0, 10_000, 1000).unwrap(), target);
+        // A window spanning twice the expected time doubles the target
+        assert_eq!(calc_new_target(&targets, 0, 20_000, 1000).unwrap(), target * 2u64);
+    }
+
+    #[test]
+    fn test_calc_new_target_overflow() {
+        // Engineered to exceed 2^256: a near maximal target scaled by a huge timestamps span
+        let targets = vec![Uint256::MAX; 3];
+        assert!(matches!(calc_new_target(&targets, 0, u64::MAX, 1), Err(DifficultyError::TargetOverflow)));
+        assert!(matches!(calc_new_target(&targets[..1], 1, u64::MAX, 2), Err(DifficultyError::TargetOverflow)));
+
+        // The same targets over a normal span do not overflow
+        assert!(calc_new_target(&targets, 0, 1000, 1000).is_ok());
+    }
+
+    #[test]
+    fn test_non_monotonic_timestamps() {
+        assert_eq!(timestamps_span(1000, 3000).unwrap(), 2000);
+        assert!(matches!(timestamps_span(3000, 3000), Err(DifficultyError::EmptyTimestampRange)));
+        assert!(matches!(timestamps_span(3000, 1000), Err(DifficultyError::NonMonotonicTimestamps { min: 3000, max: 1000 })));
+
+        let targets = vec![Uint256::from_compact_target_bits(0x1e7fffff); 10];
+        assert!(matches!(
+            calc_new_target(&targets, 3000, 1000, 1000),
+            Err(DifficultyError::NonMonotonicTimestamps { min: 3000, max: 1000 })
+        ));
+        // Equal timestamps are still accepted by the target calculation
+        assert!(calc_new_target(&targets, 3000, 3000, 1000).is_ok());
+    }
+
+    #[test]
+    fn test_calculate_difficulty() {
+        let params = Params { difficulty_window_size: 10, target_time_per_block: 1000, ..MAINNET_PARAMS };
+        let target = Uint256::from_compact_target_bits(0x1e7fffff);
+        let window = |block_interval: u64| (0..10u64).map(|i| (i * block_interval, target)).collect_vec();
+
+        // Blocks mined at the expected rate keep the target unchanged
+        assert_eq!(calculate_difficulty(&window(1000), &params).unwrap(), target);
+        // Blocks mined twice slower double the target
+        assert_eq!(calculate_difficulty(&window(2000), &params).unwrap(), target * 2u64);
+        // Blocks mined twice faster halve the target
+        assert_eq!(calculate_difficulty(&window(500), &params).unwrap(), target / 2u64);
+
+        // The target of the block with the minimal timestamp is left out of the average, wherever it is in the window
+        let mut shuffled = window(1000);
+        shuffled.swap(0, 5);
+        shuffled[5].1 = Uint256::MAX;
+        assert_eq!(calculate_difficulty(&shuffled, &params).unwrap(), target);
+    }
+
+    #[test]
+    fn test_calculate_difficulty_matches_consensus() {
+        const WINDOW_SIZE: usize = 10;
+        let params = Params { difficulty_window_size: WINDOW_SIZE, target_time_per_block: 1000, ..MAINNET_PARAMS };
+        let (_lifetime, db) = create_temp_db();
+        let headers_store = Arc::new(DbHeadersStore::new(db, 100));
+
+        // Irregular timestamps and bits, the minimal timestamp being neither the first nor the last of the window
+        let timestamps = [3_100, 4_000, 1_200, 5_900, 6_300, 8_800, 7_700, 10_400, 9_950, 12_600];
+        let bits =
+            [0x1e7fffff, 0x1e7a0000, 0x1e700000, 0x1e6fffff, 0x1e65a000, 0x1e600000, 0x1e5fffff, 0x1e5a0000, 0x1e550000, 0x1e500000];
+        let mut window = BlockWindowHeap::new();
+        let mut pairs = vec![];
+        for (i, (timestamp, bits)) in timestamps.into_iter().zip(bits).enumerate() {
+            let hash = Hash::from_u64_word(i as u64 + 1);
+            let mut header = Header::from_precomputed_hash(hash, vec![]);
+            header.timestamp = timestamp;
+            header.bits = bits;
+            headers_store.insert(hash, Arc::new(header), 0).unwrap();
+            window.push(Reverse(SortableBlock::new(hash, BlueWorkType::from_u64(i as u64 * 100))));
+            pairs.push((timestamp, Uint256::from_compact_target_bits(bits)));
+        }
+
+        let manager = DifficultyManager::new(headers_store, 0x1e7fffff, WINDOW_SIZE, params.target_time_per_block);
+        let consensus_bits = manager.calculate_difficulty_bits(&window).unwrap();
+        assert_ne!(consensus_bits, 0x1e7fffff, "a full window should yield a difficulty of its own");
+        assert_eq!(calculate_difficulty(&pairs, &params).unwrap().compact_target_bits(), consensus_bits);
+    }
+
+    #[test]
+    fn test_calculate_difficulty_errors() {
+        let params = Params { difficulty_window_size: 10, target_time_per_block: 1000, ..MAINNET_PARAMS };
+        let target = Uint256::from_compact_target_bits(0x1e7fffff);
+        let window = (0..9u64).map(|i| (i * 1000, target)).collect_vec();
+        assert!(matches!(calculate_difficulty(&window, &params), Err(DifficultyError::UnderMinWindowSizeAllowed(9, 10))));
+        assert!(matches!(calculate_difficulty(&[], &params), Err(DifficultyError::EmptyWindow)));
+
+        // A single block never makes a window, whatever the configured size
+        let params = Params { difficulty_window_size: 0, ..params };
+        assert!(matches!(calculate_difficulty(&window[..1], &params), Err(DifficultyError::UnderMinWindowSizeAllowed(1, 2))));
+    }
+
+    #[test]
+    fn test_custom_min_window_size() {
+        const MIN_WINDOW_SIZE: usize = 5;
+        let (_lifetime, db) = create_temp_db();
+        let headers_store = Arc::new(DbHeadersStore::new(db, 100));
+        let windows = (1..=MIN_WINDOW_SIZE as u64)
+            .scan(BlockWindowHeap::new(), |window, i| {
+                let hash = Hash::from_u64_word(i);
+                let mut header = Header::from_precomputed_hash(hash, vec![]);
+                header.timestamp = i * 1000;
+                header.bits = 0x1e7fffff;
+                headers_store.insert(hash, Arc::new(header), 0).unwrap();
+                window.push(Reverse(SortableBlock::new(hash, BlueWorkType::from_u64(i * 100))));
+                Some(window.clone())
+            })
+            .collect_vec();
+        let (short_window, full_window) = (&windows[MIN_WINDOW_SIZE - 2], &windows[MIN_WINDOW_SIZE - 1]);
+
+        // The default minimum matches mainnet
+        let manager = DifficultyManager::new(headers_store.clone(), 0x1e7fffff, 10, 1000);
+        assert_eq!(manager.min_window_size(), DEFAULT_MIN_WINDOW_SIZE);
+        assert_eq!(MAINNET_PARAMS.min_difficulty_window_size, DEFAULT_MIN_WINDOW_SIZE);
+        assert!(matches!(
+            manager.estimate_network_hashes_per_second(full_window),
+            Err(DifficultyError::UnderMinWindowSizeAllowed(MIN_WINDOW_SIZE, DEFAULT_MIN_WINDOW_SIZE))
+        ));
+
+        let manager = manager.with_min_window_size(MIN_WINDOW_SIZE);
+        assert!(matches!(
+            manager.estimate_network_hashes_per_second(short_window),
+            Err(DifficultyError::UnderMinWindowSizeAllowed(size, MIN_WINDOW_SIZE)) if size == MIN_WINDOW_SIZE - 1
+        ));
+        // 400 blue work over a window of 4 seconds
+        assert_eq!(manager.estimate_network_hashes_per_second(full_window).unwrap(), 100);
+    }
+}
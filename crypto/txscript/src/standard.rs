@@ -1,5 +1,5 @@
 use crate::{
-    opcodes::codes::{OpBlake2b, OpCheckSig, OpCheckSigECDSA, OpData32, OpData33, OpEqual},
+    opcodes::codes::{OpBlake2b, OpCheckMultiSig, OpCheckSig, OpCheckSigECDSA, OpData32, OpData33, OpEqual},
     script_builder::{ScriptBuilder, ScriptBuilderResult},
     script_class::ScriptClass,
 };
@@ -49,6 +49,19 @@ pub fn pay_to_script_hash_script(redeem_script: &[u8]) -> ScriptPublicKey {
     ScriptPublicKey::new(ScriptClass::ScriptHash.version(), script)
 }
 
+/// Creates a redeem script requiring `required` signatures out of the given schnorr public keys.
+/// The keys are used in the order they are provided.
+pub fn multisig_redeem_script(pub_keys: &[[u8; 32]], required: usize) -> ScriptBuilderResult<Vec<u8>> {
+    let mut builder = ScriptBuilder::new();
+    builder.add_i64(required as i64)?;
+    for pub_key in pub_keys {
+        builder.add_data(pub_key)?;
+    }
+    builder.add_i64(pub_keys.len() as i64)?;
+    builder.add_op(OpCheckMultiSig)?;
+    Ok(builder.drain())
+}
+
 /// Generates a signature script that fits a pay-to-script-hash script
 pub fn pay_to_script_hash_signature_script(redeem_script: Vec<u8>, signature: Vec<u8>) -> ScriptBuilderResult<Vec<u8>> {
     let redeem_script_as_data = ScriptBuilder::new().add_data(&redeem_script)?.drain();
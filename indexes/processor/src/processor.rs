@@ -242,7 +242,7 @@ mod tests {
     #[tokio::test]
     async fn test_pruning_point_utxo_set_override_notification() {
         let pipeline = NotifyPipeline::new();
-        let test_notification = consensus_notification::PruningPointUtxoSetOverrideNotification {};
+        let test_notification = consensus_notification::PruningPointUtxoSetOverrideNotification::new();
         pipeline
             .consensus_sender
             .send(ConsensusNotification::PruningPointUtxoSetOverride(test_notification.clone()))
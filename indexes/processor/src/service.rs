@@ -1,6 +1,6 @@
 use crate::{processor::Processor, IDENT};
 use kaspa_consensus_notify::{
-    connection::ConsensusChannelConnection, notification::Notification as ConsensusNotification, notifier::ConsensusNotifier,
+    connection::CoalescingChannelConnection, notification::Notification as ConsensusNotification, notifier::ConsensusNotifier,
 };
 use kaspa_core::{
     task::service::{AsyncService, AsyncServiceError, AsyncServiceFuture},
@@ -27,8 +27,8 @@ impl IndexService {
     pub fn new(consensus_notifier: &Arc<ConsensusNotifier>, utxoindex: DynUtxoIndexApi) -> Self {
         // Prepare consensus-notify objects
         let consensus_notify_channel = Channel::<ConsensusNotification>::default();
-        let consensus_notify_listener_id =
-            consensus_notifier.register_new_listener(ConsensusChannelConnection::new(consensus_notify_channel.sender()));
+        let consensus_notify_listener_id = consensus_notifier
+            .register_new_listener(CoalescingChannelConnection::without_coalescing(consensus_notify_channel.sender()));
 
         // Prepare the index-processor notifier
         // No subscriber is defined here because the subscription are manually created during the construction and never changed after that.
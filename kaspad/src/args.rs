@@ -11,6 +11,7 @@ pub struct Defaults {
     pub rpclisten_borsh: &'static str,
     pub rpclisten_json: &'static str,
     pub unsafe_rpc: bool,
+    pub coalesce_score_notifications: bool,
     pub async_threads: usize,
     pub utxoindex: bool,
     pub reset_db: bool,
@@ -29,6 +30,7 @@ impl Default for Defaults {
             rpclisten_borsh: "127.0.0.1:17110",
             rpclisten_json: "127.0.0.1:18110",
             unsafe_rpc: false,
+            coalesce_score_notifications: false,
             async_threads: num_cpus::get(),
             utxoindex: false,
             reset_db: false,
@@ -51,6 +53,7 @@ pub struct Args {
     pub rpclisten_borsh: Option<ContextualNetAddress>,
     pub rpclisten_json: Option<ContextualNetAddress>,
     pub unsafe_rpc: bool,
+    pub coalesce_score_notifications: bool,
     pub wrpc_verbose: bool,
     pub log_level: String,
     pub async_threads: usize,
@@ -122,6 +125,7 @@ pub fn cli(defaults: &Defaults) -> Command {
                 .help(format!("Interface:port to listen for wRPC JSON connections (default: {}).", defaults.rpclisten_json)),
         )
         .arg(arg!(--unsaferpc "Enable RPC commands which affect the state of the node"))
+        .arg(arg!(--coalescescores "Only relay the newest of the queued DAA score, blue score and mempool size notifications"))
         .arg(
             Arg::new("connect-peers")
                 .long("connect")
@@ -189,6 +193,10 @@ impl Args {
             rpclisten_borsh: m.get_one::<ContextualNetAddress>("rpclisten-borsh").cloned(),
             rpclisten_json: m.get_one::<ContextualNetAddress>("rpclisten-json").cloned(),
             unsafe_rpc: m.get_one::<bool>("unsaferpc").cloned().unwrap_or(defaults.unsafe_rpc),
+            coalesce_score_notifications: m
+                .get_one::<bool>("coalescescores")
+                .cloned()
+                .unwrap_or(defaults.coalesce_score_notifications),
             wrpc_verbose: false,
             log_level: m.get_one::<String>("log_level").cloned().unwrap(),
             async_threads: m.get_one::<usize>("async_threads").cloned().unwrap_or(defaults.async_threads),
@@ -209,6 +217,7 @@ impl Args {
     pub fn apply_to_config(&self, config: &mut Config) {
         config.utxoindex = self.utxoindex;
         config.unsafe_rpc = self.unsafe_rpc;
+        config.coalesce_score_notifications = self.coalesce_score_notifications;
         config.user_agent_comments = self.user_agent_comments.clone();
     }
 }
@@ -184,6 +184,15 @@ impl MiningManager {
         self.mempool.read().transaction_count(include_transaction_pool, include_orphan_pool)
     }
 
+    /// Returns the number of transactions held in the selected pools and the sum of their mass, read under a same lock.
+    pub fn transaction_count_and_mass(&self, include_transaction_pool: bool, include_orphan_pool: bool) -> (usize, u64) {
+        let mempool = self.mempool.read();
+        (
+            mempool.transaction_count(include_transaction_pool, include_orphan_pool),
+            mempool.transactions_mass(include_transaction_pool, include_orphan_pool),
+        )
+    }
+
     pub fn handle_new_block_transactions(
         &self,
         consensus: &dyn ConsensusApi,
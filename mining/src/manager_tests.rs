@@ -346,6 +346,8 @@ mod tests {
         for child in child_txs.iter() {
             assert!(contained_by_mtxs(child.id(), &orphans), "child transaction {} should exist in the orphan pool", child.id());
         }
+        assert_eq!(mining_manager.transaction_count_and_mass(false, true), (orphans.len(), total_mass(&orphans)));
+        assert_eq!(mining_manager.transaction_count_and_mass(true, false), (0, 0));
 
         // Try to build a block template.
         // It is expected to only contain a coinbase transaction since all children are orphans.
@@ -417,6 +419,11 @@ mod tests {
         for child in child_txs.iter().take(SKIPPED_TXS) {
             assert!(contained_by_mtxs(child.id(), &orphans), "child transaction {} should exist in the orphan pool", child.id());
         }
+        assert_eq!(
+            mining_manager.transaction_count_and_mass(true, true),
+            (populated_txs.len() + orphans.len(), total_mass(&populated_txs) + total_mass(&orphans)),
+            "the total mass of the mempool should follow the transactions moved from the orphan pool to the transaction pool"
+        );
 
         // Build a new block template with all ready transactions, meaning all child transactions but one.
         // Note that the call to get_block_template will actually build a new block template and not use the
@@ -910,6 +917,10 @@ mod tests {
         transactions.iter().any(|x| x.id() == transaction_id)
     }
 
+    fn total_mass(transactions: &[MutableTransaction]) -> u64 {
+        transactions.iter().map(|x| x.calculated_mass.unwrap_or_default()).sum()
+    }
+
     fn contained_by_txs(transaction_id: TransactionId, transactions: &[Transaction]) -> bool {
         transactions.iter().any(|x| x.id() == transaction_id)
     }
@@ -119,6 +119,17 @@ impl Mempool {
         count
     }
 
+    pub(crate) fn transactions_mass(&self, include_transaction_pool: bool, include_orphan_pool: bool) -> u64 {
+        let mut mass = 0;
+        if include_transaction_pool {
+            mass += self.transaction_pool.total_mass()
+        }
+        if include_orphan_pool {
+            mass += self.orphan_pool.total_mass()
+        }
+        mass
+    }
+
     pub(crate) fn block_candidate_transactions(&self) -> Vec<CandidateTransaction> {
         self.transaction_pool.all_ready_transactions()
     }
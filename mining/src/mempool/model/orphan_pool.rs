@@ -36,6 +36,8 @@ use super::pool::TransactionsEdges;
 pub(crate) struct OrphanPool {
     config: Arc<Config>,
     all_orphans: MempoolTransactionCollection,
+    /// Sum of the calculated mass of the orphans in store
+    total_mass: u64,
     /// Transactions dependencies formed by outputs present in pool - successor relations.
     chained_orphans: TransactionsEdges,
     outpoint_owner_id: OutpointIndex,
@@ -47,6 +49,7 @@ impl OrphanPool {
         Self {
             config,
             all_orphans: MempoolTransactionCollection::default(),
+            total_mass: 0,
             chained_orphans: TransactionsEdges::default(),
             outpoint_owner_id: OutpointIndex::default(),
             last_expire_scan: 0,
@@ -153,6 +156,7 @@ impl OrphanPool {
             }
         }
 
+        self.total_mass += transaction.mtx.calculated_mass.unwrap_or_default();
         self.all_orphans.insert(id, transaction);
         Ok(())
     }
@@ -180,6 +184,7 @@ impl OrphanPool {
 
     fn remove_single_orphan(&mut self, transaction_id: &TransactionId) -> RuleResult<MempoolTransaction> {
         if let Some(transaction) = self.all_orphans.remove(transaction_id) {
+            self.total_mass -= transaction.mtx.calculated_mass.unwrap_or_default();
             // Remove all chained_transaction relations...
             // ... incoming
             let parents = self.get_parent_transaction_ids_in_pool(&transaction.mtx);
@@ -283,6 +288,10 @@ impl Pool for OrphanPool {
         &mut self.all_orphans
     }
 
+    fn total_mass(&self) -> u64 {
+        self.total_mass
+    }
+
     fn chained(&self) -> &TransactionsEdges {
         &self.chained_orphans
     }
@@ -33,6 +33,9 @@ pub(crate) trait Pool {
         self.all().len()
     }
 
+    /// Returns the sum of the calculated mass of all transactions in the pool, kept up to date on insertion and removal
+    fn total_mass(&self) -> u64;
+
     /// Returns an index over either high or low priority transaction ids which can
     /// in turn be topologically ordered.
     fn index(&self, priority: Priority) -> PoolIndex {
@@ -47,6 +47,8 @@ pub(crate) struct TransactionsPool {
 
     /// Store of transactions
     all_transactions: MempoolTransactionCollection,
+    /// Sum of the calculated mass of the transactions in store
+    total_mass: u64,
     /// Transactions dependencies formed by inputs present in pool - ancestor relations.
     parent_transactions: TransactionsEdges,
     /// Transactions dependencies formed by outputs present in pool - successor relations.
@@ -65,6 +67,7 @@ impl TransactionsPool {
         Self {
             config,
             all_transactions: MempoolTransactionCollection::default(),
+            total_mass: 0,
             parent_transactions: TransactionsEdges::default(),
             chained_transactions: TransactionsEdges::default(),
             last_expire_scan_daa_score: 0,
@@ -107,6 +110,7 @@ impl TransactionsPool {
         }
 
         self.utxo_set.add_transaction(&transaction.mtx);
+        self.total_mass += transaction.mtx.calculated_mass.unwrap_or_default();
         self.all_transactions.insert(id, transaction);
         Ok(())
     }
@@ -147,7 +151,9 @@ impl TransactionsPool {
         self.chained_transactions.remove(transaction_id);
 
         // Remove the transaction itself
-        self.all_transactions.remove(transaction_id).ok_or(RuleError::RejectMissingTransaction(*transaction_id))
+        let transaction = self.all_transactions.remove(transaction_id).ok_or(RuleError::RejectMissingTransaction(*transaction_id))?;
+        self.total_mass -= transaction.mtx.calculated_mass.unwrap_or_default();
+        Ok(transaction)
     }
 
     pub(crate) fn expire_low_priority_transactions(&mut self, virtual_daa_score: u64) -> RuleResult<()> {
@@ -291,6 +297,10 @@ impl Pool for TransactionsPool {
         &mut self.all_transactions
     }
 
+    fn total_mass(&self) -> u64 {
+        self.total_mass
+    }
+
     #[inline]
     fn chained(&self) -> &TransactionsEdges {
         &self.chained_transactions
@@ -0,0 +1,81 @@
+use async_channel::Receiver;
+use criterion::{criterion_group, criterion_main, Criterion};
+use kaspa_notify::{
+    events::EVENT_TYPE_ARRAY,
+    notification::test_helpers::{BlockAddedNotification, TestNotification},
+    notifier::{
+        test_helpers::{TestConnection, TestNotifier},
+        Notify,
+    },
+    scope::{BlockAddedScope, Scope},
+};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// A global allocator counting the allocations
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const LISTENER_COUNT: usize = 1000;
+
+/// Sends a `BlockAdded` notification and waits until every listener has received it
+fn broadcast(runtime: &tokio::runtime::Runtime, notifier: &TestNotifier, receivers: &[Receiver<TestNotification>]) {
+    notifier.notify(TestNotification::BlockAdded(BlockAddedNotification { data: 1 })).unwrap();
+    runtime.block_on(async {
+        for receiver in receivers.iter() {
+            receiver.recv().await.unwrap();
+        }
+    });
+}
+
+pub fn block_added_broadcast_benchmark(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let _guard = runtime.enter();
+    let notifier = Arc::new(TestNotifier::new(EVENT_TYPE_ARRAY[..].into(), vec![], vec![], 1, "bench"));
+    notifier.clone().start();
+    let receivers = (0..LISTENER_COUNT)
+        .map(|_| {
+            let (sender, receiver) = async_channel::unbounded();
+            let id = notifier.register_new_listener(TestConnection::new(sender));
+            notifier.try_start_notify(id, Scope::BlockAdded(BlockAddedScope::default())).unwrap();
+            receiver
+        })
+        .collect::<Vec<_>>();
+    // Let the broadcaster process the subscriptions
+    std::thread::sleep(Duration::from_millis(100));
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    broadcast(&runtime, &notifier, &receivers);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!("BlockAdded broadcast to {LISTENER_COUNT} listeners: {allocations} allocations");
+
+    c.bench_function(&format!("Notifier::notify BlockAdded to {LISTENER_COUNT} listeners"), |b| {
+        b.iter(|| broadcast(&runtime, &notifier, &receivers))
+    });
+
+    runtime.block_on(notifier.stop()).unwrap();
+}
+
+criterion_group!(benches, block_added_broadcast_benchmark);
+criterion_main!(benches);
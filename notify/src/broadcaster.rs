@@ -5,7 +5,7 @@ use super::{
     events::EventArray,
     listener::ListenerId,
     notification::Notification,
-    subscription::DynSubscription,
+    subscription::{single::OverallSubscription, DynSubscription},
 };
 use async_channel::{Receiver, Sender};
 use core::fmt::Debug;
@@ -18,14 +18,17 @@ use kaspa_core::trace;
 use std::{
     collections::HashMap,
     sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
     },
 };
 use workflow_core::channel::Channel;
 
 type ConnectionSet<T> = HashMap<ListenerId, T>;
 
+/// Count of notifications dropped because the connection of a listener could not accept them, by listener
+pub(crate) type DropCounters = Mutex<HashMap<ListenerId, u64>>;
+
 /// Broadcast plan
 #[derive(Deref)]
 struct Plan<C: Connection>(HashMap<DynSubscription, HashMap<C::Encoding, ConnectionSet<C>>>);
@@ -103,6 +106,11 @@ where
     ctl: Channel<Ctl<C>>,
     incoming: Receiver<N>,
     shutdown: Channel<()>,
+    drops: Arc<DropCounters>,
+    /// Reports the listeners found with a closed connection, so their owner can unregister them
+    closed_listeners: Sender<ListenerId>,
+    /// Maximum number of entries of a delivered notification, larger ones being paginated, zero disabling the pagination
+    page_size: Arc<AtomicUsize>,
     /// Sync channel, for handling of messages in predictable sequence; exclusively intended for tests.
     _sync: Option<Sender<()>>,
 }
@@ -112,7 +120,13 @@ where
     N: Notification,
     C: Connection<Notification = N>,
 {
-    pub fn new(name: &'static str, incoming: Receiver<N>) -> Self {
+    pub fn new(
+        name: &'static str,
+        incoming: Receiver<N>,
+        drops: Arc<DropCounters>,
+        closed_listeners: Sender<ListenerId>,
+        page_size: Arc<AtomicUsize>,
+    ) -> Self {
         Self {
             name,
             started: Arc::new(AtomicBool::default()),
@@ -120,11 +134,21 @@ where
             incoming,
             _sync: None,
             shutdown: Channel::oneshot(),
+            drops,
+            closed_listeners,
+            page_size,
         }
     }
 
     #[cfg(test)]
-    pub fn with_sync(name: &'static str, incoming: Receiver<N>, _sync: Option<Sender<()>>) -> Self {
+    pub fn with_sync(
+        name: &'static str,
+        incoming: Receiver<N>,
+        drops: Arc<DropCounters>,
+        closed_listeners: Sender<ListenerId>,
+        page_size: Arc<AtomicUsize>,
+        _sync: Option<Sender<()>>,
+    ) -> Self {
         Self {
             name,
             started: Arc::new(AtomicBool::default()),
@@ -132,6 +156,9 @@ where
             incoming,
             _sync,
             shutdown: Channel::oneshot(),
+            drops,
+            closed_listeners,
+            page_size,
         }
     }
 
@@ -174,14 +201,40 @@ where
                         if let Ok(notification) = notification {
                             // Broadcast the notification...
                             let event = notification.event_type();
+                            // Messages of the unfiltered notification by encoding, shared by all the overall subscriptions
+                            let mut shared_messages: HashMap<C::Encoding, C::Message> = HashMap::new();
                             for (subscription, encoding_set) in plan[event].iter() {
                                 // ... by subscription scope
-                                if let Some(applied_notification) = notification.apply_subscription(&**subscription) {
-                                    for (encoding, connection_set) in encoding_set.iter() {
-                                        // ... by message encoding
-                                        let message = C::into_message(&applied_notification, encoding);
-                                        for (id, connection) in connection_set.iter() {
-                                            // ... to listeners connections
+                                let unfiltered = matches!(
+                                    subscription.as_any().downcast_ref::<OverallSubscription>(),
+                                    Some(overall) if overall.min_blue_score().is_none()
+                                );
+                                let applied_notification = match unfiltered {
+                                    // An active overall subscription with no blue score floor lets the notification through unchanged
+                                    true => None,
+                                    false => match notification.apply_subscription(&**subscription) {
+                                        Some(applied_notification) => Some(applied_notification),
+                                        None => continue,
+                                    },
+                                };
+                                // A notification too large for a single message is delivered as a sequence of pages
+                                let pages = match self.page_size.load(Ordering::Relaxed) {
+                                    0 => None,
+                                    page_size => applied_notification.as_ref().unwrap_or(&notification).paginate(page_size),
+                                };
+                                for (encoding, connection_set) in encoding_set.iter() {
+                                    // ... by message encoding
+                                    let messages = match (&pages, &applied_notification) {
+                                        (Some(pages), _) => pages.iter().map(|page| C::into_message(page, encoding)).collect(),
+                                        (None, Some(applied_notification)) => vec![C::into_message(applied_notification, encoding)],
+                                        (None, None) => vec![shared_messages
+                                            .entry(encoding.clone())
+                                            .or_insert_with(|| C::into_message(&notification, encoding))
+                                            .clone()],
+                                    };
+                                    for (id, connection) in connection_set.iter() {
+                                        // ... to listeners connections
+                                        for message in messages.iter() {
                                             match connection.send(message.clone()) {
                                                 Ok(_) => {
                                                     trace!("[Broadcaster-{}] sent notification {notification} to listener {id}", self.name);
@@ -190,6 +243,10 @@ where
                                                     if connection.is_closed() {
                                                         trace!("[Broadcaster-{}] could not send a notification to listener {id} because its connection is closed - removing it", self.name);
                                                         purge.push(*id);
+                                                        break;
+                                                    } else {
+                                                        trace!("[Broadcaster-{}] dropped notification {notification} for listener {id}", self.name);
+                                                        *self.drops.lock().unwrap().entry(*id).or_default() += 1;
                                                     }
                                                 }
                                             }
@@ -197,8 +254,11 @@ where
                                     }
                                 }
                             }
-                            // Remove closed connections
-                            purge.drain(..).for_each(|id| { plan[event].remove(&id); });
+                            // Remove closed connections and report their listeners as dead
+                            purge.drain(..).for_each(|id| {
+                                plan[event].remove(&id);
+                                let _ = self.closed_listeners.try_send(id);
+                            });
 
                         }
                     }
@@ -268,7 +328,14 @@ mod tests {
         fn new(name: &'static str, listener_count: usize, steps: Vec<Step>) -> Self {
             let (sync_sender, sync_receiver) = unbounded();
             let (notification_sender, notification_receiver) = unbounded();
-            let broadcaster = Arc::new(TestBroadcaster::with_sync("test", notification_receiver, Some(sync_sender)));
+            let broadcaster = Arc::new(TestBroadcaster::with_sync(
+                "test",
+                notification_receiver,
+                Default::default(),
+                unbounded().0,
+                Default::default(),
+                Some(sync_sender),
+            ));
             let mut listeners = Vec::with_capacity(listener_count);
             let mut notification_receivers = Vec::with_capacity(listener_count);
             for _ in 0..listener_count {
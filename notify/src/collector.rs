@@ -11,7 +11,7 @@ use futures::{
     pin_mut,
     select,
 };
-use futures_util::stream::StreamExt;
+use futures_util::stream::{Stream, StreamExt};
 use kaspa_core::trace;
 use kaspa_utils::{channel::Channel, triggers::DuplexTrigger};
 use std::sync::{
@@ -44,12 +44,14 @@ pub type DynCollector<N> = Arc<dyn Collector<N>>;
 
 /// A notification [`Collector`] that receives `I` from a channel,
 /// converts it into a `N` and sends it to a [`DynNotify<N>`].
+///
+/// The channel is a [`CollectorNotificationReceiver`] by default, but any cloneable stream of `I` can be used instead.
 #[derive(Debug)]
-pub struct CollectorFrom<C>
+pub struct CollectorFrom<C, R = CollectorNotificationReceiver<<C as Converter>::Incoming>>
 where
     C: Converter,
 {
-    recv_channel: CollectorNotificationReceiver<C::Incoming>,
+    recv_channel: R,
 
     converter: Arc<C>,
 
@@ -59,11 +61,12 @@ where
     collect_shutdown: Arc<DuplexTrigger>,
 }
 
-impl<C> CollectorFrom<C>
+impl<C, R> CollectorFrom<C, R>
 where
     C: Converter + 'static,
+    R: Stream<Item = C::Incoming> + Clone + Debug + Send + Sync + 'static,
 {
-    pub fn new(recv_channel: CollectorNotificationReceiver<C::Incoming>, converter: Arc<C>) -> Self {
+    pub fn new(recv_channel: R, converter: Arc<C>) -> Self {
         Self {
             recv_channel,
             converter,
@@ -126,10 +129,11 @@ where
 }
 
 #[async_trait]
-impl<N, C> Collector<N> for CollectorFrom<C>
+impl<N, C, R> Collector<N> for CollectorFrom<C, R>
 where
     N: Notification,
     C: Converter<Outgoing = N> + 'static,
+    R: Stream<Item = C::Incoming> + Clone + Debug + Send + Sync + 'static,
 {
     fn start(self: Arc<Self>, notifier: DynNotify<N>) {
         self.spawn_collecting_task(notifier);
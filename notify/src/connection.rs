@@ -17,12 +17,29 @@ pub trait Connection: Clone + Debug + Send + Sync + 'static {
     fn is_closed(&self) -> bool;
 }
 
+/// Behavior of a [`ChannelConnection`] when its channel is full
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DeliveryMode {
+    /// The notification is dropped
+    #[default]
+    DropOnFull,
+
+    /// The notification is queued and delivered as soon as the channel has room.
+    ///
+    /// Delivery is awaited by a dedicated task, so the sending side is never stalled.
+    /// The queue is unbounded though, so a consumer lagging forever makes it grow forever.
+    Block,
+}
+
 #[derive(Clone, Debug)]
 pub struct ChannelConnection<N>
 where
     N: Notification,
 {
     sender: Sender<N>,
+
+    /// Queue of the notifications awaiting delivery in [`DeliveryMode::Block`]
+    queue: Option<Sender<N>>,
 }
 
 impl<N> ChannelConnection<N>
@@ -30,7 +47,36 @@ where
     N: Notification,
 {
     pub fn new(sender: Sender<N>) -> Self {
-        Self { sender }
+        Self { sender, queue: None }
+    }
+
+    /// Creates a connection delivering notifications to `sender` according to `mode`.
+    ///
+    /// In [`DeliveryMode::Block`], a delivery task is spawned, so this must be called in the context of an async runtime.
+    pub fn with_delivery_mode(sender: Sender<N>, mode: DeliveryMode) -> Self {
+        match mode {
+            DeliveryMode::DropOnFull => Self::new(sender),
+            DeliveryMode::Block => {
+                let (queue, pending) = async_channel::unbounded::<N>();
+                let target = sender.clone();
+                workflow_core::task::spawn(async move {
+                    while let Ok(notification) = pending.recv().await {
+                        if target.send(notification).await.is_err() {
+                            break;
+                        }
+                    }
+                    pending.close();
+                });
+                Self { sender, queue: Some(queue) }
+            }
+        }
+    }
+
+    pub fn delivery_mode(&self) -> DeliveryMode {
+        match self.queue {
+            Some(_) => DeliveryMode::Block,
+            None => DeliveryMode::DropOnFull,
+        }
     }
 }
 
@@ -58,13 +104,17 @@ where
     }
 
     fn send(&self, message: Self::Message) -> Result<(), Self::Error> {
-        match !self.is_closed() {
-            true => Ok(self.sender.try_send(message)?),
-            false => Err(Error::ConnectionClosed),
+        match (self.is_closed(), &self.queue) {
+            (false, Some(queue)) => Ok(queue.try_send(message)?),
+            (false, None) => Ok(self.sender.try_send(message)?),
+            (true, _) => Err(Error::ConnectionClosed),
         }
     }
 
     fn close(&self) -> bool {
+        if let Some(ref queue) = self.queue {
+            queue.close();
+        }
         self.sender.close()
     }
 
@@ -72,3 +122,45 @@ where
         self.sender.is_closed()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notification::test_helpers::{BlockAddedNotification, TestNotification};
+    use async_channel::bounded;
+    use std::time::Duration;
+    use workflow_core::task::sleep;
+
+    const CAPACITY: usize = 2;
+    const COUNT: u64 = 20;
+
+    /// Sends [`COUNT`] notifications at once, then consumes them slowly and returns the received ones
+    async fn send_to_slow_consumer(mode: DeliveryMode) -> (Vec<u64>, usize) {
+        let (sender, receiver) = bounded(CAPACITY);
+        let connection = ChannelConnection::with_delivery_mode(sender, mode);
+        assert_eq!(connection.delivery_mode(), mode);
+        let failures =
+            (0..COUNT).filter(|&data| connection.send(TestNotification::BlockAdded(BlockAddedNotification { data })).is_err()).count();
+        let mut received = vec![];
+        loop {
+            sleep(Duration::from_millis(5)).await;
+            match receiver.try_recv() {
+                Ok(TestNotification::BlockAdded(notification)) => received.push(notification.data),
+                Ok(_) => panic!("unexpected notification"),
+                Err(_) => break,
+            }
+        }
+        (received, failures)
+    }
+
+    #[tokio::test]
+    async fn test_delivery_modes() {
+        let (received, failures) = send_to_slow_consumer(DeliveryMode::DropOnFull).await;
+        assert_eq!(received, (0..CAPACITY as u64).collect::<Vec<_>>(), "only the notifications fitting in the channel are delivered");
+        assert_eq!(failures, COUNT as usize - CAPACITY);
+
+        let (received, failures) = send_to_slow_consumer(DeliveryMode::Block).await;
+        assert_eq!(received, (0..COUNT).collect::<Vec<_>>(), "all notifications are delivered in order");
+        assert_eq!(failures, 0);
+    }
+}
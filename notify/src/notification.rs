@@ -27,6 +27,14 @@ pub trait Notification: Clone + Debug + Display + Send + Sync + 'static {
     }
 
     fn event_type(&self) -> EventType;
+
+    /// Splits the notification into a sequence of pages holding at most `page_size` entries each, returning `None`
+    /// if it fits in a single page.
+    ///
+    /// Only notifications carrying large collections, such as a UTXO diff, are paginated, none by default.
+    fn paginate(&self, _page_size: usize) -> Option<Vec<Self>> {
+        None
+    }
 }
 
 #[macro_export]
@@ -155,6 +163,24 @@ pub mod test_helpers {
         fn event_type(&self) -> EventType {
             self.into()
         }
+
+        fn paginate(&self, page_size: usize) -> Option<Vec<Self>> {
+            match self {
+                TestNotification::UtxosChanged(payload) if payload.addresses.len() > page_size => Some(
+                    payload
+                        .addresses
+                        .chunks(page_size)
+                        .map(|addresses| {
+                            TestNotification::UtxosChanged(UtxosChangedNotification {
+                                data: payload.data,
+                                addresses: Arc::new(addresses.to_vec()),
+                            })
+                        })
+                        .collect(),
+                ),
+                _ => None,
+            }
+        }
     }
 
     /// A trait to help tests match notification received and expected thanks to some predefined data
@@ -1,12 +1,12 @@
 use super::{
-    broadcaster::Broadcaster,
+    broadcaster::{Broadcaster, DropCounters},
     collector::DynCollector,
     connection::Connection,
     error::{Error, Result},
-    events::{EventArray, EventSwitches, EventType},
+    events::{EventArray, EventSwitches, EventType, EVENT_TYPE_ARRAY},
     listener::{Listener, ListenerId},
     notification::Notification,
-    scope::Scope,
+    scope::{Scope, UtxosChangedScope},
     subscriber::{Subscriber, SubscriptionManager},
     subscription::{array::ArrayBuilder, Command, CompoundedSubscription, Mutation},
 };
@@ -14,11 +14,12 @@ use async_channel::Sender;
 use async_trait::async_trait;
 use core::fmt::Debug;
 use futures::future::join_all;
+use kaspa_addresses::Address;
 use kaspa_core::trace;
 use std::{
     collections::{hash_map::Entry, HashMap},
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc, Mutex,
     },
 };
@@ -53,6 +54,10 @@ pub type DynNotify<N> = Arc<dyn Notify<N>>;
 ///
 /// A notifier broadcasts its incoming notifications to its listeners.
 ///
+/// Every listener is served by a single broadcaster, handling the notifications one at a time in the order they were
+/// received by the notifier, so a listener always gets its notifications in emission order, however many broadcasters
+/// the notifier runs in parallel. Chain views built from `VirtualChainChanged` notifications rely on this guarantee.
+///
 /// A notifier is build with a specific set of enabled event types (see `enabled_events`). All disabled
 /// event types are ignored by it. It is however possible to manually subscribe to a disabled scope and
 /// thus have a custom made collector of the notifier receive notifications of the disabled scope,
@@ -93,6 +98,15 @@ where
         Self { inner: Arc::new(Inner::with_sync(enabled_events, collectors, subscribers, broadcasters, name, _sync)) }
     }
 
+    /// Delivers the notifications holding more than `page_size` entries, such as a large `UtxosChanged` diff, as a
+    /// sequence of notifications of at most `page_size` entries each, keeping the size of every message bounded.
+    ///
+    /// A `page_size` of zero, the default, disables the pagination. See [`Notification::paginate`].
+    pub fn with_page_size(self, page_size: usize) -> Self {
+        self.inner.page_size.store(page_size, Ordering::Relaxed);
+        self
+    }
+
     pub fn start(self: Arc<Self>) {
         self.inner.clone().start(self.clone());
     }
@@ -113,10 +127,63 @@ where
         self.inner.clone().stop_notify(id, scope)
     }
 
+    /// Starts notifying listener `id` of every event type enabled in this notifier, with the default scope of each.
+    ///
+    /// `UtxosChanged` is thus subscribed to for all addresses, replacing any address set previously subscribed to.
+    pub fn start_notify_all(&self, id: ListenerId) -> Result<()> {
+        self.inner.execute_subscribe_command_all(id, Command::Start)
+    }
+
+    /// Stops notifying listener `id` of every event type enabled in this notifier, see [`Notifier::start_notify_all`]
+    pub fn stop_notify_all(&self, id: ListenerId) -> Result<()> {
+        self.inner.execute_subscribe_command_all(id, Command::Stop)
+    }
+
     pub fn unregister_listener(&self, id: ListenerId) -> Result<()> {
         self.inner.unregister_listener(id)
     }
 
+    /// Adds `addresses` to the `UtxosChanged` subscription of listener `id`, starting it if needed.
+    ///
+    /// Only the addresses not yet subscribed to are forwarded to the subscribers.
+    pub fn add_utxo_addresses(&self, id: ListenerId, addresses: Vec<Address>) -> Result<()> {
+        // An empty address set means all addresses, so there is nothing to add
+        if addresses.is_empty() {
+            return Ok(());
+        }
+        self.inner.clone().start_notify(id, Scope::UtxosChanged(UtxosChangedScope::new(addresses)))
+    }
+
+    /// Removes `addresses` from the `UtxosChanged` subscription of listener `id`, stopping it if no address remains.
+    ///
+    /// Only the addresses actually subscribed to are forwarded to the subscribers.
+    pub fn remove_utxo_addresses(&self, id: ListenerId, addresses: Vec<Address>) -> Result<()> {
+        // An empty address set means all addresses, so there is nothing to remove
+        if addresses.is_empty() {
+            return Ok(());
+        }
+        self.inner.clone().stop_notify(id, Scope::UtxosChanged(UtxosChangedScope::new(addresses)))
+    }
+
+    /// Returns true if listener `id` has an active subscription matching `scope`.
+    ///
+    /// For `UtxosChanged`, a subscription matches if it shares at least one address with `scope`,
+    /// or if any of both covers all addresses.
+    pub fn is_subscribed(&self, id: ListenerId, scope: &Scope) -> bool {
+        self.inner.is_subscribed(id, scope)
+    }
+
+    /// Returns the number of notifications dropped so far because the connection of listener `id` could not accept them,
+    /// typically because its channel is full.
+    pub fn notification_drops(&self, id: ListenerId) -> u64 {
+        self.inner.notification_drops(id)
+    }
+
+    /// Returns the number of registered listeners, subscribed to some event types or not
+    pub fn listener_count(&self) -> usize {
+        self.inner.listener_count()
+    }
+
     pub async fn stop(&self) -> Result<()> {
         self.inner.clone().stop().await
     }
@@ -169,12 +236,21 @@ where
     /// Has this notifier been started?
     started: Arc<AtomicBool>,
 
-    /// Channel used to send the notifications to the broadcasters
-    notification_channel: Channel<N>,
+    /// Channels sending the notifications to the broadcasters, one per broadcaster
+    notification_channels: Vec<Channel<N>>,
 
-    /// Array of notification broadcasters
+    /// Array of notification broadcasters, each serving its own share of the listeners, see [`Inner::broadcaster_of`]
     broadcasters: Vec<Arc<Broadcaster<N, C>>>,
 
+    /// Dropped notifications by listener, maintained by the broadcasters
+    drops: Arc<DropCounters>,
+
+    /// Channel used by the broadcasters to report the listeners whose connection is closed
+    closed_listeners: Channel<ListenerId>,
+
+    /// Maximum number of entries of a notification delivered to a listener, shared with the broadcasters
+    page_size: Arc<AtomicUsize>,
+
     /// Collectors
     collectors: Vec<DynCollector<N>>,
 
@@ -201,16 +277,32 @@ where
         name: &'static str,
     ) -> Self {
         assert!(broadcasters > 0, "a notifier requires a minimum of one broadcaster");
-        let notification_channel = Channel::unbounded();
-        let broadcasters =
-            (0..broadcasters).map(|_| Arc::new(Broadcaster::new(name, notification_channel.receiver.clone()))).collect::<Vec<_>>();
+        let notification_channels = (0..broadcasters).map(|_| Channel::unbounded()).collect::<Vec<_>>();
+        let drops: Arc<DropCounters> = Default::default();
+        let closed_listeners = Channel::unbounded();
+        let page_size: Arc<AtomicUsize> = Default::default();
+        let broadcasters = notification_channels
+            .iter()
+            .map(|notification_channel| {
+                Arc::new(Broadcaster::new(
+                    name,
+                    notification_channel.receiver.clone(),
+                    drops.clone(),
+                    closed_listeners.sender.clone(),
+                    page_size.clone(),
+                ))
+            })
+            .collect::<Vec<_>>();
         Self {
             enabled_events,
             listeners: Mutex::new(HashMap::new()),
             subscriptions: Mutex::new(ArrayBuilder::compounded()),
             started: Arc::new(AtomicBool::new(false)),
-            notification_channel,
+            notification_channels,
             broadcasters,
+            drops,
+            closed_listeners,
+            page_size,
             collectors,
             subscribers,
             name,
@@ -228,17 +320,33 @@ where
         _sync: Option<Sender<()>>,
     ) -> Self {
         assert!(broadcasters > 0, "a notifier requires a minimum of one broadcaster");
-        let notification_channel = Channel::unbounded();
-        let broadcasters = (0..broadcasters)
-            .map(|_| Arc::new(Broadcaster::with_sync(name, notification_channel.receiver.clone(), _sync.clone())))
+        let notification_channels = (0..broadcasters).map(|_| Channel::unbounded()).collect::<Vec<_>>();
+        let drops: Arc<DropCounters> = Default::default();
+        let closed_listeners = Channel::unbounded();
+        let page_size: Arc<AtomicUsize> = Default::default();
+        let broadcasters = notification_channels
+            .iter()
+            .map(|notification_channel| {
+                Arc::new(Broadcaster::with_sync(
+                    name,
+                    notification_channel.receiver.clone(),
+                    drops.clone(),
+                    closed_listeners.sender.clone(),
+                    page_size.clone(),
+                    _sync.clone(),
+                ))
+            })
             .collect::<Vec<_>>();
         Self {
             enabled_events,
             listeners: Mutex::new(HashMap::new()),
             subscriptions: Mutex::new(ArrayBuilder::compounded()),
             started: Arc::new(AtomicBool::new(false)),
-            notification_channel,
+            notification_channels,
             broadcasters,
+            drops,
+            closed_listeners,
+            page_size,
             collectors,
             subscribers,
             name,
@@ -246,17 +354,36 @@ where
         }
     }
 
-    fn start(&self, notifier: Arc<Notifier<N, C>>) {
+    fn start(self: Arc<Self>, notifier: Arc<Notifier<N, C>>) {
         if self.started.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst).is_ok() {
             self.subscribers.iter().for_each(|x| x.start());
             self.collectors.iter().for_each(|x| x.clone().start(notifier.clone()));
             self.broadcasters.iter().for_each(|x| x.start());
+            self.clone().spawn_closed_listeners_task();
             trace!("[Notifier-{}] started", self.name);
         } else {
             trace!("[Notifier-{}] start ignored since already started", self.name);
         }
     }
 
+    /// Unregisters the listeners reported by the broadcasters as having a closed connection, typically because their
+    /// receiver was dropped without unregistering them.
+    ///
+    /// Unregistering a listener stops all its subscriptions, so the parents stop sending the notifications no listener
+    /// needs anymore.
+    fn spawn_closed_listeners_task(self: Arc<Self>) {
+        let closed_listeners = self.closed_listeners.receiver.clone();
+        workflow_core::task::spawn(async move {
+            while let Ok(id) = closed_listeners.recv().await {
+                // A listener subscribed to several event types may be reported more than once
+                if self.listeners.lock().unwrap().contains_key(&id) {
+                    trace!("[Notifier-{}] unregistering listener {id} since its connection is closed", self.name);
+                    let _ = self.unregister_listener(id);
+                }
+            }
+        });
+    }
+
     fn register_new_listener(self: &Arc<Self>, connection: C) -> ListenerId {
         let mut listeners = self.listeners.lock().unwrap();
         loop {
@@ -289,9 +416,37 @@ where
         });
         // Remove listener
         self.listeners.lock().unwrap().remove(&id);
+        self.drops.lock().unwrap().remove(&id);
         Ok(())
     }
 
+    fn is_subscribed(&self, id: ListenerId, scope: &Scope) -> bool {
+        let listeners = self.listeners.lock().unwrap();
+        let Some(listener) = listeners.get(&id) else {
+            return false;
+        };
+        let subscription = &listener.subscriptions[EventType::from(scope)];
+        if !subscription.active() {
+            return false;
+        }
+        match (scope, subscription.scope()) {
+            (Scope::UtxosChanged(requested), Scope::UtxosChanged(active)) => {
+                requested.addresses.is_empty()
+                    || active.addresses.is_empty()
+                    || requested.addresses.iter().any(|address| active.addresses.contains(address))
+            }
+            _ => true,
+        }
+    }
+
+    fn notification_drops(&self, id: ListenerId) -> u64 {
+        self.drops.lock().unwrap().get(&id).copied().unwrap_or_default()
+    }
+
+    fn listener_count(&self) -> usize {
+        self.listeners.lock().unwrap().len()
+    }
+
     pub fn execute_subscribe_command(&self, id: ListenerId, scope: Scope, command: Command) -> Result<()> {
         let event: EventType = (&scope).into();
         if self.enabled_events[event] {
@@ -300,11 +455,9 @@ where
                 let mut subscriptions = self.subscriptions.lock().unwrap();
                 trace!("[Notifier-{}] {command} notifying to {id} about {scope:?}", self.name);
                 if let Some(mutations) = listener.mutate(Mutation::new(command, scope)) {
-                    // Update broadcasters
+                    // Update the broadcaster of the listener
                     let subscription = listener.subscriptions[event].clone_arc();
-                    self.broadcasters.iter().for_each(|broadcaster| {
-                        let _ = broadcaster.register(subscription.clone(), id, listener.connection());
-                    });
+                    let _ = self.broadcaster_of(id).register(subscription, id, listener.connection());
                     // Compound mutations
                     let mut compound_result = None;
                     for mutation in mutations {
@@ -335,9 +488,25 @@ where
         self.execute_subscribe_command(id, scope, Command::Start)
     }
 
+    /// Executes `command` for every enabled event type, the default scope of `UtxosChanged` covering all addresses
+    fn execute_subscribe_command_all(&self, id: ListenerId, command: Command) -> Result<()> {
+        EVENT_TYPE_ARRAY
+            .iter()
+            .filter(|event| self.enabled_events[**event])
+            .try_for_each(|event| self.execute_subscribe_command(id, Scope::from(*event), command))
+    }
+
+    /// Broadcaster serving listener `id`
+    fn broadcaster_of(&self, id: ListenerId) -> &Arc<Broadcaster<N, C>> {
+        &self.broadcasters[(id % self.broadcasters.len() as u64) as usize]
+    }
+
+    /// Sends `notification` to every broadcaster, each delivering it to the listeners it serves
     fn notify(&self, notification: N) -> Result<()> {
         if self.enabled_events[notification.event_type()] {
-            self.notification_channel.try_send(notification)?;
+            for notification_channel in self.notification_channels.iter() {
+                notification_channel.try_send(notification.clone())?;
+            }
         }
         Ok(())
     }
@@ -357,6 +526,7 @@ where
             join_all(self.broadcasters.iter().map(|x| x.stop())).await.into_iter().collect::<std::result::Result<Vec<()>, _>>()?;
             trace!("[Notifier-{}] stopping subscribers", self.name);
             join_all(self.subscribers.iter().map(|x| x.stop())).await.into_iter().collect::<std::result::Result<Vec<()>, _>>()?;
+            self.closed_listeners.receiver.close();
         } else {
             trace!("[Notifier-{}] stop ignored since already stopped", self.name);
             return Err(Error::AlreadyStoppedError);
@@ -431,10 +601,13 @@ pub mod test_helpers {
 
     pub fn overall_test_steps(listener_id: ListenerId) -> Vec<Step> {
         fn m(command: Command) -> Option<Mutation> {
-            Some(Mutation { command, scope: Scope::BlockAdded(BlockAddedScope {}) })
+            Some(Mutation { command, scope: Scope::BlockAdded(BlockAddedScope::default()) })
         }
         let s = |command: Command| -> Option<SubscriptionMessage> {
-            Some(SubscriptionMessage { listener_id, mutation: Mutation { command, scope: Scope::BlockAdded(BlockAddedScope {}) } })
+            Some(SubscriptionMessage {
+                listener_id,
+                mutation: Mutation { command, scope: Scope::BlockAdded(BlockAddedScope::default()) },
+            })
         };
         fn n() -> TestNotification {
             TestNotification::BlockAdded(BlockAddedNotification::default())
@@ -661,9 +834,11 @@ mod tests {
         converter::ConverterFrom,
         events::EVENT_TYPE_ARRAY,
         notification::test_helpers::*,
+        scope::{BlockAddedScope, VirtualChainChangedScope},
         subscriber::test_helpers::{SubscriptionManagerMock, SubscriptionMessage},
     };
     use async_channel::{unbounded, Receiver, Sender};
+    use std::collections::HashSet;
 
     const SUBSCRIPTION_MANAGER_ID: u64 = 0;
 
@@ -818,4 +993,343 @@ mod tests {
         let test = Test::new("UtxosChanged broadcast", 3, utxos_changed_test_steps(SUBSCRIPTION_MANAGER_ID));
         test.run().await;
     }
+
+    #[tokio::test]
+    async fn test_utxo_addresses_delta() {
+        let (subscription_sender, subscription_receiver) = unbounded();
+        let subscription_manager = Arc::new(SubscriptionManagerMock::new(subscription_sender));
+        let subscriber = Arc::new(Subscriber::new(EVENT_TYPE_ARRAY[..].into(), subscription_manager, SUBSCRIPTION_MANAGER_ID));
+        let notifier = Arc::new(TestNotifier::new(EVENT_TYPE_ARRAY[..].into(), vec![], vec![subscriber], 1, "test"));
+        notifier.clone().start();
+        let (sender, _receiver) = unbounded();
+        let id = notifier.register_new_listener(TestConnection::new(sender));
+        let addresses = crate::address::test_helpers::get_3_addresses(false);
+        let utxos_changed = |command, addresses: &[Address]| {
+            SubscriptionMessage::new(SUBSCRIPTION_MANAGER_ID, command, Scope::UtxosChanged(UtxosChangedScope::new(addresses.to_vec())))
+        };
+
+        notifier.add_utxo_addresses(id, addresses[0..2].to_vec()).unwrap();
+        assert_eq!(subscription_receiver.recv().await.unwrap(), utxos_changed(Command::Start, &addresses[0..2]));
+
+        // Only the new address is sent
+        notifier.add_utxo_addresses(id, addresses[1..3].to_vec()).unwrap();
+        assert_eq!(subscription_receiver.recv().await.unwrap(), utxos_changed(Command::Start, &addresses[2..3]));
+
+        // Only the subscribed address is removed
+        notifier
+            .remove_utxo_addresses(
+                id,
+                vec![
+                    addresses[0].clone(),
+                    Address::new(kaspa_addresses::Prefix::Mainnet, kaspa_addresses::Version::PubKey, &[9u8; 32]),
+                ],
+            )
+            .unwrap();
+        assert_eq!(subscription_receiver.recv().await.unwrap(), utxos_changed(Command::Stop, &addresses[0..1]));
+
+        // Empty sets are no-ops
+        notifier.add_utxo_addresses(id, vec![]).unwrap();
+        notifier.remove_utxo_addresses(id, vec![]).unwrap();
+        assert!(notifier.is_subscribed(id, &Scope::UtxosChanged(UtxosChangedScope::new(addresses[1..3].to_vec()))));
+
+        assert!(notifier.stop().await.is_ok(), "notifier failed to stop");
+        assert!(subscription_receiver.is_empty(), "no other subscription should be sent");
+    }
+    #[tokio::test]
+    async fn test_utxos_changed_overlapping_listeners() {
+        let (sync_sender, sync_receiver) = unbounded();
+        let (subscription_sender, subscription_receiver) = unbounded();
+        let subscription_manager = Arc::new(SubscriptionManagerMock::new(subscription_sender));
+        let subscriber = Arc::new(Subscriber::new(EVENT_TYPE_ARRAY[..].into(), subscription_manager, SUBSCRIPTION_MANAGER_ID));
+        let notifier =
+            Arc::new(TestNotifier::with_sync(EVENT_TYPE_ARRAY[..].into(), vec![], vec![subscriber], 1, "test", Some(sync_sender)));
+        notifier.clone().start();
+        let addresses = crate::address::test_helpers::get_3_addresses(true);
+        let scope =
+            |indexes: &[usize]| Scope::UtxosChanged(UtxosChangedScope::new(indexes.iter().map(|i| addresses[*i].clone()).collect()));
+        let notification = |indexes: &[usize]| {
+            TestNotification::UtxosChanged(UtxosChangedNotification {
+                data: 0,
+                addresses: Arc::new(indexes.iter().map(|i| addresses[*i].clone()).collect()),
+            })
+        };
+
+        // Server side subscription, as the union of the address sets of the listeners
+        let mut server_addresses = HashSet::new();
+        async fn apply_subscriptions(
+            receiver: &Receiver<SubscriptionMessage>,
+            server_addresses: &mut HashSet<Address>,
+            count: usize,
+        ) -> HashSet<Address> {
+            for _ in 0..count {
+                let message = receiver.recv().await.unwrap();
+                let Scope::UtxosChanged(ref scope) = message.mutation.scope else { panic!("unexpected scope") };
+                match message.mutation.command {
+                    Command::Start => {
+                        scope.addresses.iter().for_each(|x| assert!(server_addresses.insert(x.clone()), "{x} subscribed twice"))
+                    }
+                    Command::Stop => {
+                        scope.addresses.iter().for_each(|x| assert!(server_addresses.remove(x), "{x} was not subscribed"))
+                    }
+                }
+            }
+            assert!(receiver.is_empty(), "no other subscription should be sent");
+            server_addresses.clone()
+        }
+        let expected_addresses = |indexes: &[usize]| indexes.iter().map(|i| addresses[*i].clone()).collect::<HashSet<_>>();
+
+        let mut receivers = vec![];
+        let mut listeners = vec![];
+        for indexes in [[0, 1], [1, 2]] {
+            let (sender, receiver) = unbounded();
+            let id = notifier.register_new_listener(TestConnection::new(sender));
+            notifier.try_start_notify(id, scope(&indexes)).unwrap();
+            sync_receiver.recv().await.unwrap();
+            receivers.push(receiver);
+            listeners.push(id);
+        }
+        assert_eq!(
+            apply_subscriptions(&subscription_receiver, &mut server_addresses, 2).await,
+            expected_addresses(&[0, 1, 2]),
+            "the server should get the union of the address sets"
+        );
+
+        // Each listener only gets its own addresses
+        notifier.notify(notification(&[0, 1, 2])).unwrap();
+        sync_receiver.recv().await.unwrap();
+        assert_eq!(receivers[0].recv().await.unwrap(), notification(&[0, 1]));
+        assert_eq!(receivers[1].recv().await.unwrap(), notification(&[1, 2]));
+
+        notifier.notify(notification(&[0])).unwrap();
+        sync_receiver.recv().await.unwrap();
+        assert_eq!(receivers[0].recv().await.unwrap(), notification(&[0]));
+        assert!(receivers[1].is_empty(), "listener 1 should get no notification for address 0");
+
+        // The shared address remains subscribed while a listener still needs it
+        notifier.try_stop_notify(listeners[0], scope(&[0, 1])).unwrap();
+        sync_receiver.recv().await.unwrap();
+        assert_eq!(apply_subscriptions(&subscription_receiver, &mut server_addresses, 1).await, expected_addresses(&[1, 2]));
+        notifier.try_stop_notify(listeners[1], scope(&[1, 2])).unwrap();
+        sync_receiver.recv().await.unwrap();
+        assert!(apply_subscriptions(&subscription_receiver, &mut server_addresses, 1).await.is_empty());
+
+        assert!(notifier.stop().await.is_ok(), "notifier failed to stop");
+    }
+
+    #[tokio::test]
+    async fn test_closed_listener_unregistration() {
+        let (sync_sender, sync_receiver) = unbounded();
+        let (subscription_sender, subscription_receiver) = unbounded();
+        let subscription_manager = Arc::new(SubscriptionManagerMock::new(subscription_sender));
+        let subscriber = Arc::new(Subscriber::new(EVENT_TYPE_ARRAY[..].into(), subscription_manager, SUBSCRIPTION_MANAGER_ID));
+        let notifier =
+            Arc::new(TestNotifier::with_sync(EVENT_TYPE_ARRAY[..].into(), vec![], vec![subscriber], 1, "test", Some(sync_sender)));
+        notifier.clone().start();
+        let scope = Scope::BlockAdded(BlockAddedScope::default());
+        let notification = || TestNotification::BlockAdded(BlockAddedNotification::default());
+
+        let mut receivers = vec![];
+        let mut listeners = vec![];
+        for _ in 0..2 {
+            let (sender, receiver) = unbounded();
+            let id = notifier.register_new_listener(TestConnection::new(sender));
+            notifier.try_start_notify(id, scope.clone()).unwrap();
+            sync_receiver.recv().await.unwrap();
+            receivers.push(receiver);
+            listeners.push(id);
+        }
+        assert_eq!(
+            subscription_receiver.recv().await.unwrap(),
+            SubscriptionMessage::new(SUBSCRIPTION_MANAGER_ID, Command::Start, scope.clone())
+        );
+
+        // A full channel only drops the notification and keeps the listener
+        let (sender, _full_receiver) = async_channel::bounded(1);
+        let full = notifier.register_new_listener(TestConnection::new(sender));
+        notifier.try_start_notify(full, scope.clone()).unwrap();
+        sync_receiver.recv().await.unwrap();
+
+        // Dropping the receiver of a listener without unregistering it gets the listener unregistered on the next notification
+        drop(receivers.pop());
+        notifier.notify(notification()).unwrap();
+        notifier.notify(notification()).unwrap();
+        // One sync message for each notification, then one for the unregistration of the subscription
+        for _ in 0..3 {
+            sync_receiver.recv().await.unwrap();
+        }
+        assert!(!notifier.is_subscribed(listeners[1], &scope), "the listener with a closed connection should be unregistered");
+        assert!(notifier.is_subscribed(full, &scope), "a listener with a full channel should be kept");
+        assert_eq!(notifier.notification_drops(full), 1);
+        assert_eq!(receivers[0].len(), 2);
+        assert!(subscription_receiver.is_empty(), "the subscription is still needed by other listeners");
+
+        // The last listener of the event type going away stops the subscription of the parents
+        notifier.unregister_listener(full).unwrap();
+        sync_receiver.recv().await.unwrap();
+        drop(receivers.pop());
+        notifier.notify(notification()).unwrap();
+        for _ in 0..2 {
+            sync_receiver.recv().await.unwrap();
+        }
+        assert!(!notifier.is_subscribed(listeners[0], &scope));
+        assert_eq!(
+            subscription_receiver.recv().await.unwrap(),
+            SubscriptionMessage::new(SUBSCRIPTION_MANAGER_ID, Command::Stop, scope)
+        );
+
+        assert!(notifier.stop().await.is_ok(), "notifier failed to stop");
+    }
+
+    #[tokio::test]
+    async fn test_notify_all() {
+        let (sync_sender, sync_receiver) = unbounded();
+        let (subscription_sender, subscription_receiver) = unbounded();
+        let subscription_manager = Arc::new(SubscriptionManagerMock::new(subscription_sender));
+        let subscriber = Arc::new(Subscriber::new(EVENT_TYPE_ARRAY[..].into(), subscription_manager, SUBSCRIPTION_MANAGER_ID));
+        let notifier =
+            Arc::new(TestNotifier::with_sync(EVENT_TYPE_ARRAY[..].into(), vec![], vec![subscriber], 1, "test", Some(sync_sender)));
+        notifier.clone().start();
+        let addresses = crate::address::test_helpers::get_3_addresses(true);
+
+        let (sender, receiver) = unbounded();
+        let id = notifier.register_new_listener(TestConnection::new(sender));
+        let filtered = Scope::UtxosChanged(UtxosChangedScope::new(vec![addresses[0].clone()]));
+        notifier.try_start_notify(id, filtered.clone()).unwrap();
+        sync_receiver.recv().await.unwrap();
+        assert_eq!(
+            subscription_receiver.recv().await.unwrap(),
+            SubscriptionMessage::new(SUBSCRIPTION_MANAGER_ID, Command::Start, filtered)
+        );
+
+        // Every event type is subscribed to in one call, UtxosChanged switching to all addresses
+        notifier.start_notify_all(id).unwrap();
+        for event in EVENT_TYPE_ARRAY {
+            sync_receiver.recv().await.unwrap();
+            assert!(notifier.is_subscribed(id, &Scope::from(event)), "the listener should be subscribed to {event:?}");
+            assert_eq!(
+                subscription_receiver.recv().await.unwrap(),
+                SubscriptionMessage::new(SUBSCRIPTION_MANAGER_ID, Command::Start, Scope::from(event))
+            );
+        }
+        assert!(subscription_receiver.is_empty());
+
+        let notifications = vec![
+            TestNotification::BlockAdded(BlockAddedNotification { data: 1 }),
+            TestNotification::VirtualChainChanged(VirtualChainChangedNotification { data: 2, accepted_transaction_ids: None }),
+            TestNotification::UtxosChanged(UtxosChangedNotification { data: 3, addresses: Arc::new(vec![addresses[2].clone()]) }),
+        ];
+        for notification in notifications.iter().cloned() {
+            notifier.notify(notification).unwrap();
+            sync_receiver.recv().await.unwrap();
+        }
+        for expected in notifications.iter() {
+            assert_eq!(&receiver.recv().await.unwrap(), expected);
+        }
+
+        notifier.stop_notify_all(id).unwrap();
+        for event in EVENT_TYPE_ARRAY {
+            sync_receiver.recv().await.unwrap();
+            assert!(!notifier.is_subscribed(id, &Scope::from(event)), "the listener should no longer be subscribed to {event:?}");
+            assert_eq!(
+                subscription_receiver.recv().await.unwrap(),
+                SubscriptionMessage::new(SUBSCRIPTION_MANAGER_ID, Command::Stop, Scope::from(event))
+            );
+        }
+        notifier.notify(notifications[0].clone()).unwrap();
+        sync_receiver.recv().await.unwrap();
+        assert!(receiver.is_empty(), "no notification should be sent once unsubscribed");
+
+        assert!(notifier.stop().await.is_ok(), "notifier failed to stop");
+    }
+
+    #[tokio::test]
+    async fn test_paginated_delivery() {
+        const PAGE_SIZE: usize = 10;
+        let (sync_sender, sync_receiver) = unbounded();
+        let notifier = Arc::new(
+            TestNotifier::with_sync(EVENT_TYPE_ARRAY[..].into(), vec![], vec![], 1, "test", Some(sync_sender))
+                .with_page_size(PAGE_SIZE),
+        );
+        notifier.clone().start();
+        let addresses = (0..25u8)
+            .map(|i| Address::new(kaspa_addresses::Prefix::Mainnet, kaspa_addresses::Version::PubKey, &[i; 32]))
+            .collect::<Vec<_>>();
+
+        let (sender, receiver) = unbounded();
+        let id = notifier.register_new_listener(TestConnection::new(sender));
+        for scope in
+            [Scope::BlockAdded(BlockAddedScope::default()), Scope::UtxosChanged(UtxosChangedScope::new(addresses[..23].to_vec()))]
+        {
+            notifier.try_start_notify(id, scope).unwrap();
+            sync_receiver.recv().await.unwrap();
+        }
+
+        // The diff filtered by the subscription of the listener is delivered in pages totaling the original
+        notifier
+            .notify(TestNotification::UtxosChanged(UtxosChangedNotification { data: 1, addresses: Arc::new(addresses.clone()) }))
+            .unwrap();
+        sync_receiver.recv().await.unwrap();
+        let mut pages = vec![];
+        while let Ok(TestNotification::UtxosChanged(page)) = receiver.try_recv() {
+            assert!(page.addresses.len() <= PAGE_SIZE, "a page should hold at most {PAGE_SIZE} entries");
+            pages.push(page);
+        }
+        assert_eq!(pages.len(), 3);
+        assert_eq!(pages.iter().flat_map(|page| page.addresses.iter().cloned()).collect::<Vec<_>>(), addresses[..23].to_vec());
+        assert!(pages.iter().all(|page| page.data == 1));
+
+        // Small notifications are delivered unchanged
+        let small =
+            TestNotification::UtxosChanged(UtxosChangedNotification { data: 2, addresses: Arc::new(addresses[..PAGE_SIZE].to_vec()) });
+        let block = TestNotification::BlockAdded(BlockAddedNotification { data: 3 });
+        for notification in [small.clone(), block.clone()] {
+            notifier.notify(notification).unwrap();
+            sync_receiver.recv().await.unwrap();
+        }
+        assert_eq!(receiver.recv().await.unwrap(), small);
+        assert_eq!(receiver.recv().await.unwrap(), block);
+        assert!(receiver.is_empty());
+
+        assert!(notifier.stop().await.is_ok(), "notifier failed to stop");
+    }
+
+    #[tokio::test]
+    async fn test_per_listener_ordering() {
+        const BROADCASTERS: usize = 8;
+        const LISTENERS: usize = 5;
+        const NOTIFICATIONS: u64 = 10_000;
+
+        let (sync_sender, sync_receiver) = unbounded();
+        let notifier =
+            Arc::new(TestNotifier::with_sync(EVENT_TYPE_ARRAY[..].into(), vec![], vec![], BROADCASTERS, "test", Some(sync_sender)));
+        notifier.clone().start();
+
+        let mut receivers = vec![];
+        for _ in 0..LISTENERS {
+            let (sender, receiver) = unbounded();
+            let id = notifier.register_new_listener(TestConnection::new(sender));
+            notifier.try_start_notify(id, Scope::VirtualChainChanged(VirtualChainChangedScope::new(true))).unwrap();
+            sync_receiver.recv().await.unwrap();
+            receivers.push(receiver);
+        }
+
+        // The notifications are processed by all the broadcasters in parallel
+        for data in 0..NOTIFICATIONS {
+            let notification = VirtualChainChangedNotification { data, accepted_transaction_ids: Some(data) };
+            notifier.notify(TestNotification::VirtualChainChanged(notification)).unwrap();
+        }
+        for (i, receiver) in receivers.iter().enumerate() {
+            for expected in 0..NOTIFICATIONS {
+                match receiver.recv().await.unwrap() {
+                    TestNotification::VirtualChainChanged(notification) => {
+                        assert_eq!(notification.data, expected, "listener #{i} received a notification out of order")
+                    }
+                    notification => panic!("unexpected notification {notification:?}"),
+                }
+            }
+            assert!(receiver.is_empty());
+        }
+
+        assert!(notifier.stop().await.is_ok(), "notifier failed to stop");
+    }
 }
@@ -2,6 +2,7 @@ use super::events::EventType;
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use kaspa_addresses::Address;
 use serde::{Deserialize, Serialize};
+use std::io;
 
 macro_rules! scope_enum {
     ($(#[$meta:meta])* $vis:vis enum $name:ident {
@@ -35,11 +36,69 @@ pub enum Scope {
     VirtualDaaScoreChanged,
     PruningPointUtxoSetOverride,
     NewBlockTemplate,
+    MempoolSizeChanged,
 }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema)]
-pub struct BlockAddedScope {}
+impl Scope {
+    /// Blue score below which the notifications are suppressed, for the events referencing a blue score
+    pub fn min_blue_score(&self) -> Option<u64> {
+        match self {
+            Scope::BlockAdded(scope) => scope.min_blue_score,
+            Scope::SinkBlueScoreChanged(scope) => scope.min_blue_score,
+            _ => None,
+        }
+    }
+}
+
+/// Borsh encoding of the blue score floor of a scope, appended to the encoding of the scope
+///
+/// The scopes carrying a floor were empty before it was introduced, so the floor is only encoded when set, a scope
+/// without floor keeping its original encoding. A scope decoded at the end of its payload with no bytes left has no floor,
+/// so the payloads of the clients predating the floor still decode. The scopes are only encoded as whole payloads.
+mod min_blue_score_encoding {
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use std::io;
+
+    pub fn serialize<W: io::Write>(min_blue_score: &Option<u64>, writer: &mut W) -> io::Result<()> {
+        match min_blue_score {
+            Some(_) => min_blue_score.serialize(writer),
+            None => Ok(()),
+        }
+    }
+
+    pub fn deserialize(buf: &mut &[u8]) -> io::Result<Option<u64>> {
+        match buf.is_empty() {
+            true => Ok(None),
+            false => Option::<u64>::deserialize(buf),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, BorshSchema)]
+pub struct BlockAddedScope {
+    /// Suppresses the blocks with a blue score lower than this floor, as the old blocks received during the initial sync
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_blue_score: Option<u64>,
+}
+
+impl BorshSerialize for BlockAddedScope {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        min_blue_score_encoding::serialize(&self.min_blue_score, writer)
+    }
+}
+
+impl BorshDeserialize for BlockAddedScope {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        Ok(Self { min_blue_score: min_blue_score_encoding::deserialize(buf)? })
+    }
+}
+
+impl BlockAddedScope {
+    pub fn new(min_blue_score: Option<u64>) -> Self {
+        Self { min_blue_score }
+    }
+}
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema)]
 pub struct VirtualChainChangedScope {
@@ -77,8 +136,30 @@ impl UtxosChangedScope {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema)]
-pub struct SinkBlueScoreChangedScope {}
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, BorshSchema)]
+pub struct SinkBlueScoreChangedScope {
+    /// Suppresses the sink blue scores lower than this floor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_blue_score: Option<u64>,
+}
+
+impl BorshSerialize for SinkBlueScoreChangedScope {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        min_blue_score_encoding::serialize(&self.min_blue_score, writer)
+    }
+}
+
+impl BorshDeserialize for SinkBlueScoreChangedScope {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        Ok(Self { min_blue_score: min_blue_score_encoding::deserialize(buf)? })
+    }
+}
+
+impl SinkBlueScoreChangedScope {
+    pub fn new(min_blue_score: Option<u64>) -> Self {
+        Self { min_blue_score }
+    }
+}
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema)]
 pub struct VirtualDaaScoreChangedScope {}
@@ -88,3 +169,38 @@ pub struct PruningPointUtxoSetOverrideScope {}
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema)]
 pub struct NewBlockTemplateScope {}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct MempoolSizeChangedScope {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_min_blue_score_encoding() {
+        // Encodings of the scopes predating the blue score floor, which were empty
+        let block_added = [0u8];
+        let sink_blue_score_changed = [5u8];
+        assert_eq!(Scope::try_from_slice(&block_added).unwrap(), Scope::BlockAdded(BlockAddedScope::default()));
+        assert_eq!(
+            Scope::try_from_slice(&sink_blue_score_changed).unwrap(),
+            Scope::SinkBlueScoreChanged(SinkBlueScoreChangedScope::default())
+        );
+        assert_eq!(serde_json::from_str::<Scope>(r#"{"BlockAdded":{}}"#).unwrap(), Scope::BlockAdded(BlockAddedScope::default()));
+
+        // A scope without floor keeps the original encoding
+        assert_eq!(Scope::BlockAdded(BlockAddedScope::default()).try_to_vec().unwrap(), block_added);
+        assert_eq!(Scope::SinkBlueScoreChanged(SinkBlueScoreChangedScope::default()).try_to_vec().unwrap(), sink_blue_score_changed);
+        assert_eq!(serde_json::to_string(&Scope::BlockAdded(BlockAddedScope::default())).unwrap(), r#"{"BlockAdded":{}}"#);
+
+        for scope in [
+            Scope::BlockAdded(BlockAddedScope::new(Some(100))),
+            Scope::SinkBlueScoreChanged(SinkBlueScoreChangedScope::new(Some(u64::MAX))),
+            Scope::BlockAdded(BlockAddedScope::new(None)),
+        ] {
+            assert_eq!(Scope::try_from_slice(&scope.try_to_vec().unwrap()).unwrap(), scope);
+            assert_eq!(serde_json::from_str::<Scope>(&serde_json::to_string(&scope).unwrap()).unwrap(), scope);
+        }
+    }
+}
@@ -1,12 +1,15 @@
 use async_trait::async_trait;
 use core::fmt::Debug;
 use kaspa_core::trace;
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
 };
 extern crate derive_more;
-use crate::events::EventSwitches;
+use crate::events::{EventSwitches, EventType};
 
 use super::{
     error::{Error, Result},
@@ -36,6 +39,9 @@ pub trait SubscriptionManager: Send + Sync + Debug {
 
 pub type DynSubscriptionManager = Arc<dyn SubscriptionManager>;
 
+/// Maximum number of attempts made by [`Subscriber::resubscribe_all`] to restore a subscription
+const RESUBSCRIBE_MAX_ATTEMPTS: usize = 3;
+
 #[derive(Clone, Debug)]
 enum Ctl {
     Shutdown,
@@ -56,6 +62,9 @@ pub struct Subscriber {
     /// Has this subscriber been started?
     started: Arc<AtomicBool>,
 
+    /// Scopes currently subscribed to in the subscription manager, by event type
+    active_scopes: Mutex<HashMap<EventType, Scope>>,
+
     ctl: Channel<Ctl>,
     incoming: Channel<Mutation>,
     shutdown: Channel<()>,
@@ -68,6 +77,7 @@ impl Subscriber {
             subscription_manager,
             listener_id,
             started: Arc::new(AtomicBool::default()),
+            active_scopes: Mutex::new(HashMap::new()),
             ctl: Channel::unbounded(),
             incoming: Channel::unbounded(),
             shutdown: Channel::oneshot(),
@@ -103,6 +113,7 @@ impl Subscriber {
                     mutation = self.incoming.recv().fuse() => {
                         if let Ok(mutation) = mutation {
                             if self.enabled_events[mutation.event_type()] {
+                                self.record(&mutation);
                                 if let Err(err) = self.subscription_manager.clone().execute_subscribe_command(self.listener_id, mutation.scope, mutation.command).await {
                                     trace!("[Subscriber] the subscription command returned an error: {:?}", err);
                                 }
@@ -119,6 +130,72 @@ impl Subscriber {
         Ok(())
     }
 
+    /// Track the active subscriptions by applying a mutation sent to the subscription manager
+    fn record(&self, mutation: &Mutation) {
+        let event = mutation.event_type();
+        let mut active_scopes = self.active_scopes.lock().unwrap();
+        match (&mutation.command, &mutation.scope) {
+            (Command::Start, Scope::UtxosChanged(scope)) if !scope.addresses.is_empty() => {
+                // Add the addresses to the current set, or replace a subscription to all addresses
+                let extended = match active_scopes.get_mut(&event) {
+                    Some(Scope::UtxosChanged(active)) if !active.addresses.is_empty() => {
+                        scope.addresses.iter().for_each(|address| {
+                            if !active.addresses.contains(address) {
+                                active.addresses.push(address.clone());
+                            }
+                        });
+                        true
+                    }
+                    _ => false,
+                };
+                if !extended {
+                    active_scopes.insert(event, mutation.scope.clone());
+                }
+            }
+            (Command::Stop, Scope::UtxosChanged(scope)) if !scope.addresses.is_empty() => {
+                let emptied = match active_scopes.get_mut(&event) {
+                    Some(Scope::UtxosChanged(active)) if !active.addresses.is_empty() => {
+                        active.addresses.retain(|address| !scope.addresses.contains(address));
+                        active.addresses.is_empty()
+                    }
+                    _ => false,
+                };
+                if emptied {
+                    active_scopes.remove(&event);
+                }
+            }
+            (Command::Start, _) => {
+                active_scopes.insert(event, mutation.scope.clone());
+            }
+            (Command::Stop, _) => {
+                active_scopes.remove(&event);
+            }
+        }
+    }
+
+    /// Re-issue a start command to the subscription manager for every active subscription.
+    ///
+    /// This is intended to be called after the underlying connection of the subscription manager was
+    /// re-established, so that a new server session gets the same subscriptions as the previous one.
+    /// Failing subscriptions are retried up to [`RESUBSCRIBE_MAX_ATTEMPTS`] times.
+    pub async fn resubscribe_all(self: &Arc<Self>) -> Result<()> {
+        let mut pending = self.active_scopes.lock().unwrap().values().cloned().collect::<Vec<_>>();
+        for attempt in 1..=RESUBSCRIBE_MAX_ATTEMPTS {
+            let mut failed = vec![];
+            for scope in pending {
+                if let Err(err) = self.subscription_manager.start_notify(self.listener_id, scope.clone()).await {
+                    trace!("[Subscriber] resubscribing to {:?} failed at attempt {}: {:?}", scope, attempt, err);
+                    failed.push(scope);
+                }
+            }
+            if failed.is_empty() {
+                return Ok(());
+            }
+            pending = failed;
+        }
+        Err(Error::General(format!("failed to resubscribe to {} scope(s)", pending.len())))
+    }
+
     async fn stop_subscription_receiver_task(self: &Arc<Self>) -> Result<()> {
         if self.started.compare_exchange(true, false, Ordering::SeqCst, Ordering::SeqCst).is_err() {
             return Err(Error::AlreadyStoppedError);
@@ -171,3 +248,119 @@ pub mod test_helpers {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{test_helpers::*, *};
+    use crate::{
+        address::test_helpers::get_3_addresses,
+        events::EVENT_TYPE_ARRAY,
+        scope::{BlockAddedScope, UtxosChangedScope, VirtualChainChangedScope, VirtualDaaScoreChangedScope},
+    };
+    use async_channel::{unbounded, Receiver};
+
+    /// A subscription manager failing the first start command received for some event type
+    #[derive(Debug)]
+    struct FlakySubscriptionManager {
+        inner: SubscriptionManagerMock,
+        failing: Mutex<Vec<EventType>>,
+    }
+
+    #[async_trait]
+    impl SubscriptionManager for FlakySubscriptionManager {
+        async fn start_notify(&self, id: ListenerId, scope: Scope) -> Result<()> {
+            let event: EventType = (&scope).into();
+            let should_fail = {
+                let mut failing = self.failing.lock().unwrap();
+                let position = failing.iter().position(|x| *x == event);
+                position.map(|i| failing.remove(i)).is_some()
+            };
+            if should_fail {
+                return Err(Error::General("simulated failure".to_string()));
+            }
+            self.inner.start_notify(id, scope).await
+        }
+
+        async fn stop_notify(&self, id: ListenerId, scope: Scope) -> Result<()> {
+            self.inner.stop_notify(id, scope).await
+        }
+    }
+
+    fn drain(receiver: &Receiver<SubscriptionMessage>) -> Vec<SubscriptionMessage> {
+        std::iter::from_fn(|| receiver.try_recv().ok()).collect()
+    }
+
+    const LISTENER_ID: ListenerId = 7;
+
+    /// Applies a sequence of mutations to the subscriber and returns the start commands expected when resubscribing
+    async fn mutate_and_wait(subscriber: &Arc<Subscriber>, receiver: &Receiver<SubscriptionMessage>) -> Vec<SubscriptionMessage> {
+        let addresses = get_3_addresses(false);
+        let mutations = vec![
+            Mutation::new(Command::Start, Scope::BlockAdded(BlockAddedScope::default())),
+            Mutation::new(Command::Start, Scope::VirtualChainChanged(VirtualChainChangedScope::new(true))),
+            Mutation::new(Command::Start, Scope::UtxosChanged(UtxosChangedScope::new(addresses[0..2].to_vec()))),
+            Mutation::new(Command::Stop, Scope::UtxosChanged(UtxosChangedScope::new(vec![addresses[1].clone()]))),
+            Mutation::new(Command::Start, Scope::UtxosChanged(UtxosChangedScope::new(vec![addresses[2].clone()]))),
+            Mutation::new(Command::Start, Scope::VirtualDaaScoreChanged(VirtualDaaScoreChangedScope {})),
+            Mutation::new(Command::Stop, Scope::VirtualDaaScoreChanged(VirtualDaaScoreChangedScope {})),
+        ];
+        let count = mutations.len();
+        mutations.into_iter().for_each(|x| subscriber.mutate(x).unwrap());
+        // Wait for the subscriber to process all mutations
+        for _ in 0..count {
+            receiver.recv().await.unwrap();
+        }
+
+        vec![
+            SubscriptionMessage::new(LISTENER_ID, Command::Start, Scope::BlockAdded(BlockAddedScope::default())),
+            SubscriptionMessage::new(LISTENER_ID, Command::Start, Scope::VirtualChainChanged(VirtualChainChangedScope::new(true))),
+            SubscriptionMessage::new(
+                LISTENER_ID,
+                Command::Start,
+                Scope::UtxosChanged(UtxosChangedScope::new(vec![addresses[0].clone(), addresses[2].clone()])),
+            ),
+        ]
+    }
+
+    fn assert_resubscribed(received: Vec<SubscriptionMessage>, expected: Vec<SubscriptionMessage>) {
+        assert_eq!(received.len(), expected.len(), "unexpected resubscriptions {received:?}");
+        expected.iter().for_each(|x| assert!(received.contains(x), "missing resubscription {x:?}"));
+    }
+
+    #[tokio::test]
+    async fn test_resubscribe_all() {
+        let (sender, receiver) = unbounded();
+        let manager = Arc::new(SubscriptionManagerMock::new(sender));
+        let subscriber = Arc::new(Subscriber::new(EVENT_TYPE_ARRAY[..].into(), manager, LISTENER_ID));
+        subscriber.start();
+        let expected = mutate_and_wait(&subscriber, &receiver).await;
+
+        // Simulate a reconnect
+        subscriber.resubscribe_all().await.unwrap();
+        assert_resubscribed(drain(&receiver), expected);
+
+        subscriber.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_resubscribe_all_with_failures() {
+        let (sender, receiver) = unbounded();
+        let manager = Arc::new(FlakySubscriptionManager { inner: SubscriptionManagerMock::new(sender), failing: Mutex::new(vec![]) });
+        let subscriber = Arc::new(Subscriber::new(EVENT_TYPE_ARRAY[..].into(), manager.clone(), LISTENER_ID));
+        subscriber.start();
+        let expected = mutate_and_wait(&subscriber, &receiver).await;
+
+        // Simulate a reconnect where the first attempts of some subscriptions fail
+        *manager.failing.lock().unwrap() = vec![EventType::BlockAdded, EventType::UtxosChanged, EventType::UtxosChanged];
+        subscriber.resubscribe_all().await.unwrap();
+        assert!(manager.failing.lock().unwrap().is_empty(), "all simulated failures should have been hit");
+        assert_resubscribed(drain(&receiver), expected);
+
+        // Failing more often than the maximum number of attempts is reported
+        *manager.failing.lock().unwrap() = vec![EventType::VirtualChainChanged; RESUBSCRIBE_MAX_ATTEMPTS];
+        assert!(subscriber.resubscribe_all().await.is_err());
+        assert_eq!(drain(&receiver).len(), 2);
+
+        subscriber.stop().await.unwrap();
+    }
+}
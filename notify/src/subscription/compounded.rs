@@ -17,6 +17,12 @@ impl OverallSubscription {
     pub fn new(event_type: EventType) -> Self {
         Self { event_type, active: 0 }
     }
+
+    /// The mutation to propagate upstream, stripped from the blue score floor of the listener, which is applied
+    /// downstream and must not filter the notifications of the other listeners
+    fn unfiltered(&self, mutation: Mutation) -> Mutation {
+        Mutation::new(mutation.command, self.event_type.into())
+    }
 }
 
 impl Compounded for OverallSubscription {
@@ -26,14 +32,14 @@ impl Compounded for OverallSubscription {
             Command::Start => {
                 self.active += 1;
                 if self.active == 1 {
-                    return Some(mutation);
+                    return Some(self.unfiltered(mutation));
                 }
             }
             Command::Stop => {
                 assert!(self.active > 0);
                 self.active -= 1;
                 if self.active == 0 {
-                    return Some(mutation);
+                    return Some(self.unfiltered(mutation));
                 }
             }
         }
@@ -254,8 +260,8 @@ mod tests {
     #[allow(clippy::redundant_clone)]
     fn test_overall_compounding() {
         let none = || Box::new(OverallSubscription::new(EventType::BlockAdded));
-        let add = || Mutation::new(Command::Start, Scope::BlockAdded(BlockAddedScope {}));
-        let remove = || Mutation::new(Command::Stop, Scope::BlockAdded(BlockAddedScope {}));
+        let add = || Mutation::new(Command::Start, Scope::BlockAdded(BlockAddedScope::default()));
+        let remove = || Mutation::new(Command::Stop, Scope::BlockAdded(BlockAddedScope::default()));
         let test = Test {
             name: "OverallSubscription 0 to 2 to 0",
             initial_state: none(),
@@ -264,6 +270,13 @@ mod tests {
                 Step { name: "add 2", mutation: add(), result: None },
                 Step { name: "remove 2", mutation: remove(), result: None },
                 Step { name: "remove 1", mutation: remove(), result: Some(remove()) },
+                // The blue score floor of a listener is not propagated upstream
+                Step {
+                    name: "add 1 above a floor",
+                    mutation: Mutation::new(Command::Start, Scope::BlockAdded(BlockAddedScope::new(Some(100)))),
+                    result: Some(add()),
+                },
+                Step { name: "remove 1", mutation: remove(), result: Some(remove()) },
             ],
             final_state: none(),
         };
@@ -2,7 +2,7 @@ use super::{Mutation, Single, Subscription};
 use crate::{
     address::UtxoAddress,
     events::EventType,
-    scope::{Scope, UtxosChangedScope, VirtualChainChangedScope},
+    scope::{BlockAddedScope, Scope, SinkBlueScoreChangedScope, UtxosChangedScope, VirtualChainChangedScope},
     subscription::Command,
 };
 use kaspa_addresses::Address;
@@ -21,11 +21,26 @@ use std::{
 pub struct OverallSubscription {
     event_type: EventType,
     active: bool,
+    min_blue_score: Option<u64>,
 }
 
 impl OverallSubscription {
     pub fn new(event_type: EventType, active: bool) -> Self {
-        Self { event_type, active }
+        Self { event_type, active, min_blue_score: None }
+    }
+
+    /// Suppresses the notifications referencing a blue score lower than `min_blue_score`, as old blocks
+    /// received during the initial sync.
+    ///
+    /// The floor is otherwise set by the scope of each activating mutation, see [`Scope::min_blue_score`].
+    /// Notifications with no blue score are not affected.
+    pub fn with_min_blue_score(mut self, min_blue_score: u64) -> Self {
+        self.min_blue_score = Some(min_blue_score);
+        self
+    }
+
+    pub fn min_blue_score(&self) -> Option<u64> {
+        self.min_blue_score
     }
 }
 
@@ -37,6 +52,10 @@ impl Single for OverallSubscription {
 
     fn mutate(&mut self, mutation: Mutation) -> Option<Vec<Mutation>> {
         assert_eq!(self.event_type(), mutation.event_type());
+        if mutation.active() {
+            // A floor change alone filters the notifications of this listener only, so it does not propagate
+            self.min_blue_score = mutation.scope.min_blue_score();
+        }
         if self.active != mutation.active() {
             self.active = mutation.active();
             Some(vec![mutation])
@@ -46,7 +65,11 @@ impl Single for OverallSubscription {
     }
 
     fn scope(&self) -> Scope {
-        self.event_type.into()
+        match self.event_type {
+            EventType::BlockAdded => Scope::BlockAdded(BlockAddedScope::new(self.min_blue_score)),
+            EventType::SinkBlueScoreChanged => Scope::SinkBlueScoreChanged(SinkBlueScoreChangedScope::new(self.min_blue_score)),
+            event_type => event_type.into(),
+        }
     }
 }
 
@@ -320,7 +343,7 @@ impl Subscription for UtxosChangedSubscription {
 mod tests {
     use super::super::*;
     use super::*;
-    use crate::{address::test_helpers::get_3_addresses, scope::BlockAddedScope};
+    use crate::address::test_helpers::get_3_addresses;
     use std::collections::hash_map::DefaultHasher;
 
     #[test]
@@ -474,20 +497,22 @@ mod tests {
 
     #[test]
     fn test_overall_mutation() {
-        fn s(active: bool) -> SingleSubscription {
-            Box::new(OverallSubscription { event_type: EventType::BlockAdded, active })
+        fn s(active: bool, min_blue_score: Option<u64>) -> SingleSubscription {
+            Box::new(OverallSubscription { event_type: EventType::BlockAdded, active, min_blue_score })
         }
-        fn m(command: Command) -> Mutation {
-            Mutation { command, scope: Scope::BlockAdded(BlockAddedScope {}) }
+        fn m(command: Command, min_blue_score: Option<u64>) -> Mutation {
+            Mutation { command, scope: Scope::BlockAdded(BlockAddedScope::new(min_blue_score)) }
         }
 
         // Subscriptions
-        let none = || s(false);
-        let all = || s(true);
+        let none = || s(false, None);
+        let all = || s(true, None);
+        let floored = || s(true, Some(100));
 
         // Mutations
-        let start_all = || m(Command::Start);
-        let stop_all = || m(Command::Stop);
+        let start_all = || m(Command::Start, None);
+        let stop_all = || m(Command::Stop, None);
+        let start_floored = || m(Command::Start, Some(100));
 
         // Tests
         let tests = MutationTests::new(vec![
@@ -519,6 +544,27 @@ mod tests {
                 new_state: none(),
                 result: Some(vec![stop_all()]),
             },
+            MutationTest {
+                name: "OverallSubscription None to All above a floor",
+                state: none(),
+                mutation: start_floored(),
+                new_state: floored(),
+                result: Some(vec![start_floored()]),
+            },
+            MutationTest {
+                name: "OverallSubscription All to All above a floor",
+                state: all(),
+                mutation: start_floored(),
+                new_state: floored(),
+                result: None,
+            },
+            MutationTest {
+                name: "OverallSubscription All above a floor to All",
+                state: floored(),
+                mutation: start_all(),
+                new_state: all(),
+                result: None,
+            },
         ]);
         tests.run()
     }
@@ -12,7 +12,9 @@ use kaspa_consensus_core::config::Config;
 use kaspa_consensus_core::tx::{Transaction, TransactionId};
 use kaspa_consensus_core::{api::ConsensusApi, errors::block::RuleError};
 use kaspa_consensus_notify::{
-    notification::{NewBlockTemplateNotification, Notification, PruningPointUtxoSetOverrideNotification},
+    notification::{
+        MempoolSizeChangedNotification, NewBlockTemplateNotification, Notification, PruningPointUtxoSetOverrideNotification,
+    },
     root::ConsensusNotificationRoot,
 };
 use kaspa_consensusmanager::{ConsensusInstance, ConsensusManager};
@@ -66,6 +68,8 @@ pub struct FlowContextInner {
     connection_manager: RwLock<Option<Arc<ConnectionManager>>>,
     mining_manager: Arc<MiningManager>,
     notification_root: Arc<ConsensusNotificationRoot>,
+    /// Transaction count and total mass of the mempool last notified
+    mempool_size: Mutex<(u64, u64)>,
 }
 
 #[derive(Clone)]
@@ -134,6 +138,7 @@ impl FlowContext {
                 connection_manager: Default::default(),
                 mining_manager,
                 notification_root,
+                mempool_size: Default::default(),
             }),
         }
     }
@@ -241,6 +246,7 @@ impl FlowContext {
                 self.mining_manager().handle_new_block_transactions(consensus, &block.transactions)?.iter().map(|x| x.id()),
             );
         }
+        self.on_mempool_size_changed();
 
         // Don't relay transactions when in IBD
         if self.is_ibd_running() {
@@ -250,6 +256,7 @@ impl FlowContext {
         if self.should_rebroadcast_transactions().await {
             transactions_to_broadcast
                 .enqueue_chunk(self.mining_manager().revalidate_high_priority_transactions(consensus)?.into_iter());
+            self.on_mempool_size_changed();
         }
 
         self.broadcast_transactions(transactions_to_broadcast).await
@@ -261,7 +268,7 @@ impl FlowContext {
         self.mining_manager().clear_block_template();
         // TODO: better handle notification errors
         self.notification_root
-            .notify(Notification::NewBlockTemplate(NewBlockTemplateNotification {}))
+            .notify(Notification::NewBlockTemplate(NewBlockTemplateNotification::new()))
             .map_err(|_| ProtocolError::Other("Notification error"))?;
         Ok(())
     }
@@ -269,12 +276,29 @@ impl FlowContext {
     /// Notifies that the UTXO set was reset due to pruning point change via IBD.
     pub fn on_pruning_point_utxoset_override(&self) {
         // TODO: handle notify return error
-        let _ = self.notification_root.notify(Notification::PruningPointUtxoSetOverride(PruningPointUtxoSetOverrideNotification {}));
+        let _ =
+            self.notification_root.notify(Notification::PruningPointUtxoSetOverride(PruningPointUtxoSetOverrideNotification::new()));
     }
 
     /// Notifies that a transaction has been added to the mempool.
     pub async fn on_transaction_added_to_mempool(&self) {
         // TODO: call a handler function or a predefined registered service
+        self.on_mempool_size_changed();
+    }
+
+    /// Notifies that transactions entered or left the mempool, if either its transaction count or its total mass changed
+    /// since the last notification.
+    pub fn on_mempool_size_changed(&self) {
+        // Holding the lock while reading the mempool keeps concurrent notifications in order
+        let mut last_size = self.mempool_size.lock();
+        let (transaction_count, total_mass) = self.mining_manager().transaction_count_and_mass(true, true);
+        let size = (transaction_count as u64, total_mass);
+        if size == *last_size {
+            return;
+        }
+        *last_size = size;
+        // TODO: handle notify return error
+        let _ = self.notification_root.notify(Notification::MempoolSizeChanged(MempoolSizeChangedNotification::new(size.0, size.1)));
     }
 
     pub async fn add_transaction(
@@ -285,6 +309,7 @@ impl FlowContext {
     ) -> Result<(), ProtocolError> {
         let accepted_transactions =
             self.mining_manager().validate_and_insert_transaction(consensus, transaction, Priority::High, orphan)?;
+        self.on_transaction_added_to_mempool().await;
         self.broadcast_transactions(accepted_transactions.iter().map(|x| x.id())).await
     }
 
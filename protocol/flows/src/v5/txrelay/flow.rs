@@ -186,6 +186,7 @@ impl RelayTransactionsFlow {
             let Response::Transaction(transaction) = response else { continue; };
             match self.ctx.mining_manager().validate_and_insert_transaction(consensus, transaction, Priority::Low, Orphan::Allowed) {
                 Ok(accepted_transactions) => {
+                    self.ctx.on_transaction_added_to_mempool().await;
                     // trace!("Broadcast {} accepted transaction ids", accepted_transactions.len());
                     self.ctx.broadcast_transactions(accepted_transactions.iter().map(|x| x.id())).await?;
                 }
@@ -0,0 +1,225 @@
+//! Convenience extensions of the [`RpcApi`] trait
+
+use crate::{
+    api::rpc::RpcApi, notify::connection::ChannelConnection, GetBlocksRequest, Notification, RpcAddress, RpcBalancesByAddressesEntry,
+    RpcBlock, RpcError, RpcHash, RpcResult, RpcTransaction, RpcTransactionId, RpcUtxosByAddressesEntry,
+};
+use async_channel::Receiver;
+use async_trait::async_trait;
+use futures::{
+    future::{join, FutureExt},
+    pin_mut, select,
+    stream::{self, BoxStream, StreamExt},
+};
+use kaspa_notify::{
+    connection::DeliveryMode,
+    events::EventType,
+    listener::ListenerId,
+    notification::Notification as NotificationTrait,
+    scope::{Scope, VirtualChainChangedScope},
+};
+use std::time::Duration;
+
+/// Capacity of the notification channel of a listener registered with [`RpcApiExt::register_new_channel_listener`]
+pub const DEFAULT_LISTENER_CHANNEL_CAPACITY: usize = 1_024;
+
+/// Number of lookups of the accepting block of a transaction by [`RpcApiExt::transaction_confirmations`] before giving up
+/// on a chain reorganizing on each of them
+pub const TRANSACTION_CONFIRMATIONS_ATTEMPTS: usize = 3;
+
+/// Balances and UTXOs of a set of addresses, see [`RpcApiExt::get_address_state`]
+#[derive(Clone, Debug)]
+pub struct AddressState {
+    pub balances: Vec<RpcBalancesByAddressesEntry>,
+    pub utxos: Vec<RpcUtxosByAddressesEntry>,
+}
+
+/// Chain block accepting a transaction, see [`RpcApiExt::submit_and_await_acceptance`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AcceptanceInfo {
+    pub transaction_id: RpcTransactionId,
+    pub accepting_block_hash: RpcHash,
+}
+
+/// Extension methods available to every [`RpcApi`] implementor using [`ChannelConnection`] listeners.
+#[async_trait]
+pub trait RpcApiExt: RpcApi<ChannelConnection> {
+    /// Register a new listener backed by a channel of [`DEFAULT_LISTENER_CHANNEL_CAPACITY`] notifications.
+    ///
+    /// Returns the id of the listener and the receiving end of its channel.
+    fn register_new_channel_listener(&self) -> (ListenerId, Receiver<Notification>) {
+        self.register_new_listener_with_capacity(DEFAULT_LISTENER_CHANNEL_CAPACITY)
+    }
+
+    /// Register a new listener backed by a channel able to buffer `capacity` notifications.
+    ///
+    /// Notifications are sent to the listener without waiting, so a notification arriving while the channel
+    /// is full is dropped. A larger capacity lets a slow consumer of high-rate notifications like
+    /// `UtxosChanged` absorb bursts without losing any, at the cost of holding up to `capacity`
+    /// notifications in memory. The capacity must be greater than zero.
+    ///
+    /// Returns the id of the listener and the receiving end of its channel.
+    fn register_new_listener_with_capacity(&self, capacity: usize) -> (ListenerId, Receiver<Notification>) {
+        self.register_new_listener_with_delivery_mode(capacity, DeliveryMode::DropOnFull)
+    }
+
+    /// Register a new listener backed by a channel able to buffer `capacity` notifications, handling
+    /// a full channel according to `mode`.
+    ///
+    /// Returns the id of the listener and the receiving end of its channel.
+    fn register_new_listener_with_delivery_mode(&self, capacity: usize, mode: DeliveryMode) -> (ListenerId, Receiver<Notification>) {
+        let (sender, receiver) = async_channel::bounded(capacity);
+        (self.register_new_listener(ChannelConnection::with_delivery_mode(sender, mode)), receiver)
+    }
+
+    /// Waits for the next notification of type `event` and returns it.
+    ///
+    /// A temporary listener is registered and subscribed to `event` for the duration of the call.
+    /// The listener is always unregistered before returning, including when no notification was
+    /// received before `timeout` elapsed, in which case [`RpcError::NotificationTimeout`] is returned.
+    async fn next_notification(&self, event: EventType, timeout: Duration) -> RpcResult<Notification> {
+        let (sender, receiver) = async_channel::unbounded();
+        let id = self.register_new_listener(ChannelConnection::new(sender));
+        let result = match self.start_notify(id, event.into()).await {
+            Ok(()) => {
+                let notification = async {
+                    loop {
+                        match receiver.recv().await {
+                            Ok(notification) if notification.event_type() == event => return Ok(notification),
+                            Ok(_) => {}
+                            Err(err) => return Err(RpcError::from(kaspa_notify::error::Error::from(err))),
+                        }
+                    }
+                }
+                .fuse();
+                let delay = workflow_core::task::sleep(timeout).fuse();
+                pin_mut!(notification, delay);
+                select! {
+                    notification = notification => notification,
+                    _ = delay => Err(RpcError::NotificationTimeout(event)),
+                }
+            }
+            Err(err) => Err(err),
+        };
+        let _ = self.unregister_listener(id).await;
+        result
+    }
+
+    /// Requests both the balances and the UTXOs of `addresses`.
+    ///
+    /// The two are fetched by concurrent `GetBalancesByAddresses` and `GetUtxosByAddresses` calls, the server offering
+    /// no atomic operation covering both, so they may reflect slightly different states of the DAG if a block is
+    /// accepted in between. This call is only available when the node was started with `--utxoindex`.
+    async fn get_address_state(&self, addresses: Vec<RpcAddress>) -> RpcResult<AddressState> {
+        let (balances, utxos) = join(self.get_balances_by_addresses(addresses.clone()), self.get_utxos_by_addresses(addresses)).await;
+        Ok(AddressState { balances: balances?, utxos: utxos? })
+    }
+
+    /// Streams the blocks from `low_hash` (or genesis if `None`) up to the sink and its anticone.
+    ///
+    /// Blocks are fetched in pages with [`RpcApi::get_blocks_call`], each page starting at the last block
+    /// of the previous one, so only one page is held in memory at a time. The stream ends when a page brings
+    /// no new block. After an error, the stream yields it and ends.
+    fn get_blocks_stream(&self, low_hash: Option<RpcHash>, include_transactions: bool) -> BoxStream<'_, RpcResult<RpcBlock>> {
+        let pages = stream::unfold(Some((low_hash, true)), move |state| async move {
+            let (cursor, is_first_page) = state?;
+            let request = GetBlocksRequest::new(cursor, true, include_transactions);
+            match self.get_blocks_call(request).await {
+                Ok(response) => {
+                    // Next pages start with their low hash, already yielded as the last block of the previous page
+                    let mut blocks = response.blocks;
+                    if !is_first_page && blocks.first().map(|block| block.header.hash) == cursor {
+                        blocks.remove(0);
+                    }
+                    let next = Some(blocks.last()?.header.hash);
+                    Some((Ok(blocks), Some((next, false))))
+                }
+                Err(err) => Some((Err(err), None)),
+            }
+        });
+        pages
+            .flat_map(|page| match page {
+                Ok(blocks) => stream::iter(blocks.into_iter().map(Ok).collect::<Vec<_>>()),
+                Err(err) => stream::iter(vec![Err(err)]),
+            })
+            .boxed()
+    }
+
+    /// Submits `transaction` and waits for a chain block to accept it, returning the accepting block.
+    ///
+    /// A temporary listener is subscribed to `VirtualChainChanged` notifications, including the accepted transaction ids,
+    /// before the transaction is submitted, so an acceptance cannot be missed however soon it happens. The listener is
+    /// always unregistered before returning, including when the transaction was not accepted within `timeout` after its
+    /// submission, in which case [`RpcError::TransactionAcceptanceTimeout`] is returned.
+    ///
+    /// The accepting block may later be reorganized out of the selected chain, see [`Self::transaction_confirmations`].
+    async fn submit_and_await_acceptance(&self, transaction: RpcTransaction, timeout: Duration) -> RpcResult<AcceptanceInfo> {
+        let (sender, receiver) = async_channel::unbounded();
+        let id = self.register_new_listener(ChannelConnection::new(sender));
+        let result: RpcResult<AcceptanceInfo> = async {
+            self.start_notify(id, Scope::VirtualChainChanged(VirtualChainChangedScope::new(true))).await?;
+            let transaction_id = self.submit_transaction(transaction, false).await?;
+            let acceptance = async {
+                loop {
+                    match receiver.recv().await {
+                        Ok(Notification::VirtualChainChanged(notification)) => {
+                            let accepted = notification
+                                .accepted_transaction_ids
+                                .iter()
+                                .find(|entry| entry.accepted_transaction_ids.contains(&transaction_id));
+                            if let Some(entry) = accepted {
+                                return Ok(AcceptanceInfo { transaction_id, accepting_block_hash: entry.accepting_block_hash });
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(err) => return Err(RpcError::from(kaspa_notify::error::Error::from(err))),
+                    }
+                }
+            }
+            .fuse();
+            let delay = workflow_core::task::sleep(timeout).fuse();
+            pin_mut!(acceptance, delay);
+            select! {
+                acceptance = acceptance => acceptance,
+                _ = delay => Err(RpcError::TransactionAcceptanceTimeout(transaction_id)),
+            }
+        }
+        .await;
+        let _ = self.unregister_listener(id).await;
+        result
+    }
+
+    /// Requests the number of confirmations of the transaction `txid`, or `None` if no block of the selected chain accepted it.
+    ///
+    /// The accepting block is looked up in the virtual chain from the pruning point, so a transaction accepted before the
+    /// pruning point is reported as unaccepted. A transaction accepted by the sink has one confirmation, each increment
+    /// of the blue score of the sink adding one.
+    ///
+    /// If the accepting block is reorganized out of the selected chain while its blue score is fetched, the lookup is
+    /// retried, the transaction being then accepted by another chain block or by none. A chain reorganizing on each of
+    /// [`TRANSACTION_CONFIRMATIONS_ATTEMPTS`] lookups yields [`RpcError::TransactionAcceptanceReorged`].
+    async fn transaction_confirmations(&self, txid: RpcTransactionId) -> RpcResult<Option<u64>> {
+        for _ in 0..TRANSACTION_CONFIRMATIONS_ATTEMPTS {
+            let pruning_point = self.get_block_dag_info().await?.pruning_point_hash;
+            let chain = self.get_virtual_chain_from_block(pruning_point, true).await?;
+            let Some(accepting_block_hash) = chain
+                .accepted_transaction_ids
+                .iter()
+                .find(|entry| entry.accepted_transaction_ids.contains(&txid))
+                .map(|entry| entry.accepting_block_hash)
+            else {
+                return Ok(None);
+            };
+            let accepting_blue_score = self.get_block(accepting_block_hash, false).await?.header.blue_score;
+            let sink_blue_score = self.get_sink_blue_score().await?;
+
+            // The chain from a block removes no block as long as this block is on the selected chain
+            if self.get_virtual_chain_from_block(accepting_block_hash, false).await?.removed_chain_block_hashes.is_empty() {
+                return Ok(Some(sink_blue_score.saturating_sub(accepting_blue_score) + 1));
+            }
+        }
+        Err(RpcError::TransactionAcceptanceReorged(txid))
+    }
+}
+
+impl<T> RpcApiExt for T where T: RpcApi<ChannelConnection> + ?Sized {}
@@ -1,3 +1,4 @@
+pub mod ext;
 pub mod notifications;
 pub mod ops;
 pub mod rpc;
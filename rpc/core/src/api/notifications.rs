@@ -24,7 +24,7 @@ pub enum Notification {
     #[display(fmt = "VirtualChainChanged notification: {} removed blocks, {} added blocks, {} accepted transactions", "_0.removed_chain_block_hashes.len()", "_0.added_chain_block_hashes.len()", "_0.accepted_transaction_ids.len()")]
     VirtualChainChanged(VirtualChainChangedNotification),
 
-    #[display(fmt = "FinalityConflict notification: violating block hash {}", "_0.violating_block_hash")]
+    #[display(fmt = "FinalityConflict notification ({} severity): violating block hash {}, competing tip {}, blue work difference {}", "_0.severity", "_0.violating_block_hash", "_0.competing_tip_hash", "_0.blue_work_difference")]
     FinalityConflict(FinalityConflictNotification),
 
     #[display(fmt = "FinalityConflict notification: violating block hash {}", "_0.finality_block_hash")]
@@ -44,6 +44,9 @@ pub enum Notification {
 
     #[display(fmt = "NewBlockTemplate notification")]
     NewBlockTemplate(NewBlockTemplateNotification),
+
+    #[display(fmt = "MempoolSizeChanged notification: {} transactions, total mass {}", "_0.transaction_count", "_0.total_mass")]
+    MempoolSizeChanged(MempoolSizeChangedNotification),
 }
 }
 
@@ -60,6 +63,7 @@ impl Notification {
             Notification::VirtualDaaScoreChanged(v) => to_value(&v),
             Notification::SinkBlueScoreChanged(v) => to_value(&v),
             Notification::VirtualChainChanged(v) => to_value(&v),
+            Notification::MempoolSizeChanged(v) => to_value(&v),
         }
     }
 }
@@ -123,4 +127,25 @@ mod test {
         let notification = Notification::try_from_slice(bytes);
         println!("notification: {notification:?}");
     }
+
+    #[test]
+    fn test_finality_conflict_conversion() {
+        use crate::{RpcBlueWorkType, RpcHash};
+        use kaspa_consensus_notify::notification as consensus_notify;
+
+        let conflict = consensus_notify::FinalityConflictNotification::assess(
+            RpcHash::from_u64_word(1),
+            2_300u64.into(),
+            RpcHash::from_u64_word(2),
+            2_000u64.into(),
+            1_000u64.into(),
+        );
+        let notification: Notification = (&consensus_notify::Notification::FinalityConflict(conflict)).into();
+        let Notification::FinalityConflict(ref payload) = notification else { panic!("expected a FinalityConflict notification") };
+        assert_eq!(payload.competing_tip_hash, RpcHash::from_u64_word(2));
+        assert_eq!(payload.blue_work_difference, RpcBlueWorkType::from_u64(300));
+        assert_eq!(payload.severity, RpcFinalityConflictSeverity::High);
+        let display = notification.to_string();
+        assert!(display.contains("high severity"), "severity missing from `{display}`");
+    }
 }
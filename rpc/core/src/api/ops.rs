@@ -59,6 +59,7 @@ pub enum RpcApiOps {
     NotifyVirtualDaaScoreChanged,
     NotifyVirtualChainChanged,
     NotifySinkBlueScoreChanged,
+    NotifyMempoolSizeChanged,
 
     // ~
     Subscribe,
@@ -79,6 +80,10 @@ pub enum RpcApiOps {
     VirtualDaaScoreChangedNotification,
     PruningPointUtxoSetOverrideNotification,
     NewBlockTemplateNotification,
+    MempoolSizeChangedNotification,
+
+    // Sent by a wRPC server to a newly accepted connection
+    WelcomeNotification,
 }
 //});
 
@@ -102,6 +107,7 @@ impl From<EventType> for RpcApiOps {
             EventType::VirtualDaaScoreChanged => RpcApiOps::VirtualDaaScoreChangedNotification,
             EventType::PruningPointUtxoSetOverride => RpcApiOps::PruningPointUtxoSetOverrideNotification,
             EventType::NewBlockTemplate => RpcApiOps::NewBlockTemplateNotification,
+            EventType::MempoolSizeChanged => RpcApiOps::MempoolSizeChangedNotification,
         }
     }
 }
@@ -1,7 +1,8 @@
 use crate::{
     utxo::utxo_set_into_rpc, BlockAddedNotification, FinalityConflictNotification, FinalityConflictResolvedNotification,
-    NewBlockTemplateNotification, Notification, PruningPointUtxoSetOverrideNotification, SinkBlueScoreChangedNotification,
-    UtxosChangedNotification, VirtualChainChangedNotification, VirtualDaaScoreChangedNotification,
+    MempoolSizeChangedNotification, NewBlockTemplateNotification, Notification, PruningPointUtxoSetOverrideNotification,
+    RpcFinalityConflictSeverity, SinkBlueScoreChangedNotification, UtxosChangedNotification, VirtualChainChangedNotification,
+    VirtualDaaScoreChangedNotification,
 };
 use kaspa_consensus_notify::notification as consensus_notify;
 use kaspa_index_core::notification as index_notify;
@@ -29,6 +30,7 @@ impl From<&consensus_notify::Notification> for Notification {
             consensus_notify::Notification::VirtualDaaScoreChanged(msg) => Notification::VirtualDaaScoreChanged(msg.into()),
             consensus_notify::Notification::PruningPointUtxoSetOverride(msg) => Notification::PruningPointUtxoSetOverride(msg.into()),
             consensus_notify::Notification::NewBlockTemplate(msg) => Notification::NewBlockTemplate(msg.into()),
+            consensus_notify::Notification::MempoolSizeChanged(msg) => Notification::MempoolSizeChanged(msg.into()),
         }
     }
 }
@@ -52,7 +54,22 @@ impl From<&consensus_notify::VirtualChainChangedNotification> for VirtualChainCh
 
 impl From<&consensus_notify::FinalityConflictNotification> for FinalityConflictNotification {
     fn from(item: &consensus_notify::FinalityConflictNotification) -> Self {
-        Self { violating_block_hash: item.violating_block_hash }
+        Self {
+            violating_block_hash: item.violating_block_hash,
+            competing_tip_hash: item.competing_tip_hash,
+            blue_work_difference: item.blue_work_difference,
+            severity: item.severity.into(),
+        }
+    }
+}
+
+impl From<consensus_notify::FinalityConflictSeverity> for RpcFinalityConflictSeverity {
+    fn from(item: consensus_notify::FinalityConflictSeverity) -> Self {
+        match item {
+            consensus_notify::FinalityConflictSeverity::Low => RpcFinalityConflictSeverity::Low,
+            consensus_notify::FinalityConflictSeverity::Medium => RpcFinalityConflictSeverity::Medium,
+            consensus_notify::FinalityConflictSeverity::High => RpcFinalityConflictSeverity::High,
+        }
     }
 }
 
@@ -93,6 +110,12 @@ impl From<&consensus_notify::NewBlockTemplateNotification> for NewBlockTemplateN
     }
 }
 
+impl From<&consensus_notify::MempoolSizeChangedNotification> for MempoolSizeChangedNotification {
+    fn from(item: &consensus_notify::MempoolSizeChangedNotification) -> Self {
+        Self { transaction_count: item.transaction_count, total_mass: item.total_mass }
+    }
+}
+
 // ----------------------------------------------------------------------------
 // index to rpc_core
 // ----------------------------------------------------------------------------
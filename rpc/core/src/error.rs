@@ -1,4 +1,5 @@
 use kaspa_consensus_core::tx::TransactionId;
+use kaspa_notify::events::EventType;
 use kaspa_utils::networking::IpAddress;
 use std::{net::AddrParseError, num::TryFromIntError};
 use thiserror::Error;
@@ -94,6 +95,15 @@ pub enum RpcError {
     #[error(transparent)]
     NodeIdError(#[from] uuid::Error),
 
+    #[error("Timed out waiting for a {0:?} notification")]
+    NotificationTimeout(EventType),
+
+    #[error("Timed out waiting for the acceptance of transaction {0}")]
+    TransactionAcceptanceTimeout(RpcTransactionId),
+
+    #[error("Transaction {0} kept being reorganized out of the selected chain")]
+    TransactionAcceptanceReorged(RpcTransactionId),
+
     #[error("{0}")]
     General(String),
 }
@@ -793,10 +793,36 @@ impl NotifyFinalityConflictRequest {
 #[serde(rename_all = "camelCase")]
 pub struct NotifyFinalityConflictResponse {}
 
+/// Assessment of a finality conflict, see [`FinalityConflictNotification`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RpcFinalityConflictSeverity {
+    #[default]
+    Low,
+    Medium,
+    High,
+}
+
+impl Display for RpcFinalityConflictSeverity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let severity = match self {
+            RpcFinalityConflictSeverity::Low => "low",
+            RpcFinalityConflictSeverity::Medium => "medium",
+            RpcFinalityConflictSeverity::High => "high",
+        };
+        f.write_str(severity)
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FinalityConflictNotification {
     pub violating_block_hash: RpcHash,
+    /// Tip of the selected chain the violating block competes with
+    pub competing_tip_hash: RpcHash,
+    /// Blue work difference between the competing tip and the violating block
+    pub blue_work_difference: RpcBlueWorkType,
+    pub severity: RpcFinalityConflictSeverity,
 }
 
 // ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
@@ -949,6 +975,36 @@ pub struct VirtualDaaScoreChangedNotification {
     pub virtual_daa_score: u64,
 }
 
+// ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+// MempoolSizeChangedNotification
+
+#[derive(Clone, Debug, Serialize, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NotifyMempoolSizeChangedRequest {
+    pub command: Command,
+}
+
+impl NotifyMempoolSizeChangedRequest {
+    pub fn new(command: Command) -> Self {
+        Self { command }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NotifyMempoolSizeChangedResponse {}
+
+// MempoolSizeChangedNotification is sent whenever transactions enter
+// or leave the mempool.
+//
+// See NotifyMempoolSizeChangedRequest
+#[derive(Clone, Debug, Serialize, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MempoolSizeChangedNotification {
+    pub transaction_count: u64,
+    pub total_mass: u64,
+}
+
 // ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
 // PruningPointUtxoSetOverrideNotification
 
@@ -1022,3 +1078,19 @@ impl SubscribeResponse {
 #[derive(Clone, Debug, Serialize, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UnsubscribeResponse {}
+
+///
+///  wRPC notification sent by the server to a connection right after it has been accepted
+///
+#[derive(Clone, Debug, Serialize, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WelcomeNotification {
+    /// Label identifying the connection in the server logs
+    pub connection_id: String,
+}
+
+impl WelcomeNotification {
+    pub fn new(connection_id: String) -> Self {
+        Self { connection_id }
+    }
+}
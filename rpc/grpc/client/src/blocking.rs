@@ -0,0 +1,227 @@
+//! A blocking facade over [`GrpcClient`], for callers not running an async runtime.
+
+use crate::{
+    error::{Error, Result},
+    GrpcClient,
+};
+use futures::Future;
+use kaspa_notify::{listener::ListenerId, scope::Scope};
+use kaspa_rpc_core::{api::rpc::RpcApi, notify::connection::ChannelConnection, prelude::*, Notification, RpcResult};
+use std::{
+    sync::{mpsc, Mutex},
+    thread::JoinHandle,
+};
+use tokio::{runtime::Handle, sync::oneshot};
+
+macro_rules! blocking_route {
+    ($fn:ident, $name:tt) => {
+        paste::paste! {
+            pub fn $fn(&self, request: [<$name Request>]) -> RpcResult<[<$name Response>]> {
+                self.block_on(self.client.$fn(request))
+            }
+        }
+    };
+}
+
+/// A [`GrpcClient`] exposing blocking equivalents of the [`RpcApi`] methods.
+///
+/// The client owns a current-thread runtime, driven by a dedicated thread, on which the async client runs.
+/// Notifications are delivered through a std [`mpsc::Receiver`], see [`BlockingGrpcClient::take_notification_receiver`].
+///
+/// The methods of this client block the calling thread so they must not be called from an async context.
+pub struct BlockingGrpcClient {
+    client: GrpcClient,
+    handle: Handle,
+    listener_id: ListenerId,
+    notification_receiver: Mutex<Option<mpsc::Receiver<Notification>>>,
+    shutdown: Option<oneshot::Sender<()>>,
+    driver: Option<JoinHandle<()>>,
+}
+
+impl BlockingGrpcClient {
+    pub fn connect(
+        address: String,
+        reconnect: bool,
+        override_handle_stop_notify: bool,
+        timeout_duration: Option<u64>,
+    ) -> Result<Self> {
+        let runtime =
+            tokio::runtime::Builder::new_current_thread().enable_all().build().map_err(|err| Error::String(err.to_string()))?;
+        let handle = runtime.handle().clone();
+        let (shutdown, shutdown_listener) = oneshot::channel::<()>();
+        let driver = std::thread::Builder::new()
+            .name("grpc-client-runtime".to_string())
+            .spawn(move || {
+                let _ = runtime.block_on(shutdown_listener);
+            })
+            .map_err(|err| Error::String(err.to_string()))?;
+
+        let client =
+            match handle.block_on(GrpcClient::connect(address, reconnect, None, override_handle_stop_notify, timeout_duration)) {
+                Ok(client) => client,
+                Err(err) => {
+                    let _ = shutdown.send(());
+                    let _ = driver.join();
+                    return Err(err);
+                }
+            };
+        handle.block_on(client.start());
+
+        // Forward the notifications to a std channel
+        let (sender, receiver) = async_channel::unbounded();
+        let listener_id = client.register_new_listener(ChannelConnection::new(sender));
+        let (notification_sender, notification_receiver) = mpsc::channel();
+        handle.spawn(async move {
+            while let Ok(notification) = receiver.recv().await {
+                if notification_sender.send(notification).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            client,
+            handle,
+            listener_id,
+            notification_receiver: Mutex::new(Some(notification_receiver)),
+            shutdown: Some(shutdown),
+            driver: Some(driver),
+        })
+    }
+
+    /// Runs `future` to completion on the runtime of the client, blocking the calling thread.
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.handle.block_on(future)
+    }
+
+    /// Returns the async client driven by this facade.
+    pub fn client(&self) -> &GrpcClient {
+        &self.client
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.client.is_connected()
+    }
+
+    /// Takes the receiving end of the notifications the client is subscribed to, see [`BlockingGrpcClient::start_notify`].
+    ///
+    /// Returns `None` if already taken.
+    pub fn take_notification_receiver(&self) -> Option<mpsc::Receiver<Notification>> {
+        self.notification_receiver.lock().unwrap().take()
+    }
+
+    /// Start sending notifications matching `scope` to the notification receiver.
+    pub fn start_notify(&self, scope: Scope) -> RpcResult<()> {
+        self.block_on(self.client.start_notify(self.listener_id, scope))
+    }
+
+    /// Stop sending notifications matching `scope` to the notification receiver.
+    pub fn stop_notify(&self, scope: Scope) -> RpcResult<()> {
+        self.block_on(self.client.stop_notify(self.listener_id, scope))
+    }
+
+    pub fn ping(&self) -> RpcResult<()> {
+        self.block_on(self.client.ping())
+    }
+
+    pub fn get_info(&self) -> RpcResult<GetInfoResponse> {
+        self.block_on(self.client.get_info())
+    }
+
+    pub fn get_current_network(&self) -> RpcResult<RpcNetworkType> {
+        self.block_on(self.client.get_current_network())
+    }
+
+    pub fn get_block(&self, hash: RpcHash, include_transactions: bool) -> RpcResult<RpcBlock> {
+        self.block_on(self.client.get_block(hash, include_transactions))
+    }
+
+    pub fn get_blocks(
+        &self,
+        low_hash: Option<RpcHash>,
+        include_blocks: bool,
+        include_transactions: bool,
+    ) -> RpcResult<GetBlocksResponse> {
+        self.block_on(self.client.get_blocks(low_hash, include_blocks, include_transactions))
+    }
+
+    pub fn get_block_count(&self) -> RpcResult<GetBlockCountResponse> {
+        self.block_on(self.client.get_block_count())
+    }
+
+    pub fn get_block_dag_info(&self) -> RpcResult<GetBlockDagInfoResponse> {
+        self.block_on(self.client.get_block_dag_info())
+    }
+
+    pub fn get_headers(&self, start_hash: RpcHash, limit: u64, is_ascending: bool) -> RpcResult<Vec<RpcHeader>> {
+        self.block_on(self.client.get_headers(start_hash, limit, is_ascending))
+    }
+
+    pub fn get_sink_blue_score(&self) -> RpcResult<u64> {
+        self.block_on(self.client.get_sink_blue_score())
+    }
+
+    pub fn submit_transaction(&self, transaction: RpcTransaction, allow_orphan: bool) -> RpcResult<RpcTransactionId> {
+        self.block_on(self.client.submit_transaction(transaction, allow_orphan))
+    }
+
+    pub fn get_balance_by_address(&self, address: RpcAddress) -> RpcResult<u64> {
+        self.block_on(self.client.get_balance_by_address(address))
+    }
+
+    pub fn get_utxos_by_addresses(&self, addresses: Vec<RpcAddress>) -> RpcResult<Vec<RpcUtxosByAddressesEntry>> {
+        self.block_on(self.client.get_utxos_by_addresses(addresses))
+    }
+
+    blocking_route!(ping_call, Ping);
+    blocking_route!(get_process_metrics_call, GetProcessMetrics);
+    blocking_route!(submit_block_call, SubmitBlock);
+    blocking_route!(get_block_template_call, GetBlockTemplate);
+    blocking_route!(get_block_call, GetBlock);
+    blocking_route!(get_info_call, GetInfo);
+    blocking_route!(get_current_network_call, GetCurrentNetwork);
+    blocking_route!(get_peer_addresses_call, GetPeerAddresses);
+    blocking_route!(get_selected_tip_hash_call, GetSelectedTipHash);
+    blocking_route!(get_mempool_entry_call, GetMempoolEntry);
+    blocking_route!(get_mempool_entries_call, GetMempoolEntries);
+    blocking_route!(get_connected_peer_info_call, GetConnectedPeerInfo);
+    blocking_route!(add_peer_call, AddPeer);
+    blocking_route!(submit_transaction_call, SubmitTransaction);
+    blocking_route!(get_subnetwork_call, GetSubnetwork);
+    blocking_route!(get_virtual_chain_from_block_call, GetVirtualChainFromBlock);
+    blocking_route!(get_blocks_call, GetBlocks);
+    blocking_route!(get_block_count_call, GetBlockCount);
+    blocking_route!(get_block_dag_info_call, GetBlockDagInfo);
+    blocking_route!(resolve_finality_conflict_call, ResolveFinalityConflict);
+    blocking_route!(shutdown_call, Shutdown);
+    blocking_route!(get_headers_call, GetHeaders);
+    blocking_route!(get_utxos_by_addresses_call, GetUtxosByAddresses);
+    blocking_route!(get_balance_by_address_call, GetBalanceByAddress);
+    blocking_route!(get_balances_by_addresses_call, GetBalancesByAddresses);
+    blocking_route!(get_sink_blue_score_call, GetSinkBlueScore);
+    blocking_route!(ban_call, Ban);
+    blocking_route!(unban_call, Unban);
+    blocking_route!(estimate_network_hashes_per_second_call, EstimateNetworkHashesPerSecond);
+    blocking_route!(get_mempool_entries_by_addresses_call, GetMempoolEntriesByAddresses);
+    blocking_route!(get_coin_supply_call, GetCoinSupply);
+
+    /// Disconnects from the server and stops the runtime of the client.
+    pub fn disconnect(mut self) -> Result<()> {
+        let handle = self.handle.clone();
+        handle.block_on(async {
+            self.client.stop().await?;
+            self.client.shutdown().await
+        })
+    }
+}
+
+impl Drop for BlockingGrpcClient {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(driver) = self.driver.take() {
+            let _ = driver.join();
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use crate::{
+    connection_event::ConnectionEvent,
+    error::Result,
+    reconnect::ReconnectPolicy,
+    request_id::{DynRequestIdSource, RandomRequestIdSource},
+    wire_observer::DynWireObserver,
+    GrpcClient, DEFAULT_MAX_REQUEST_SIZE, REQUEST_TIMEOUT_DURATION,
+};
+use async_channel::Sender;
+use std::{sync::Arc, time::Duration};
+use tonic::transport::Endpoint;
+
+/// HTTP/2 flow control and keepalive settings of the connection to the server.
+///
+/// Unset values keep the defaults of the transport. Larger windows improve the throughput of bulk
+/// operations over high latency links, at the cost of more memory buffered per connection.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Http2Settings {
+    /// Connection-level flow control window size, in bytes
+    pub initial_connection_window_size: Option<u32>,
+    /// Stream-level flow control window size, in bytes
+    pub initial_stream_window_size: Option<u32>,
+    /// Interval between two HTTP/2 keepalive pings
+    pub keep_alive_interval: Option<Duration>,
+    /// Delay after which the connection is closed if a keepalive ping is not acknowledged
+    pub keep_alive_timeout: Option<Duration>,
+}
+
+impl Http2Settings {
+    pub(crate) fn apply(&self, mut endpoint: Endpoint) -> Endpoint {
+        if let Some(size) = self.initial_connection_window_size {
+            endpoint = endpoint.initial_connection_window_size(size);
+        }
+        if let Some(size) = self.initial_stream_window_size {
+            endpoint = endpoint.initial_stream_window_size(size);
+        }
+        if let Some(interval) = self.keep_alive_interval {
+            endpoint = endpoint.http2_keep_alive_interval(interval);
+        }
+        if let Some(timeout) = self.keep_alive_timeout {
+            endpoint = endpoint.keep_alive_timeout(timeout);
+        }
+        endpoint
+    }
+}
+
+/// Behavior of the client when the channel forwarding the received notifications to its notifier is full
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NotifyChannelPolicy {
+    /// Drop the notification
+    #[default]
+    Drop,
+    /// Wait up to the given delay for the channel to have room, dropping the notification if it has none by then.
+    ///
+    /// No response is processed while waiting, so this trades the throughput of the stream for reliability.
+    Await(Duration),
+    /// Close the connection to the server, letting the client reconnect if so configured
+    Disconnect,
+}
+
+/// Strategy matching the responses received from the server with the pending requests
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResolverKind {
+    /// Match a response with the request bearing the same id, requiring the server to echo the request ids
+    Id,
+    /// Match a response with the oldest pending request of the same operation and properties
+    Queue,
+}
+
+/// Builder of a [`GrpcClient`], for the connection settings not covered by [`GrpcClient::connect`].
+#[derive(Clone)]
+pub struct GrpcClientBuilder {
+    pub(crate) address: String,
+    pub(crate) reconnect: bool,
+    pub(crate) connection_event_sender: Option<Sender<ConnectionEvent>>,
+    pub(crate) override_handle_stop_notify: bool,
+    pub(crate) timeout_duration: u64,
+    pub(crate) request_id_source: DynRequestIdSource,
+    pub(crate) handshake: bool,
+    pub(crate) timeout_monitor: bool,
+    pub(crate) http2: Http2Settings,
+    pub(crate) info_refresh_interval: Option<Duration>,
+    pub(crate) notify_channel_capacity: Option<usize>,
+    pub(crate) notify_channel_policy: NotifyChannelPolicy,
+    pub(crate) lazy: bool,
+    pub(crate) coalesce_reads: bool,
+    pub(crate) reconnect_policy: ReconnectPolicy,
+    pub(crate) wire_observer: Option<DynWireObserver>,
+    pub(crate) resolver_kind: Option<ResolverKind>,
+    pub(crate) strict_handshake: bool,
+    pub(crate) max_request_size: usize,
+}
+
+impl GrpcClientBuilder {
+    pub fn new(address: String) -> Self {
+        Self {
+            address,
+            reconnect: false,
+            connection_event_sender: None,
+            override_handle_stop_notify: false,
+            timeout_duration: REQUEST_TIMEOUT_DURATION,
+            request_id_source: Arc::new(RandomRequestIdSource),
+            handshake: true,
+            timeout_monitor: true,
+            http2: Http2Settings::default(),
+            info_refresh_interval: None,
+            notify_channel_capacity: None,
+            notify_channel_policy: NotifyChannelPolicy::default(),
+            lazy: false,
+            coalesce_reads: false,
+            reconnect_policy: ReconnectPolicy::default(),
+            wire_observer: None,
+            resolver_kind: None,
+            strict_handshake: false,
+            max_request_size: DEFAULT_MAX_REQUEST_SIZE,
+        }
+    }
+
+    /// Automatically reconnect to the server when the connection is lost
+    pub fn reconnect(mut self, reconnect: bool) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    /// Delays between the reconnection attempts, see [`ReconnectPolicy`]
+    pub fn reconnect_policy(mut self, reconnect_policy: ReconnectPolicy) -> Self {
+        self.reconnect_policy = reconnect_policy;
+        self
+    }
+
+    /// Double the delay between two reconnection attempts after each failed one, up to `max_delay`
+    pub fn reconnect_max_delay(mut self, max_delay: Duration) -> Self {
+        self.reconnect_policy.max_delay = max_delay;
+        self
+    }
+
+    /// Randomize each reconnection delay between zero and its backoff window
+    pub fn reconnect_jitter(mut self, jitter: bool) -> Self {
+        self.reconnect_policy.jitter = jitter;
+        self
+    }
+
+    pub fn connection_event_sender(mut self, connection_event_sender: Sender<ConnectionEvent>) -> Self {
+        self.connection_event_sender = Some(connection_event_sender);
+        self
+    }
+
+    pub fn override_handle_stop_notify(mut self, override_handle_stop_notify: bool) -> Self {
+        self.override_handle_stop_notify = override_handle_stop_notify;
+        self
+    }
+
+    /// Request timeout in milliseconds
+    pub fn timeout_duration(mut self, timeout_duration: u64) -> Self {
+        self.timeout_duration = timeout_duration;
+        self
+    }
+
+    pub fn request_id_source(mut self, request_id_source: DynRequestIdSource) -> Self {
+        self.request_id_source = request_id_source;
+        self
+    }
+
+    /// Query the server capabilities with a `GetInfo` request when connecting, true by default.
+    ///
+    /// With no handshake, the stream is opened with a `Ping` request and the server is assumed to handle neither
+    /// message ids nor `StopNotify` commands. This suits servers where `GetInfo` is expensive or restricted.
+    pub fn handshake(mut self, handshake: bool) -> Self {
+        self.handshake = handshake;
+        self
+    }
+
+    /// Run the client-side monitor expiring the pending requests after the request timeout.
+    ///
+    /// When disabled, no timeout task is spawned and calls rely solely on the deadlines of the transport.
+    pub fn timeout_monitor(mut self, timeout_monitor: bool) -> Self {
+        self.timeout_monitor = timeout_monitor;
+        self
+    }
+
+    /// Connection-level HTTP/2 flow control window size, in bytes
+    pub fn initial_connection_window_size(mut self, size: u32) -> Self {
+        self.http2.initial_connection_window_size = Some(size);
+        self
+    }
+
+    /// Stream-level HTTP/2 flow control window size, in bytes
+    pub fn initial_stream_window_size(mut self, size: u32) -> Self {
+        self.http2.initial_stream_window_size = Some(size);
+        self
+    }
+
+    /// Send HTTP/2 keepalive pings every `interval`, closing the connection if one is not acknowledged within `timeout`
+    pub fn keep_alive(mut self, interval: Duration, timeout: Duration) -> Self {
+        self.http2.keep_alive_interval = Some(interval);
+        self.http2.keep_alive_timeout = Some(timeout);
+        self
+    }
+
+    pub fn http2_settings(mut self, http2: Http2Settings) -> Self {
+        self.http2 = http2;
+        self
+    }
+
+    /// Refresh the cached `GetInfo` response of the server every `interval`, see [`GrpcClient::cached_get_info`].
+    ///
+    /// A [`ConnectionEvent::SyncStatusChanged`] event is sent whenever the `is_synced` status of the server flips.
+    /// Disabled by default.
+    pub fn info_refresh_interval(mut self, interval: Duration) -> Self {
+        self.info_refresh_interval = Some(interval);
+        self
+    }
+
+    /// Bounds the channel forwarding the received notifications to the notifier, unbounded by default.
+    ///
+    /// The capacity must be greater than zero. See [`GrpcClientBuilder::notify_channel_policy`] for the handling of a full channel.
+    pub fn notify_channel_capacity(mut self, capacity: usize) -> Self {
+        self.notify_channel_capacity = Some(capacity);
+        self
+    }
+
+    /// Behavior when the notification channel is full, dropping the notification by default
+    pub fn notify_channel_policy(mut self, policy: NotifyChannelPolicy) -> Self {
+        self.notify_channel_policy = policy;
+        self
+    }
+
+    /// Defer the connection to the server until the first call, see [`GrpcClient::new_lazy`].
+    ///
+    /// A lazy client always reconnects automatically, whatever [`GrpcClientBuilder::reconnect`] is set to.
+    pub fn lazy(mut self, lazy: bool) -> Self {
+        self.lazy = lazy;
+        self
+    }
+
+    /// Share a single server round trip between the identical idempotent reads in flight, such as concurrent `GetBlock`
+    /// calls for a same hash, fanning the response out to all the callers. Mutating calls are never coalesced.
+    ///
+    /// Disabled by default.
+    pub fn coalesce_reads(mut self, coalesce_reads: bool) -> Self {
+        self.coalesce_reads = coalesce_reads;
+        self
+    }
+
+    /// Report the op, the id and the encoded size of every request sent and of every response and notification received,
+    /// for diagnosing protocol or bandwidth issues. The handshake of the connection is not reported.
+    ///
+    /// No observer by default.
+    pub fn wire_observer(mut self, wire_observer: DynWireObserver) -> Self {
+        self.wire_observer = Some(wire_observer);
+        self
+    }
+
+    /// Force the strategy matching the responses with the requests, instead of choosing it from the capabilities
+    /// reported by the server, see [`GrpcClient::resolver_kind`].
+    ///
+    /// Forcing [`ResolverKind::Id`] against a server not echoing the request ids is allowed but unsafe: no response
+    /// would match its request, every call then failing on timeout.
+    pub fn resolver_kind(mut self, resolver_kind: ResolverKind) -> Self {
+        self.resolver_kind = Some(resolver_kind);
+        self
+    }
+
+    /// Fail the connection with [`Error::Handshake`] if the `GetInfo` handshake response of the server cannot be parsed.
+    ///
+    /// By default such a failure is tolerated, the server then being assumed to handle neither message ids nor
+    /// `StopNotify` commands. Has no effect without a handshake.
+    ///
+    /// [`Error::Handshake`]: crate::error::Error::Handshake
+    pub fn strict_handshake(mut self, strict_handshake: bool) -> Self {
+        self.strict_handshake = strict_handshake;
+        self
+    }
+
+    /// Fail the calls whose encoded request exceeds `max_request_size` bytes with [`Error::RequestTooLarge`], before
+    /// sending anything to the server.
+    ///
+    /// Defaults to [`DEFAULT_MAX_REQUEST_SIZE`], the largest message accepted by the server.
+    ///
+    /// [`Error::RequestTooLarge`]: crate::error::Error::RequestTooLarge
+    pub fn max_request_size(mut self, max_request_size: usize) -> Self {
+        self.max_request_size = max_request_size;
+        self
+    }
+
+    pub async fn connect(self) -> Result<GrpcClient> {
+        GrpcClient::connect_with_builder(self).await
+    }
+}
@@ -0,0 +1,123 @@
+//! Coalescing of the identical read requests in flight, see [`GrpcClientBuilder::coalesce_reads`].
+//!
+//! [`GrpcClientBuilder::coalesce_reads`]: crate::builder::GrpcClientBuilder::coalesce_reads
+
+use crate::error::{Error, Result};
+use kaspa_grpc_core::protowire::{KaspadRequest, KaspadResponse};
+use kaspa_rpc_core::api::ops::RpcApiOps;
+use prost::Message;
+use std::{collections::HashMap, sync::Mutex};
+use tokio::sync::oneshot;
+
+/// Operation and encoded payload of a request, the id set by the client being left out
+type RequestKey = (RpcApiOps, Vec<u8>);
+
+type Followers = Vec<oneshot::Sender<Result<KaspadResponse>>>;
+
+/// Returns true if a request of `op` only reads the state of the server, so concurrent identical requests may share a response.
+///
+/// Mutating operations, subscriptions included, are never coalesced.
+fn is_idempotent_read(op: &RpcApiOps) -> bool {
+    matches!(
+        op,
+        RpcApiOps::Ping
+            | RpcApiOps::GetProcessMetrics
+            | RpcApiOps::GetCurrentNetwork
+            | RpcApiOps::GetBlockTemplate
+            | RpcApiOps::GetPeerAddresses
+            | RpcApiOps::GetSelectedTipHash
+            | RpcApiOps::GetMempoolEntry
+            | RpcApiOps::GetMempoolEntries
+            | RpcApiOps::GetConnectedPeerInfo
+            | RpcApiOps::GetBlock
+            | RpcApiOps::GetSubnetwork
+            | RpcApiOps::GetVirtualChainFromBlock
+            | RpcApiOps::GetBlocks
+            | RpcApiOps::GetBlockCount
+            | RpcApiOps::GetBlockDagInfo
+            | RpcApiOps::GetHeaders
+            | RpcApiOps::GetUtxosByAddresses
+            | RpcApiOps::GetBalanceByAddress
+            | RpcApiOps::GetBalancesByAddresses
+            | RpcApiOps::GetSinkBlueScore
+            | RpcApiOps::GetInfo
+            | RpcApiOps::EstimateNetworkHashesPerSecond
+            | RpcApiOps::GetMempoolEntriesByAddresses
+            | RpcApiOps::GetCoinSupply
+    )
+}
+
+/// Role of a call in the coalescing of its request, see [`Coalescer::join`]
+pub(crate) enum Coalesced<'a> {
+    /// No identical request is in flight, so the call sends its request and hands the outcome over to the followers
+    Leader(Leader<'a>),
+    /// An identical request is in flight, its outcome being delivered by the receiver
+    Follower(oneshot::Receiver<Result<KaspadResponse>>),
+}
+
+/// Registry of the coalescable requests in flight, each with the calls awaiting its outcome
+#[derive(Debug, Default)]
+pub(crate) struct Coalescer {
+    in_flight: Mutex<HashMap<RequestKey, Followers>>,
+}
+
+impl Coalescer {
+    /// Joins the call of `request` to an identical request in flight if any, making it the leader of a new one otherwise.
+    ///
+    /// Returns `None` if requests of `op` are never coalesced.
+    pub(crate) fn join(&self, op: &RpcApiOps, request: &KaspadRequest) -> Option<Coalesced<'_>> {
+        if !is_idempotent_read(op) {
+            return None;
+        }
+        let key = (op.clone(), KaspadRequest { id: 0, payload: request.payload.clone() }.encode_to_vec());
+        let mut in_flight = self.in_flight.lock().unwrap();
+        match in_flight.get_mut(&key) {
+            Some(followers) => {
+                let (sender, receiver) = oneshot::channel();
+                followers.push(sender);
+                Some(Coalesced::Follower(receiver))
+            }
+            None => {
+                in_flight.insert(key.clone(), vec![]);
+                Some(Coalesced::Leader(Leader { coalescer: self, key: Some(key) }))
+            }
+        }
+    }
+}
+
+/// Call actually sending a coalesced request.
+///
+/// A leader dropped before resolving its request, as when its call is cancelled, releases the followers with a
+/// [`Error::ChannelRecvError`].
+pub(crate) struct Leader<'a> {
+    coalescer: &'a Coalescer,
+    key: Option<RequestKey>,
+}
+
+impl Leader<'_> {
+    /// Hands the outcome of the request over to the followers.
+    ///
+    /// Errors other than a timeout or a lost connection reach the followers as [`Error::String`].
+    pub(crate) fn resolve(mut self, result: &Result<KaspadResponse>) {
+        for follower in self.take_followers() {
+            let result = match result {
+                Ok(response) => Ok(response.clone()),
+                Err(Error::Timeout) => Err(Error::Timeout),
+                Err(Error::NotConnected) => Err(Error::NotConnected),
+                Err(err) => Err(Error::String(err.to_string())),
+            };
+            let _ = follower.send(result);
+        }
+    }
+
+    /// Removes the request from the registry, so an identical request sent from now on is not coalesced with this one
+    fn take_followers(&mut self) -> Followers {
+        self.key.take().and_then(|key| self.coalescer.in_flight.lock().unwrap().remove(&key)).unwrap_or_default()
+    }
+}
+
+impl Drop for Leader<'_> {
+    fn drop(&mut self) {
+        self.take_followers();
+    }
+}
@@ -2,4 +2,8 @@
 pub enum ConnectionEvent {
     Connected,
     Disconnected,
+    /// The `is_synced` status of the server changed, as observed by the periodic `GetInfo` refresh
+    SyncStatusChanged {
+        is_synced: bool,
+    },
 }
@@ -1,6 +1,7 @@
 use kaspa_notify::error::Error as NotifyError;
 use kaspa_rpc_core::RpcError;
 use thiserror::Error;
+use tonic::Code;
 
 pub type BoxedStdError = Box<(dyn std::error::Error + Sync + std::marker::Send + 'static)>;
 
@@ -12,8 +13,30 @@ pub enum Error {
     #[error("gRPC invalid address schema {0}")]
     GrpcAddressSchema(String),
 
+    /// A gRPC status not mapped to a more specific variant
     #[error("gRPC client error {0}")]
-    TonicStatus(#[from] tonic::Status),
+    TonicStatus(tonic::Status),
+
+    #[error("gRPC server unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("gRPC deadline exceeded: {0}")]
+    DeadlineExceeded(String),
+
+    #[error("gRPC resource exhausted: {0}")]
+    ResourceExhausted(String),
+
+    #[error("gRPC request cancelled: {0}")]
+    Cancelled(String),
+
+    #[error("gRPC invalid argument: {0}")]
+    InvalidArgument(String),
+
+    #[error("gRPC unauthenticated: {0}")]
+    Unauthenticated(String),
+
+    #[error("gRPC permission denied: {0}")]
+    PermissionDenied(String),
 
     /// RPC call timeout
     #[error("RPC request timeout")]
@@ -34,11 +57,61 @@ pub enum Error {
     #[error("Missing request payload")]
     MissingRequestPayload,
 
+    /// The encoded request exceeds the maximum request size of the client, see [`GrpcClientBuilder::max_request_size`]
+    ///
+    /// [`GrpcClientBuilder::max_request_size`]: crate::builder::GrpcClientBuilder::max_request_size
+    #[error("Request of {0} bytes exceeds the maximum request size of {1} bytes")]
+    RequestTooLarge(usize, usize),
+
     #[error("Missing response payload")]
     MissingResponsePayload,
 
     #[error("Not connected to server")]
     NotConnected,
+
+    #[error("Timeout while waiting for the connection to the server")]
+    ConnectionTimeout,
+
+    #[error("The server does not handle message ids")]
+    MessageIdUnsupported,
+
+    /// The `GetInfo` handshake response could not be parsed, in strict handshake mode
+    #[error("gRPC handshake failed: {0}")]
+    Handshake(String),
+
+    /// The server capabilities changed on reconnection while the request was pending, switching the strategy matching
+    /// the responses with the requests, see [`GrpcClient::resolver_kind`]
+    ///
+    /// [`GrpcClient::resolver_kind`]: crate::GrpcClient::resolver_kind
+    #[error("The server capabilities changed while the request was pending")]
+    ResolverSwitched,
+
+    /// The client was shut down while the request was pending
+    #[error("The client is shutting down")]
+    Shutdown,
+}
+
+impl Error {
+    /// Returns true if the error is likely transient, meaning the same request may succeed if retried later.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Error::Unavailable(_) | Error::DeadlineExceeded(_) | Error::ResourceExhausted(_) | Error::Timeout)
+    }
+}
+
+impl From<tonic::Status> for Error {
+    fn from(status: tonic::Status) -> Self {
+        let message = status.message().to_string();
+        match status.code() {
+            Code::Unavailable => Error::Unavailable(message),
+            Code::DeadlineExceeded => Error::DeadlineExceeded(message),
+            Code::ResourceExhausted => Error::ResourceExhausted(message),
+            Code::Cancelled => Error::Cancelled(message),
+            Code::InvalidArgument => Error::InvalidArgument(message),
+            Code::Unauthenticated => Error::Unauthenticated(message),
+            Code::PermissionDenied => Error::PermissionDenied(message),
+            _ => Error::TonicStatus(status),
+        }
+    }
 }
 
 impl From<Error> for RpcError {
@@ -97,3 +170,29 @@ impl From<Error> for NotifyError {
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_mapping() {
+        let cases: Vec<(Code, fn(&Error) -> bool, bool)> = vec![
+            (Code::Unavailable, |x| matches!(x, Error::Unavailable(_)), true),
+            (Code::DeadlineExceeded, |x| matches!(x, Error::DeadlineExceeded(_)), true),
+            (Code::ResourceExhausted, |x| matches!(x, Error::ResourceExhausted(_)), true),
+            (Code::Cancelled, |x| matches!(x, Error::Cancelled(_)), false),
+            (Code::InvalidArgument, |x| matches!(x, Error::InvalidArgument(_)), false),
+            (Code::Unauthenticated, |x| matches!(x, Error::Unauthenticated(_)), false),
+            (Code::PermissionDenied, |x| matches!(x, Error::PermissionDenied(_)), false),
+            (Code::Internal, |x| matches!(x, Error::TonicStatus(status) if status.code() == Code::Internal), false),
+            (Code::Unknown, |x| matches!(x, Error::TonicStatus(status) if status.code() == Code::Unknown), false),
+        ];
+        for (code, is_expected_variant, is_retryable) in cases {
+            let err = Error::from(tonic::Status::new(code, "test message"));
+            assert!(is_expected_variant(&err), "status code {code:?} mapped to unexpected error {err:?}");
+            assert_eq!(err.is_retryable(), is_retryable, "unexpected retryability of {err:?}");
+            assert!(err.to_string().contains("test message"));
+        }
+    }
+}
@@ -1,8 +1,17 @@
 use self::{
+    builder::{GrpcClientBuilder, Http2Settings, NotifyChannelPolicy, ResolverKind},
+    coalesce::{Coalesced, Coalescer},
     error::{Error, Result},
-    resolver::{id::IdResolver, queue::QueueResolver, DynResolver},
+    op_stats::{CallOutcome, OpStats, OpStatsRecorder},
+    priority::{RequestPriority, RequestQueue},
+    reconnect::ReconnectPolicy,
+    request_id::DynRequestIdSource,
+    resolver::{id::IdResolver, queue::QueueResolver, DynResolver, PendingGuard},
+    stream_closed::StreamClosed,
+    subscription::Subscription,
+    wire_observer::DynWireObserver,
 };
-use async_channel::Sender;
+use async_channel::{Sender, TrySendError};
 use async_trait::async_trait;
 use connection_event::ConnectionEvent;
 use futures::{
@@ -13,15 +22,15 @@ use futures::{
 use kaspa_core::{debug, trace};
 use kaspa_grpc_core::{
     channel::NotificationChannel,
-    protowire::{kaspad_request, rpc_client::RpcClient, GetInfoRequestMessage, KaspadRequest, KaspadResponse},
+    protowire::{kaspad_request, rpc_client::RpcClient, GetInfoRequestMessage, KaspadRequest, KaspadResponse, PingRequestMessage},
     RPC_MAX_MESSAGE_SIZE,
 };
 use kaspa_notify::{
     error::Result as NotifyResult,
-    events::{EventType, EVENT_TYPE_ARRAY},
+    events::{EventArray, EventType, EVENT_TYPE_ARRAY},
     listener::ListenerId,
     notifier::Notifier,
-    scope::Scope,
+    scope::{Scope, VirtualChainChangedScope},
     subscriber::{Subscriber, SubscriptionManager},
     subscription::Command,
 };
@@ -35,25 +44,50 @@ use kaspa_rpc_core::{
         collector::{RpcCoreCollector, RpcCoreConverter},
         connection::ChannelConnection,
     },
-    Notification, NotificationSender,
+    Notification, NotificationSender, RpcAddress,
 };
 use kaspa_utils::triggers::DuplexTrigger;
+use prost::Message;
 use regex::Regex;
 use std::{
+    collections::HashMap,
     sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, Weak,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
+use tokio::sync::{mpsc, watch};
+use tokio_stream::wrappers::UnboundedReceiverStream;
 use tonic::Streaming;
 use tonic::{codec::CompressionEncoding, transport::Endpoint};
+use tracing::{field, Instrument, Span};
 
+pub mod blocking;
+pub mod builder;
+mod coalesce;
 mod connection_event;
 pub mod error;
+#[cfg(any(test, feature = "testing"))]
+pub mod mock;
+pub mod op_stats;
+pub mod pool;
+pub mod priority;
+pub mod reconnect;
+pub mod request_id;
 mod resolver;
 #[macro_use]
 mod route;
+pub mod stream_closed;
+pub mod subscription;
+#[cfg(test)]
+mod test_server;
+pub mod wire_observer;
+
+type KaspadResponseStreamSender = mpsc::UnboundedSender<RpcResult<KaspadResponse>>;
+
+/// Responses to a streaming call, see [`GrpcClient::call_stream`]
+pub type KaspadResponseStream = UnboundedReceiverStream<RpcResult<KaspadResponse>>;
 
 #[derive(Debug)]
 pub struct GrpcClient {
@@ -71,24 +105,56 @@ impl GrpcClient {
         override_handle_stop_notify: bool,
         timeout_duration: Option<u64>,
     ) -> Result<GrpcClient> {
+        Self::builder_from_args(address, reconnect, connection_event_sender, override_handle_stop_notify, timeout_duration)
+            .connect()
+            .await
+    }
+
+    fn builder_from_args(
+        address: String,
+        reconnect: bool,
+        connection_event_sender: Option<Sender<ConnectionEvent>>,
+        override_handle_stop_notify: bool,
+        timeout_duration: Option<u64>,
+    ) -> GrpcClientBuilder {
+        let mut builder =
+            GrpcClientBuilder::new(address).reconnect(reconnect).override_handle_stop_notify(override_handle_stop_notify);
+        if let Some(connection_event_sender) = connection_event_sender {
+            builder = builder.connection_event_sender(connection_event_sender);
+        }
+        if let Some(timeout_duration) = timeout_duration {
+            builder = builder.timeout_duration(timeout_duration);
+        }
+        builder
+    }
+
+    /// Creates a client for the server at `address` without connecting to it.
+    ///
+    /// The connection is only initiated by the first call or by [`GrpcClient::start`], and retried in the background until
+    /// it succeeds, the client reconnecting automatically afterwards. Calls made in the meantime wait for the connection
+    /// up to the request timeout. This suits a service starting before the node it depends on.
+    pub async fn new_lazy(address: String) -> Result<GrpcClient> {
+        GrpcClientBuilder::new(address).lazy(true).connect().await
+    }
+
+    async fn connect_with_builder(builder: GrpcClientBuilder) -> Result<GrpcClient> {
         let schema = Regex::new(r"^grpc://").unwrap();
-        if !schema.is_match(&address) {
-            return Err(Error::GrpcAddressSchema(address));
+        if !schema.is_match(&builder.address) {
+            return Err(Error::GrpcAddressSchema(builder.address));
         }
-        let notify_channel = NotificationChannel::default();
-        let inner = Inner::connect(
-            address,
-            reconnect,
-            notify_channel.sender(),
-            connection_event_sender,
-            override_handle_stop_notify,
-            timeout_duration.unwrap_or(REQUEST_TIMEOUT_DURATION),
-        )
-        .await?;
+        let notify_channel = match builder.notify_channel_capacity {
+            Some(capacity) => NotificationChannel::new(async_channel::bounded(capacity)),
+            None => NotificationChannel::default(),
+        };
+        let inner = match builder.lazy {
+            true => Inner::new_lazy(builder, notify_channel.sender()),
+            false => Inner::connect(builder, notify_channel.sender()).await?,
+        };
         let core_events = EVENT_TYPE_ARRAY[..].into();
         let converter = Arc::new(RpcCoreConverter::new());
         let collector = Arc::new(RpcCoreCollector::new(notify_channel.receiver(), converter));
         let subscriber = Arc::new(Subscriber::new(core_events, inner.clone(), 0));
+        inner.set_subscriber(&subscriber);
 
         let notifier = Arc::new(Notifier::new(core_events, vec![collector], vec![subscriber], 10, GRPC_CLIENT));
 
@@ -101,9 +167,28 @@ impl GrpcClient {
     }
 
     pub async fn start(&self) {
+        self.connect_lazily();
         self.notifier().start();
     }
 
+    /// Registers a consumer of the [`StreamClosed`] signals, sent whenever the stream of responses of the server closes
+    /// while the client is not shutting down.
+    ///
+    /// The signal rides along the notifications, letting consumers mark the data they derive from them as stale until
+    /// the client reconnects. The returned receiver is unbounded.
+    pub fn register_stream_closed_listener(&self) -> async_channel::Receiver<StreamClosed> {
+        let (sender, receiver) = async_channel::unbounded();
+        self.inner.stream_closed_senders.lock().unwrap().push(sender);
+        receiver
+    }
+
+    /// Starts connecting to the server if the client is lazy and not connecting yet, see [`GrpcClient::new_lazy`].
+    fn connect_lazily(&self) {
+        if self.inner.lazy {
+            self.inner.clone().start_lazy_connection();
+        }
+    }
+
     pub async fn stop(&self) -> Result<()> {
         self.notifier().stop().await?;
         Ok(())
@@ -113,14 +198,154 @@ impl GrpcClient {
         self.inner.is_connected()
     }
 
+    /// Sends a request to the server and returns a stream yielding all the responses the server sends back for it.
+    ///
+    /// The server ends the stream by sending a response with no payload for the request id, so this call mode
+    /// requires a server handling message ids.
+    pub async fn call_stream(&self, op: RpcApiOps, request: impl Into<KaspadRequest>) -> Result<KaspadResponseStream> {
+        self.connect_lazily();
+        self.inner.call_stream(op, request).await
+    }
+
+    /// Sends a raw protowire request to the server and returns the raw response, bypassing the typed wrappers.
+    ///
+    /// Meant for tooling and tests, this allows sending an op not yet routed by the client or a deliberately malformed
+    /// request. The request goes through the same checks, timeout and priority as any other call.
+    pub async fn raw_call(&self, op: RpcApiOps, request: KaspadRequest) -> Result<KaspadResponse> {
+        self.connect_lazily();
+        self.inner.call(op, request).await
+    }
+
+    /// Waits until the client is connected to the server, failing with [`Error::ConnectionTimeout`] if it is
+    /// still not connected once `timeout` has elapsed.
+    pub async fn wait_for_connected(&self, timeout: Duration) -> Result<()> {
+        self.inner.wait_for_connected(timeout).await
+    }
+
     pub fn handle_message_id(&self) -> bool {
         self.inner.handle_message_id()
     }
 
+    /// Returns the strategy matching the responses with the requests, forced by the builder or chosen from the
+    /// capabilities reported by the server on the last connection
+    pub fn resolver_kind(&self) -> ResolverKind {
+        self.inner.resolver().kind()
+    }
+
     pub fn handle_stop_notify(&self) -> bool {
         self.inner.handle_stop_notify()
     }
 
+    /// Adds `addresses` to the `UtxosChanged` subscription of listener `id`, subscribing to the new addresses only.
+    pub async fn add_utxo_addresses(&self, id: ListenerId, addresses: Vec<RpcAddress>) -> RpcResult<()> {
+        self.notifier.add_utxo_addresses(id, addresses)?;
+        Ok(())
+    }
+
+    /// Removes `addresses` from the `UtxosChanged` subscription of listener `id`, unsubscribing from these addresses only.
+    pub async fn remove_utxo_addresses(&self, id: ListenerId, addresses: Vec<RpcAddress>) -> RpcResult<()> {
+        self.notifier.remove_utxo_addresses(id, addresses)?;
+        Ok(())
+    }
+
+    /// Starts sending `VirtualChainChanged` notifications to listener `id`.
+    ///
+    /// When `include_accepted_transaction_ids` is false, the notifications are delivered with empty `accepted_transaction_ids`
+    /// and the server is asked not to compute them.
+    pub async fn start_notify_chain_changed(&self, id: ListenerId, include_accepted_transaction_ids: bool) -> RpcResult<()> {
+        self.start_notify(id, Scope::VirtualChainChanged(VirtualChainChangedScope::new(include_accepted_transaction_ids))).await
+    }
+
+    /// Registers a new listener and starts sending it the notifications of `event_type`, returning a guard stopping
+    /// the notifications and unregistering the listener when dropped.
+    ///
+    /// The guard derefs to the unbounded receiver of the notifications.
+    pub async fn subscribe(&self, event_type: EventType) -> RpcResult<Subscription> {
+        let (sender, receiver) = async_channel::unbounded();
+        let id = self.register_new_listener(ChannelConnection::new(sender));
+        let scope = Scope::from(event_type);
+        if let Err(err) = self.start_notify(id, scope.clone()).await {
+            self.notifier.unregister_listener(id)?;
+            return Err(err);
+        }
+        Ok(Subscription::new(id, scope, self.notifier(), receiver))
+    }
+
+    /// Returns true if listener `id` is currently subscribed to notifications matching `scope`.
+    ///
+    /// A `UtxosChanged` scope is considered subscribed if any of its addresses is.
+    pub fn is_subscribed(&self, id: ListenerId, scope: &Scope) -> bool {
+        self.notifier.is_subscribed(id, scope)
+    }
+
+    /// Returns the number of notifications dropped so far for listener `id` because its channel was full.
+    pub fn notification_drops(&self, id: ListenerId) -> u64 {
+        self.notifier.notification_drops(id)
+    }
+
+    /// Returns the number of listeners registered on the notifier of the client, whatever their subscriptions.
+    ///
+    /// A count growing over time hints at listeners never unregistered.
+    pub fn listener_count(&self) -> usize {
+        self.notifier.listener_count()
+    }
+
+    /// Returns the time taken by the last successful connection to the server, from dialing to the end of the
+    /// GetInfo handshake.
+    pub fn last_connect_duration(&self) -> Option<Duration> {
+        self.inner.last_connect_duration()
+    }
+
+    /// Returns the number of attempts made so far to connect to the server, including the reconnection attempts.
+    pub fn connect_attempts(&self) -> u64 {
+        self.inner.connect_attempts()
+    }
+
+    /// Returns the number of requests expired so far by the timeout monitor, having waited for their response longer than
+    /// the request timeout.
+    ///
+    /// A rising count hints at a too short request timeout or at a struggling server.
+    pub fn expired_request_count(&self) -> u64 {
+        self.inner.expired_request_count()
+    }
+
+    /// Returns the outcomes of the calls of each op made through the [`RpcApi`] methods since the client was created,
+    /// broken down into successes, errors and timeouts. An error stated by the server counts as an error.
+    ///
+    /// The ops never called have no entry. The calls made with [`GrpcClient::raw_call`] are not counted.
+    pub fn op_stats(&self) -> HashMap<RpcApiOps, OpStats> {
+        self.inner.op_stats.snapshot()
+    }
+
+    /// Returns the number of requests sent to the server and still awaiting their response.
+    ///
+    /// A call whose future is dropped before its response is received no longer counts as pending.
+    pub fn pending_request_count(&self) -> usize {
+        self.inner.pending_request_count()
+    }
+
+    /// Returns the number of notifications of each event type received from the server since connecting.
+    ///
+    /// The counts are kept across reconnections, see [`GrpcClient::reset_notification_counts`].
+    pub fn notification_counts(&self) -> HashMap<EventType, u64> {
+        self.inner.notification_counts()
+    }
+
+    /// Resets all the notification counts to zero, for instance when receiving a [`ConnectionEvent::Connected`] event.
+    pub fn reset_notification_counts(&self) {
+        self.inner.reset_notification_counts()
+    }
+
+    /// Returns the [`GetInfoResponse`] received from the server while establishing the current connection, if any.
+    pub fn cached_get_info(&self) -> Option<GetInfoResponse> {
+        self.inner.cached_get_info()
+    }
+
+    /// Returns the version of the server, as stated in the cached [`GetInfoResponse`], see [`parse_server_version`].
+    pub fn server_version(&self) -> Option<semver::Version> {
+        self.cached_get_info().and_then(|info| parse_server_version(&info.server_version))
+    }
+
     pub async fn shutdown(&mut self) -> Result<()> {
         self.inner.shutdown().await?;
         Ok(())
@@ -136,7 +361,7 @@ impl RpcApi<ChannelConnection> for GrpcClient {
 
     route!(ping_call, Ping);
     route!(get_process_metrics_call, GetProcessMetrics);
-    route!(submit_block_call, SubmitBlock);
+    route!(submit_block_call, SubmitBlock, RequestPriority::High);
     route!(get_block_template_call, GetBlockTemplate);
     route!(get_block_call, GetBlock);
     route!(get_info_call, GetInfo);
@@ -204,11 +429,37 @@ pub const KEEP_ALIVE_DURATION: u64 = 5_000;
 pub const REQUEST_TIMEOUT_DURATION: u64 = 5_000;
 pub const TIMEOUT_MONITORING_INTERVAL: u64 = 10_000;
 pub const RECONNECT_INTERVAL: u64 = 2_000;
+/// Default maximum size of an encoded request, matching the largest message the server decodes
+pub const DEFAULT_MAX_REQUEST_SIZE: usize = RPC_MAX_MESSAGE_SIZE;
 
-type KaspadRequestSender = async_channel::Sender<KaspadRequest>;
-type KaspadRequestReceiver = async_channel::Receiver<KaspadRequest>;
+/// Parses a server version string into a comparable [`semver::Version`], returning `None` if unparseable.
+///
+/// A leading `v` and missing minor or patch numbers are tolerated, so `v0.12` parses as `0.12.0`.
+/// Pre-release and build suffixes are kept, a pre-release ordering before its release.
+pub fn parse_server_version(version: &str) -> Option<semver::Version> {
+    let version = version.trim();
+    let version = version.strip_prefix('v').unwrap_or(version);
+    if let Ok(version) = semver::Version::parse(version) {
+        return Some(version);
+    }
+    // Pad the missing numbers of the version core
+    let suffix_start = version.find(['-', '+']).unwrap_or(version.len());
+    let (core, suffix) = version.split_at(suffix_start);
+    let numbers = core.split('.').count();
+    if numbers >= 3 {
+        return None;
+    }
+    semver::Version::parse(&format!("{core}{}{suffix}", ".0".repeat(3 - numbers))).ok()
+}
 
-#[derive(Debug, Default)]
+/// Settings of the background tasks of a lazy client, spawned on its first call
+#[derive(Debug)]
+struct LazyConnect {
+    timeout_monitor: bool,
+    info_refresh_interval: Option<Duration>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
 struct ServerFeatures {
     pub handle_stop_notify: bool,
     pub handle_message_id: bool,
@@ -251,21 +502,51 @@ struct ServerFeatures {
 struct Inner {
     address: String,
 
-    server_features: ServerFeatures,
+    // Capabilities of the server reported on the last connection, only known once connected for the first time by a lazy client
+    server_features: Mutex<ServerFeatures>,
+
+    // Connection deferred until the first call, for a lazy client
+    lazy: bool,
+    lazy_connect: Mutex<Option<LazyConnect>>,
+
+    // GetInfo response received during the handshake of the current connection
+    server_info: Mutex<Option<GetInfoResponse>>,
+
+    // Connection establishment metrics
+    connect_attempts: AtomicU64,
+    last_connect_duration: Mutex<Option<Duration>>,
+
+    // Requests expired by the timeout monitor
+    expired_requests: AtomicU64,
+
+    // Outcomes of the calls, by op
+    op_stats: OpStatsRecorder,
 
     // Pushing incoming notifications forward
     notify_sender: NotificationSender,
+    notify_channel_policy: NotifyChannelPolicy,
+
+    // Received notifications, by event type
+    notification_counts: EventArray<AtomicU64>,
 
     // Sending to server
-    request_sender: KaspadRequestSender,
-    request_receiver: KaspadRequestReceiver,
+    request_queue: RequestQueue,
+    // Identical read requests in flight, if coalescing them
+    coalescer: Option<Coalescer>,
 
     // Receiving from server
     receiver_is_running: AtomicBool,
     receiver_shutdown: DuplexTrigger,
 
     /// Matching responses with pending requests
-    resolver: DynResolver,
+    resolver: Mutex<DynResolver>,
+    forced_resolver_kind: Option<ResolverKind>,
+
+    /// Ids of the requests
+    request_id_source: DynRequestIdSource,
+
+    /// Observer of the messages crossing the wire
+    wire_observer: Option<DynWireObserver>,
 
     // Pending timeout cleaning task
     timeout_is_running: AtomicBool,
@@ -273,84 +554,196 @@ struct Inner {
     timeout_timer_interval: u64,
     timeout_duration: u64,
 
+    // Periodic refresh of the cached GetInfo response
+    info_refresh_is_running: AtomicBool,
+    info_refresh_shutdown: DuplexTrigger,
+
     // Connection monitor allowing to reconnect automatically to the server
     connector_is_running: AtomicBool,
     connector_shutdown: DuplexTrigger,
-    connector_timer_interval: u64,
+    reconnect_policy: ReconnectPolicy,
+    // Wakes the connection monitor up ahead of its next check, after a transient error ended the stream
+    reconnect_now: tokio::sync::Notify,
 
     // Connection event channel
     connection_event_sender: Option<Sender<ConnectionEvent>>,
 
+    // Connection state, following the connection events
+    connected: watch::Sender<bool>,
+
+    // Consumers of the stream closed signals
+    stream_closed_senders: Mutex<Vec<Sender<StreamClosed>>>,
+
+    /// Streaming calls, indexed by request id
+    streams: Mutex<HashMap<u64, KaspadResponseStreamSender>>,
+
+    // Subscriber forwarding the notification subscriptions to the server, replayed on reconnection
+    subscriber: Mutex<Option<Weak<Subscriber>>>,
+
     // temporary hack to override the handle_stop_notify flag
     override_handle_stop_notify: bool,
+
+    // Whether the server capabilities are queried when connecting
+    handshake: bool,
+
+    // Whether an unparseable handshake response fails the connection
+    strict_handshake: bool,
+
+    // Maximum size of an encoded request
+    max_request_size: usize,
+
+    // HTTP/2 settings of the endpoint
+    http2: Http2Settings,
 }
 
 impl Inner {
+    /// Creates an instance configured by `builder`, connected to a server of capabilities `server_features` unless
+    /// `builder` is lazy.
     fn new(
-        address: String,
-        server_features: ServerFeatures,
+        builder: GrpcClientBuilder,
         notify_sender: NotificationSender,
-        request_sender: KaspadRequestSender,
-        request_receiver: KaspadRequestReceiver,
-        connection_event_sender: Option<Sender<ConnectionEvent>>,
-        override_handle_stop_notify: bool,
-        timeout_duration: u64,
+        request_queue: RequestQueue,
+        server_features: ServerFeatures,
+        server_info: Option<GetInfoResponse>,
     ) -> Self {
-        let resolver: DynResolver = match server_features.handle_message_id {
-            true => Arc::new(IdResolver::new()),
-            false => Arc::new(QueueResolver::new()),
-        };
+        let GrpcClientBuilder {
+            address,
+            connection_event_sender,
+            override_handle_stop_notify,
+            timeout_duration,
+            request_id_source,
+            handshake,
+            timeout_monitor,
+            http2,
+            info_refresh_interval,
+            notify_channel_policy,
+            lazy,
+            coalesce_reads,
+            reconnect_policy,
+            wire_observer,
+            resolver_kind,
+            strict_handshake,
+            max_request_size,
+            ..
+        } = builder;
         Self {
             address,
-            server_features,
+            resolver: Mutex::new(Inner::resolver_for(&server_features, resolver_kind)),
+            forced_resolver_kind: resolver_kind,
+            server_features: Mutex::new(server_features),
+            lazy,
+            lazy_connect: Mutex::new(lazy.then_some(LazyConnect { timeout_monitor, info_refresh_interval })),
+            server_info: Mutex::new(server_info),
+            connect_attempts: AtomicU64::new(0),
+            last_connect_duration: Mutex::new(None),
+            expired_requests: AtomicU64::new(0),
+            op_stats: OpStatsRecorder::default(),
             notify_sender,
-            request_sender,
-            request_receiver,
-            resolver,
+            notify_channel_policy,
+            notification_counts: EventArray::default(),
+            request_queue,
+            coalescer: coalesce_reads.then(Coalescer::default),
+            request_id_source,
+            wire_observer,
             receiver_is_running: AtomicBool::new(false),
             receiver_shutdown: DuplexTrigger::new(),
             timeout_is_running: AtomicBool::new(false),
             timeout_shutdown: DuplexTrigger::new(),
+            info_refresh_is_running: AtomicBool::new(false),
+            info_refresh_shutdown: DuplexTrigger::new(),
             timeout_duration,
             timeout_timer_interval: TIMEOUT_MONITORING_INTERVAL,
             connector_is_running: AtomicBool::new(false),
             connector_shutdown: DuplexTrigger::new(),
-            connector_timer_interval: RECONNECT_INTERVAL,
+            reconnect_policy,
+            reconnect_now: tokio::sync::Notify::new(),
             connection_event_sender,
+            connected: watch::channel(false).0,
+            stream_closed_senders: Mutex::new(vec![]),
+            streams: Mutex::new(HashMap::new()),
+            subscriber: Mutex::new(None),
             override_handle_stop_notify,
+            handshake,
+            strict_handshake,
+            max_request_size,
+            http2,
         }
     }
 
+    /// Creates the resolver of kind `forced_kind` if set, the one best suited to the server capabilities otherwise
+    fn resolver_for(server_features: &ServerFeatures, forced_kind: Option<ResolverKind>) -> DynResolver {
+        let kind = forced_kind.unwrap_or(match server_features.handle_message_id {
+            true => ResolverKind::Id,
+            false => ResolverKind::Queue,
+        });
+        match kind {
+            ResolverKind::Id => Arc::new(IdResolver::new()),
+            ResolverKind::Queue => Arc::new(QueueResolver::new()),
+        }
+    }
+
+    /// Creates an unconnected instance, connecting to the server only when [`Inner::start_lazy_connection`] is called.
+    fn new_lazy(builder: GrpcClientBuilder, notify_sender: NotificationSender) -> Arc<Self> {
+        Arc::new(Inner::new(builder, notify_sender, RequestQueue::new(), ServerFeatures::default(), None))
+    }
+
+    /// Starts the background tasks of a lazy instance, connecting to the server and then monitoring the connection.
+    ///
+    /// Does nothing if already started or if the instance is not lazy.
+    fn start_lazy_connection(self: Arc<Self>) {
+        let Some(lazy_connect) = self.lazy_connect.lock().unwrap().take() else { return };
+        if lazy_connect.timeout_monitor {
+            self.clone().spawn_request_timeout_monitor();
+        }
+        if let Some(interval) = lazy_connect.info_refresh_interval {
+            self.clone().spawn_info_refresh_task(interval);
+        }
+        tokio::spawn(async move {
+            if let Err(err) = self.clone().reconnect().await {
+                trace!("[GrpcClient] lazy connection to server failed with error {err:?}, retrying in the background");
+            }
+            self.spawn_connection_monitor();
+        });
+    }
+
+    fn set_subscriber(&self, subscriber: &Arc<Subscriber>) {
+        *self.subscriber.lock().unwrap() = Some(Arc::downgrade(subscriber));
+    }
+
     // TODO - remove the override (discuss how to handle this in relation to the golang client)
-    async fn connect(
-        address: String,
-        reconnect: bool,
-        notify_sender: NotificationSender,
-        connection_event_sender: Option<Sender<ConnectionEvent>>,
-        override_handle_stop_notify: bool,
-        timeout_duration: u64,
-    ) -> Result<Arc<Self>> {
+    async fn connect(builder: GrpcClientBuilder, notify_sender: NotificationSender) -> Result<Arc<Self>> {
         // Request channel
-        let (request_sender, request_receiver) = async_channel::unbounded();
+        let request_queue = RequestQueue::new();
 
         // Try to connect to the server
-        let (stream, server_features) =
-            Inner::try_connect(address.clone(), request_sender.clone(), request_receiver.clone(), timeout_duration).await?;
+        let start = Instant::now();
+        let (stream, server_features, server_info) = Inner::try_connect(
+            builder.address.clone(),
+            request_queue.clone(),
+            builder.timeout_duration,
+            builder.handshake,
+            builder.strict_handshake,
+            &builder.http2,
+        )
+        .await?;
+        let connect_duration = start.elapsed();
 
         // create the inner object
-        let inner = Arc::new(Inner::new(
-            address,
-            server_features,
-            notify_sender,
-            request_sender,
-            request_receiver,
-            connection_event_sender,
-            override_handle_stop_notify,
-            timeout_duration,
-        ));
+        let (reconnect, timeout_monitor, info_refresh_interval) =
+            (builder.reconnect, builder.timeout_monitor, builder.info_refresh_interval);
+        let inner = Arc::new(Inner::new(builder, notify_sender, request_queue, server_features, server_info));
+        inner.connect_attempts.store(1, Ordering::SeqCst);
+        *inner.last_connect_duration.lock().unwrap() = Some(connect_duration);
 
         // Start the request timeout cleaner
-        inner.clone().spawn_request_timeout_monitor();
+        if timeout_monitor {
+            inner.clone().spawn_request_timeout_monitor();
+        }
+
+        // Start the GetInfo refresh
+        if let Some(interval) = info_refresh_interval {
+            inner.clone().spawn_info_refresh_task(interval);
+        }
 
         // Start the response receiving task
         inner.clone().spawn_response_receiver_task(stream);
@@ -365,17 +758,14 @@ impl Inner {
 
     async fn try_connect(
         address: String,
-        request_sender: KaspadRequestSender,
-        request_receiver: KaspadRequestReceiver,
+        request_queue: RequestQueue,
         request_timeout: u64,
-    ) -> Result<(Streaming<KaspadResponse>, ServerFeatures)> {
+        handshake: bool,
+        strict_handshake: bool,
+        http2: &Http2Settings,
+    ) -> Result<(Streaming<KaspadResponse>, ServerFeatures, Option<GetInfoResponse>)> {
         // gRPC endpoint
-        let channel = Endpoint::from_shared(address.clone())?
-            .timeout(tokio::time::Duration::from_millis(request_timeout))
-            .connect_timeout(tokio::time::Duration::from_millis(CONNECT_TIMEOUT_DURATION))
-            .tcp_keepalive(Some(tokio::time::Duration::from_millis(KEEP_ALIVE_DURATION)))
-            .connect()
-            .await?;
+        let channel = Inner::endpoint(address.clone(), request_timeout, http2)?.connect().await?;
 
         let mut client = RpcClient::new(channel)
             .send_compressed(CompressionEncoding::Gzip)
@@ -383,13 +773,15 @@ impl Inner {
             .max_decoding_message_size(RPC_MAX_MESSAGE_SIZE);
 
         // Force the opening of the stream when connected to a go kaspad server.
-        // This is also needed for querying server capabilities.
-        request_sender.send(GetInfoRequestMessage {}.into()).await?;
+        // This is also needed for querying server capabilities, otherwise a lightweight Ping is enough.
+        match handshake {
+            true => request_queue.send(GetInfoRequestMessage {}.into(), RequestPriority::High).await?,
+            false => request_queue.send(PingRequestMessage {}.into(), RequestPriority::High).await?,
+        }
 
         // Prepare a request receiver stream
-        let stream_receiver = request_receiver.clone();
         let request_stream = async_stream::stream! {
-            while let Ok(item) = stream_receiver.recv().await {
+            while let Ok(item) = request_queue.recv().await {
                 yield item;
             }
         };
@@ -399,43 +791,110 @@ impl Inner {
 
         // Collect server capabilities as stated in GetInfoResponse
         let mut server_features = ServerFeatures::default();
-        match stream.message().await? {
+        let server_info = match stream.message().await? {
+            Some(ref msg) if !handshake => {
+                trace!("Ping got response {:?}", msg);
+                None
+            }
             Some(ref msg) => {
                 trace!("GetInfo got response {:?}", msg);
                 let response: RpcResult<GetInfoResponse> = msg.try_into();
-                if let Ok(response) = response {
-                    server_features.handle_stop_notify = response.has_notify_command;
-                    server_features.handle_message_id = response.has_message_id;
+                match response {
+                    Ok(response) => {
+                        server_features.handle_stop_notify = response.has_notify_command;
+                        server_features.handle_message_id = response.has_message_id;
+                        Some(response)
+                    }
+                    Err(err) if strict_handshake => return Err(Error::Handshake(err.to_string())),
+                    Err(err) => {
+                        // Conservatively assume the server handles neither message ids nor StopNotify commands
+                        debug!("[GrpcClient] GetInfo handshake response could not be parsed: {err}");
+                        None
+                    }
                 }
             }
             None => {
                 return Err(Error::String("gRPC stream was closed by the server".to_string()));
             }
-        }
+        };
+
+        Ok((stream, server_features, server_info))
+    }
 
-        Ok((stream, server_features))
+    fn endpoint(address: String, request_timeout: u64, http2: &Http2Settings) -> Result<Endpoint> {
+        let endpoint = Endpoint::from_shared(address)?
+            .timeout(tokio::time::Duration::from_millis(request_timeout))
+            .connect_timeout(tokio::time::Duration::from_millis(CONNECT_TIMEOUT_DURATION))
+            .tcp_keepalive(Some(tokio::time::Duration::from_millis(KEEP_ALIVE_DURATION)));
+        Ok(http2.apply(endpoint))
     }
 
     async fn reconnect(self: Arc<Self>) -> Result<()> {
-        // TODO: verify if server feature have changed since first connection
-        // TODO: re-register to notifications
+        // The info of the previous connection is outdated
+        *self.server_info.lock().unwrap() = None;
 
         // Try to connect to the server
-        let (stream, _) = Inner::try_connect(
+        self.connect_attempts.fetch_add(1, Ordering::SeqCst);
+        let start = Instant::now();
+        let (stream, server_features, server_info) = Inner::try_connect(
             self.address.clone(),
-            self.request_sender.clone(),
-            self.request_receiver.clone(),
+            self.request_queue.clone(),
             self.timeout_duration,
+            self.handshake,
+            self.strict_handshake,
+            &self.http2,
         )
         .await?;
+        *self.last_connect_duration.lock().unwrap() = Some(start.elapsed());
+        *self.server_info.lock().unwrap() = server_info;
+
+        // A lazy client learns the server capabilities on its first connection, before any request is sent, and any
+        // client may find them changed on reconnection, after an upgrade of the server
+        self.update_server_features(server_features);
 
         // Start the response receiving task
-        self.spawn_response_receiver_task(stream);
+        self.clone().spawn_response_receiver_task(stream);
+
+        // Re-register to notifications
+        let subscriber = self.subscriber.lock().unwrap().as_ref().and_then(Weak::upgrade);
+        if let Some(subscriber) = subscriber {
+            if let Err(err) = subscriber.resubscribe_all().await {
+                debug!("[GrpcClient] resubscribing to notifications after reconnection failed: {err}");
+            }
+        }
 
         Ok(())
     }
 
+    /// Records the capabilities of the server, switching the resolver if the server now matches the responses with the
+    /// requests differently.
+    ///
+    /// The requests still pending in the previous resolver were sent over a lost connection, so no response will ever
+    /// resolve them: they are failed with [`Error::ResolverSwitched`].
+    fn update_server_features(&self, server_features: ServerFeatures) {
+        *self.server_features.lock().unwrap() = server_features;
+        let resolver = Inner::resolver_for(&server_features, self.forced_resolver_kind);
+        let previous = {
+            let mut current = self.resolver.lock().unwrap();
+            if current.kind() == resolver.kind() {
+                return;
+            }
+            std::mem::replace(&mut *current, resolver)
+        };
+        debug!("[GrpcClient] switching from the {:?} resolver to the {:?} one", previous.kind(), self.resolver().kind());
+        previous.drain(|| Error::ResolverSwitched);
+    }
+
     fn send_connection_event(&self, event: ConnectionEvent) {
+        match event {
+            ConnectionEvent::Connected => {
+                self.connected.send_replace(true);
+            }
+            ConnectionEvent::Disconnected => {
+                self.connected.send_replace(false);
+            }
+            ConnectionEvent::SyncStatusChanged { .. } => {}
+        }
         if let Some(ref connection_event_sender) = self.connection_event_sender {
             if let Err(err) = connection_event_sender.try_send(event) {
                 debug!("Send connection event error: {err}");
@@ -447,9 +906,71 @@ impl Inner {
         self.receiver_is_running.load(Ordering::SeqCst)
     }
 
+    /// Sends `closed` to every stream closed consumer, forgetting the ones gone.
+    fn send_stream_closed(&self, closed: StreamClosed) {
+        self.stream_closed_senders.lock().unwrap().retain(|sender| sender.try_send(closed.clone()).is_ok());
+    }
+
+    async fn wait_for_connected(&self, timeout: Duration) -> Result<()> {
+        let mut connected = self.connected.subscribe();
+        let wait = async {
+            while !*connected.borrow_and_update() {
+                if connected.changed().await.is_err() {
+                    return Err(Error::NotConnected);
+                }
+            }
+            Ok(())
+        };
+        tokio::time::timeout(timeout, wait).await.map_err(|_| Error::ConnectionTimeout)?
+    }
+
+    fn last_connect_duration(&self) -> Option<Duration> {
+        *self.last_connect_duration.lock().unwrap()
+    }
+
+    fn connect_attempts(&self) -> u64 {
+        self.connect_attempts.load(Ordering::SeqCst)
+    }
+
+    fn expired_request_count(&self) -> u64 {
+        self.expired_requests.load(Ordering::SeqCst)
+    }
+
+    fn pending_request_count(&self) -> usize {
+        self.resolver().pending_count()
+    }
+
+    /// Resolves the requests pending for longer than the request timeout with a [`Error::Timeout`], counting them
+    fn remove_expired_requests(&self) {
+        let expired = self.resolver().remove_expired_requests(Duration::from_millis(self.timeout_duration));
+        self.expired_requests.fetch_add(expired as u64, Ordering::SeqCst);
+    }
+
+    /// Records the outcome of a call of `op`, `timed_out` telling whether the response failed to arrive in time
+    fn record_call_outcome<T>(&self, op: RpcApiOps, result: &RpcResult<T>, timed_out: bool) {
+        let outcome = match result {
+            Ok(_) => CallOutcome::Success,
+            Err(_) if timed_out => CallOutcome::Timeout,
+            Err(_) => CallOutcome::Error,
+        };
+        self.op_stats.record(op, outcome);
+    }
+
+    fn notification_counts(&self) -> HashMap<EventType, u64> {
+        EVENT_TYPE_ARRAY.iter().map(|event| (*event, self.notification_counts[*event].load(Ordering::SeqCst))).collect()
+    }
+
+    fn reset_notification_counts(&self) {
+        EVENT_TYPE_ARRAY.iter().for_each(|event| self.notification_counts[*event].store(0, Ordering::SeqCst));
+    }
+
+    fn cached_get_info(&self) -> Option<GetInfoResponse> {
+        self.server_info.lock().unwrap().clone()
+    }
+
     #[inline(always)]
     fn handle_message_id(&self) -> bool {
-        self.server_features.handle_message_id
+        self.server_features.lock().unwrap().handle_message_id
     }
 
     #[inline(always)]
@@ -458,32 +979,149 @@ impl Inner {
         if self.override_handle_stop_notify {
             true
         } else {
-            self.server_features.handle_stop_notify
+            self.server_features.lock().unwrap().handle_stop_notify
         }
     }
 
     #[inline(always)]
     fn resolver(&self) -> DynResolver {
-        self.resolver.clone()
+        self.resolver.lock().unwrap().clone()
     }
 
+    /// Sends a request to the server with a normal priority and awaits its response, see [`Inner::call_with_priority`].
     async fn call(&self, op: RpcApiOps, request: impl Into<KaspadRequest>) -> Result<KaspadResponse> {
-        // Calls are only allowed if the client is connected to the server
-        if self.is_connected() {
-            let id = u64::from_le_bytes(rand::random::<[u8; 8]>());
-            let mut request: KaspadRequest = request.into();
-            request.id = id;
-
-            trace!("resolver call: {:?}", request);
-            if request.payload.is_some() {
-                let receiver = self.resolver().register_request(op, &request);
-                self.request_sender.send(request).await.map_err(|_| Error::ChannelRecvError)?;
-                receiver.await?
+        self.call_with_priority(op, request, RequestPriority::Normal).await
+    }
+
+    /// Sends a request to the server and awaits its response.
+    ///
+    /// A request with a high `priority` is sent ahead of any pending normal one. If coalescing is enabled, a read
+    /// identical to one in flight is not sent but shares the response of the latter.
+    async fn call_with_priority(
+        &self,
+        op: RpcApiOps,
+        request: impl Into<KaspadRequest>,
+        priority: RequestPriority,
+    ) -> Result<KaspadResponse> {
+        let request: KaspadRequest = request.into();
+        match self.coalescer.as_ref().and_then(|coalescer| coalescer.join(&op, &request)) {
+            Some(Coalesced::Follower(receiver)) => receiver.await.map_err(|_| Error::ChannelRecvError)?,
+            Some(Coalesced::Leader(leader)) => {
+                let result = self.send_request(op, request, priority).await;
+                leader.resolve(&result);
+                result
+            }
+            None => self.send_request(op, request, priority).await,
+        }
+    }
+
+    /// Sends a request to the server and awaits its response, see [`Inner::call_with_priority`].
+    ///
+    /// The call runs inside a `grpc_call` span carrying the `op`, the request `id` and the `outcome` of the call.
+    async fn send_request(&self, op: RpcApiOps, mut request: KaspadRequest, priority: RequestPriority) -> Result<KaspadResponse> {
+        let span = tracing::debug_span!("grpc_call", op = ?op, id = field::Empty, outcome = field::Empty);
+        let result = async {
+            self.check_request_size(&request)?;
+
+            // A lazy client may still be connecting to the server
+            if self.lazy && !self.is_connected() {
+                self.wait_for_connected(Duration::from_millis(self.timeout_duration)).await?;
+            }
+
+            // Calls are only allowed if the client is connected to the server
+            if self.is_connected() {
+                let id = self.request_id_source.next_id();
+                request.id = id;
+                Span::current().record("id", id);
+
+                tracing::trace!(?request, "sending request");
+                if request.payload.is_some() {
+                    self.observe_request(&op, &request);
+                    let resolver = self.resolver();
+                    let receiver = resolver.register_request(op, &request);
+                    // Dropping the call future, at any await point below, removes the request from the resolver
+                    let guard = PendingGuard::new(resolver, id);
+                    self.request_queue.send(request, priority).await.map_err(|_| Error::ChannelRecvError)?;
+                    let response = receiver.await;
+                    guard.disarm();
+                    response?
+                } else {
+                    Err(Error::MissingRequestPayload)
+                }
             } else {
-                Err(Error::MissingRequestPayload)
+                Err(Error::NotConnected)
             }
-        } else {
-            Err(Error::NotConnected)
+        }
+        .instrument(span.clone())
+        .await;
+        match result {
+            Ok(_) => span.record("outcome", "ok"),
+            Err(ref err) => span.record("outcome", field::display(err)),
+        };
+        result
+    }
+
+    /// Sends a request to the server and returns a stream of all the responses bearing its id, bypassing the resolver.
+    ///
+    /// The stream ends when the server sends a response with no payload for this id or when the connection is lost.
+    async fn call_stream(&self, op: RpcApiOps, request: impl Into<KaspadRequest>) -> Result<KaspadResponseStream> {
+        if !self.is_connected() {
+            return Err(Error::NotConnected);
+        }
+        // Responses can only be matched with their request by id
+        if !self.handle_message_id() {
+            return Err(Error::MessageIdUnsupported);
+        }
+        let id = self.request_id_source.next_id();
+        let mut request: KaspadRequest = request.into();
+        request.id = id;
+        if request.payload.is_none() {
+            return Err(Error::MissingRequestPayload);
+        }
+        self.check_request_size(&request)?;
+
+        tracing::trace!(?op, id, "sending streaming request");
+        self.observe_request(&op, &request);
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.streams.lock().unwrap().insert(id, sender);
+        if self.request_queue.send(request, RequestPriority::Normal).await.is_err() {
+            self.streams.lock().unwrap().remove(&id);
+            return Err(Error::ChannelSendError);
+        }
+        Ok(UnboundedReceiverStream::new(receiver))
+    }
+
+    /// Fails with [`Error::RequestTooLarge`] if the encoded `request` exceeds the maximum request size
+    fn check_request_size(&self, request: &KaspadRequest) -> Result<()> {
+        let size = request.encoded_len();
+        match size > self.max_request_size {
+            true => Err(Error::RequestTooLarge(size, self.max_request_size)),
+            false => Ok(()),
+        }
+    }
+
+    fn observe_request(&self, op: &RpcApiOps, request: &KaspadRequest) {
+        if let Some(ref observer) = self.wire_observer {
+            observer.on_request(op, request.id, request.encoded_len());
+        }
+    }
+
+    /// Forwards `response` to its streaming call if any, otherwise gives it back.
+    fn handle_stream_response(&self, response: KaspadResponse) -> Option<KaspadResponse> {
+        let mut streams = self.streams.lock().unwrap();
+        let id = response.id;
+        let Some(sender) = streams.get(&id) else { return Some(response) };
+        // An empty response or a dropped stream ends the call
+        if response.payload.is_none() || sender.send(Ok(response)).is_err() {
+            streams.remove(&id);
+        }
+        None
+    }
+
+    /// Ends all the streaming calls with a [`Error::NotConnected`] error.
+    fn close_streams(&self) {
+        for (_, sender) in self.streams.lock().unwrap().drain() {
+            let _ = sender.send(Err(Error::NotConnected.into()));
         }
     }
 
@@ -511,8 +1149,7 @@ impl Inner {
                     _ = shutdown => { break; },
                     _ = delay => {
                         trace!("[GrpcClient] running timeout task");
-                        let timeout = Duration::from_millis(self.timeout_duration);
-                        self.resolver().remove_expired_requests(timeout);
+                        self.remove_expired_requests();
                     },
                 }
             }
@@ -523,6 +1160,55 @@ impl Inner {
         });
     }
 
+    /// Launch a task re-issuing a `GetInfo` request every `interval` and caching its response.
+    fn spawn_info_refresh_task(self: Arc<Self>, interval: Duration) {
+        // The task can only be spawned once
+        if self.info_refresh_is_running.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst).is_err() {
+            trace!("[GrpcClient] spawn GetInfo refresh task ignored since already spawned");
+            return;
+        }
+
+        tokio::spawn(async move {
+            let shutdown = self.info_refresh_shutdown.request.listener.clone().fuse();
+            pin_mut!(shutdown);
+
+            loop {
+                let delay = tokio::time::sleep(interval).fuse();
+                pin_mut!(delay);
+
+                select! {
+                    _ = shutdown => { break; },
+                    _ = delay => {
+                        if self.is_connected() {
+                            self.refresh_info().await;
+                        }
+                    },
+                }
+            }
+
+            trace!("[GrpcClient] terminating GetInfo refresh task");
+            self.info_refresh_is_running.store(false, Ordering::SeqCst);
+            self.info_refresh_shutdown.response.trigger.trigger();
+        });
+    }
+
+    async fn refresh_info(&self) {
+        let info = match self.call(RpcApiOps::GetInfo, GetInfoRequest {}).await {
+            Ok(response) => GetInfoResponse::try_from(&response).map_err(|err| err.to_string()),
+            Err(err) => Err(err.to_string()),
+        };
+        match info {
+            Ok(info) => {
+                let is_synced = info.is_synced;
+                let previous = self.server_info.lock().unwrap().replace(info);
+                if matches!(previous, Some(previous) if previous.is_synced != is_synced) {
+                    self.send_connection_event(ConnectionEvent::SyncStatusChanged { is_synced });
+                }
+            }
+            Err(err) => debug!("[GrpcClient] refreshing the server info failed: {err}"),
+        }
+    }
+
     /// Launch a task receiving and handling response messages sent by the server.
     fn spawn_response_receiver_task(self: Arc<Self>, mut stream: Streaming<KaspadResponse>) {
         // Note: self is a cloned Arc here so that it can be used in the spawned task.
@@ -537,6 +1223,8 @@ impl Inner {
         self.send_connection_event(ConnectionEvent::Connected);
 
         tokio::spawn(async move {
+            let mut closed = None;
+            let mut transient_error = false;
             loop {
                 trace!("[GrpcClient] response receiver loop");
 
@@ -552,18 +1240,29 @@ impl Inner {
                             Ok(msg) => {
                                 match msg {
                                     Some(response) => {
-                                        self.handle_response(response);
+                                        if let Some(notification) = self.handle_response(response) {
+                                            if !self.handle_full_notify_channel(notification).await {
+                                                debug!("[GrpcClient] disconnecting since the notification channel is full");
+                                                break;
+                                            }
+                                        }
                                     },
                                     None =>{
                                         trace!("[GrpcClient] the connection to the server is closed");
+                                        closed = Some(StreamClosed::ByServer);
 
                                         // A reconnection is needed
                                         break;
                                     }
                                 }
                             },
-                            Err(err) => {
-                                trace!("[GrpcClient] the response receiver gets an error from the server: {:?}", err);
+                            Err(status) => {
+                                // The stream yields nothing after an error, so a reconnection is needed whatever the error
+                                let err = Error::from(status);
+                                debug!("[GrpcClient] the response receiver gets an error from the server: {:?}", err);
+                                transient_error = err.is_retryable();
+                                closed = Some(StreamClosed::Error(err.to_string()));
+                                break;
                             }
                         }
                     }
@@ -574,6 +1273,14 @@ impl Inner {
             // Mark as not connected
             self.receiver_is_running.store(false, Ordering::SeqCst);
             self.send_connection_event(ConnectionEvent::Disconnected);
+            self.close_streams();
+            if let Some(closed) = closed {
+                self.send_stream_closed(closed);
+            }
+            if transient_error {
+                // The server is likely to be reachable again right away
+                self.reconnect_now.notify_one();
+            }
 
             if self.receiver_shutdown.request.listener.is_triggered() {
                 self.receiver_shutdown.response.trigger.trigger();
@@ -583,6 +1290,9 @@ impl Inner {
 
     /// Launch a task that periodically checks if the connection to the server is alive
     /// and if not that tries to reconnect to the server.
+    ///
+    /// The checks are spaced by the delays of the [`ReconnectPolicy`], backing off while the reconnection attempts fail.
+    /// A transient error ending the response stream triggers a check right away.
     fn spawn_connection_monitor(self: Arc<Self>) {
         // Note: self is a cloned Arc here so that it can be used in the spawned task.
 
@@ -595,26 +1305,33 @@ impl Inner {
         tokio::spawn(async move {
             let shutdown = self.connector_shutdown.request.listener.clone().fuse();
             pin_mut!(shutdown);
+            let mut failed_attempts = 0;
             loop {
-                let connector_timer_interval = Duration::from_millis(self.connector_timer_interval);
+                let connector_timer_interval = self.reconnect_policy.delay(failed_attempts, &mut rand::thread_rng());
                 let delay = tokio::time::sleep(connector_timer_interval).fuse();
                 pin_mut!(delay);
+                let reconnect_now = self.reconnect_now.notified().fuse();
+                pin_mut!(reconnect_now);
                 select! {
                     _ = shutdown => { break; },
-                    _ = delay => {
-                        trace!("[GrpcClient] running connection monitor task");
-                        if !self.is_connected() {
-                            match self.clone().reconnect().await {
-                                Ok(_) => {
-                                    trace!("[GrpcClient] reconnection to server succeeded");
-                                },
-                                Err(err) => {
-                                    trace!("[GrpcClient] reconnection to server failed with error {err:?}");
-                                }
-                            }
-                        }
+                    _ = delay => {},
+                    _ = reconnect_now => {
+                        trace!("[GrpcClient] connection monitor woken up by a transient stream error");
                     },
                 }
+                trace!("[GrpcClient] running connection monitor task");
+                if !self.is_connected() {
+                    match self.clone().reconnect().await {
+                        Ok(_) => {
+                            trace!("[GrpcClient] reconnection to server succeeded");
+                            failed_attempts = 0;
+                        }
+                        Err(err) => {
+                            trace!("[GrpcClient] reconnection to server failed with error {err:?}");
+                            failed_attempts = failed_attempts.saturating_add(1);
+                        }
+                    }
+                }
             }
             trace!("[GrpcClient] terminating connection monitor");
             self.connector_is_running.store(false, Ordering::SeqCst);
@@ -622,35 +1339,74 @@ impl Inner {
         });
     }
 
-    fn handle_response(&self, response: KaspadResponse) {
+    /// Dispatches a message received from the server, inside a `grpc_response` span carrying the response `id`
+    /// and whether it is a `notification`.
+    ///
+    /// Returns the received notification if it could not be forwarded because the notification channel is full.
+    fn handle_response(&self, response: KaspadResponse) -> Option<Notification> {
+        let span = tracing::debug_span!("grpc_response", id = response.id, notification = response.is_notification());
+        let _enter = span.enter();
+        if let (Some(observer), Some(payload)) = (self.wire_observer.as_ref(), response.payload.as_ref()) {
+            observer.on_response(&payload.into(), response.id, response.encoded_len());
+        }
+        let response = match response.is_notification() {
+            true => response,
+            false => self.handle_stream_response(response)?,
+        };
         if response.is_notification() {
-            trace!("[GrpcClient] handle_response received a notification");
             match Notification::try_from(&response) {
                 Ok(notification) => {
                     let event: EventType = (&notification).into();
-                    trace!("[GrpcClient] handle_response received notification: {:?}", event);
+                    tracing::trace!(?event, "received notification");
+                    self.notification_counts[event].fetch_add(1, Ordering::SeqCst);
 
-                    // Here we ignore any returned error
                     match self.notify_sender.try_send(notification) {
                         Ok(_) => {}
+                        Err(TrySendError::Full(notification)) => return Some(notification),
                         Err(err) => {
-                            trace!("[GrpcClient] error while trying to send a notification to the notifier: {:?}", err);
+                            tracing::trace!(%err, "failed to send a notification to the notifier");
                         }
                     }
                 }
                 Err(err) => {
-                    trace!("[GrpcClient] handle_response error converting response into notification: {:?}", err);
+                    tracing::trace!(%err, "failed to convert a response into a notification");
                 }
             }
         } else if response.payload.is_some() {
             self.resolver().handle_response(response);
         }
+        None
+    }
+
+    /// Applies the notification channel policy to a notification received while the channel is full.
+    ///
+    /// Returns false if the connection must be closed.
+    async fn handle_full_notify_channel(&self, notification: Notification) -> bool {
+        match self.notify_channel_policy {
+            NotifyChannelPolicy::Drop => {
+                trace!("[GrpcClient] dropping a notification since the notification channel is full");
+                true
+            }
+            NotifyChannelPolicy::Await(timeout) => {
+                match tokio::time::timeout(timeout, self.notify_sender.send(notification)).await {
+                    Ok(Ok(_)) => {}
+                    Ok(Err(err)) => trace!("[GrpcClient] failed to send a notification to the notifier: {err}"),
+                    Err(_) => trace!("[GrpcClient] dropping a notification since the notification channel stayed full"),
+                }
+                true
+            }
+            NotifyChannelPolicy::Disconnect => false,
+        }
     }
 
     async fn shutdown(&self) -> Result<()> {
         self.stop_timeout_monitor().await?;
+        self.stop_info_refresh_task().await?;
         self.stop_response_receiver_task().await?;
         self.stop_connector_monitor().await?;
+
+        // No response can be received anymore, so fail the pending calls rather than leaving them hanging
+        self.resolver().drain(|| Error::Shutdown);
         Ok(())
     }
 
@@ -662,6 +1418,14 @@ impl Inner {
         Ok(())
     }
 
+    async fn stop_info_refresh_task(&self) -> Result<()> {
+        if self.info_refresh_is_running.compare_exchange(true, false, Ordering::SeqCst, Ordering::SeqCst).is_ok() {
+            self.info_refresh_shutdown.request.trigger.trigger();
+            self.info_refresh_shutdown.response.listener.clone().await;
+        }
+        Ok(())
+    }
+
     async fn stop_timeout_monitor(&self) -> Result<()> {
         if self.timeout_is_running.compare_exchange(true, false, Ordering::SeqCst, Ordering::SeqCst).is_ok() {
             self.timeout_shutdown.request.trigger.trigger();
@@ -699,3 +1463,1049 @@ impl SubscriptionManager for Inner {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request_id::RequestIdSource;
+    use crate::test_server::{Exchange, TestServer};
+    use futures::StreamExt;
+    use kaspa_grpc_core::protowire::RpcNotifyCommand;
+    use kaspa_grpc_core::protowire::{kaspad_request, kaspad_response};
+    use kaspa_rpc_core::{RpcAcceptedTransactionIds, RpcHash, RpcNetworkType};
+    use tonic::Status;
+    use tracing::{field::Visit, span};
+
+    fn test_info() -> GetInfoResponse {
+        GetInfoResponse {
+            p2p_id: "test-p2p-id".to_string(),
+            mempool_size: 3,
+            server_version: "1.2.3".to_string(),
+            is_utxo_indexed: false,
+            is_synced: true,
+            has_notify_command: true,
+            has_message_id: false,
+        }
+    }
+
+    /// A [`TestServer`] answering every request with `info` and recording the ids of the requests, in order
+    fn info_server(info: GetInfoResponse) -> (TestServer, Arc<Mutex<Vec<u64>>>) {
+        let request_ids = Arc::new(Mutex::new(vec![]));
+        let ids = request_ids.clone();
+        let server = TestServer::new(move |exchange| {
+            ids.lock().unwrap().push(exchange.request.id);
+            exchange.respond(RpcResult::Ok(info.clone()).into());
+        });
+        (server, request_ids)
+    }
+
+    /// Starts an [`info_server`] and returns its address and the ids of the requests it receives
+    async fn start_info_server(info: GetInfoResponse) -> (String, Arc<Mutex<Vec<u64>>>) {
+        let (server, request_ids) = info_server(info);
+        (server.start().await, request_ids)
+    }
+
+    /// A [`TestServer`] answering the GetInfo handshake of each stream with `info`, then calling `after_handshake`
+    /// and leaving any later request unanswered
+    fn handshake_server(info: GetInfoResponse, after_handshake: impl Fn(&Exchange) + Send + Sync + 'static) -> TestServer {
+        TestServer::new(move |exchange| {
+            if exchange.index == 0 {
+                exchange.respond(RpcResult::Ok(info.clone()).into());
+                after_handshake(&exchange);
+            }
+        })
+    }
+
+    /// Error stated by [`read_server`] and [`slow_block_server`] in their GetBlock responses
+    const BLOCK_NOT_FOUND: &str = "block not found";
+
+    fn block_not_found() -> KaspadResponse {
+        RpcResult::<GetBlockResponse>::Err(RpcError::General(BLOCK_NOT_FOUND.to_string())).into()
+    }
+
+    /// Delay after which [`slow_block_server`] answers a GetBlock request
+    const SLOW_BLOCK_DELAY: Duration = Duration::from_millis(200);
+
+    /// A [`TestServer`] answering GetInfo requests with `info` and counting the GetBlock requests it receives,
+    /// answering them after [`SLOW_BLOCK_DELAY`]
+    fn slow_block_server(info: GetInfoResponse, block_requests: Arc<AtomicU64>) -> TestServer {
+        TestServer::new(move |exchange| {
+            if matches!(exchange.request.payload, Some(kaspad_request::Payload::GetBlockRequest(_))) {
+                block_requests.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    tokio::time::sleep(SLOW_BLOCK_DELAY).await;
+                    exchange.respond(block_not_found());
+                });
+            } else {
+                exchange.respond(RpcResult::Ok(info.clone()).into());
+            }
+        })
+    }
+
+    /// Waits for `condition` to hold, failing the test if it does not within a second
+    async fn wait_for(condition: impl Fn() -> bool, what: &str) {
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while !condition() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap_or_else(|_| panic!("timed out waiting for {what}"));
+    }
+
+    #[tokio::test]
+    async fn test_cached_get_info() {
+        let (address, _) = start_info_server(test_info()).await;
+        let mut client = GrpcClient::connect(address, false, None, false, None).await.unwrap();
+        let cached = client.cached_get_info().expect("the handshake GetInfo response should be cached");
+        let fresh = client.get_info_call(GetInfoRequest {}).await.unwrap();
+        assert_eq!(cached.p2p_id, fresh.p2p_id);
+        assert_eq!(cached.mempool_size, fresh.mempool_size);
+        assert_eq!(cached.server_version, fresh.server_version);
+        assert_eq!(cached.is_utxo_indexed, fresh.is_utxo_indexed);
+        assert_eq!(cached.is_synced, fresh.is_synced);
+        assert_eq!(cached.has_notify_command, fresh.has_notify_command);
+        assert_eq!(cached.has_message_id, fresh.has_message_id);
+
+        client.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_strict_handshake() {
+        // Every request is answered with an error instead of a GetInfo response, so the handshake response cannot be parsed
+        let address = TestServer::new(|exchange| {
+            exchange.respond(RpcResult::<GetInfoResponse>::Err(RpcError::General("malformed info".to_string())).into())
+        })
+        .start()
+        .await;
+
+        match GrpcClientBuilder::new(address.clone()).strict_handshake(true).connect().await {
+            Err(Error::Handshake(message)) => assert!(message.contains("malformed info"), "unexpected message: {message}"),
+            Err(err) => panic!("unexpected error {err}"),
+            Ok(_) => panic!("a strict handshake should fail on an unparseable GetInfo response"),
+        }
+
+        // The lenient default connects, assuming conservative server capabilities
+        let mut client = GrpcClientBuilder::new(address).connect().await.unwrap();
+        assert!(client.is_connected());
+        assert!(!client.handle_message_id(), "message ids should not be assumed from an unparseable handshake");
+        assert!(!client.handle_stop_notify(), "StopNotify should not be assumed from an unparseable handshake");
+        assert!(client.cached_get_info().is_none());
+
+        client.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connect_without_handshake() {
+        let info = GetInfoResponse { has_notify_command: true, has_message_id: true, ..test_info() };
+        let (address, request_ids) = start_info_server(info).await;
+        let mut client = GrpcClientBuilder::new(address).handshake(false).connect().await.unwrap();
+        assert!(client.is_connected());
+        assert!(!client.handle_message_id(), "message ids should not be assumed without a handshake");
+        assert!(!client.handle_stop_notify(), "StopNotify should not be assumed without a handshake");
+        assert!(client.cached_get_info().is_none());
+
+        let info = client.get_info_call(GetInfoRequest {}).await.unwrap();
+        assert_eq!(info.p2p_id, test_info().p2p_id);
+        assert_eq!(request_ids.lock().unwrap().len(), 2, "the stream should be opened by a single request");
+
+        client.shutdown().await.unwrap();
+    }
+
+    /// A [`handshake_server`] pushing `notifications` once the handshake is answered
+    fn notifying_server(info: GetInfoResponse, notifications: Vec<Notification>) -> TestServer {
+        handshake_server(info, move |exchange| notifications.iter().for_each(|notification| exchange.push(notification.into())))
+    }
+
+    #[tokio::test]
+    async fn test_notification_counts() {
+        let daa_score =
+            |virtual_daa_score| Notification::VirtualDaaScoreChanged(VirtualDaaScoreChangedNotification { virtual_daa_score });
+        let blue_score = |sink_blue_score| Notification::SinkBlueScoreChanged(SinkBlueScoreChangedNotification { sink_blue_score });
+        let notifications = vec![daa_score(1), blue_score(1), daa_score(2), daa_score(3), blue_score(2)];
+        let address = notifying_server(test_info(), notifications).start().await;
+
+        let mut client = GrpcClient::connect(address, false, None, false, None).await.unwrap();
+        wait_for(|| client.notification_counts().values().sum::<u64>() == 5, "all the notifications").await;
+        let counts = client.notification_counts();
+        assert_eq!(counts[&EventType::VirtualDaaScoreChanged], 3);
+        assert_eq!(counts[&EventType::SinkBlueScoreChanged], 2);
+        assert_eq!(counts[&EventType::BlockAdded], 0);
+        assert_eq!(counts.len(), EVENT_TYPE_ARRAY.len());
+
+        client.reset_notification_counts();
+        assert!(client.notification_counts().values().all(|count| *count == 0));
+
+        client.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_start_notify_chain_changed() {
+        let hash = RpcHash::from_u64_word(1);
+        let notification = Notification::VirtualChainChanged(VirtualChainChangedNotification {
+            removed_chain_block_hashes: Arc::new(vec![]),
+            added_chain_block_hashes: Arc::new(vec![hash]),
+            accepted_transaction_ids: Arc::new(vec![RpcAcceptedTransactionIds {
+                accepting_block_hash: hash,
+                accepted_transaction_ids: vec![RpcHash::from_u64_word(2)],
+            }]),
+        });
+        let include_flags = Arc::new(Mutex::new(vec![]));
+        // The include flag of the subscriptions is recorded and each subscription is followed by the notification
+        let (info, flags) = (test_info(), include_flags.clone());
+        let address = TestServer::new(move |exchange| match exchange.request.payload {
+            Some(kaspad_request::Payload::NotifyVirtualChainChangedRequest(ref message)) => {
+                flags.lock().unwrap().push(message.include_accepted_transaction_ids);
+                exchange.respond(RpcResult::Ok(NotifyVirtualChainChangedResponse {}).into());
+                exchange.push((&notification).into());
+            }
+            _ => exchange.respond(RpcResult::Ok(info.clone()).into()),
+        })
+        .start()
+        .await;
+
+        let mut client = GrpcClient::connect(address, false, None, false, None).await.unwrap();
+        client.start().await;
+        let (sender, receiver) = async_channel::unbounded();
+        let id = client.register_new_listener(ChannelConnection::new(sender));
+        client.start_notify_chain_changed(id, false).await.unwrap();
+        assert_eq!(*include_flags.lock().unwrap(), vec![false], "the server should be asked to omit the accepted transaction ids");
+
+        let delivered = tokio::time::timeout(Duration::from_secs(1), receiver.recv())
+            .await
+            .expect("the notification should be delivered")
+            .unwrap();
+        match delivered {
+            Notification::VirtualChainChanged(ref payload) => {
+                assert_eq!(*payload.added_chain_block_hashes, vec![hash]);
+                assert!(payload.accepted_transaction_ids.is_empty(), "the accepted transaction ids should be stripped");
+            }
+            _ => panic!("unexpected notification {delivered:?}"),
+        }
+
+        client.stop().await.unwrap();
+        client.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_notify_channel_policy() {
+        const NOTIFICATION_COUNT: u64 = 3;
+        let notifications = (0..NOTIFICATION_COUNT)
+            .map(|virtual_daa_score| Notification::VirtualDaaScoreChanged(VirtualDaaScoreChangedNotification { virtual_daa_score }))
+            .collect::<Vec<_>>();
+        let address = notifying_server(test_info(), notifications).start().await;
+
+        // Connects with a notification channel able to hold a single notification and left undrained
+        let connect = |policy| {
+            let notify_channel = NotificationChannel::new(async_channel::bounded(1));
+            let address = address.clone();
+            async move {
+                let inner = Inner::connect(GrpcClientBuilder::new(address).notify_channel_policy(policy), notify_channel.sender())
+                    .await
+                    .unwrap();
+                (inner, notify_channel)
+            }
+        };
+        let received = |inner: &Arc<Inner>| inner.notification_counts[EventType::VirtualDaaScoreChanged].load(Ordering::SeqCst);
+
+        let (inner, notify_channel) = connect(NotifyChannelPolicy::Drop).await;
+        wait_for(|| received(&inner) == NOTIFICATION_COUNT, "all the notifications").await;
+        assert_eq!(notify_channel.len(), 1);
+        assert!(inner.is_connected(), "dropping notifications should keep the connection");
+        inner.shutdown().await.unwrap();
+
+        let (inner, notify_channel) = connect(NotifyChannelPolicy::Await(Duration::from_secs(5))).await;
+        wait_for(|| received(&inner) == 2, "a notification awaiting room in the channel").await;
+        assert_eq!(notify_channel.len(), 1);
+        for virtual_daa_score in 0..NOTIFICATION_COUNT {
+            match tokio::time::timeout(Duration::from_secs(1), notify_channel.recv()).await.unwrap().unwrap() {
+                Notification::VirtualDaaScoreChanged(payload) => assert_eq!(payload.virtual_daa_score, virtual_daa_score),
+                notification => panic!("unexpected notification {notification:?}"),
+            }
+        }
+        assert!(inner.is_connected(), "awaiting room in the channel should keep the connection");
+        inner.shutdown().await.unwrap();
+
+        let (inner, notify_channel) = connect(NotifyChannelPolicy::Disconnect).await;
+        wait_for(|| !inner.is_connected(), "the connection to close").await;
+        assert_eq!(notify_channel.len(), 1, "a full notification channel should close the connection");
+        inner.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_lazy_connect() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = GrpcClient::new_lazy(format!("grpc://{}", listener.local_addr().unwrap())).await.unwrap();
+        assert!(!client.is_connected());
+        assert!(client.cached_get_info().is_none());
+
+        // The server only starts serving after the client was created and gets called
+        let info = GetInfoResponse { has_message_id: true, ..test_info() };
+        let (server, _) = info_server(info.clone());
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            server.serve(listener);
+        });
+
+        let response = client.get_info_call(GetInfoRequest {}).await.expect("the first call should succeed once the server is up");
+        assert_eq!(response.p2p_id, info.p2p_id);
+        assert!(client.is_connected());
+        assert!(client.handle_message_id(), "the server capabilities should be learned on the first connection");
+        assert_eq!(client.cached_get_info().unwrap().p2p_id, info.p2p_id);
+
+        client.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_listener_count() {
+        // Registering listeners involves no server
+        let mut client = GrpcClient::new_lazy("grpc://127.0.0.1:1".to_string()).await.unwrap();
+        assert_eq!(client.listener_count(), 0);
+
+        let (sender, _receiver) = async_channel::unbounded();
+        let first = client.register_new_listener(ChannelConnection::new(sender.clone()));
+        let _second = client.register_new_listener(ChannelConnection::new(sender));
+        assert_eq!(client.listener_count(), 2);
+
+        client.unregister_listener(first).await.unwrap();
+        assert_eq!(client.listener_count(), 1);
+
+        client.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_max_request_size() {
+        const MAX_REQUEST_SIZE: usize = 1_024;
+
+        let (address, request_ids) = start_info_server(test_info()).await;
+        let mut client = GrpcClientBuilder::new(address).max_request_size(MAX_REQUEST_SIZE).connect().await.unwrap();
+        let sent_requests = request_ids.lock().unwrap().len();
+
+        let addresses = kaspa_notify::address::test_helpers::get_3_addresses(false).into_iter().cycle().take(1_000).collect();
+        let request: KaspadRequest = GetUtxosByAddressesRequest::new(addresses).into();
+        let size = request.encoded_len();
+        match client.raw_call(RpcApiOps::GetUtxosByAddresses, request).await {
+            Err(Error::RequestTooLarge(request_size, max)) => assert_eq!((request_size, max), (size, MAX_REQUEST_SIZE)),
+            result => panic!("an oversize request should be rejected by the client, got {result:?}"),
+        }
+        assert_eq!(request_ids.lock().unwrap().len(), sent_requests, "no oversize request should reach the server");
+
+        // Requests within bounds are still sent
+        client.get_info_call(GetInfoRequest {}).await.unwrap();
+        assert_eq!(request_ids.lock().unwrap().len(), sent_requests + 1);
+
+        client.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_no_timeout_monitor() {
+        let (address, _) = start_info_server(test_info()).await;
+        let mut client = GrpcClientBuilder::new(address).timeout_monitor(false).connect().await.unwrap();
+        assert!(!client.inner.timeout_is_running.load(Ordering::SeqCst), "no timeout task should be spawned");
+
+        let info = client.get_info_call(GetInfoRequest {}).await.unwrap();
+        assert_eq!(info.p2p_id, test_info().p2p_id);
+
+        client.shutdown().await.unwrap();
+        assert!(!client.inner.timeout_is_running.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_forced_resolver_kind() {
+        let info = GetInfoResponse { has_message_id: true, ..test_info() };
+        let (address, _) = start_info_server(info).await;
+
+        let mut client = GrpcClient::connect(address.clone(), false, None, false, None).await.unwrap();
+        assert_eq!(client.resolver_kind(), ResolverKind::Id, "a server echoing the request ids should get an id resolver");
+        client.shutdown().await.unwrap();
+
+        let client = Arc::new(GrpcClientBuilder::new(address).resolver_kind(ResolverKind::Queue).connect().await.unwrap());
+        assert!(client.handle_message_id());
+        assert_eq!(client.resolver_kind(), ResolverKind::Queue, "the forced resolver kind should win over the server capabilities");
+        let calls = (0..5)
+            .map(|_| {
+                let client = client.clone();
+                tokio::spawn(async move { client.get_info_call(GetInfoRequest {}).await })
+            })
+            .collect::<Vec<_>>();
+        for call in calls {
+            assert_eq!(call.await.unwrap().unwrap().p2p_id, test_info().p2p_id);
+        }
+
+        Arc::try_unwrap(client).unwrap().shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_resolver_switch_on_reconnect() {
+        let has_message_id = Arc::new(AtomicBool::new(false));
+        let close = Arc::new(tokio::sync::Notify::new());
+        // The handshake of each stream states the `has_message_id` capability the server has at that time
+        let upgraded = has_message_id.clone();
+        let address = TestServer::new(move |exchange| {
+            if exchange.index == 0 {
+                let info = GetInfoResponse { has_message_id: upgraded.load(Ordering::SeqCst), ..test_info() };
+                exchange.respond(RpcResult::Ok(info).into());
+            }
+        })
+        .close_on(close.clone())
+        .start()
+        .await;
+
+        let delay = Duration::from_millis(50);
+        let mut client = GrpcClientBuilder::new(address)
+            .reconnect(true)
+            .reconnect_policy(ReconnectPolicy { initial_delay: delay, max_delay: delay, jitter: false })
+            .connect()
+            .await
+            .unwrap();
+        assert_eq!(client.resolver_kind(), ResolverKind::Queue);
+
+        // A call left unanswered by the server
+        let inner = client.inner.clone();
+        let call = tokio::spawn(async move { inner.call(RpcApiOps::GetInfo, GetInfoRequest {}).await });
+        wait_for(|| client.pending_request_count() == 1, "the call to be pending").await;
+
+        // The server gets upgraded and the client reconnects
+        has_message_id.store(true, Ordering::SeqCst);
+        close.notify_one();
+        let result = tokio::time::timeout(Duration::from_secs(2), call)
+            .await
+            .expect("the pending call should be resolved by the switch of resolver")
+            .unwrap();
+        assert!(matches!(result, Err(Error::ResolverSwitched)), "unexpected call result {result:?}");
+        assert!(client.handle_message_id());
+        assert_eq!(client.resolver_kind(), ResolverKind::Id, "the resolver should follow the upgraded server capabilities");
+        assert_eq!(client.pending_request_count(), 0);
+
+        client.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_resolves_pending_calls() {
+        // Check both the queue and the id resolvers
+        for has_message_id in [false, true] {
+            let info = GetInfoResponse { has_message_id, ..test_info() };
+            let address = handshake_server(info, |_| {}).start().await;
+
+            let mut client = GrpcClient::connect(address, false, None, false, None).await.unwrap();
+            assert_eq!(client.handle_message_id(), has_message_id);
+            let inner = client.inner.clone();
+            let call = tokio::spawn(async move { inner.call(RpcApiOps::GetInfo, GetInfoRequest {}).await });
+            wait_for(|| client.pending_request_count() == 1, "the request to reach the resolver").await;
+            assert!(!call.is_finished(), "the call should be pending");
+
+            client.shutdown().await.unwrap();
+            let result = tokio::time::timeout(Duration::from_millis(500), call)
+                .await
+                .expect("the pending call should be resolved by the shutdown")
+                .unwrap();
+            assert!(matches!(result, Err(Error::Shutdown)), "unexpected call result {result:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http2_settings() {
+        let (address, _) = start_info_server(test_info()).await;
+        assert_eq!(GrpcClientBuilder::new(address.clone()).http2, Http2Settings::default(), "transport defaults should be kept");
+
+        let builder = GrpcClientBuilder::new(address)
+            .initial_connection_window_size(4 << 20)
+            .initial_stream_window_size(1 << 20)
+            .keep_alive(Duration::from_secs(10), Duration::from_secs(3));
+        let expected = Http2Settings {
+            initial_connection_window_size: Some(4 << 20),
+            initial_stream_window_size: Some(1 << 20),
+            keep_alive_interval: Some(Duration::from_secs(10)),
+            keep_alive_timeout: Some(Duration::from_secs(3)),
+        };
+        assert_eq!(builder.http2, expected);
+        assert!(Inner::endpoint(builder.address.clone(), REQUEST_TIMEOUT_DURATION, &builder.http2).is_ok());
+
+        let mut client = builder.connect().await.unwrap();
+        assert_eq!(client.inner.http2, expected, "the settings should be kept for reconnecting");
+        let info = client.get_info_call(GetInfoRequest {}).await.unwrap();
+        assert_eq!(info.p2p_id, test_info().p2p_id);
+
+        client.shutdown().await.unwrap();
+    }
+
+    /// Balance of any address, as answered by [`read_server`]
+    const BALANCE: u64 = 4_200;
+
+    /// A [`TestServer`] answering the common read requests and failing any GetBlock request
+    fn read_server(info: GetInfoResponse, dag_info: GetBlockDagInfoResponse) -> TestServer {
+        TestServer::new(move |exchange| {
+            exchange.respond(match exchange.request.payload {
+                Some(kaspad_request::Payload::GetInfoRequest(_)) => RpcResult::Ok(info.clone()).into(),
+                Some(kaspad_request::Payload::GetBlockDagInfoRequest(_)) => RpcResult::Ok(dag_info.clone()).into(),
+                Some(kaspad_request::Payload::GetBalanceByAddressRequest(_)) => {
+                    RpcResult::Ok(GetBalanceByAddressResponse::new(BALANCE)).into()
+                }
+                _ => block_not_found(),
+            })
+        })
+    }
+
+    #[tokio::test]
+    async fn test_read_wrappers() {
+        let info = GetInfoResponse { has_message_id: true, ..test_info() };
+        let dag_info = GetBlockDagInfoResponse {
+            network_type: RpcNetworkType::Mainnet,
+            block_count: 7,
+            header_count: 9,
+            tip_hashes: vec![RpcHash::from_u64_word(1), RpcHash::from_u64_word(2)],
+            difficulty: 1.5,
+            past_median_time: 1_000,
+            virtual_parent_hashes: vec![RpcHash::from_u64_word(2)],
+            pruning_point_hash: RpcHash::from_u64_word(3),
+            virtual_daa_score: 11,
+        };
+        let address = read_server(info, dag_info.clone()).start().await;
+        let mut client = GrpcClient::connect(address, false, None, false, None).await.unwrap();
+
+        assert_eq!(client.get_info().await.unwrap().p2p_id, test_info().p2p_id);
+
+        let received = client.get_block_dag_info().await.unwrap();
+        assert_eq!(received.block_count, dag_info.block_count);
+        assert_eq!(received.header_count, dag_info.header_count);
+        assert_eq!(received.tip_hashes, dag_info.tip_hashes);
+        assert_eq!(received.pruning_point_hash, dag_info.pruning_point_hash);
+        assert_eq!(received.virtual_daa_score, dag_info.virtual_daa_score);
+
+        let address = RpcAddress::try_from("kaspa:qp0l70zd5x85ttwd6jv7g3s3a8llzj96d8dncn4zmhv4tlzx5k2jyqh70xmfj").unwrap();
+        assert_eq!(client.get_balance_by_address(address).await.unwrap(), BALANCE);
+
+        // The error stated by the server should be surfaced as is
+        let result = client.get_block(RpcHash::from_u64_word(1), true).await;
+        assert!(matches!(result, Err(RpcError::General(ref message)) if message == BLOCK_NOT_FOUND), "unexpected result {result:?}");
+
+        client.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_info_refresh() {
+        // The sync status stated by the server flips with each request
+        let address = TestServer::new(|exchange| {
+            let info = GetInfoResponse { is_synced: exchange.index % 2 == 0, ..test_info() };
+            exchange.respond(RpcResult::Ok(info).into());
+        })
+        .start()
+        .await;
+
+        let (sender, receiver) = async_channel::unbounded();
+        let mut client = GrpcClientBuilder::new(address)
+            .connection_event_sender(sender)
+            .info_refresh_interval(Duration::from_millis(50))
+            .connect()
+            .await
+            .unwrap();
+        assert!(client.cached_get_info().unwrap().is_synced);
+        assert!(matches!(receiver.recv().await, Ok(ConnectionEvent::Connected)));
+
+        for expected in [false, true] {
+            let event = tokio::time::timeout(Duration::from_secs(1), receiver.recv()).await;
+            assert!(
+                matches!(event, Ok(Ok(ConnectionEvent::SyncStatusChanged { is_synced })) if is_synced == expected),
+                "expected a sync status change to {expected}, got {event:?}"
+            );
+        }
+        assert!(client.is_connected(), "sync status events should not affect the connection state");
+
+        client.shutdown().await.unwrap();
+        assert!(!client.inner.info_refresh_is_running.load(Ordering::SeqCst), "the refresh task should be stopped");
+    }
+
+    #[test]
+    fn test_parse_server_version() {
+        let version = |x| parse_server_version(x).unwrap_or_else(|| panic!("{x} should be parsed"));
+        assert_eq!(version("1.2.3"), semver::Version::new(1, 2, 3));
+        assert_eq!(version("v0.12.11"), semver::Version::new(0, 12, 11));
+        assert_eq!(version("0.12"), semver::Version::new(0, 12, 0));
+        assert_eq!(version(" 2 "), semver::Version::new(2, 0, 0));
+        assert_eq!(version("0.1.0-dev").pre.as_str(), "dev");
+        assert_eq!(version("0.12-rc.1").pre.as_str(), "rc.1");
+        for unparseable in ["", "kaspad", "1.2.3.4", "1.x.3", "1.2.3-"] {
+            assert!(parse_server_version(unparseable).is_none(), "{unparseable:?} should not be parsed");
+        }
+
+        let ordered = ["0.1.0-dev", "0.1.0", "0.1.1", "0.2.0-rc.1", "0.2.0-rc.2", "0.2.0", "v0.12.11", "1.0"];
+        let versions = ordered.into_iter().map(version).collect::<Vec<_>>();
+        assert!(versions.windows(2).all(|x| x[0] < x[1]), "versions should be ordered: {versions:?}");
+        assert!(version("0.12.11") >= version("v0.12.11"));
+    }
+
+    #[tokio::test]
+    async fn test_server_version() {
+        let (address, _) = start_info_server(test_info()).await;
+        let mut client = GrpcClient::connect(address, false, None, false, None).await.unwrap();
+        assert_eq!(client.server_version(), Some(semver::Version::new(1, 2, 3)));
+        client.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connect_metrics() {
+        let (address, _) = start_info_server(test_info()).await;
+        let mut client = GrpcClient::connect(address, false, None, false, None).await.unwrap();
+        let duration = client.last_connect_duration().expect("a successful connection should be timed");
+        assert!(duration > Duration::ZERO);
+        assert_eq!(client.connect_attempts(), 1);
+
+        client.shutdown().await.unwrap();
+    }
+
+    /// Number of responses streamed by the server of [`test_call_stream`]
+    const STREAMED_RESPONSES: u64 = 3;
+
+    #[tokio::test]
+    async fn test_call_stream() {
+        // Any request other than the GetInfo handshake is answered with a stream of blocks, ended by an empty response
+        let info = GetInfoResponse { has_message_id: true, ..test_info() };
+        let address = TestServer::new(move |exchange| match exchange.request.payload {
+            Some(kaspad_request::Payload::GetInfoRequest(_)) => exchange.respond(RpcResult::Ok(info.clone()).into()),
+            _ => {
+                for i in 0..STREAMED_RESPONSES {
+                    exchange.respond(RpcResult::Ok(GetBlocksResponse::new(vec![RpcHash::from_u64_word(i)], vec![])).into());
+                }
+                exchange.respond(KaspadResponse { id: 0, payload: None });
+            }
+        })
+        .start()
+        .await;
+
+        let mut client = GrpcClient::connect(address, false, None, false, None).await.unwrap();
+        let stream = client.call_stream(RpcApiOps::GetBlocks, GetBlocksRequest::new(None, false, false)).await.unwrap();
+        let hashes =
+            stream.map(|response| GetBlocksResponse::try_from(&response.unwrap()).unwrap().block_hashes[0]).collect::<Vec<_>>().await;
+        assert_eq!(hashes, (0..STREAMED_RESPONSES).map(RpcHash::from_u64_word).collect::<Vec<_>>());
+
+        client.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_raw_call() {
+        let (address, request_ids) = start_info_server(test_info()).await;
+        let mut client = GrpcClient::connect(address, false, None, false, None).await.unwrap();
+
+        let request: KaspadRequest = GetInfoRequest {}.into();
+        let response = client.raw_call(RpcApiOps::GetInfo, request).await.unwrap();
+        assert!(matches!(response.payload, Some(kaspad_response::Payload::GetInfoResponse(_))));
+        assert_eq!(response.id, *request_ids.lock().unwrap().last().unwrap(), "the response should carry the request id");
+        let info = GetInfoResponse::try_from(&response).unwrap();
+        assert_eq!(info.p2p_id, test_info().p2p_id);
+        assert_eq!(info.mempool_size, test_info().mempool_size);
+        assert_eq!(info.server_version, test_info().server_version);
+        assert_eq!(info.is_synced, test_info().is_synced);
+
+        client.shutdown().await.unwrap();
+    }
+
+    /// A [`WireObserver`](crate::wire_observer::WireObserver) capturing the observed messages
+    #[derive(Debug, Default)]
+    struct CapturingObserver {
+        requests: Mutex<Vec<(RpcApiOps, u64, usize)>>,
+        responses: Mutex<Vec<(RpcApiOps, u64, usize)>>,
+    }
+
+    impl crate::wire_observer::WireObserver for CapturingObserver {
+        fn on_request(&self, op: &RpcApiOps, id: u64, size: usize) {
+            self.requests.lock().unwrap().push((op.clone(), id, size));
+        }
+
+        fn on_response(&self, op: &RpcApiOps, id: u64, size: usize) {
+            self.responses.lock().unwrap().push((op.clone(), id, size));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wire_observer() {
+        let (address, _) = start_info_server(test_info()).await;
+        let observer = Arc::new(CapturingObserver::default());
+        let mut client = GrpcClientBuilder::new(address).wire_observer(observer.clone()).connect().await.unwrap();
+        assert!(observer.requests.lock().unwrap().is_empty(), "the handshake should not be observed");
+
+        client.get_info_call(GetInfoRequest {}).await.unwrap();
+        let requests = observer.requests.lock().unwrap().clone();
+        let responses = observer.responses.lock().unwrap().clone();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(responses.len(), 1);
+        let (request_op, request_id, request_size) = requests[0].clone();
+        let (response_op, response_id, response_size) = responses[0].clone();
+        assert_eq!(request_op, RpcApiOps::GetInfo);
+        assert_eq!(response_op, RpcApiOps::GetInfo);
+        assert_eq!(request_id, response_id, "the response should match the request");
+
+        // An empty request only holds its id and its payload tag, while the response carries the server info
+        let request: KaspadRequest = GetInfoRequest {}.into();
+        assert_eq!(request_size, KaspadRequest { id: request_id, ..request }.encoded_len());
+        assert!(response_size > request_size + test_info().p2p_id.len(), "implausible response size {response_size}");
+
+        client.shutdown().await.unwrap();
+    }
+
+    #[test]
+    fn test_blocking_get_info() {
+        let server_runtime = tokio::runtime::Runtime::new().unwrap();
+        let (address, _) = server_runtime.block_on(start_info_server(test_info()));
+
+        let client = blocking::BlockingGrpcClient::connect(address, false, false, None).unwrap();
+        assert!(client.is_connected());
+        let info = client.get_info().unwrap();
+        assert_eq!(info.p2p_id, test_info().p2p_id);
+        assert_eq!(info.server_version, test_info().server_version);
+        assert!(client.take_notification_receiver().is_some());
+        assert!(client.take_notification_receiver().is_none(), "the notification receiver can only be taken once");
+
+        client.disconnect().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_is_connected() {
+        let (address, _) = start_info_server(test_info()).await;
+        let mut client = GrpcClient::connect(address, false, None, false, None).await.unwrap();
+        client.wait_for_connected(Duration::from_secs(1)).await.unwrap();
+        assert!(client.is_connected());
+
+        client.shutdown().await.unwrap();
+        assert!(!client.is_connected());
+        assert!(matches!(client.wait_for_connected(Duration::from_millis(100)).await, Err(Error::ConnectionTimeout)));
+    }
+
+    #[tokio::test]
+    async fn test_stream_error_stops_receiver() {
+        let address = handshake_server(test_info(), |exchange| exchange.fail(Status::internal("injected failure"))).start().await;
+
+        let (sender, receiver) = async_channel::unbounded();
+        let mut client = GrpcClient::connect(address, false, Some(sender), false, None).await.unwrap();
+        assert!(matches!(receiver.recv().await, Ok(ConnectionEvent::Connected)));
+        let event = tokio::time::timeout(Duration::from_secs(1), receiver.recv()).await;
+        assert!(matches!(event, Ok(Ok(ConnectionEvent::Disconnected))), "the receiver should stop on a fatal stream error");
+        assert!(!client.is_connected());
+        assert!(!client.inner.receiver_is_running.load(Ordering::SeqCst));
+
+        client.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_transient_stream_error_reconnects() {
+        // The first stream fails with a transient error, the next ones are kept open
+        let address = handshake_server(test_info(), |exchange| {
+            if exchange.stream == 0 {
+                exchange.fail(Status::unavailable("injected failure"));
+            }
+        })
+        .start()
+        .await;
+
+        // The periodic checks of the connection are far apart, so only the stream error can trigger the reconnection
+        let delay = Duration::from_secs(60);
+        let (sender, receiver) = async_channel::unbounded();
+        let mut client = GrpcClientBuilder::new(address)
+            .reconnect(true)
+            .reconnect_policy(ReconnectPolicy { initial_delay: delay, max_delay: delay, jitter: false })
+            .connection_event_sender(sender)
+            .connect()
+            .await
+            .unwrap();
+        assert!(matches!(receiver.recv().await, Ok(ConnectionEvent::Connected)));
+        let event = tokio::time::timeout(Duration::from_secs(1), receiver.recv()).await;
+        assert!(matches!(event, Ok(Ok(ConnectionEvent::Disconnected))), "the stream error should end the connection");
+        let event = tokio::time::timeout(Duration::from_secs(1), receiver.recv()).await;
+        assert!(matches!(event, Ok(Ok(ConnectionEvent::Connected))), "the client should reconnect right away, got {event:?}");
+        assert!(client.is_connected());
+        assert_eq!(client.connect_attempts(), 2, "the client should reconnect once");
+
+        client.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stream_closed_signal() {
+        let close = Arc::new(tokio::sync::Notify::new());
+        let address = handshake_server(test_info(), |_| {}).close_on(close.clone()).start().await;
+
+        let mut client = GrpcClient::connect(address, false, None, false, None).await.unwrap();
+        let consumers = [client.register_stream_closed_listener(), client.register_stream_closed_listener()];
+        assert!(consumers.iter().all(|consumer| consumer.is_empty()));
+
+        close.notify_one();
+        for consumer in consumers.iter() {
+            let closed = tokio::time::timeout(Duration::from_secs(1), consumer.recv()).await;
+            assert!(matches!(closed, Ok(Ok(StreamClosed::ByServer))), "every consumer should receive the stale signal");
+        }
+        assert!(!client.is_connected());
+
+        // Shutting down the client is no stream closure
+        client.shutdown().await.unwrap();
+        assert!(consumers.iter().all(|consumer| consumer.is_empty()));
+    }
+
+    /// A span captured by [`SpanCapture`]
+    #[derive(Debug)]
+    struct CapturedSpan {
+        name: &'static str,
+        fields: HashMap<&'static str, String>,
+    }
+
+    impl Visit for CapturedSpan {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.fields.insert(field.name(), format!("{value:?}"));
+        }
+
+        fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+            self.fields.insert(field.name(), value.to_string());
+        }
+    }
+
+    /// A tracing subscriber recording the spans it is given, including the fields recorded after their creation
+    #[derive(Clone, Default)]
+    struct SpanCapture {
+        spans: Arc<Mutex<Vec<CapturedSpan>>>,
+    }
+
+    impl tracing::Subscriber for SpanCapture {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+            let mut captured = CapturedSpan { name: span.metadata().name(), fields: HashMap::new() };
+            span.record(&mut captured);
+            let mut spans = self.spans.lock().unwrap();
+            spans.push(captured);
+            span::Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, id: &span::Id, values: &span::Record<'_>) {
+            values.record(&mut self.spans.lock().unwrap()[id.into_u64() as usize - 1]);
+        }
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+        fn event(&self, _: &tracing::Event<'_>) {}
+
+        fn enter(&self, _: &span::Id) {}
+
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    #[tokio::test]
+    async fn test_call_spans() {
+        let (address, request_ids) = start_info_server(test_info()).await;
+        let mut client = GrpcClient::connect(address, false, None, false, None).await.unwrap();
+
+        let capture = SpanCapture::default();
+        let guard = tracing::subscriber::set_default(capture.clone());
+        client.get_info_call(GetInfoRequest {}).await.unwrap();
+        drop(guard);
+
+        let id = request_ids.lock().unwrap().last().copied().unwrap().to_string();
+        let spans = capture.spans.lock().unwrap();
+        let call = spans.iter().find(|x| x.name == "grpc_call").expect("a span should be emitted for the call");
+        assert_eq!(call.fields.get("op").map(String::as_str), Some("GetInfo"));
+        assert_eq!(call.fields.get("id"), Some(&id));
+        assert_eq!(call.fields.get("outcome").map(String::as_str), Some("ok"));
+        let response = spans.iter().find(|x| x.name == "grpc_response").expect("a span should be emitted for the response");
+        assert_eq!(response.fields.get("id"), Some(&id));
+        assert_eq!(response.fields.get("notification").map(String::as_str), Some("false"));
+        drop(spans);
+
+        client.shutdown().await.unwrap();
+    }
+
+    /// A request id source replaying a fixed sequence
+    #[derive(Debug)]
+    struct SequenceRequestIdSource(Mutex<std::vec::IntoIter<u64>>);
+
+    impl RequestIdSource for SequenceRequestIdSource {
+        fn next_id(&self) -> u64 {
+            self.0.lock().unwrap().next().expect("the sequence should not be exhausted")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_id_source() {
+        let sequence = vec![17, 3, 42];
+        let (address, request_ids) = start_info_server(test_info()).await;
+        let source = Arc::new(SequenceRequestIdSource(Mutex::new(sequence.clone().into_iter())));
+        let mut client = GrpcClientBuilder::new(address).request_id_source(source).connect().await.unwrap();
+        for _ in 0..sequence.len() {
+            client.get_info_call(GetInfoRequest {}).await.unwrap();
+        }
+
+        // The handshake request is sent before any id is assigned
+        let expected = std::iter::once(0).chain(sequence).collect::<Vec<_>>();
+        assert_eq!(*request_ids.lock().unwrap(), expected);
+
+        client.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_reads() {
+        let block_requests = Arc::new(AtomicU64::new(0));
+        let address = slow_block_server(test_info(), block_requests.clone()).start().await;
+
+        let client = Arc::new(GrpcClientBuilder::new(address).coalesce_reads(true).connect().await.unwrap());
+        let request = GetBlockRequest::new(RpcHash::from_u64_word(1), true);
+        let calls = (0..10)
+            .map(|_| {
+                let client = client.clone();
+                let request = request.clone();
+                tokio::spawn(async move { client.get_block_call(request).await })
+            })
+            .collect::<Vec<_>>();
+        for call in calls {
+            let result = call.await.unwrap();
+            assert!(
+                matches!(result, Err(RpcError::General(ref message)) if message == BLOCK_NOT_FOUND),
+                "unexpected result {result:?}"
+            );
+        }
+        assert_eq!(block_requests.load(Ordering::SeqCst), 1, "identical concurrent reads should share a single request");
+
+        // Once resolved, the request is no longer in flight
+        client.get_block_call(request).await.unwrap_err();
+        assert_eq!(block_requests.load(Ordering::SeqCst), 2);
+
+        Arc::try_unwrap(client).unwrap().shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_expired_request_count() {
+        let address = slow_block_server(test_info(), Default::default()).start().await;
+
+        // The monitor task is replaced by explicit runs of the expiration, its interval being far longer than the test
+        let timeout = SLOW_BLOCK_DELAY / 4;
+        let client = Arc::new(
+            GrpcClientBuilder::new(address)
+                .timeout_duration(timeout.as_millis() as u64)
+                .timeout_monitor(false)
+                .connect()
+                .await
+                .unwrap(),
+        );
+        assert_eq!(client.expired_request_count(), 0);
+
+        let call = {
+            let client = client.clone();
+            let request = GetBlockRequest::new(RpcHash::from_u64_word(1), true).into();
+            tokio::spawn(async move { client.raw_call(RpcApiOps::GetBlock, request).await })
+        };
+        // A request still within its timeout is kept
+        client.inner.remove_expired_requests();
+        assert_eq!(client.expired_request_count(), 0);
+
+        tokio::time::sleep(timeout * 2).await;
+        client.inner.remove_expired_requests();
+        assert!(matches!(call.await.unwrap(), Err(Error::Timeout)));
+        assert_eq!(client.expired_request_count(), 1, "the expired request should be counted once");
+
+        // The late response of the server resolves nothing
+        tokio::time::sleep(SLOW_BLOCK_DELAY).await;
+        client.inner.remove_expired_requests();
+        assert_eq!(client.expired_request_count(), 1);
+
+        Arc::try_unwrap(client).unwrap().shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_op_stats() {
+        let address = slow_block_server(test_info(), Default::default()).start().await;
+
+        // Requests only expire on explicit runs of the expiration, the others waiting for their response however late
+        let timeout = SLOW_BLOCK_DELAY / 4;
+        let client = Arc::new(
+            GrpcClientBuilder::new(address)
+                .timeout_duration(timeout.as_millis() as u64)
+                .timeout_monitor(false)
+                .connect()
+                .await
+                .unwrap(),
+        );
+        assert!(client.op_stats().is_empty(), "the handshake should not be counted");
+
+        client.get_info().await.unwrap();
+        client.get_info().await.unwrap();
+
+        let call = {
+            let client = client.clone();
+            tokio::spawn(async move { client.get_block(RpcHash::from_u64_word(1), true).await })
+        };
+        tokio::time::sleep(timeout * 2).await;
+        client.inner.remove_expired_requests();
+        assert!(call.await.unwrap().is_err());
+
+        // The error stated by the server in its late response
+        assert!(client.get_block(RpcHash::from_u64_word(2), true).await.is_err());
+
+        let stats = client.op_stats();
+        assert_eq!(stats.len(), 2, "only the called ops should have an entry");
+        assert_eq!(stats[&RpcApiOps::GetInfo], OpStats { successes: 2, errors: 0, timeouts: 0 });
+        assert_eq!(stats[&RpcApiOps::GetBlock], OpStats { successes: 0, errors: 1, timeouts: 1 });
+        assert_eq!(stats[&RpcApiOps::GetBlock].total(), 2);
+
+        Arc::try_unwrap(client).unwrap().shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dropped_call_frees_pending_request() {
+        check_dropped_call_frees_pending_request(ResolverKind::Id).await;
+        check_dropped_call_frees_pending_request(ResolverKind::Queue).await;
+    }
+
+    async fn check_dropped_call_frees_pending_request(resolver_kind: ResolverKind) {
+        let address = slow_block_server(test_info(), Default::default()).start().await;
+
+        // No monitor run can free the request, so only the drop of the call does
+        let client = GrpcClientBuilder::new(address).resolver_kind(resolver_kind).timeout_monitor(false).connect().await.unwrap();
+        assert_eq!(client.resolver_kind(), resolver_kind);
+        assert_eq!(client.pending_request_count(), 0);
+
+        let request: KaspadRequest = GetBlockRequest::new(RpcHash::from_u64_word(1), true).into();
+        let call = tokio::time::timeout(SLOW_BLOCK_DELAY / 4, client.raw_call(RpcApiOps::GetBlock, request.clone()));
+        assert!(call.await.is_err(), "the call should be cancelled before the server answers");
+        assert_eq!(client.pending_request_count(), 0, "the cancelled call should no longer be pending");
+
+        // The late response of the server, arriving while a new call of the same op is pending, does not resolve it
+        let start = Instant::now();
+        assert!(client.raw_call(RpcApiOps::GetBlock, request).await.is_ok());
+        assert!(start.elapsed() >= SLOW_BLOCK_DELAY, "the new call should be resolved by its own response ({resolver_kind:?})");
+        assert_eq!(client.pending_request_count(), 0);
+        assert_eq!(client.expired_request_count(), 0);
+
+        client.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_subscription_guard() {
+        let commands = Arc::new(Mutex::new(vec![]));
+        // The commands of the BlockAdded subscriptions are recorded
+        let (info, recorded) = (test_info(), commands.clone());
+        let address = TestServer::new(move |exchange| match exchange.request.payload {
+            Some(kaspad_request::Payload::NotifyBlockAddedRequest(ref message)) => {
+                recorded.lock().unwrap().push(message.command);
+                exchange.respond(RpcResult::Ok(NotifyBlockAddedResponse {}).into());
+            }
+            _ => exchange.respond(RpcResult::Ok(info.clone()).into()),
+        })
+        .start()
+        .await;
+
+        let mut client = GrpcClient::connect(address, false, None, false, None).await.unwrap();
+        client.start().await;
+        let subscription = client.subscribe(EventType::BlockAdded).await.unwrap();
+        let id = subscription.id();
+        assert!(client.is_subscribed(id, subscription.scope()));
+        let receiver = (*subscription).clone();
+        assert!(!receiver.is_closed());
+
+        wait_for(|| commands.lock().unwrap().len() == 1, "the server to receive a start command").await;
+
+        drop(subscription);
+        assert!(receiver.is_closed(), "the listener should be unregistered");
+        assert!(!client.is_subscribed(id, &Scope::from(EventType::BlockAdded)));
+        wait_for(|| commands.lock().unwrap().len() == 2, "the server to receive a stop command").await;
+        assert_eq!(*commands.lock().unwrap(), vec![RpcNotifyCommand::NotifyStart as i32, RpcNotifyCommand::NotifyStop as i32]);
+
+        client.stop().await.unwrap();
+        client.shutdown().await.unwrap();
+    }
+}
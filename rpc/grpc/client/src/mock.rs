@@ -0,0 +1,568 @@
+//! A programmable in-memory implementation of [`RpcApi`] intended for testing code depending on [`GrpcClient`](crate::GrpcClient).
+//!
+//! Each route of a [`MockGrpcClient`] can be programmed either with a canned response or with a closure
+//! receiving the request. Calling a route that has not been programmed returns [`RpcError::NotImplemented`].
+//! Fake notifications can be pushed with [`MockGrpcClient::notify`] and are delivered to the registered
+//! listeners through a regular notifier, exactly like notifications received from a server.
+
+use async_trait::async_trait;
+use kaspa_grpc_core::channel::NotificationChannel;
+use kaspa_notify::{events::EVENT_TYPE_ARRAY, listener::ListenerId, notifier::Notifier, scope::Scope};
+use kaspa_rpc_core::{
+    api::ops::RpcApiOps,
+    api::rpc::RpcApi,
+    error::RpcError,
+    error::RpcResult,
+    model::message::*,
+    notify::{
+        collector::{RpcCoreCollector, RpcCoreConverter},
+        connection::ChannelConnection,
+    },
+    Notification, RpcAddress,
+};
+use std::{
+    any::Any,
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+type Payload = Box<dyn Any + Send>;
+type Handler = Arc<dyn Fn(Payload) -> RpcResult<Payload> + Send + Sync>;
+
+const MOCK_GRPC_CLIENT: &str = "mock-grpc-client";
+
+/// Implements [`RpcApi`] for [`MockGrpcClient`], routing each listed call to the handler programmed for its op.
+///
+/// The whole impl is generated, so that `#[async_trait]` applies to the routes once they are expanded.
+macro_rules! mock_rpc_api {
+    ([$(($fn:ident, $name:tt)),* $(,)?] $($rest:tt)*) => {
+        paste::paste! {
+            #[async_trait]
+            impl RpcApi<ChannelConnection> for MockGrpcClient {
+                $(
+                    async fn $fn(&self, request: [<$name Request>]) -> RpcResult<[<$name Response>]> {
+                        self.call(RpcApiOps::$name, request)
+                    }
+                )*
+
+                $($rest)*
+            }
+        }
+    };
+}
+
+/// A [`RpcApi`] implementor whose routes are programmed by the test using it.
+pub struct MockGrpcClient {
+    handlers: Mutex<HashMap<RpcApiOps, Handler>>,
+    notify_channel: NotificationChannel,
+    notifier: Arc<Notifier<Notification, ChannelConnection>>,
+}
+
+impl MockGrpcClient {
+    pub fn new() -> Self {
+        let notify_channel = NotificationChannel::default();
+        let core_events = EVENT_TYPE_ARRAY[..].into();
+        let converter = Arc::new(RpcCoreConverter::new());
+        let collector = Arc::new(RpcCoreCollector::new(notify_channel.receiver(), converter));
+        let notifier = Arc::new(Notifier::new(core_events, vec![collector], vec![], 1, MOCK_GRPC_CLIENT));
+        Self { handlers: Mutex::new(HashMap::new()), notify_channel, notifier }
+    }
+
+    #[inline(always)]
+    pub fn notifier(&self) -> Arc<Notifier<Notification, ChannelConnection>> {
+        self.notifier.clone()
+    }
+
+    pub async fn start(&self) {
+        self.notifier().start();
+    }
+
+    pub async fn stop(&self) -> RpcResult<()> {
+        self.notifier().stop().await?;
+        Ok(())
+    }
+
+    /// Adds `addresses` to the `UtxosChanged` subscription of listener `id`, subscribing to the new addresses only.
+    pub async fn add_utxo_addresses(&self, id: ListenerId, addresses: Vec<RpcAddress>) -> RpcResult<()> {
+        self.notifier.add_utxo_addresses(id, addresses)?;
+        Ok(())
+    }
+
+    /// Removes `addresses` from the `UtxosChanged` subscription of listener `id`, unsubscribing from these addresses only.
+    pub async fn remove_utxo_addresses(&self, id: ListenerId, addresses: Vec<RpcAddress>) -> RpcResult<()> {
+        self.notifier.remove_utxo_addresses(id, addresses)?;
+        Ok(())
+    }
+
+    /// Returns true if listener `id` is currently subscribed to notifications matching `scope`.
+    ///
+    /// A `UtxosChanged` scope is considered subscribed if any of its addresses is.
+    pub fn is_subscribed(&self, id: ListenerId, scope: &Scope) -> bool {
+        self.notifier.is_subscribed(id, scope)
+    }
+
+    /// Returns the number of notifications dropped so far for listener `id` because its channel was full.
+    pub fn notification_drops(&self, id: ListenerId) -> u64 {
+        self.notifier.notification_drops(id)
+    }
+
+    /// Returns the number of listeners currently registered, whatever their subscriptions.
+    pub fn listener_count(&self) -> usize {
+        self.notifier.listener_count()
+    }
+
+    /// Program the route `op` with a closure receiving the request and returning the response.
+    ///
+    /// `Req` and `Resp` must be the request and response types of `op`, otherwise calling the route
+    /// returns an [`RpcError::General`].
+    pub fn on<Req, Resp, F>(&self, op: RpcApiOps, handler: F)
+    where
+        Req: Send + 'static,
+        Resp: Send + 'static,
+        F: Fn(Req) -> RpcResult<Resp> + Send + Sync + 'static,
+    {
+        let handler: Handler = Arc::new(move |request: Payload| {
+            let request = request.downcast::<Req>().map_err(|_| RpcError::General(format!("unexpected request type for {op:?}")))?;
+            Ok(Box::new(handler(*request)?) as Payload)
+        });
+        self.handlers.lock().unwrap().insert(op, handler);
+    }
+
+    /// Program the route `op` to always return a clone of `response`, whatever the request.
+    pub fn respond_with<Resp>(&self, op: RpcApiOps, response: Resp)
+    where
+        Resp: Clone + Send + Sync + 'static,
+    {
+        let handler: Handler = Arc::new(move |_: Payload| Ok(Box::new(response.clone()) as Payload));
+        self.handlers.lock().unwrap().insert(op, handler);
+    }
+
+    /// Push a fake notification to the notifier, as if it had been received from a server.
+    pub fn notify(&self, notification: Notification) -> RpcResult<()> {
+        self.notify_channel.try_send(notification).map_err(kaspa_notify::error::Error::from)?;
+        Ok(())
+    }
+
+    fn call<Req, Resp>(&self, op: RpcApiOps, request: Req) -> RpcResult<Resp>
+    where
+        Req: Send + 'static,
+        Resp: Send + 'static,
+    {
+        let handler = self.handlers.lock().unwrap().get(&op).cloned().ok_or(RpcError::NotImplemented)?;
+        let response = handler(Box::new(request))?;
+        response.downcast::<Resp>().map(|x| *x).map_err(|_| RpcError::General(format!("unexpected response type for {op:?}")))
+    }
+}
+
+impl Default for MockGrpcClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+mock_rpc_api! {
+    [
+        (ping_call, Ping),
+        (get_process_metrics_call, GetProcessMetrics),
+        (submit_block_call, SubmitBlock),
+        (get_block_template_call, GetBlockTemplate),
+        (get_block_call, GetBlock),
+        (get_info_call, GetInfo),
+        (get_current_network_call, GetCurrentNetwork),
+        (get_peer_addresses_call, GetPeerAddresses),
+        (get_selected_tip_hash_call, GetSelectedTipHash),
+        (get_mempool_entry_call, GetMempoolEntry),
+        (get_mempool_entries_call, GetMempoolEntries),
+        (get_connected_peer_info_call, GetConnectedPeerInfo),
+        (add_peer_call, AddPeer),
+        (submit_transaction_call, SubmitTransaction),
+        (get_subnetwork_call, GetSubnetwork),
+        (get_virtual_chain_from_block_call, GetVirtualChainFromBlock),
+        (get_blocks_call, GetBlocks),
+        (get_block_count_call, GetBlockCount),
+        (get_block_dag_info_call, GetBlockDagInfo),
+        (resolve_finality_conflict_call, ResolveFinalityConflict),
+        (shutdown_call, Shutdown),
+        (get_headers_call, GetHeaders),
+        (get_utxos_by_addresses_call, GetUtxosByAddresses),
+        (get_balance_by_address_call, GetBalanceByAddress),
+        (get_balances_by_addresses_call, GetBalancesByAddresses),
+        (get_sink_blue_score_call, GetSinkBlueScore),
+        (ban_call, Ban),
+        (unban_call, Unban),
+        (estimate_network_hashes_per_second_call, EstimateNetworkHashesPerSecond),
+        (get_mempool_entries_by_addresses_call, GetMempoolEntriesByAddresses),
+        (get_coin_supply_call, GetCoinSupply),
+    ]
+
+    // ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+    // Notification API
+
+    /// Register a new listener and returns an id identifying it.
+    fn register_new_listener(&self, connection: ChannelConnection) -> ListenerId {
+        self.notifier.register_new_listener(connection)
+    }
+
+    /// Unregister an existing listener.
+    ///
+    /// Stop all notifications for this listener, unregister the id and its associated connection.
+    async fn unregister_listener(&self, id: ListenerId) -> RpcResult<()> {
+        self.notifier.unregister_listener(id)?;
+        Ok(())
+    }
+
+    /// Start sending notifications of some type to a listener.
+    async fn start_notify(&self, id: ListenerId, scope: Scope) -> RpcResult<()> {
+        self.notifier().start_notify(id, scope).await?;
+        Ok(())
+    }
+
+    /// Stop sending notifications of some type to a listener.
+    async fn stop_notify(&self, id: ListenerId, scope: Scope) -> RpcResult<()> {
+        self.notifier().stop_notify(id, scope).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use kaspa_notify::{
+        address::test_helpers::get_3_addresses,
+        events::EventType,
+        scope::{BlockAddedScope, UtxosChangedScope},
+    };
+    use kaspa_rpc_core::{
+        api::ext::{AcceptanceInfo, RpcApiExt},
+        RpcAcceptedTransactionIds, RpcBalancesByAddressesEntry, RpcBlock, RpcHash, RpcHeader, RpcNetworkType, RpcScriptPublicKey,
+        RpcSubnetworkId, RpcTransaction, RpcTransactionOutpoint, RpcUtxoEntry, RpcUtxosByAddressesEntry,
+    };
+    use std::collections::VecDeque;
+    use std::time::Duration;
+
+    fn block_added(word: u64) -> Notification {
+        let block = RpcBlock {
+            header: RpcHeader::from_precomputed_hash(RpcHash::from_u64_word(word), vec![]),
+            transactions: vec![],
+            verbose_data: None,
+        };
+        Notification::BlockAdded(BlockAddedNotification { block: Arc::new(block) })
+    }
+
+    #[tokio::test]
+    async fn test_mock_grpc_client() {
+        let client = MockGrpcClient::new();
+        client.start().await;
+
+        // Program a canned get_info response
+        let info = GetInfoResponse {
+            p2p_id: "mock-p2p-id".to_string(),
+            mempool_size: 7,
+            server_version: "0.0.0-mock".to_string(),
+            is_utxo_indexed: true,
+            is_synced: true,
+            has_notify_command: true,
+            has_message_id: true,
+        };
+        client.respond_with(RpcApiOps::GetInfo, info.clone());
+        let response = client.get_info_call(GetInfoRequest {}).await.unwrap();
+        assert_eq!(response.p2p_id, info.p2p_id);
+        assert_eq!(response.mempool_size, info.mempool_size);
+        assert_eq!(response.server_version, info.server_version);
+
+        // Unprogrammed routes are not implemented
+        assert!(matches!(client.get_block_count_call(GetBlockCountRequest {}).await, Err(RpcError::NotImplemented)));
+
+        // Push a fake BlockAdded notification to a subscribed listener
+        let channel = NotificationChannel::default();
+        let id = client.register_new_listener(ChannelConnection::new(channel.sender()));
+        client.start_notify(id, Scope::BlockAdded(BlockAddedScope::default())).await.unwrap();
+        // Give the broadcaster some time to process the subscription
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let hash = RpcHash::from_u64_word(42);
+        client.notify(block_added(42)).unwrap();
+
+        let notification = tokio::time::timeout(Duration::from_secs(1), channel.receiver().recv()).await.unwrap().unwrap();
+        match notification {
+            Notification::BlockAdded(payload) => assert_eq!(payload.block.header.hash, hash),
+            _ => panic!("unexpected notification {notification:?}"),
+        }
+
+        client.unregister_listener(id).await.unwrap();
+        client.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_next_notification() {
+        let client = MockGrpcClient::new();
+        client.start().await;
+
+        let hash = RpcHash::from_u64_word(7);
+        let trigger = async {
+            // Give the broadcaster some time to process the subscription
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            client.notify(block_added(7)).unwrap();
+        };
+        let (notification, _) = tokio::join!(client.next_notification(EventType::BlockAdded, Duration::from_secs(1)), trigger);
+        match notification.unwrap() {
+            Notification::BlockAdded(payload) => assert_eq!(payload.block.header.hash, hash),
+            notification => panic!("unexpected notification {notification:?}"),
+        }
+
+        // No notification is ever sent for this event type
+        let result = client.next_notification(EventType::VirtualDaaScoreChanged, Duration::from_millis(50)).await;
+        assert!(matches!(result, Err(RpcError::NotificationTimeout(EventType::VirtualDaaScoreChanged))));
+
+        client.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_listener_capacity() {
+        const SMALL_CAPACITY: usize = 4;
+        const NOTIFICATION_COUNT: u64 = 32;
+
+        let client = MockGrpcClient::new();
+        client.start().await;
+
+        let (small_id, small_receiver) = client.register_new_listener_with_capacity(SMALL_CAPACITY);
+        let (large_id, large_receiver) = client.register_new_channel_listener();
+        for id in [small_id, large_id] {
+            client.start_notify(id, Scope::BlockAdded(BlockAddedScope::default())).await.unwrap();
+        }
+        // Give the broadcaster some time to process the subscriptions
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Nobody consumes the notifications while they are sent
+        (0..NOTIFICATION_COUNT).for_each(|i| client.notify(block_added(i)).unwrap());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // The small listener kept the oldest notifications and dropped the rest
+        let received = std::iter::from_fn(|| small_receiver.try_recv().ok()).collect::<Vec<_>>();
+        assert_eq!(received.len(), SMALL_CAPACITY);
+        // The large listener buffered them all
+        let received = std::iter::from_fn(|| large_receiver.try_recv().ok()).collect::<Vec<_>>();
+        assert_eq!(received.len(), NOTIFICATION_COUNT as usize);
+
+        client.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_notification_drops() {
+        const CAPACITY: usize = 2;
+
+        let client = MockGrpcClient::new();
+        client.start().await;
+
+        let (id, receiver) = client.register_new_listener_with_capacity(CAPACITY);
+        client.start_notify(id, Scope::BlockAdded(BlockAddedScope::default())).await.unwrap();
+        // Give the broadcaster some time to process the subscription
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(client.notification_drops(id), 0);
+
+        // Overflow the listener
+        (0..10).for_each(|i| client.notify(block_added(i)).unwrap());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(client.notification_drops(id), 8);
+
+        // Once consumed, notifications are delivered again but the count of drops keeps growing on further overflows
+        assert_eq!(std::iter::from_fn(|| receiver.try_recv().ok()).count(), CAPACITY);
+        (10..13).for_each(|i| client.notify(block_added(i)).unwrap());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(client.notification_drops(id), 9);
+
+        client.unregister_listener(id).await.unwrap();
+        assert_eq!(client.notification_drops(id), 0);
+        client.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_blocks_stream() {
+        const BLOCK_COUNT: u64 = 10;
+        const PAGE_SIZE: usize = 4;
+
+        let client = MockGrpcClient::new();
+        let hashes = (0..BLOCK_COUNT).map(RpcHash::from_u64_word).collect::<Vec<_>>();
+        let pages = Arc::new(Mutex::new(0));
+
+        // Serve the blocks in pages including their low hash, like a node does
+        let served_hashes = hashes.clone();
+        let served_pages = pages.clone();
+        client.on(RpcApiOps::GetBlocks, move |request: GetBlocksRequest| {
+            *served_pages.lock().unwrap() += 1;
+            let start = request.low_hash.map_or(0, |low_hash| served_hashes.iter().position(|x| *x == low_hash).unwrap());
+            let page = &served_hashes[start..(start + PAGE_SIZE).min(served_hashes.len())];
+            let blocks = page
+                .iter()
+                .map(|hash| RpcBlock {
+                    header: RpcHeader::from_precomputed_hash(*hash, vec![]),
+                    transactions: vec![],
+                    verbose_data: None,
+                })
+                .collect();
+            Ok(GetBlocksResponse::new(page.to_vec(), blocks))
+        });
+
+        let streamed = client.get_blocks_stream(None, false).map(|block| block.unwrap().header.hash).collect::<Vec<_>>().await;
+        assert_eq!(streamed, hashes);
+        // Pages start at blocks 0, 3, 6 and 9, the last one bringing no new block
+        assert_eq!(*pages.lock().unwrap(), 4);
+
+        // Streaming from some low hash includes it
+        let streamed =
+            client.get_blocks_stream(Some(hashes[5]), false).map(|block| block.unwrap().header.hash).collect::<Vec<_>>().await;
+        assert_eq!(streamed, hashes[5..]);
+    }
+
+    #[tokio::test]
+    async fn test_get_address_state() {
+        let client = MockGrpcClient::new();
+        let addresses = get_3_addresses(true);
+        let balances = addresses.iter().map(|address| RpcBalancesByAddressesEntry { address: address.clone(), balance: Some(100) });
+        client.respond_with(RpcApiOps::GetBalancesByAddresses, GetBalancesByAddressesResponse::new(balances.collect()));
+        let utxos = addresses
+            .iter()
+            .enumerate()
+            .map(|(i, address)| RpcUtxosByAddressesEntry {
+                address: Some(address.clone()),
+                outpoint: RpcTransactionOutpoint::new(RpcHash::from_u64_word(i as u64), 0),
+                utxo_entry: RpcUtxoEntry::new(100, RpcScriptPublicKey::from_vec(0, vec![]), 1, false),
+            })
+            .collect();
+        client.respond_with(RpcApiOps::GetUtxosByAddresses, GetUtxosByAddressesResponse::new(utxos));
+
+        let state = client.get_address_state(addresses.clone()).await.unwrap();
+        assert_eq!(state.balances.iter().map(|entry| entry.address.clone()).collect::<Vec<_>>(), addresses);
+        assert!(state.balances.iter().all(|entry| entry.balance == Some(100)));
+        assert_eq!(state.utxos.iter().map(|entry| entry.address.clone().unwrap()).collect::<Vec<_>>(), addresses);
+
+        // The state is not returned partially
+        let client = MockGrpcClient::new();
+        client.respond_with(RpcApiOps::GetBalancesByAddresses, GetBalancesByAddressesResponse::new(vec![]));
+        assert!(matches!(client.get_address_state(addresses).await, Err(RpcError::NotImplemented)));
+    }
+
+    #[tokio::test]
+    async fn test_transaction_confirmations() {
+        const SINK_BLUE_SCORE: u64 = 20;
+
+        let client = MockGrpcClient::new();
+        let pruning_point = RpcHash::from_u64_word(0);
+        // Chain blocks 1 to 5 have blue scores 11 to 15
+        let chain = (1..=5).map(RpcHash::from_u64_word).collect::<Vec<_>>();
+        let blue_scores = chain.iter().zip(11..).map(|(hash, blue_score)| (*hash, blue_score)).collect::<HashMap<_, _>>();
+        let (txid, unaccepted_txid) = (RpcHash::from_u64_word(100), RpcHash::from_u64_word(101));
+
+        // The accepting block of txid, and the ones accepting it after each of the reorgs still to occur
+        let accepting = Arc::new(Mutex::new(chain[1]));
+        let reorgs = Arc::new(Mutex::new(VecDeque::<RpcHash>::new()));
+
+        let info =
+            GetBlockDagInfoResponse::new(RpcNetworkType::Mainnet, 6, 6, vec![chain[4]], 1.0, 0, vec![chain[4]], pruning_point, 0);
+        client.respond_with(RpcApiOps::GetBlockDagInfo, info);
+        client.respond_with(RpcApiOps::GetSinkBlueScore, GetSinkBlueScoreResponse::new(SINK_BLUE_SCORE));
+        client.on(RpcApiOps::GetBlock, move |request: GetBlockRequest| {
+            let mut header = RpcHeader::from_precomputed_hash(request.hash, vec![]);
+            header.blue_score = blue_scores[&request.hash];
+            Ok(GetBlockResponse { block: RpcBlock { header, transactions: vec![], verbose_data: None } })
+        });
+        let (served_accepting, served_reorgs) = (accepting.clone(), reorgs.clone());
+        client.on(RpcApiOps::GetVirtualChainFromBlock, move |request: GetVirtualChainFromBlockRequest| {
+            let mut accepting = served_accepting.lock().unwrap();
+            if request.start_hash == pruning_point {
+                let accepted =
+                    vec![RpcAcceptedTransactionIds { accepting_block_hash: *accepting, accepted_transaction_ids: vec![txid] }];
+                return Ok(GetVirtualChainFromBlockResponse::new(vec![], chain.clone(), accepted));
+            }
+            // The accepting block was just reorganized out of the selected chain
+            match served_reorgs.lock().unwrap().pop_front() {
+                Some(next) => {
+                    *accepting = next;
+                    Ok(GetVirtualChainFromBlockResponse::new(vec![request.start_hash], vec![next], vec![]))
+                }
+                None => Ok(GetVirtualChainFromBlockResponse::new(vec![], vec![], vec![])),
+            }
+        });
+
+        assert_eq!(client.transaction_confirmations(txid).await.unwrap(), Some(SINK_BLUE_SCORE - 12 + 1));
+        assert_eq!(client.transaction_confirmations(unaccepted_txid).await.unwrap(), None);
+
+        // A reorg while the blue scores are fetched retries the lookup, finding the new accepting block
+        reorgs.lock().unwrap().push_back(RpcHash::from_u64_word(4));
+        assert_eq!(client.transaction_confirmations(txid).await.unwrap(), Some(SINK_BLUE_SCORE - 14 + 1));
+
+        // A chain reorganizing on every lookup is reported as such
+        reorgs.lock().unwrap().extend((3..=5).map(RpcHash::from_u64_word));
+        let result = client.transaction_confirmations(txid).await;
+        assert!(matches!(result, Err(RpcError::TransactionAcceptanceReorged(id)) if id == txid));
+        assert_eq!(*accepting.lock().unwrap(), RpcHash::from_u64_word(5));
+    }
+
+    #[tokio::test]
+    async fn test_submit_and_await_acceptance() {
+        let client = MockGrpcClient::new();
+        client.start().await;
+        let transaction = RpcTransaction {
+            version: 0,
+            inputs: vec![],
+            outputs: vec![],
+            lock_time: 0,
+            subnetwork_id: RpcSubnetworkId::from_byte(0),
+            gas: 0,
+            payload: vec![],
+            verbose_data: None,
+        };
+        let (txid, accepting_block_hash) = (RpcHash::from_u64_word(100), RpcHash::from_u64_word(7));
+        client.respond_with(RpcApiOps::SubmitTransaction, SubmitTransactionResponse { transaction_id: txid });
+        let chain_changed = |accepted_transaction_ids| {
+            Notification::VirtualChainChanged(VirtualChainChangedNotification {
+                removed_chain_block_hashes: Arc::new(vec![]),
+                added_chain_block_hashes: Arc::new(vec![accepting_block_hash]),
+                accepted_transaction_ids: Arc::new(vec![RpcAcceptedTransactionIds { accepting_block_hash, accepted_transaction_ids }]),
+            })
+        };
+
+        let trigger = async {
+            // Give the broadcaster some time to process the subscription
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            // A chain change accepting other transactions leaves the call waiting
+            client.notify(chain_changed(vec![RpcHash::from_u64_word(101)])).unwrap();
+            client.notify(chain_changed(vec![RpcHash::from_u64_word(102), txid])).unwrap();
+        };
+        let (acceptance, _) = tokio::join!(client.submit_and_await_acceptance(transaction.clone(), Duration::from_secs(1)), trigger);
+        assert_eq!(acceptance.unwrap(), AcceptanceInfo { transaction_id: txid, accepting_block_hash });
+        assert_eq!(client.listener_count(), 0, "the temporary listener should be unregistered");
+
+        // No acceptance is ever notified
+        let result = client.submit_and_await_acceptance(transaction, Duration::from_millis(50)).await;
+        assert!(matches!(result, Err(RpcError::TransactionAcceptanceTimeout(id)) if id == txid));
+
+        client.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_is_subscribed() {
+        let client = MockGrpcClient::new();
+        let addresses = get_3_addresses(false);
+        let utxos_changed = |addresses: &[kaspa_rpc_core::RpcAddress]| Scope::UtxosChanged(UtxosChangedScope::new(addresses.to_vec()));
+        let (id, _receiver) = client.register_new_channel_listener();
+
+        let block_added = Scope::BlockAdded(BlockAddedScope::default());
+        assert!(!client.is_subscribed(id, &block_added));
+        client.start_notify(id, block_added.clone()).await.unwrap();
+        assert!(client.is_subscribed(id, &block_added));
+        client.stop_notify(id, block_added.clone()).await.unwrap();
+        assert!(!client.is_subscribed(id, &block_added));
+
+        client.start_notify(id, utxos_changed(&addresses[0..2])).await.unwrap();
+        assert!(client.is_subscribed(id, &utxos_changed(&addresses[1..3])), "overlapping addresses are subscribed");
+        assert!(!client.is_subscribed(id, &utxos_changed(&addresses[2..3])), "other addresses are not subscribed");
+        assert!(client.is_subscribed(id, &utxos_changed(&[])));
+        client.stop_notify(id, utxos_changed(&addresses[0..2])).await.unwrap();
+        assert!(!client.is_subscribed(id, &utxos_changed(&addresses[1..3])));
+
+        // Unknown listeners have no subscription
+        assert!(!client.is_subscribed(id + 1, &block_added));
+    }
+}
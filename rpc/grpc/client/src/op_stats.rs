@@ -0,0 +1,56 @@
+//! Outcomes of the calls of each op, see [`GrpcClient::op_stats`].
+//!
+//! [`GrpcClient::op_stats`]: crate::GrpcClient::op_stats
+
+use kaspa_rpc_core::api::ops::RpcApiOps;
+use std::{collections::HashMap, sync::Mutex};
+
+/// Outcome of a completed call
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CallOutcome {
+    Success,
+    /// The call failed, either on the client side or with an error stated by the server
+    Error,
+    /// No response was received within the request timeout
+    Timeout,
+}
+
+/// Counts of the outcomes of the calls of an op
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OpStats {
+    pub successes: u64,
+    /// Failed calls, the timeouts excluded
+    pub errors: u64,
+    pub timeouts: u64,
+}
+
+impl OpStats {
+    /// Number of completed calls, whatever their outcome
+    pub fn total(&self) -> u64 {
+        self.successes + self.errors + self.timeouts
+    }
+
+    fn record(&mut self, outcome: CallOutcome) {
+        match outcome {
+            CallOutcome::Success => self.successes += 1,
+            CallOutcome::Error => self.errors += 1,
+            CallOutcome::Timeout => self.timeouts += 1,
+        }
+    }
+}
+
+/// Counts of the outcomes of the calls, by op
+#[derive(Debug, Default)]
+pub(crate) struct OpStatsRecorder {
+    stats: Mutex<HashMap<RpcApiOps, OpStats>>,
+}
+
+impl OpStatsRecorder {
+    pub(crate) fn record(&self, op: RpcApiOps, outcome: CallOutcome) {
+        self.stats.lock().unwrap().entry(op).or_default().record(outcome);
+    }
+
+    pub(crate) fn snapshot(&self) -> HashMap<RpcApiOps, OpStats> {
+        self.stats.lock().unwrap().clone()
+    }
+}
@@ -0,0 +1,304 @@
+//! A pool of [`GrpcClient`] connections to a same server, multiplying the request throughput of read-heavy workloads.
+
+use crate::{
+    builder::GrpcClientBuilder,
+    error::{Error, Result},
+    GrpcClient,
+};
+use async_trait::async_trait;
+use kaspa_notify::{listener::ListenerId, scope::Scope};
+use kaspa_rpc_core::{api::rpc::RpcApi, model::message::*, notify::connection::ChannelConnection, RpcResult};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A set of independent [`GrpcClient`] connections to a same server, exposing the [`RpcApi`] of a single client.
+///
+/// Calls are dispatched to the connections in a round-robin fashion. Notifications are all consolidated onto the first
+/// connection, so a listener never receives a same notification twice.
+pub struct GrpcClientPool {
+    clients: Vec<GrpcClient>,
+    next: AtomicUsize,
+}
+
+impl GrpcClientPool {
+    /// Opens `size` connections to the server at `address`, using the default settings of [`GrpcClientBuilder`].
+    pub async fn connect(address: String, size: usize) -> Result<GrpcClientPool> {
+        Self::connect_with_builder(GrpcClientBuilder::new(address), size).await
+    }
+
+    /// Opens `size` connections configured by `builder`.
+    pub async fn connect_with_builder(builder: GrpcClientBuilder, size: usize) -> Result<GrpcClientPool> {
+        if size == 0 {
+            return Err(Error::String("a gRPC client pool needs at least one connection".to_string()));
+        }
+        let mut clients = Vec::with_capacity(size);
+        for _ in 0..size {
+            match builder.clone().connect().await {
+                Ok(client) => clients.push(client),
+                Err(err) => {
+                    for mut client in clients {
+                        let _ = client.shutdown().await;
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        Ok(Self { clients, next: AtomicUsize::new(0) })
+    }
+
+    /// Number of connections of the pool
+    pub fn size(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Returns the underlying clients, the first one being the notification client.
+    pub fn clients(&self) -> &[GrpcClient] {
+        &self.clients
+    }
+
+    /// Returns the client all the notifications are received from.
+    pub fn notification_client(&self) -> &GrpcClient {
+        &self.clients[0]
+    }
+
+    fn next_client(&self) -> &GrpcClient {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.clients.len();
+        &self.clients[index]
+    }
+
+    /// Returns true if all the connections of the pool are connected.
+    pub fn is_connected(&self) -> bool {
+        self.clients.iter().all(|client| client.is_connected())
+    }
+
+    pub async fn start(&self) {
+        self.notification_client().start().await;
+    }
+
+    pub async fn stop(&self) -> Result<()> {
+        self.notification_client().stop().await
+    }
+
+    pub async fn shutdown(&mut self) -> Result<()> {
+        for client in self.clients.iter_mut() {
+            client.shutdown().await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RpcApi<ChannelConnection> for GrpcClientPool {
+    async fn ping_call(&self, request: PingRequest) -> RpcResult<PingResponse> {
+        self.next_client().ping_call(request).await
+    }
+
+    async fn get_process_metrics_call(&self, request: GetProcessMetricsRequest) -> RpcResult<GetProcessMetricsResponse> {
+        self.next_client().get_process_metrics_call(request).await
+    }
+
+    async fn submit_block_call(&self, request: SubmitBlockRequest) -> RpcResult<SubmitBlockResponse> {
+        self.next_client().submit_block_call(request).await
+    }
+
+    async fn get_block_template_call(&self, request: GetBlockTemplateRequest) -> RpcResult<GetBlockTemplateResponse> {
+        self.next_client().get_block_template_call(request).await
+    }
+
+    async fn get_block_call(&self, request: GetBlockRequest) -> RpcResult<GetBlockResponse> {
+        self.next_client().get_block_call(request).await
+    }
+
+    async fn get_info_call(&self, request: GetInfoRequest) -> RpcResult<GetInfoResponse> {
+        self.next_client().get_info_call(request).await
+    }
+
+    async fn get_current_network_call(&self, request: GetCurrentNetworkRequest) -> RpcResult<GetCurrentNetworkResponse> {
+        self.next_client().get_current_network_call(request).await
+    }
+
+    async fn get_peer_addresses_call(&self, request: GetPeerAddressesRequest) -> RpcResult<GetPeerAddressesResponse> {
+        self.next_client().get_peer_addresses_call(request).await
+    }
+
+    async fn get_selected_tip_hash_call(&self, request: GetSelectedTipHashRequest) -> RpcResult<GetSelectedTipHashResponse> {
+        self.next_client().get_selected_tip_hash_call(request).await
+    }
+
+    async fn get_mempool_entry_call(&self, request: GetMempoolEntryRequest) -> RpcResult<GetMempoolEntryResponse> {
+        self.next_client().get_mempool_entry_call(request).await
+    }
+
+    async fn get_mempool_entries_call(&self, request: GetMempoolEntriesRequest) -> RpcResult<GetMempoolEntriesResponse> {
+        self.next_client().get_mempool_entries_call(request).await
+    }
+
+    async fn get_connected_peer_info_call(&self, request: GetConnectedPeerInfoRequest) -> RpcResult<GetConnectedPeerInfoResponse> {
+        self.next_client().get_connected_peer_info_call(request).await
+    }
+
+    async fn add_peer_call(&self, request: AddPeerRequest) -> RpcResult<AddPeerResponse> {
+        self.next_client().add_peer_call(request).await
+    }
+
+    async fn submit_transaction_call(&self, request: SubmitTransactionRequest) -> RpcResult<SubmitTransactionResponse> {
+        self.next_client().submit_transaction_call(request).await
+    }
+
+    async fn get_subnetwork_call(&self, request: GetSubnetworkRequest) -> RpcResult<GetSubnetworkResponse> {
+        self.next_client().get_subnetwork_call(request).await
+    }
+
+    async fn get_virtual_chain_from_block_call(
+        &self,
+        request: GetVirtualChainFromBlockRequest,
+    ) -> RpcResult<GetVirtualChainFromBlockResponse> {
+        self.next_client().get_virtual_chain_from_block_call(request).await
+    }
+
+    async fn get_blocks_call(&self, request: GetBlocksRequest) -> RpcResult<GetBlocksResponse> {
+        self.next_client().get_blocks_call(request).await
+    }
+
+    async fn get_block_count_call(&self, request: GetBlockCountRequest) -> RpcResult<GetBlockCountResponse> {
+        self.next_client().get_block_count_call(request).await
+    }
+
+    async fn get_block_dag_info_call(&self, request: GetBlockDagInfoRequest) -> RpcResult<GetBlockDagInfoResponse> {
+        self.next_client().get_block_dag_info_call(request).await
+    }
+
+    async fn resolve_finality_conflict_call(
+        &self,
+        request: ResolveFinalityConflictRequest,
+    ) -> RpcResult<ResolveFinalityConflictResponse> {
+        self.next_client().resolve_finality_conflict_call(request).await
+    }
+
+    async fn shutdown_call(&self, request: ShutdownRequest) -> RpcResult<ShutdownResponse> {
+        self.next_client().shutdown_call(request).await
+    }
+
+    async fn get_headers_call(&self, request: GetHeadersRequest) -> RpcResult<GetHeadersResponse> {
+        self.next_client().get_headers_call(request).await
+    }
+
+    async fn get_utxos_by_addresses_call(&self, request: GetUtxosByAddressesRequest) -> RpcResult<GetUtxosByAddressesResponse> {
+        self.next_client().get_utxos_by_addresses_call(request).await
+    }
+
+    async fn get_balance_by_address_call(&self, request: GetBalanceByAddressRequest) -> RpcResult<GetBalanceByAddressResponse> {
+        self.next_client().get_balance_by_address_call(request).await
+    }
+
+    async fn get_balances_by_addresses_call(
+        &self,
+        request: GetBalancesByAddressesRequest,
+    ) -> RpcResult<GetBalancesByAddressesResponse> {
+        self.next_client().get_balances_by_addresses_call(request).await
+    }
+
+    async fn get_sink_blue_score_call(&self, request: GetSinkBlueScoreRequest) -> RpcResult<GetSinkBlueScoreResponse> {
+        self.next_client().get_sink_blue_score_call(request).await
+    }
+
+    async fn ban_call(&self, request: BanRequest) -> RpcResult<BanResponse> {
+        self.next_client().ban_call(request).await
+    }
+
+    async fn unban_call(&self, request: UnbanRequest) -> RpcResult<UnbanResponse> {
+        self.next_client().unban_call(request).await
+    }
+
+    async fn estimate_network_hashes_per_second_call(
+        &self,
+        request: EstimateNetworkHashesPerSecondRequest,
+    ) -> RpcResult<EstimateNetworkHashesPerSecondResponse> {
+        self.next_client().estimate_network_hashes_per_second_call(request).await
+    }
+
+    async fn get_mempool_entries_by_addresses_call(
+        &self,
+        request: GetMempoolEntriesByAddressesRequest,
+    ) -> RpcResult<GetMempoolEntriesByAddressesResponse> {
+        self.next_client().get_mempool_entries_by_addresses_call(request).await
+    }
+
+    async fn get_coin_supply_call(&self, request: GetCoinSupplyRequest) -> RpcResult<GetCoinSupplyResponse> {
+        self.next_client().get_coin_supply_call(request).await
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------
+    // Notification API
+
+    /// Register a new listener on the notification client and returns an id identifying it.
+    fn register_new_listener(&self, connection: ChannelConnection) -> ListenerId {
+        self.notification_client().register_new_listener(connection)
+    }
+
+    /// Unregister an existing listener.
+    ///
+    /// Stop all notifications for this listener, unregister the id and its associated connection.
+    async fn unregister_listener(&self, id: ListenerId) -> RpcResult<()> {
+        self.notification_client().unregister_listener(id).await
+    }
+
+    /// Start sending notifications of some type to a listener.
+    async fn start_notify(&self, id: ListenerId, scope: Scope) -> RpcResult<()> {
+        self.notification_client().start_notify(id, scope).await
+    }
+
+    /// Stop sending notifications of some type to a listener.
+    async fn stop_notify(&self, id: ListenerId, scope: Scope) -> RpcResult<()> {
+        self.notification_client().stop_notify(id, scope).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::TestServer;
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn test_round_robin() {
+        let info = GetInfoResponse {
+            p2p_id: "test-p2p-id".to_string(),
+            mempool_size: 0,
+            server_version: "1.2.3".to_string(),
+            is_utxo_indexed: false,
+            is_synced: true,
+            has_notify_command: true,
+            has_message_id: true,
+        };
+        let counts = Arc::new(Mutex::new(vec![]));
+        // The requests received on each stream are counted, in order of opening
+        let stream_counts = counts.clone();
+        let address = TestServer::new(move |exchange| {
+            let mut counts = stream_counts.lock().unwrap();
+            if counts.len() <= exchange.stream {
+                counts.resize(exchange.stream + 1, 0);
+            }
+            counts[exchange.stream] += 1;
+            exchange.respond(RpcResult::Ok(info.clone()).into());
+        })
+        .start()
+        .await;
+
+        let mut pool = GrpcClientPool::connect(address, 3).await.unwrap();
+        assert_eq!(pool.size(), 3);
+        assert!(pool.is_connected());
+        assert_eq!(*counts.lock().unwrap(), vec![1, 1, 1], "each connection should open its own stream with a handshake");
+
+        for _ in 0..6 {
+            pool.get_info_call(GetInfoRequest {}).await.unwrap();
+        }
+        assert_eq!(*counts.lock().unwrap(), vec![3, 3, 3], "the calls should be evenly distributed across the connections");
+
+        pool.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_empty_pool() {
+        assert!(GrpcClientPool::connect("grpc://127.0.0.1:1".to_string(), 0).await.is_err());
+    }
+}
@@ -0,0 +1,67 @@
+use crate::error::{Error, Result};
+use futures::{select_biased, FutureExt};
+use kaspa_grpc_core::protowire::KaspadRequest;
+
+/// Dispatch priority of a request sent to the server
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RequestPriority {
+    /// Latency-sensitive requests, sent ahead of any pending normal one
+    High,
+    #[default]
+    Normal,
+}
+
+/// Two-tier queue of the requests waiting to be sent to the server, drained high priority first.
+///
+/// Requests of a same priority are sent in order.
+#[derive(Clone, Debug)]
+pub(crate) struct RequestQueue {
+    high: (async_channel::Sender<KaspadRequest>, async_channel::Receiver<KaspadRequest>),
+    normal: (async_channel::Sender<KaspadRequest>, async_channel::Receiver<KaspadRequest>),
+}
+
+impl RequestQueue {
+    pub(crate) fn new() -> Self {
+        Self { high: async_channel::unbounded(), normal: async_channel::unbounded() }
+    }
+
+    pub(crate) async fn send(&self, request: KaspadRequest, priority: RequestPriority) -> Result<()> {
+        match priority {
+            RequestPriority::High => self.high.0.send(request).await?,
+            RequestPriority::Normal => self.normal.0.send(request).await?,
+        }
+        Ok(())
+    }
+
+    /// Waits for the next request to send, a pending high priority one if any.
+    pub(crate) async fn recv(&self) -> Result<KaspadRequest> {
+        let request = select_biased! {
+            request = self.high.1.recv().fuse() => request,
+            request = self.normal.1.recv().fuse() => request,
+        };
+        request.map_err(|_| Error::ChannelRecvError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kaspa_grpc_core::protowire::{GetBlocksRequestMessage, SubmitBlockRequestMessage};
+
+    #[tokio::test]
+    async fn test_high_priority_first() {
+        let queue = RequestQueue::new();
+        let bulk = |id| KaspadRequest { id, ..GetBlocksRequestMessage::default().into() };
+        let urgent = |id| KaspadRequest { id, ..SubmitBlockRequestMessage::default().into() };
+        queue.send(bulk(1), RequestPriority::Normal).await.unwrap();
+        queue.send(bulk(2), RequestPriority::Normal).await.unwrap();
+        queue.send(urgent(3), RequestPriority::High).await.unwrap();
+        queue.send(urgent(4), RequestPriority::High).await.unwrap();
+
+        let mut sent = vec![];
+        for _ in 0..4 {
+            sent.push(queue.recv().await.unwrap().id);
+        }
+        assert_eq!(sent, vec![3, 4, 1, 2], "high priority requests should be sent first, each tier in order");
+    }
+}
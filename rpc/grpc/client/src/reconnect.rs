@@ -0,0 +1,78 @@
+use crate::RECONNECT_INTERVAL;
+use rand::Rng;
+use std::time::Duration;
+
+/// Delays between the attempts of a client to reconnect to the server.
+///
+/// The delay window starts at `initial_delay` and doubles after each failed attempt, up to `max_delay`. With `jitter`,
+/// each delay is drawn uniformly between zero and the current window ("full jitter"), so that clients disconnected
+/// together by a restart of the server do not reconnect in lockstep.
+///
+/// The default waits a fixed [`RECONNECT_INTERVAL`] between attempts, with no jitter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Delay window of the first attempt
+    pub initial_delay: Duration,
+    /// Upper bound of the delay window
+    pub max_delay: Duration,
+    /// Randomize each delay within its window
+    pub jitter: bool,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        let interval = Duration::from_millis(RECONNECT_INTERVAL);
+        Self { initial_delay: interval, max_delay: interval, jitter: false }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Delay window after `failed_attempts` consecutive failed attempts
+    pub fn window(&self, failed_attempts: u32) -> Duration {
+        let factor = 1u32.checked_shl(failed_attempts).unwrap_or(u32::MAX);
+        self.initial_delay.checked_mul(factor).unwrap_or(Duration::MAX).min(self.max_delay)
+    }
+
+    /// Delay before the next attempt after `failed_attempts` consecutive failed attempts, jittered with `rng` if so configured
+    pub fn delay<R: Rng + ?Sized>(&self, failed_attempts: u32, rng: &mut R) -> Duration {
+        let window = self.window(failed_attempts);
+        match self.jitter {
+            true => rng.gen_range(Duration::ZERO..=window),
+            false => window,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_reconnect_delays() {
+        let policy = ReconnectPolicy { initial_delay: Duration::from_millis(100), max_delay: Duration::from_secs(1), jitter: false };
+        let windows = (0..6).map(|attempts| policy.window(attempts).as_millis()).collect::<Vec<_>>();
+        assert_eq!(windows, vec![100, 200, 400, 800, 1_000, 1_000]);
+        assert_eq!(policy.window(u32::MAX), policy.max_delay, "the window should never overflow the cap");
+        assert_eq!(
+            policy.delay(2, &mut StdRng::seed_from_u64(0)),
+            Duration::from_millis(400),
+            "no jitter should wait the whole window"
+        );
+
+        let policy = ReconnectPolicy { jitter: true, ..policy };
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut delays = vec![];
+        for attempts in 0..20 {
+            let delay = policy.delay(attempts, &mut rng);
+            assert!(delay <= policy.window(attempts), "delay {delay:?} of attempt {attempts} exceeds its window");
+            assert!(delay <= policy.max_delay, "delay {delay:?} of attempt {attempts} exceeds the cap");
+            delays.push(delay);
+        }
+        assert!(delays[4..].iter().any(|delay| *delay != policy.max_delay), "capped delays should still be jittered");
+        assert_eq!(delays, {
+            let mut rng = StdRng::seed_from_u64(42);
+            (0..20).map(|attempts| policy.delay(attempts, &mut rng)).collect::<Vec<_>>()
+        });
+    }
+}
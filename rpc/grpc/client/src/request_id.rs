@@ -0,0 +1,51 @@
+use core::fmt::Debug;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+/// A source of ids assigned to the requests sent to the server
+pub trait RequestIdSource: Send + Sync + Debug {
+    fn next_id(&self) -> u64;
+}
+
+pub type DynRequestIdSource = Arc<dyn RequestIdSource>;
+
+/// Random request ids, the default
+#[derive(Debug, Default)]
+pub struct RandomRequestIdSource;
+
+impl RequestIdSource for RandomRequestIdSource {
+    fn next_id(&self) -> u64 {
+        u64::from_le_bytes(rand::random::<[u8; 8]>())
+    }
+}
+
+/// Monotonic request ids, starting at some initial value
+#[derive(Debug, Default)]
+pub struct CounterRequestIdSource {
+    next: AtomicU64,
+}
+
+impl CounterRequestIdSource {
+    pub fn new(first: u64) -> Self {
+        Self { next: AtomicU64::new(first) }
+    }
+}
+
+impl RequestIdSource for CounterRequestIdSource {
+    fn next_id(&self) -> u64 {
+        self.next.fetch_add(1, Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counter_request_id_source() {
+        let source = CounterRequestIdSource::new(u64::MAX - 1);
+        assert_eq!((0..3).map(|_| source.next_id()).collect::<Vec<_>>(), vec![u64::MAX - 1, u64::MAX, 0]);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{
+    builder::ResolverKind,
     error::{Error, Result},
     resolver::{KaspadResponseReceiver, KaspadResponseSender, Resolver},
 };
@@ -36,6 +37,10 @@ impl IdResolver {
 }
 
 impl Resolver for IdResolver {
+    fn kind(&self) -> ResolverKind {
+        ResolverKind::Id
+    }
+
     fn register_request(&self, _: RpcApiOps, request: &KaspadRequest) -> KaspadResponseReceiver {
         let (sender, receiver) = oneshot::channel::<Result<KaspadResponse>>();
         {
@@ -63,7 +68,15 @@ impl Resolver for IdResolver {
         }
     }
 
-    fn remove_expired_requests(&self, timeout: std::time::Duration) {
+    fn remove_request(&self, id: u64) -> bool {
+        self.pending_calls.lock().unwrap().remove(&id).is_some()
+    }
+
+    fn pending_count(&self) -> usize {
+        self.pending_calls.lock().unwrap().len()
+    }
+
+    fn remove_expired_requests(&self, timeout: std::time::Duration) -> usize {
         let mut pending_calls = self.pending_calls.lock().unwrap();
         let mut purge = Vec::<u64>::new();
         for (id, pending) in pending_calls.iter() {
@@ -80,5 +93,15 @@ impl Resolver for IdResolver {
                 }
             }
         }
+        purge.len()
+    }
+
+    fn drain(&self, error: fn() -> Error) {
+        let pending_calls = std::mem::take(&mut *self.pending_calls.lock().unwrap());
+        for (_, pending) in pending_calls.into_iter() {
+            if let Err(err) = pending.sender.send(Err(error())) {
+                trace!("[Resolver] drain failed to send an error to a pending request: {:?}", err);
+            }
+        }
     }
 }
@@ -1,4 +1,7 @@
-use super::error::Result;
+use super::{
+    builder::ResolverKind,
+    error::{Error, Result},
+};
 use core::fmt::Debug;
 use kaspa_grpc_core::protowire::{KaspadRequest, KaspadResponse};
 use kaspa_rpc_core::api::ops::RpcApiOps;
@@ -10,12 +13,47 @@ pub(crate) mod matcher;
 pub(crate) mod queue;
 
 pub(crate) trait Resolver: Send + Sync + Debug {
+    fn kind(&self) -> ResolverKind;
     fn register_request(&self, op: RpcApiOps, request: &KaspadRequest) -> KaspadResponseReceiver;
     fn handle_response(&self, response: KaspadResponse);
-    fn remove_expired_requests(&self, timeout: Duration);
+    /// Removes the request bearing `id` without resolving it, returning `false` if no such request is pending
+    fn remove_request(&self, id: u64) -> bool;
+    fn pending_count(&self) -> usize;
+    /// Resolves every request pending for longer than `timeout` with [`Error::Timeout`], returning the number of such requests
+    fn remove_expired_requests(&self, timeout: Duration) -> usize;
+    /// Resolves every pending request with the error returned by `error`
+    fn drain(&self, error: fn() -> Error);
 }
 
 pub(crate) type DynResolver = Arc<dyn Resolver>;
 
 pub(crate) type KaspadResponseSender = oneshot::Sender<Result<KaspadResponse>>;
 pub(crate) type KaspadResponseReceiver = oneshot::Receiver<Result<KaspadResponse>>;
+
+/// Removes a registered request from its resolver when dropped before being disarmed.
+///
+/// A call whose future is dropped before getting its response, as when cancelled by a `tokio::time::timeout`, thus frees
+/// its pending slot right away instead of leaving it to the timeout monitor.
+pub(crate) struct PendingGuard {
+    resolver: DynResolver,
+    id: Option<u64>,
+}
+
+impl PendingGuard {
+    pub(crate) fn new(resolver: DynResolver, id: u64) -> Self {
+        Self { resolver, id: Some(id) }
+    }
+
+    /// Leaves the request to the resolver, once it has been resolved
+    pub(crate) fn disarm(mut self) {
+        self.id = None;
+    }
+}
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        if let Some(id) = self.id.take() {
+            self.resolver.remove_request(id);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{
+    builder::ResolverKind,
     error::{Error, Result},
     resolver::{matcher::Matcher, KaspadResponseReceiver, KaspadResponseSender, Resolver},
 };
@@ -18,11 +19,14 @@ struct Pending {
     op: RpcApiOps,
     request: KaspadRequest,
     sender: KaspadResponseSender,
+    /// The caller gave up on the request, whose response, if any, must still be consumed so as not to resolve another
+    /// request of the same op
+    cancelled: bool,
 }
 
 impl Pending {
     fn new(op: RpcApiOps, request: KaspadRequest, sender: KaspadResponseSender) -> Self {
-        Self { timestamp: Instant::now(), op, request, sender }
+        Self { timestamp: Instant::now(), op, request, sender, cancelled: false }
     }
 
     fn is_matching(&self, response: &KaspadResponse, response_op: RpcApiOps) -> bool {
@@ -42,6 +46,10 @@ impl QueueResolver {
 }
 
 impl Resolver for QueueResolver {
+    fn kind(&self) -> ResolverKind {
+        ResolverKind::Queue
+    }
+
     fn register_request(&self, op: RpcApiOps, request: &KaspadRequest) -> KaspadResponseReceiver {
         let (sender, receiver) = oneshot::channel::<Result<KaspadResponse>>();
         {
@@ -75,6 +83,10 @@ impl Resolver for QueueResolver {
         }
         drop(pending_calls);
         if let Some(pending) = pending {
+            if pending.cancelled {
+                trace!("[Resolver] handle_response discarding the response of a cancelled request: {:?}", pending.request);
+                return;
+            }
             trace!("[Resolver] handle_response matching request found: {:?}", pending.request);
             match pending.sender.send(Ok(response)) {
                 Ok(_) => {}
@@ -85,9 +97,27 @@ impl Resolver for QueueResolver {
         }
     }
 
-    fn remove_expired_requests(&self, timeout: std::time::Duration) {
+    /// Responses carry no id a queue resolver can rely on, so the request is kept in place as a tombstone consuming its
+    /// late response, if any, until it expires
+    fn remove_request(&self, id: u64) -> bool {
+        let mut pending_calls = self.pending_calls.lock().unwrap();
+        match pending_calls.iter_mut().find(|pending| pending.request.id == id && !pending.cancelled) {
+            Some(pending) => {
+                pending.cancelled = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn pending_count(&self) -> usize {
+        self.pending_calls.lock().unwrap().iter().filter(|pending| !pending.cancelled).count()
+    }
+
+    fn remove_expired_requests(&self, timeout: std::time::Duration) -> usize {
         let mut pending_calls = self.pending_calls.lock().unwrap();
         let mut index: usize = 0;
+        let mut expired: usize = 0;
         loop {
             if index >= pending_calls.len() {
                 break;
@@ -95,6 +125,10 @@ impl Resolver for QueueResolver {
             let pending = pending_calls.get(index).unwrap();
             if pending.timestamp.elapsed() > timeout {
                 let pending = pending_calls.remove(index).unwrap();
+                if pending.cancelled {
+                    continue;
+                }
+                expired += 1;
                 match pending.sender.send(Err(Error::Timeout)) {
                     Ok(_) => {}
                     Err(err) => {
@@ -108,5 +142,15 @@ impl Resolver for QueueResolver {
                 index += 1;
             }
         }
+        expired
+    }
+
+    fn drain(&self, error: fn() -> Error) {
+        let pending_calls = std::mem::take(&mut *self.pending_calls.lock().unwrap());
+        for pending in pending_calls.into_iter().filter(|pending| !pending.cancelled) {
+            if let Err(err) = pending.sender.send(Err(error())) {
+                trace!("[Resolver] drain failed to send an error to a pending request: {:?}", err);
+            }
+        }
     }
 }
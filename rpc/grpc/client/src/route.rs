@@ -1,5 +1,9 @@
 macro_rules! route {
     ($fn:ident, $name:tt) => {
+        route!($fn, $name, RequestPriority::Normal);
+    };
+
+    ($fn:ident, $name:tt, $priority:expr) => {
         paste::paste! {
             #[allow(
                 clippy::let_unit_value,
@@ -23,8 +27,14 @@ macro_rules! route {
                     }
                     let __self = self;
                     let request = request;
-                    let __ret: RpcResult<[<$name Response>]> =
-                        { __self.inner.call(RpcApiOps::$name, request).await?.as_ref().try_into() };
+                    let __ret: RpcResult<[<$name Response>]> = {
+                        __self.connect_lazily();
+                        let response = __self.inner.call_with_priority(RpcApiOps::$name, request, $priority).await;
+                        let timed_out = matches!(response, Err(Error::Timeout));
+                        let result = response.map_err(RpcError::from).and_then(|response| response.as_ref().try_into());
+                        __self.inner.record_call_outcome(RpcApiOps::$name, &result, timed_out);
+                        result
+                    };
                     #[allow(unreachable_code)]
                     __ret
                 })
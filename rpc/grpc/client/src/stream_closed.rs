@@ -0,0 +1,9 @@
+/// Signal that the stream of responses of the server is closed, so that no notification is received until the client
+/// reconnects, see [`GrpcClient::register_stream_closed_listener`](crate::GrpcClient::register_stream_closed_listener).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StreamClosed {
+    /// The server closed the stream
+    ByServer,
+    /// The stream failed with an error
+    Error(String),
+}
@@ -0,0 +1,55 @@
+//! Notification listener with a lifecycle bound to a guard, see [`GrpcClient::subscribe`].
+//!
+//! [`GrpcClient::subscribe`]: crate::GrpcClient::subscribe
+
+use async_channel::Receiver;
+use kaspa_notify::{listener::ListenerId, notifier::Notifier, scope::Scope};
+use kaspa_rpc_core::{notify::connection::ChannelConnection, Notification};
+use std::{ops::Deref, sync::Arc};
+
+/// A listener subscribed to the notifications of a scope, and derefing to the receiver of these notifications.
+///
+/// Dropping the guard stops the subscription and unregisters the listener, so a listener cannot outlive its consumer.
+#[derive(Debug)]
+pub struct Subscription {
+    id: ListenerId,
+    scope: Scope,
+    notifier: Arc<Notifier<Notification, ChannelConnection>>,
+    receiver: Receiver<Notification>,
+}
+
+impl Subscription {
+    pub(crate) fn new(
+        id: ListenerId,
+        scope: Scope,
+        notifier: Arc<Notifier<Notification, ChannelConnection>>,
+        receiver: Receiver<Notification>,
+    ) -> Self {
+        Self { id, scope, notifier, receiver }
+    }
+
+    /// Id of the underlying listener
+    pub fn id(&self) -> ListenerId {
+        self.id
+    }
+
+    pub fn scope(&self) -> &Scope {
+        &self.scope
+    }
+}
+
+impl Deref for Subscription {
+    type Target = Receiver<Notification>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.receiver
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        // Both calls only queue the commands, the stop command reaching the server asynchronously
+        let _ = self.notifier.try_stop_notify(self.id, self.scope.clone());
+        let _ = self.notifier.unregister_listener(self.id);
+    }
+}
@@ -0,0 +1,118 @@
+//! A programmable protowire server for the tests of the client
+
+use futures::Stream;
+use kaspa_grpc_core::protowire::{
+    rpc_server::{Rpc, RpcServer},
+    KaspadRequest, KaspadResponse,
+};
+use std::{
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use tokio::{
+    net::TcpListener,
+    sync::{mpsc, Notify},
+    task::JoinHandle,
+};
+use tokio_stream::wrappers::{TcpListenerStream, UnboundedReceiverStream};
+use tonic::{codec::CompressionEncoding, transport::Server, Request, Response, Status, Streaming};
+
+type Handler = dyn Fn(Exchange) + Send + Sync + 'static;
+
+/// A request received by a [`TestServer`], along with the means of answering it
+pub(crate) struct Exchange {
+    /// Index of the stream the request was received on, in order of opening
+    pub stream: usize,
+    /// Index of the request on its stream
+    pub index: usize,
+    pub request: KaspadRequest,
+    sender: mpsc::UnboundedSender<Result<KaspadResponse, Status>>,
+}
+
+impl Exchange {
+    /// Answers the request, the response carrying the request id
+    pub fn respond(&self, mut response: KaspadResponse) {
+        response.id = self.request.id;
+        self.push(response);
+    }
+
+    /// Sends a message which is no response to the request, like a notification
+    pub fn push(&self, message: KaspadResponse) {
+        let _ = self.sender.send(Ok(message));
+    }
+
+    /// Fails the stream with `status`
+    pub fn fail(&self, status: Status) {
+        let _ = self.sender.send(Err(status));
+    }
+}
+
+/// A protowire server handing every request it receives to a handler.
+///
+/// A stream is kept open for as long as the client keeps its side open, or until `close` is notified.
+pub(crate) struct TestServer {
+    handler: Arc<Handler>,
+    close: Option<Arc<Notify>>,
+    streams: AtomicUsize,
+}
+
+impl TestServer {
+    pub fn new(handler: impl Fn(Exchange) + Send + Sync + 'static) -> Self {
+        Self { handler: Arc::new(handler), close: None, streams: AtomicUsize::new(0) }
+    }
+
+    /// Closes every open stream when `close` is notified
+    pub fn close_on(self, close: Arc<Notify>) -> Self {
+        Self { close: Some(close), ..self }
+    }
+
+    /// Serves on a free local port and returns the server address.
+    ///
+    /// The port is bound before returning, so the server can be connected to right away.
+    pub async fn start(self) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("grpc://{}", listener.local_addr().unwrap());
+        self.serve(listener);
+        address
+    }
+
+    /// Serves on `listener`
+    pub fn serve(self, listener: TcpListener) -> JoinHandle<Result<(), tonic::transport::Error>> {
+        let service = RpcServer::new(self).send_compressed(CompressionEncoding::Gzip).accept_compressed(CompressionEncoding::Gzip);
+        tokio::spawn(Server::builder().add_service(service).serve_with_incoming(TcpListenerStream::new(listener)))
+    }
+}
+
+#[tonic::async_trait]
+impl Rpc for TestServer {
+    type MessageStreamStream = Pin<Box<dyn Stream<Item = Result<KaspadResponse, Status>> + Send + Sync + 'static>>;
+
+    async fn message_stream(&self, request: Request<Streaming<KaspadRequest>>) -> Result<Response<Self::MessageStreamStream>, Status> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let stream = self.streams.fetch_add(1, Ordering::SeqCst);
+        let handler = self.handler.clone();
+        let close = self.close.clone();
+        let mut request_stream = request.into_inner();
+        tokio::spawn(async move {
+            let serve = async {
+                let mut index = 0;
+                while let Ok(Some(request)) = request_stream.message().await {
+                    handler(Exchange { stream, index, request, sender: sender.clone() });
+                    index += 1;
+                }
+            };
+            match close {
+                Some(close) => tokio::select! {
+                    _ = serve => {}
+                    _ = close.notified() => {}
+                },
+                None => serve.await,
+            }
+            // Dropping the last sender ends the stream
+        });
+        Ok(Response::new(Box::pin(UnboundedReceiverStream::new(receiver))))
+    }
+}
@@ -0,0 +1,19 @@
+use core::fmt::Debug;
+use kaspa_rpc_core::api::ops::RpcApiOps;
+use std::sync::Arc;
+
+/// An observer of the messages crossing the wire, see [`GrpcClientBuilder::wire_observer`].
+///
+/// Sizes are the lengths of the protobuf encodings of the messages, before any compression by the transport.
+/// The methods are called inline on the paths sending and receiving the messages, so they must return quickly.
+///
+/// [`GrpcClientBuilder::wire_observer`]: crate::builder::GrpcClientBuilder::wire_observer
+pub trait WireObserver: Send + Sync + Debug {
+    /// Called right before a request is sent to the server
+    fn on_request(&self, op: &RpcApiOps, id: u64, size: usize);
+
+    /// Called as soon as a response or a notification is received from the server
+    fn on_response(&self, op: &RpcApiOps, id: u64, size: usize);
+}
+
+pub type DynWireObserver = Arc<dyn WireObserver>;
@@ -47,6 +47,7 @@ impl From<&kaspad_request::Payload> for RpcApiOps {
             Payload::NotifyPruningPointUtxoSetOverrideRequest(_) => RpcApiOps::NotifyPruningPointUtxoSetOverride,
             Payload::NotifyVirtualDaaScoreChangedRequest(_) => RpcApiOps::NotifyVirtualDaaScoreChanged,
             Payload::NotifyVirtualChainChangedRequest(_) => RpcApiOps::NotifyVirtualChainChanged,
+            Payload::NotifyMempoolSizeChangedRequest(_) => RpcApiOps::NotifyMempoolSizeChanged,
 
             Payload::StopNotifyingUtxosChangedRequest(_) => RpcApiOps::NotifyUtxosChanged,
             Payload::StopNotifyingPruningPointUtxoSetOverrideRequest(_) => RpcApiOps::NotifyPruningPointUtxoSetOverride,
@@ -100,6 +101,7 @@ impl From<&kaspad_response::Payload> for RpcApiOps {
             Payload::NotifyPruningPointUtxoSetOverrideResponse(_) => RpcApiOps::NotifyPruningPointUtxoSetOverride,
             Payload::NotifyVirtualDaaScoreChangedResponse(_) => RpcApiOps::NotifyVirtualDaaScoreChanged,
             Payload::NotifyVirtualChainChangedResponse(_) => RpcApiOps::NotifyVirtualChainChanged,
+            Payload::NotifyMempoolSizeChangedResponse(_) => RpcApiOps::NotifyMempoolSizeChanged,
 
             Payload::StopNotifyingPruningPointUtxoSetOverrideResponse(_) => RpcApiOps::NotifyPruningPointUtxoSetOverride,
             Payload::StopNotifyingUtxosChangedResponse(_) => RpcApiOps::NotifyUtxosChanged,
@@ -114,6 +116,7 @@ impl From<&kaspad_response::Payload> for RpcApiOps {
             Payload::PruningPointUtxoSetOverrideNotification(_) => RpcApiOps::Notification,
             Payload::VirtualDaaScoreChangedNotification(_) => RpcApiOps::Notification,
             Payload::VirtualChainChangedNotification(_) => RpcApiOps::Notification,
+            Payload::MempoolSizeChangedNotification(_) => RpcApiOps::Notification,
         }
     }
 }
@@ -181,6 +184,7 @@ pub mod kaspad_request_convert {
     impl_into_kaspad_request!(NotifyVirtualDaaScoreChanged);
     impl_into_kaspad_request!(NotifyVirtualChainChanged);
     impl_into_kaspad_request!(NotifySinkBlueScoreChanged);
+    impl_into_kaspad_request!(NotifyMempoolSizeChanged);
 
     macro_rules! impl_into_kaspad_request {
         ($name:tt) => {
@@ -309,6 +313,7 @@ pub mod kaspad_response_convert {
     impl_into_kaspad_notify_response!(NotifyVirtualDaaScoreChanged);
     impl_into_kaspad_notify_response!(NotifyVirtualChainChanged);
     impl_into_kaspad_notify_response!(NotifySinkBlueScoreChanged);
+    impl_into_kaspad_notify_response!(NotifyMempoolSizeChanged);
 
     macro_rules! impl_into_kaspad_response {
         ($name:tt) => {
@@ -1,5 +1,6 @@
 use crate::protowire::{
-    kaspad_response::Payload, BlockAddedNotificationMessage, KaspadResponse, NewBlockTemplateNotificationMessage, RpcNotifyCommand,
+    kaspad_response::Payload, BlockAddedNotificationMessage, KaspadResponse, MempoolSizeChangedNotificationMessage,
+    NewBlockTemplateNotificationMessage, RpcFinalityConflictSeverity, RpcNotifyCommand,
 };
 use crate::protowire::{
     FinalityConflictNotificationMessage, FinalityConflictResolvedNotificationMessage, NotifyPruningPointUtxoSetOverrideRequestMessage,
@@ -11,7 +12,7 @@ use crate::protowire::{
 };
 use crate::{from, try_from};
 use kaspa_notify::subscription::Command;
-use kaspa_rpc_core::{Notification, RpcError, RpcHash};
+use kaspa_rpc_core::{FromRpcHex, Notification, RpcError, RpcHash, ToRpcHex};
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -34,6 +35,7 @@ from!(item: &kaspa_rpc_core::Notification, Payload, {
         Notification::PruningPointUtxoSetOverride(ref notification) => {
             Payload::PruningPointUtxoSetOverrideNotification(notification.into())
         }
+        Notification::MempoolSizeChanged(ref notification) => Payload::MempoolSizeChangedNotification(notification.into()),
     }
 });
 
@@ -50,7 +52,20 @@ from!(item: &kaspa_rpc_core::VirtualChainChangedNotification, VirtualChainChange
 });
 
 from!(item: &kaspa_rpc_core::FinalityConflictNotification, FinalityConflictNotificationMessage, {
-    Self { violating_block_hash: item.violating_block_hash.to_string() }
+    Self {
+        violating_block_hash: item.violating_block_hash.to_string(),
+        competing_tip_hash: item.competing_tip_hash.to_string(),
+        blue_work_difference: item.blue_work_difference.to_rpc_hex(),
+        severity: RpcFinalityConflictSeverity::from(item.severity) as i32,
+    }
+});
+
+from!(item: kaspa_rpc_core::RpcFinalityConflictSeverity, RpcFinalityConflictSeverity, {
+    match item {
+        kaspa_rpc_core::RpcFinalityConflictSeverity::Low => RpcFinalityConflictSeverity::FinalityConflictSeverityLow,
+        kaspa_rpc_core::RpcFinalityConflictSeverity::Medium => RpcFinalityConflictSeverity::FinalityConflictSeverityMedium,
+        kaspa_rpc_core::RpcFinalityConflictSeverity::High => RpcFinalityConflictSeverity::FinalityConflictSeverityHigh,
+    }
 });
 
 from!(item: &kaspa_rpc_core::FinalityConflictResolvedNotification, FinalityConflictResolvedNotificationMessage, {
@@ -74,6 +89,10 @@ from!(item: &kaspa_rpc_core::VirtualDaaScoreChangedNotification, VirtualDaaScore
 
 from!(&kaspa_rpc_core::PruningPointUtxoSetOverrideNotification, PruningPointUtxoSetOverrideNotificationMessage);
 
+from!(item: &kaspa_rpc_core::MempoolSizeChangedNotification, MempoolSizeChangedNotificationMessage, {
+    Self { transaction_count: item.transaction_count, total_mass: item.total_mass }
+});
+
 from!(item: Command, RpcNotifyCommand, {
     match item {
         Command::Start => RpcNotifyCommand::NotifyStart,
@@ -117,6 +136,7 @@ try_from!(item: &Payload, kaspa_rpc_core::Notification, {
         Payload::PruningPointUtxoSetOverrideNotification(ref notification) => {
             Notification::PruningPointUtxoSetOverride(notification.try_into()?)
         }
+        Payload::MempoolSizeChangedNotification(ref notification) => Notification::MempoolSizeChanged(notification.try_into()?),
         _ => Err(RpcError::UnsupportedFeature)?,
     }
 });
@@ -147,7 +167,20 @@ try_from!(item: &VirtualChainChangedNotificationMessage, kaspa_rpc_core::Virtual
 });
 
 try_from!(item: &FinalityConflictNotificationMessage, kaspa_rpc_core::FinalityConflictNotification, {
-    Self { violating_block_hash: RpcHash::from_str(&item.violating_block_hash)? }
+    Self {
+        violating_block_hash: RpcHash::from_str(&item.violating_block_hash)?,
+        competing_tip_hash: RpcHash::from_str(&item.competing_tip_hash)?,
+        blue_work_difference: kaspa_rpc_core::RpcBlueWorkType::from_rpc_hex(&item.blue_work_difference)?,
+        severity: RpcFinalityConflictSeverity::from_i32(item.severity).ok_or(RpcError::PrimitiveToEnumConversionError)?.into(),
+    }
+});
+
+from!(item: RpcFinalityConflictSeverity, kaspa_rpc_core::RpcFinalityConflictSeverity, {
+    match item {
+        RpcFinalityConflictSeverity::FinalityConflictSeverityLow => kaspa_rpc_core::RpcFinalityConflictSeverity::Low,
+        RpcFinalityConflictSeverity::FinalityConflictSeverityMedium => kaspa_rpc_core::RpcFinalityConflictSeverity::Medium,
+        RpcFinalityConflictSeverity::FinalityConflictSeverityHigh => kaspa_rpc_core::RpcFinalityConflictSeverity::High,
+    }
 });
 
 try_from!(item: &FinalityConflictResolvedNotificationMessage, kaspa_rpc_core::FinalityConflictResolvedNotification, {
@@ -171,6 +204,10 @@ try_from!(item: &VirtualDaaScoreChangedNotificationMessage, kaspa_rpc_core::Virt
 
 try_from!(&PruningPointUtxoSetOverrideNotificationMessage, kaspa_rpc_core::PruningPointUtxoSetOverrideNotification);
 
+try_from!(item: &MempoolSizeChangedNotificationMessage, kaspa_rpc_core::MempoolSizeChangedNotification, {
+    Self { transaction_count: item.transaction_count, total_mass: item.total_mass }
+});
+
 from!(item: RpcNotifyCommand, Command, {
     match item {
         RpcNotifyCommand::NotifyStart => Command::Start,
@@ -14,7 +14,7 @@ use kaspa_notify::{
     listener::ListenerId,
     notifier::Notifier,
     scope::{
-        BlockAddedScope, FinalityConflictResolvedScope, FinalityConflictScope, NewBlockTemplateScope,
+        BlockAddedScope, FinalityConflictResolvedScope, FinalityConflictScope, MempoolSizeChangedScope, NewBlockTemplateScope,
         PruningPointUtxoSetOverrideScope, Scope, SinkBlueScoreChangedScope, UtxosChangedScope, VirtualChainChangedScope,
         VirtualDaaScoreChangedScope,
     },
@@ -432,6 +432,23 @@ impl Rpc for GrpcService {
                                     }
                                 }
 
+                                Payload::NotifyMempoolSizeChangedRequest(ref request) => {
+                                    match kaspa_rpc_core::NotifyMempoolSizeChangedRequest::try_from(request) {
+                                        Ok(request) => {
+                                            let result = notifier
+                                                .clone()
+                                                .execute_subscribe_command(
+                                                    listener_id,
+                                                    Scope::MempoolSizeChanged(MempoolSizeChangedScope::default()),
+                                                    request.command,
+                                                )
+                                                .await;
+                                            NotifyMempoolSizeChangedResponseMessage::from(result).into()
+                                        }
+                                        Err(err) => NotifyMempoolSizeChangedResponseMessage::from(err).into(),
+                                    }
+                                }
+
                                 Payload::StopNotifyingUtxosChangedRequest(ref request) => {
                                     let notify_request = NotifyUtxosChangedRequestMessage::from(request);
                                     let response: StopNotifyingUtxosChangedResponseMessage =
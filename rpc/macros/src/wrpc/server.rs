@@ -50,13 +50,14 @@ impl ToTokens for RpcTable {
 
             targets.push(quote! {
                 #rpc_api_ops::#handler => {
-                    interface.method(#rpc_api_ops::#handler, method!(|server_ctx: #server_ctx_type, connection_ctx: #connection_ctx_type, request: #request_type| async move {
+                    interface.method(#rpc_api_ops::#handler, Method::new(|server_ctx: #server_ctx_type, connection_ctx: #connection_ctx_type, request: Lenient<#request_type>| async move {
+                        let request = connection_ctx.accept_request(request)?;
                         let verbose = server_ctx.verbose();
                         if verbose { workflow_log::log_info!("request: {:?}",request); }
-                        let response: #response_type = server_ctx.rpc_service(&connection_ctx).#fn_call(request).await
-                            .map_err(|e|ServerError::Text(e.to_string()))?;
+                        let response: #response_type = server_ctx.with_method_timeout(#rpc_api_ops::#handler, server_ctx.rpc_service(&connection_ctx).#fn_call(request)).await?
+                            .map_err(|e|RouterError::Internal(e.to_string()))?;
                         if verbose { workflow_log::log_info!("response: {:?}",response); }
-                        Ok(response)
+                        Ok::<_, RouterError>(response)
                     }));
                 }
             });
@@ -65,11 +66,7 @@ impl ToTokens for RpcTable {
         quote! {
 
             {
-                let mut interface = workflow_rpc::server::Interface::<
-                    #server_ctx_type,
-                    #connection_ctx_type,
-                    #rpc_api_ops
-                >::new(#server_ctx);
+                let mut interface = Interface::new(#server_ctx);
 
                 for op in #rpc_api_ops::list() {
                     match op {
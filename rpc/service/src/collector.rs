@@ -1,6 +1,7 @@
 use crate::converter::{consensus::ConsensusConverter, index::IndexConverter};
+use kaspa_consensus_notify::connection::CoalescingReceiver;
 use kaspa_notify::collector::CollectorFrom;
 
-pub(crate) type CollectorFromConsensus = CollectorFrom<ConsensusConverter>;
+pub(crate) type CollectorFromConsensus = CollectorFrom<ConsensusConverter, CoalescingReceiver>;
 
 pub(crate) type CollectorFromIndex = CollectorFrom<IndexConverter>;
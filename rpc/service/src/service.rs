@@ -13,7 +13,7 @@ use kaspa_consensus_core::{
 };
 use kaspa_consensus_notify::{
     notifier::ConsensusNotifier,
-    {connection::ConsensusChannelConnection, notification::Notification as ConsensusNotification},
+    {connection::CoalescingChannelConnection, notification::Notification as ConsensusNotification},
 };
 use kaspa_consensusmanager::ConsensusManager;
 use kaspa_core::{core::Core, debug, info, kaspad_env::version, signals::Shutdown, trace, warn};
@@ -87,16 +87,19 @@ impl RpcCoreService {
     ) -> Self {
         // Prepare consensus-notify objects
         let consensus_notify_channel = Channel::<ConsensusNotification>::default();
-        let consensus_notify_listener_id =
-            consensus_notifier.register_new_listener(ConsensusChannelConnection::new(consensus_notify_channel.sender()));
+        let (consensus_notify_connection, consensus_notify_receiver) = CoalescingChannelConnection::new(
+            consensus_notify_channel.sender(),
+            consensus_notify_channel.receiver(),
+            config.coalesce_score_notifications,
+        );
+        let consensus_notify_listener_id = consensus_notifier.register_new_listener(consensus_notify_connection);
 
         // Prepare the rpc-core notifier objects
         let mut consensus_events: EventSwitches = EVENT_TYPE_ARRAY[..].into();
         consensus_events[EventType::UtxosChanged] = false;
         consensus_events[EventType::PruningPointUtxoSetOverride] = index_notifier.is_none();
         let consensus_converter = Arc::new(ConsensusConverter::new(consensus_manager.clone(), config.clone()));
-        let consensus_collector =
-            Arc::new(CollectorFromConsensus::new(consensus_notify_channel.receiver(), consensus_converter.clone()));
+        let consensus_collector = Arc::new(CollectorFromConsensus::new(consensus_notify_receiver, consensus_converter.clone()));
         let consensus_subscriber = Arc::new(Subscriber::new(consensus_events, consensus_notifier, consensus_notify_listener_id));
 
         let mut collectors: Vec<DynCollector<Notification>> = vec![consensus_collector];
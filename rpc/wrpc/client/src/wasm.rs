@@ -191,6 +191,7 @@ build_wrpc_wasm_bindgen_subscriptions!([
     VirtualDaaScoreChanged,
     PruningPointUtxoSetOverride,
     NewBlockTemplate,
+    MempoolSizeChanged,
 ]);
 
 // Build RPC method invocation functions. This macro
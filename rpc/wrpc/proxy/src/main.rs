@@ -4,13 +4,7 @@ mod result;
 use clap::Parser;
 use kaspa_consensus_core::networktype::NetworkType;
 use kaspa_grpc_client::GrpcClient;
-use kaspa_rpc_core::api::ops::RpcApiOps;
-use kaspa_wrpc_server::{
-    connection::Connection,
-    router::Router,
-    server::Server,
-    service::{KaspaRpcHandler, Options},
-};
+use kaspa_wrpc_server::{router::Router, server::Server, service::Options};
 use result::Result;
 use std::sync::Arc;
 use workflow_log::*;
@@ -55,7 +49,7 @@ async fn main() -> Result<()> {
         listen_address: interface.unwrap_or_else(|| format!("wrpc://127.0.0.1:{proxy_port}")),
         grpc_proxy_address: Some(grpc_proxy_address.unwrap_or_else(|| format!("grpc://127.0.0.1:{kaspad_port}"))),
         verbose,
-        // ..Options::default()
+        ..Options::default()
     });
     log_info!("");
     log_info!("Proxy routing to `{}` on {}", network_type, options.grpc_proxy_address.as_ref().unwrap());
@@ -69,21 +63,19 @@ async fn main() -> Result<()> {
     // log_trace!("Creating proxy relay...");
 
     let tasks = threads.unwrap_or_else(num_cpus::get);
-    let rpc_handler = Arc::new(KaspaRpcHandler::new(tasks, encoding, grpc_client.clone(), grpc_client.notifier(), options.clone()));
+    let server = Server::new(tasks, encoding, grpc_client.clone(), grpc_client.notifier(), options.clone());
 
-    let router = Arc::new(Router::new(rpc_handler.server.clone()));
-    let server =
-        RpcServer::new_with_encoding::<Server, Connection, RpcApiOps, Id64>(encoding, rpc_handler.clone(), router.interface.clone());
+    let router = Arc::new(Router::new(server.clone()));
 
     // Start the notification system
     // log_trace!("starting gRPC");
     grpc_client.start().await;
     // log_tract!("starting wRPC notification system");
-    rpc_handler.server.start();
+    server.start();
 
     log_info!("Kaspa wRPC server is listening on {}", options.listen_address);
     log_info!("Using `{encoding}` protocol encoding");
-    server.listen(&options.listen_address).await?;
+    server.listen(router, &options.listen_address).await?;
 
     Ok(())
 }
@@ -0,0 +1,178 @@
+//! Negotiation and codec of the `permessage-deflate` WebSocket extension (RFC 7692).
+//!
+//! Notifications such as `VirtualChainChanged` or `UtxosChanged` are large and highly repetitive JSON, so compressing
+//! them cuts the bandwidth of a busy connection substantially. The extension is negotiated during the upgrade: a
+//! client offers it in its `Sec-WebSocket-Extensions` header and the server answers with the accepted parameters.
+//! A client not offering it, or a server with compression disabled, keeps an uncompressed connection.
+//!
+//! The server always asks for no context takeover in both directions, so each message is compressed on its own and
+//! a connection holds no compression state between two messages.
+
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress};
+
+/// Token of the extension in the `Sec-WebSocket-Extensions` header
+pub const PERMESSAGE_DEFLATE: &str = "permessage-deflate";
+
+/// Default deflate level of the compressed messages, balancing the ratio against the CPU cost per notification
+pub const DEFAULT_COMPRESSION_LEVEL: u32 = 6;
+
+/// Largest LZ77 window of the deflate streams, in bits, and the one used by the server compressor
+const MAX_WINDOW_BITS: u8 = 15;
+
+/// Tail of a sync-flushed deflate block, removed from the compressed messages by the sender and restored by the receiver
+const DEFLATE_TAIL: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// Growth step of the output buffers of the codec
+const CHUNK_SIZE: usize = 4096;
+
+/// Compression settings of the server
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompressionOptions {
+    /// Accept the `permessage-deflate` offers of the clients
+    pub enabled: bool,
+    /// Deflate level, from 0 (no compression) to 9 (best compression)
+    pub level: u32,
+}
+
+impl Default for CompressionOptions {
+    fn default() -> Self {
+        Self { enabled: false, level: DEFAULT_COMPRESSION_LEVEL }
+    }
+}
+
+/// Negotiated `permessage-deflate` extension of a connection
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PerMessageDeflate {
+    level: u32,
+}
+
+impl PerMessageDeflate {
+    pub fn new(level: u32) -> Self {
+        Self { level: level.min(9) }
+    }
+
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    /// Value of the `Sec-WebSocket-Extensions` header answering the accepted offer
+    pub fn response_header(&self) -> String {
+        format!("{PERMESSAGE_DEFLATE}; server_no_context_takeover; client_no_context_takeover")
+    }
+
+    /// Compresses the payload of a message, as a single sync-flushed deflate stream stripped of its tail
+    pub fn compress(&self, payload: &[u8]) -> Vec<u8> {
+        let mut compress = Compress::new(Compression::new(self.level), false);
+        let mut output = Vec::with_capacity(payload.len() / 2 + CHUNK_SIZE);
+        loop {
+            output.reserve(CHUNK_SIZE);
+            let consumed = compress.total_in() as usize;
+            compress.compress_vec(&payload[consumed..], &mut output, FlushCompress::Sync).expect("compressing an in-memory buffer");
+            // The flush is complete once the whole input is consumed without filling the output
+            if compress.total_in() as usize == payload.len() && output.len() < output.capacity() {
+                break;
+            }
+        }
+        if output.ends_with(&DEFLATE_TAIL) {
+            output.truncate(output.len() - DEFLATE_TAIL.len());
+        }
+        output
+    }
+
+    /// Decompresses the payload of a message compressed by [`PerMessageDeflate::compress`] or by a peer, failing if it
+    /// decompresses to more than `max_size` bytes
+    pub fn decompress(&self, payload: &[u8], max_size: usize) -> Result<Vec<u8>, String> {
+        let input = [payload, &DEFLATE_TAIL].concat();
+        let mut decompress = Decompress::new(false);
+        let mut output = Vec::with_capacity(payload.len() * 4 + CHUNK_SIZE);
+        loop {
+            output.reserve(CHUNK_SIZE);
+            let (total_in, total_out) = (decompress.total_in(), decompress.total_out());
+            decompress
+                .decompress_vec(&input[total_in as usize..], &mut output, FlushDecompress::Sync)
+                .map_err(|err| format!("invalid deflate payload: {err}"))?;
+            if output.len() > max_size {
+                return Err("decompressed message too large".to_string());
+            }
+            if decompress.total_in() as usize == input.len() && output.len() < output.capacity() {
+                return Ok(output);
+            }
+            if decompress.total_in() == total_in && decompress.total_out() == total_out {
+                return Err("truncated deflate payload".to_string());
+            }
+        }
+    }
+}
+
+/// Negotiates the compression of a connection from the `Sec-WebSocket-Extensions` header of the client.
+///
+/// Returns the first `permessage-deflate` offer the server can honor, or `None` if compression is disabled, not
+/// offered, or only offered with parameters the server cannot honor, the connection then staying uncompressed.
+pub fn negotiate_compression(offer: Option<&str>, options: &CompressionOptions) -> Option<PerMessageDeflate> {
+    if !options.enabled {
+        return None;
+    }
+    offer?
+        .split(',')
+        .find(|extension| {
+            let mut params = extension.split(';').map(str::trim);
+            params.next() == Some(PERMESSAGE_DEFLATE) && params.all(is_acceptable_param)
+        })
+        .map(|_| PerMessageDeflate::new(options.level))
+}
+
+fn is_acceptable_param(param: &str) -> bool {
+    let (name, value) = match param.split_once('=') {
+        Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+        None => (param, None),
+    };
+    match (name, value) {
+        ("server_no_context_takeover" | "client_no_context_takeover", None) => true,
+        // The window of the client compressor does not matter to the decompressor, always sized to the largest one
+        ("client_max_window_bits", None) => true,
+        ("client_max_window_bits", Some(bits)) => bits.parse::<u8>().map_or(false, |bits| (8..=MAX_WINDOW_BITS).contains(&bits)),
+        // The server compressor always uses the largest window, so a client limiting it cannot be served
+        ("server_max_window_bits", Some(bits)) => bits.parse::<u8>() == Ok(MAX_WINDOW_BITS),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_compression() {
+        let enabled = CompressionOptions { enabled: true, level: 9 };
+        let offer = "permessage-deflate; client_max_window_bits, x-webkit-deflate-frame";
+
+        let deflate = negotiate_compression(Some(offer), &enabled).expect("the server should agree to compress");
+        assert_eq!(deflate.level(), 9);
+        assert_eq!(deflate.response_header(), "permessage-deflate; server_no_context_takeover; client_no_context_takeover");
+
+        // Each unsupported offer falls back to the next one, and to an uncompressed connection if none is left
+        let offer = "permessage-deflate; server_max_window_bits=10, permessage-deflate; server_max_window_bits=15";
+        assert!(negotiate_compression(Some(offer), &enabled).is_some());
+        assert_eq!(negotiate_compression(Some("permessage-deflate; server_max_window_bits=10"), &enabled), None);
+        assert_eq!(negotiate_compression(Some("permessage-deflate; unknown_param"), &enabled), None);
+        assert_eq!(negotiate_compression(Some("x-webkit-deflate-frame"), &enabled), None);
+        assert_eq!(negotiate_compression(None, &enabled), None);
+        assert_eq!(negotiate_compression(Some(offer), &CompressionOptions::default()), None, "compression is disabled by default");
+
+        // A round-tripped notification decompresses to the original payload
+        let notification = format!(
+            r#"{{"method":"virtualChainChanged","params":{{"addedChainBlockHashes":[{}]}}}}"#,
+            vec![format!(r#""{}""#, "a1".repeat(32)); 64].join(",")
+        );
+        let compressed = deflate.compress(notification.as_bytes());
+        assert!(compressed.len() < notification.len() / 4, "a repetitive notification should compress well");
+        assert!(!compressed.ends_with(&DEFLATE_TAIL));
+        assert_eq!(deflate.decompress(&compressed, usize::MAX).unwrap(), notification.as_bytes());
+        assert_eq!(deflate.decompress(&deflate.compress(&[]), usize::MAX).unwrap(), Vec::<u8>::new());
+        assert!(deflate.decompress(&compressed, 1024).is_err(), "a message decompressing beyond the limit should be refused");
+
+        // A message compressed by another implementation, from the examples of RFC 7692
+        assert_eq!(deflate.decompress(&[0xf2, 0x48, 0xcd, 0xc9, 0xc9, 0x07, 0x00], usize::MAX).unwrap(), b"Hello");
+        assert!(deflate.decompress(&[0xff, 0xff, 0xff], usize::MAX).is_err());
+    }
+}
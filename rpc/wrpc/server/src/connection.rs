@@ -1,20 +1,69 @@
-use kaspa_notify::{connection::Connection as ConnectionT, listener::ListenerId, notification::Notification as NotificationT};
-use kaspa_rpc_core::{api::ops::RpcApiOps, Notification};
-use std::sync::{Arc, Mutex};
-use workflow_log::log_trace;
-use workflow_rpc::{
-    server::{prelude::*, result::Result as WrpcResult},
-    types::{MsgT, OpsT},
+use crate::{
+    error::RouterError,
+    frames::{Lenient, MalformedFrames, MALFORMED_FRAMES_REASON},
+    limits::{RateLimit, RateLimiter},
+    notifications::{
+        DynNotificationSerializer, Enqueued, NotificationThrottle, NotificationThrottling, OutboundQueue, OutboundQueueConfig,
+    },
+    protocol::{encode_notification, Params, RequestId, SERVER_REQUEST_ID_FLAG},
+    websocket::FrameWriter,
 };
+use borsh::BorshSerialize;
+use kaspa_notify::{
+    connection::Connection as ConnectionT, events::EventType, listener::ListenerId, notification::Notification as NotificationT,
+};
+use kaspa_rpc_core::{api::ops::RpcApiOps, Notification, WelcomeNotification};
+use kaspa_utils::triggers::SingleTrigger;
+use serde::Serialize;
+use std::{
+    hash::{Hash, Hasher},
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::{io::AsyncWrite, sync::mpsc};
+use tungstenite::{
+    protocol::frame::{coding::CloseCode, CloseFrame},
+    Message,
+};
+use workflow_log::{log_error, log_info, log_trace};
+use workflow_rpc::server::{result::Result as WrpcResult, Encoding};
 
-//
-// FIXME: Use workflow_rpc::encoding::Encoding directly in the ConnectionT implementation by deriving Hash, Eq and PartialEq in situ
-//
-#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+/// Encoding of the notifications sent to a connection: a wRPC encoding or a custom serializer.
+///
+/// Custom serializers are identified by their name.
+#[derive(Clone, Debug)]
 pub enum NotifyEncoding {
     Borsh,
     SerdeJson,
+    Custom(DynNotificationSerializer),
+}
+
+impl PartialEq for NotifyEncoding {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (NotifyEncoding::Borsh, NotifyEncoding::Borsh) | (NotifyEncoding::SerdeJson, NotifyEncoding::SerdeJson) => true,
+            (NotifyEncoding::Custom(left), NotifyEncoding::Custom(right)) => left.name() == right.name(),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for NotifyEncoding {}
+
+impl Hash for NotifyEncoding {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        if let NotifyEncoding::Custom(serializer) = self {
+            serializer.name().hash(state);
+        }
+    }
 }
+
 impl From<Encoding> for NotifyEncoding {
     fn from(value: Encoding) -> Self {
         match value {
@@ -23,39 +72,351 @@ impl From<Encoding> for NotifyEncoding {
         }
     }
 }
-impl From<NotifyEncoding> for Encoding {
-    fn from(value: NotifyEncoding) -> Self {
-        match value {
-            NotifyEncoding::Borsh => Encoding::Borsh,
-            NotifyEncoding::SerdeJson => Encoding::SerdeJson,
+
+/// WebSocket subprotocol names used to request a specific connection encoding
+pub const BORSH_SUBPROTOCOL: &str = "kaspa-borsh";
+pub const JSON_SUBPROTOCOL: &str = "kaspa-json";
+
+/// Negotiate the encoding of a connection.
+///
+/// `offer` is either the content of a `Sec-WebSocket-Protocol` header (a comma separated
+/// list of subprotocols, first match wins) or a URL query string holding an `encoding`
+/// parameter (`?encoding=borsh` or `?encoding=json`). If the offer contains no recognized
+/// encoding, `default` is returned.
+pub fn negotiate_encoding(offer: Option<&str>, default: Encoding) -> Encoding {
+    let Some(offer) = offer else { return default };
+    let query = offer.split_once('?').map(|(_, query)| query).unwrap_or(offer);
+    let from_query = query.split('&').find_map(|pair| match pair.split_once('=') {
+        Some(("encoding", value)) => parse_encoding(value.trim()),
+        _ => None,
+    });
+    from_query.or_else(|| offer.split(',').find_map(|token| parse_encoding(token.trim()))).unwrap_or(default)
+}
+
+fn parse_encoding(name: &str) -> Option<Encoding> {
+    match name.to_lowercase().as_str() {
+        BORSH_SUBPROTOCOL | "borsh" => Some(Encoding::Borsh),
+        JSON_SUBPROTOCOL | "json" | "serde-json" => Some(Encoding::SerdeJson),
+        _ => None,
+    }
+}
+
+/// Prefix of the WebSocket subprotocols naming a version of the wRPC wire format, as in `kaspa-wrpc.v1`
+pub const VERSION_SUBPROTOCOL_PREFIX: &str = "kaspa-wrpc.v";
+
+/// Version of the wire format assumed for a client offering no version, the one spoken before versions were negotiated
+pub const DEFAULT_PROTOCOL_VERSION: u32 = 1;
+
+/// Versions of the wire format supported by default
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[u32] = &[DEFAULT_PROTOCOL_VERSION];
+
+/// Returns the subprotocols advertising the `supported` versions, highest first, as the content of a
+/// `Sec-WebSocket-Protocol` header.
+pub fn advertise_protocol_versions(supported: &[u32]) -> String {
+    let mut versions = supported.to_vec();
+    versions.sort_unstable_by(|a, b| b.cmp(a));
+    versions.iter().map(|version| format!("{VERSION_SUBPROTOCOL_PREFIX}{version}")).collect::<Vec<_>>().join(", ")
+}
+
+/// Negotiate the version of the wire format spoken by a connection.
+///
+/// `offer` is the content of a `Sec-WebSocket-Protocol` header, the versions being offered as
+/// [`VERSION_SUBPROTOCOL_PREFIX`] subprotocols among possibly other ones. The highest version offered
+/// and `supported` is selected. A client offering no version gets [`DEFAULT_PROTOCOL_VERSION`].
+///
+/// Fails with the reason to send to the client if no offered version is supported.
+pub fn negotiate_protocol_version(offer: Option<&str>, supported: &[u32]) -> std::result::Result<u32, String> {
+    let offered = offer
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|token| token.trim().to_lowercase().strip_prefix(VERSION_SUBPROTOCOL_PREFIX)?.parse::<u32>().ok())
+        .collect::<Vec<_>>();
+    if offered.is_empty() {
+        return match supported.contains(&DEFAULT_PROTOCOL_VERSION) {
+            true => Ok(DEFAULT_PROTOCOL_VERSION),
+            false => Err(format!(
+                "no wRPC protocol version offered and the default version {DEFAULT_PROTOCOL_VERSION} is not supported, supported: {}",
+                advertise_protocol_versions(supported)
+            )),
+        };
+    }
+    offered.iter().filter(|version| supported.contains(version)).max().copied().ok_or_else(|| {
+        format!(
+            "no supported wRPC protocol version offered (offered: {}, supported: {})",
+            advertise_protocol_versions(&offered),
+            advertise_protocol_versions(supported)
+        )
+    })
+}
+
+/// Encoding and wire format version negotiated by a connection
+pub struct NegotiatedProtocol {
+    pub encoding: Encoding,
+    pub protocol_version: u32,
+    /// Subprotocol selected among the ones offered by the client, to be stated in the upgrade response
+    pub subprotocol: Option<String>,
+}
+
+/// Negotiate the encoding and the wire format version of a connection from its upgrade request: the `subprotocols` it
+/// offers in its `Sec-WebSocket-Protocol` header and the `query` string of its URL, the encoding of the query taking
+/// precedence. See [`negotiate_encoding`] and [`negotiate_protocol_version`].
+///
+/// Fails with the reason to send to the client if no offered version is supported.
+pub fn negotiate_protocol(
+    subprotocols: Option<&str>,
+    query: Option<&str>,
+    default_encoding: Encoding,
+    supported: &[u32],
+) -> std::result::Result<NegotiatedProtocol, String> {
+    let protocol_version = negotiate_protocol_version(subprotocols, supported)?;
+    let encoding = negotiate_encoding(query, negotiate_encoding(subprotocols, default_encoding));
+    let version_subprotocol = format!("{VERSION_SUBPROTOCOL_PREFIX}{protocol_version}");
+    let subprotocol = subprotocols
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .find(|token| parse_encoding(token).is_some() || token.eq_ignore_ascii_case(&version_subprotocol));
+    Ok(NegotiatedProtocol { encoding, protocol_version, subprotocol: subprotocol.map(str::to_owned) })
+}
+
+/// An IP network in CIDR notation (`192.168.0.0/16`, `fe80::/10`).
+/// An address without a prefix length designates a single host.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IpNetwork {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpNetwork {
+    pub fn new(addr: IpAddr, prefix_len: u8) -> std::result::Result<Self, String> {
+        let max_len = Self::max_prefix_len(&addr);
+        if prefix_len > max_len {
+            return Err(format!("invalid prefix length {prefix_len} for {addr}, max is {max_len}"));
+        }
+        Ok(Self { addr, prefix_len })
+    }
+
+    fn max_prefix_len(addr: &IpAddr) -> u8 {
+        match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        }
+    }
+
+    /// Returns `true` if `ip` belongs to this network. IPv4-mapped IPv6 addresses
+    /// are matched against IPv4 networks.
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(*ip),
+            IpAddr::V4(_) => *ip,
+        };
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix_len as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix_len as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
         }
     }
 }
 
+impl FromStr for IpNetwork {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (addr, prefix_len) = s.trim().split_once('/').map(|(addr, len)| (addr, Some(len))).unwrap_or((s.trim(), None));
+        let addr = IpAddr::from_str(addr).map_err(|err| format!("invalid network address `{s}`: {err}"))?;
+        let prefix_len = match prefix_len {
+            Some(len) => len.parse::<u8>().map_err(|err| format!("invalid prefix length in `{s}`: {err}"))?,
+            None => Self::max_prefix_len(&addr),
+        };
+        Self::new(addr, prefix_len)
+    }
+}
+
+/// Network-level gating of incoming connections.
+///
+/// A peer matching any `deny` network is rejected, regardless of the `allow` list.
+/// Otherwise, the peer is accepted if the `allow` list is empty or if it matches
+/// one of its networks.
+#[derive(Clone, Debug, Default)]
+pub struct AccessControl {
+    pub allow: Vec<IpNetwork>,
+    pub deny: Vec<IpNetwork>,
+}
+
+impl AccessControl {
+    pub fn new(allow: Vec<IpNetwork>, deny: Vec<IpNetwork>) -> Self {
+        Self { allow, deny }
+    }
+
+    pub fn is_allowed(&self, ip: &IpAddr) -> bool {
+        if self.deny.iter().any(|network| network.contains(ip)) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(|network| network.contains(ip))
+    }
+}
+
+/// Close reason sent to a client disconnected for not reading its notifications fast enough
+pub const SLOW_CLIENT_REASON: &str = "outbound notification queue overflow";
+
+/// Delay given to a client to complete the close handshake once the server closed the connection, after which the
+/// socket is released regardless, a client that stopped reading being unable to receive the close frame
+pub const CLOSE_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// Returns the label identifying the connection `id` in the logs and in the welcome message sent to the peer
+pub fn connection_label(id: u64) -> String {
+    format!("wrpc-{id}")
+}
+
 #[derive(Debug)]
 pub struct ConnectionInner {
     pub id: u64,
+    /// Short label of the connection, see [`connection_label`]
+    pub label: String,
     pub peer: SocketAddr,
-    pub messenger: Arc<Messenger>,
+    /// Frames waiting to be written to the socket, see [`Connection::start_writer`]
+    frames: mpsc::UnboundedSender<Message>,
+    /// Set once a close frame has been posted, no more frames being expected from the server
+    closing: AtomicBool,
+    /// Triggered once the connection is closed and its socket released
+    closed: SingleTrigger,
+    /// Encoding negotiated for this connection, applied to both responses and notifications
+    pub encoding: Encoding,
+    /// Version of the wire format negotiated for this connection, see [`negotiate_protocol_version`]
+    pub protocol_version: u32,
     // not using an atomic in case an Id will change type in the future...
     pub listener_id: Mutex<Option<ListenerId>>,
+    /// Throttling of the notifications sent to this connection
+    pub throttle: Arc<NotificationThrottle<Message>>,
+    /// Bounded queue of the notifications waiting to be written to the socket, see [`Connection::start_writer`]
+    pub outbound: Arc<OutboundQueue<Message>>,
+    /// Custom serializer of the notifications, replacing the negotiated encoding
+    pub serializer: Option<DynNotificationSerializer>,
+    /// Consecutive frames of the client failing to decode
+    pub malformed_frames: MalformedFrames,
+    /// Counter of the ids assigned to the requests of the client sent with no id
+    next_request_id: AtomicU64,
+    /// Rate limiting of the requests of the client, if any
+    rate_limiter: Option<RateLimiter>,
 }
 
 impl ConnectionInner {}
 
 /// [`Connection`] represents a currently connected WebSocket RPC channel.
-/// This struct owns a [`Messenger`] that has [`Messenger::notify`]
-/// function that can be used to post notifications to the connection.
-/// [`Messenger::close`] function can be used to terminate the connection
-/// asynchronously.
+/// [`Connection::post`] queues a frame for the task writing to the socket,
+/// [`Connection::close_with_reason`] terminating the connection asynchronously.
 #[derive(Debug, Clone)]
 pub struct Connection {
     inner: Arc<ConnectionInner>,
 }
 
 impl Connection {
-    pub fn new(id: u64, peer: &SocketAddr, messenger: Arc<Messenger>) -> Connection {
-        Connection { inner: Arc::new(ConnectionInner { id, peer: *peer, messenger, listener_id: Mutex::new(None) }) }
+    pub fn new(
+        id: u64,
+        peer: &SocketAddr,
+        frames: mpsc::UnboundedSender<Message>,
+        encoding: Encoding,
+        protocol_version: u32,
+        throttling: NotificationThrottling,
+        outbound: OutboundQueueConfig,
+        serializer: Option<DynNotificationSerializer>,
+        max_consecutive_malformed_frames: usize,
+        rate_limit: Option<RateLimit>,
+    ) -> Connection {
+        let throttle = Arc::new(NotificationThrottle::new(throttling));
+        let outbound = Arc::new(OutboundQueue::new(outbound));
+        let label = connection_label(id);
+        Connection {
+            inner: Arc::new(ConnectionInner {
+                id,
+                label,
+                peer: *peer,
+                frames,
+                closing: AtomicBool::new(false),
+                closed: SingleTrigger::new(),
+                encoding,
+                protocol_version,
+                listener_id: Mutex::new(None),
+                throttle,
+                outbound,
+                serializer,
+                malformed_frames: MalformedFrames::new(max_consecutive_malformed_frames),
+                next_request_id: AtomicU64::new(0),
+                rate_limiter: rate_limit.map(RateLimiter::new),
+            }),
+        }
+    }
+
+    /// Spawns the task writing to the socket the posted `frames` and the notifications of the outbound queue, until a
+    /// close frame is written or the connection is closed. The connection is marked as closed once the task ends.
+    ///
+    /// A notification is only dequeued once the previous frame is written, so a client that stops reading makes the
+    /// outbound queue fill up and its shedding policies apply.
+    pub fn start_writer<W>(&self, mut writer: FrameWriter<W>, mut frames: mpsc::UnboundedReceiver<Message>)
+    where
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let connection = self.clone();
+        tokio::spawn(async move {
+            let outbound = connection.inner.outbound.clone();
+            let mut outbound_open = true;
+            loop {
+                let frame = tokio::select! {
+                    biased;
+                    frame = frames.recv() => frame,
+                    _ = connection.closed() => None,
+                    message = outbound.pop(), if outbound_open => match message {
+                        Some(message) => Some(message),
+                        None => {
+                            // The queue is closed along with the connection, its close frame being still to come
+                            outbound_open = false;
+                            continue;
+                        }
+                    },
+                };
+                let Some(frame) = frame else { break };
+                let close = matches!(frame, Message::Close(_));
+                let written = tokio::select! {
+                    written = writer.write_message(frame) => written,
+                    _ = connection.closed() => break,
+                };
+                if let Err(err) = written {
+                    log_trace!("[{}] Error writing to {}: {}", connection.label(), connection.peer(), err);
+                    break;
+                }
+                if close {
+                    break;
+                }
+            }
+            connection.mark_closed();
+        });
+    }
+
+    /// Marks the connection as closed once the [`CLOSE_GRACE_PERIOD`] has elapsed, unless the close handshake
+    /// completed before
+    fn close_after_grace_period(&self) {
+        let connection = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(CLOSE_GRACE_PERIOD).await;
+            connection.mark_closed();
+        });
+    }
+
+    /// Waits for the connection to be closed
+    pub fn closed(&self) -> kaspa_utils::triggers::Listener {
+        self.inner.closed.listener.clone()
+    }
+
+    /// Marks the connection as closed, ending the tasks serving it
+    pub fn mark_closed(&self) {
+        self.inner.closing.store(true, Ordering::SeqCst);
+        self.inner.closed.trigger.trigger();
     }
 
     /// Obtain the connection id
@@ -63,9 +424,39 @@ impl Connection {
         self.inner.id
     }
 
-    /// Get a reference to the connection [`Messenger`]
-    pub fn messenger(&self) -> &Arc<Messenger> {
-        &self.inner.messenger
+    /// Obtain the connection label, to be included in every log related to this connection
+    pub fn label(&self) -> &str {
+        &self.inner.label
+    }
+
+    /// Assigns an id to a request of the client sent with no id, see [`SERVER_REQUEST_ID_FLAG`]
+    pub fn assign_request_id(&self) -> RequestId {
+        RequestId::Number(SERVER_REQUEST_ID_FLAG | self.inner.next_request_id.fetch_add(1, Ordering::SeqCst))
+    }
+
+    /// Fails with [`RouterError::RateLimited`] if a request of the client calling `op` exceeds its rate limit
+    pub fn check_rate_limit(&self, op: &RpcApiOps) -> std::result::Result<(), RouterError> {
+        self.inner.rate_limiter.as_ref().map_or(Ok(()), |limiter| limiter.check(op))
+    }
+
+    /// Post a frame to be written to the socket
+    pub fn post(&self, message: Message) -> WrpcResult<()> {
+        self.inner.frames.send(message).map_err(|_| "connection closed".to_string().into())
+    }
+
+    /// Get the encoding negotiated for this connection
+    pub fn encoding(&self) -> Encoding {
+        self.inner.encoding
+    }
+
+    /// Get the version of the wire format negotiated for this connection, allowing to gate the behaviors depending on it
+    pub fn protocol_version(&self) -> u32 {
+        self.inner.protocol_version
+    }
+
+    /// Get the custom serializer of the notifications sent to this connection, if any
+    pub fn notification_serializer(&self) -> Option<&DynNotificationSerializer> {
+        self.inner.serializer.as_ref()
     }
 
     pub fn listener_id(&self) -> Option<ListenerId> {
@@ -80,44 +471,145 @@ impl Connection {
         &self.inner.peer
     }
 
-    /// Creates a WebSocket [`Message`] that can be posted to the connection ([`Messenger`]) sink
-    /// directly.
-    pub fn create_serialized_notification_message<Ops, Msg>(encoding: Encoding, op: Ops, msg: Msg) -> WrpcResult<Message>
+    /// Number of notifications shed because the outbound queue of the connection was full
+    pub fn dropped_notifications(&self) -> u64 {
+        self.inner.outbound.dropped_count()
+    }
+
+    /// Stops the delivery of the notifications, dropping the queued ones
+    pub fn close_outbound(&self) {
+        self.inner.outbound.close();
+    }
+
+    /// Close the connection sending a WebSocket close frame carrying `reason`
+    /// to the peer. Falls back to a plain close if the frame cannot be posted.
+    pub fn close_with_reason(&self, reason: &str) -> bool {
+        if self.is_closed() {
+            return false;
+        }
+        self.close_outbound();
+        self.inner.closing.store(true, Ordering::SeqCst);
+        let frame = CloseFrame { code: CloseCode::Away, reason: reason.to_string().into() };
+        match self.post(Message::Close(Some(frame))) {
+            Ok(_) => {
+                self.close_after_grace_period();
+                true
+            }
+            Err(err) => {
+                log_trace!("[{}] Error sending close frame to {}: {}", self.label(), self.peer(), err);
+                self.mark_closed();
+                false
+            }
+        }
+    }
+
+    /// Unwraps a request decoded leniently from a frame of the client, a malformed frame being answered with a
+    /// malformed params error while keeping the connection alive, unless it ends a run of too many consecutive malformed frames.
+    pub fn accept_request<T>(&self, request: Lenient<T>) -> std::result::Result<T, RouterError> {
+        self.inner.malformed_frames.accept(request).map_err(|malformed| {
+            log_trace!("[{}] Malformed frame received from {}: {}", self.label(), self.peer(), malformed.error);
+            if malformed.close {
+                log_info!("[{}] Disconnecting {}: {}", self.label(), self.peer(), MALFORMED_FRAMES_REASON);
+                self.close_with_reason(MALFORMED_FRAMES_REASON);
+            }
+            malformed.error
+        })
+    }
+
+    /// Send the [`WelcomeNotification`] carrying the connection label to the peer
+    pub fn send_welcome(&self) -> WrpcResult<()> {
+        let message = Self::create_welcome_message(self.encoding(), self.label())?;
+        self.post(message)
+    }
+
+    /// Creates the serialized [`WelcomeNotification`] of the connection labeled `label`
+    pub fn create_welcome_message(encoding: Encoding, label: &str) -> WrpcResult<Message> {
+        Self::create_serialized_notification_message(
+            encoding,
+            RpcApiOps::WelcomeNotification,
+            WelcomeNotification::new(label.to_string()),
+        )
+    }
+
+    /// Queues a notification message, applying the shedding policy of `event` if the outbound queue is full
+    fn enqueue(&self, event: EventType, message: Message) -> core::result::Result<(), kaspa_notify::error::Error> {
+        match self.inner.outbound.push(event, message) {
+            Enqueued::Queued => Ok(()),
+            Enqueued::Shed => {
+                log_trace!("[{}] Outbound queue of {} is full, shedding a {:?} notification", self.label(), self.peer(), event);
+                Ok(())
+            }
+            Enqueued::Overflow => {
+                log_info!("[{}] Disconnecting {}: {} ({:?})", self.label(), self.peer(), SLOW_CLIENT_REASON, event);
+                self.close_with_reason(SLOW_CLIENT_REASON);
+                Err(kaspa_notify::error::Error::General(SLOW_CLIENT_REASON.to_string()))
+            }
+        }
+    }
+
+    /// Creates a WebSocket [`Message`] that can be posted to the connection directly, see [`Connection::post`]
+    pub fn create_serialized_notification_message<Msg>(encoding: Encoding, op: RpcApiOps, msg: Msg) -> WrpcResult<Message>
     where
-        Ops: OpsT,
-        Msg: MsgT,
+        Msg: BorshSerialize + Serialize,
     {
-        match encoding {
-            Encoding::Borsh => workflow_rpc::server::protocol::borsh::create_serialized_notification_message(op, msg),
-            Encoding::SerdeJson => workflow_rpc::server::protocol::borsh::create_serialized_notification_message(op, msg),
-        }
+        let payload = Params::encode(encoding, &msg)?;
+        Ok(encode_notification(op, payload)?)
     }
 }
 
+/// A serialized notification, along with its event type.
+///
+/// The message is `None` when the notification failed to serialize, in which case nothing is sent.
+#[derive(Clone, Debug)]
+pub struct NotificationMessage {
+    pub event: EventType,
+    pub message: Option<Message>,
+}
+
 impl ConnectionT for Connection {
     type Notification = Notification;
-    type Message = Message;
+    type Message = NotificationMessage;
     type Encoding = NotifyEncoding;
     type Error = kaspa_notify::error::Error;
 
     fn encoding(&self) -> Self::Encoding {
-        self.messenger().encoding().into()
+        match self.inner.serializer {
+            Some(ref serializer) => NotifyEncoding::Custom(serializer.clone()),
+            None => self.inner.encoding.into(),
+        }
     }
 
     fn into_message(notification: &Self::Notification, encoding: &Self::Encoding) -> Self::Message {
-        let op: RpcApiOps = notification.event_type().into();
-        Self::create_serialized_notification_message(encoding.clone().into(), op, notification.clone()).unwrap()
+        let event = notification.event_type();
+        let op: RpcApiOps = event.into();
+        let message = match encoding {
+            NotifyEncoding::Borsh => Self::create_serialized_notification_message(Encoding::Borsh, op, notification.clone()),
+            NotifyEncoding::SerdeJson => Self::create_serialized_notification_message(Encoding::SerdeJson, op, notification.clone()),
+            NotifyEncoding::Custom(serializer) => serializer.serialize(op, notification),
+        };
+        // A serializer failure must not take the broadcaster down, so the notification is skipped
+        let message = message.map_err(|err| log_error!("Error serializing a {op:?} notification: {err}")).ok();
+        NotificationMessage { event, message }
     }
 
     fn send(&self, message: Self::Message) -> core::result::Result<(), Self::Error> {
-        self.messenger().send_raw_message(message).map_err(|err| kaspa_notify::error::Error::General(err.to_string()))
+        let Some(payload) = message.message else {
+            return Ok(());
+        };
+        let connection = self.clone();
+        let event = message.event;
+        self.inner.throttle.submit(event, payload, move |message| connection.enqueue(event, message))
     }
 
     fn close(&self) -> bool {
+        self.close_outbound();
         if !self.is_closed() {
-            if let Err(err) = self.messenger().close() {
-                log_trace!("Error closing connection {}: {}", self.peer(), err);
+            self.inner.closing.store(true, Ordering::SeqCst);
+            if let Err(err) = self.post(Message::Close(None)) {
+                log_trace!("[{}] Error closing connection {}: {}", self.label(), self.peer(), err);
+                self.mark_closed();
             } else {
+                self.close_after_grace_period();
                 return true;
             }
         }
@@ -125,8 +617,284 @@ impl ConnectionT for Connection {
     }
 
     fn is_closed(&self) -> bool {
-        self.messenger().sink().is_closed()
+        self.inner.closing.load(Ordering::SeqCst)
     }
 }
 
 pub type ConnectionReference = Arc<Connection>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        notifications::{NotificationSerializer, SheddingPolicy},
+        service::Options,
+        test_client::{RawClient, TestNode},
+    };
+    use borsh::BorshSerialize;
+    use kaspa_consensus_core::block::Block;
+    use kaspa_rpc_core::{BlockAddedNotification, RpcHash};
+
+    #[test]
+    fn test_negotiate_encoding() {
+        struct Test {
+            offer: Option<&'static str>,
+            default: Encoding,
+            expected: Encoding,
+        }
+        let tests = vec![
+            Test { offer: None, default: Encoding::SerdeJson, expected: Encoding::SerdeJson },
+            Test { offer: Some(""), default: Encoding::Borsh, expected: Encoding::Borsh },
+            Test { offer: Some(BORSH_SUBPROTOCOL), default: Encoding::SerdeJson, expected: Encoding::Borsh },
+            Test { offer: Some("chat, kaspa-json, kaspa-borsh"), default: Encoding::Borsh, expected: Encoding::SerdeJson },
+            Test { offer: Some("/?encoding=borsh"), default: Encoding::SerdeJson, expected: Encoding::Borsh },
+            Test { offer: Some("?id=1&encoding=json"), default: Encoding::Borsh, expected: Encoding::SerdeJson },
+            Test { offer: Some("?encoding=xml"), default: Encoding::Borsh, expected: Encoding::Borsh },
+        ];
+        for test in tests {
+            let encoding = negotiate_encoding(test.offer, test.default);
+            assert_eq!(NotifyEncoding::from(encoding), NotifyEncoding::from(test.expected), "wrong encoding for {:?}", test.offer);
+        }
+    }
+
+    #[test]
+    fn test_negotiate_protocol_version() {
+        let supported = &[1, 2, 3];
+        assert_eq!(advertise_protocol_versions(supported), "kaspa-wrpc.v3, kaspa-wrpc.v2, kaspa-wrpc.v1");
+
+        // Matching versions
+        assert_eq!(negotiate_protocol_version(Some("kaspa-wrpc.v2"), supported), Ok(2));
+        assert_eq!(negotiate_protocol_version(Some("kaspa-wrpc.v1, kaspa-borsh, KASPA-WRPC.V3, kaspa-wrpc.v7"), supported), Ok(3));
+
+        // Mismatched versions
+        let reason = negotiate_protocol_version(Some("kaspa-wrpc.v4, kaspa-wrpc.v5"), supported).unwrap_err();
+        assert!(reason.contains("kaspa-wrpc.v5, kaspa-wrpc.v4"), "the reason {reason} should list the offered versions");
+        assert!(reason.contains(&advertise_protocol_versions(supported)), "the reason {reason} should list the supported versions");
+        assert!(negotiate_protocol_version(Some("kaspa-wrpc.v1"), &[2]).is_err());
+
+        // No version offered
+        for offer in [None, Some(""), Some("kaspa-json"), Some("kaspa-wrpc.vx"), Some("/?encoding=borsh")] {
+            assert_eq!(negotiate_protocol_version(offer, supported), Ok(DEFAULT_PROTOCOL_VERSION), "wrong version for {offer:?}");
+        }
+        assert!(negotiate_protocol_version(None, &[2, 3]).is_err(), "the default version should be rejected if unsupported");
+    }
+
+    fn access_control(allow: &[&str], deny: &[&str]) -> AccessControl {
+        AccessControl::new(
+            allow.iter().map(|network| network.parse().unwrap()).collect(),
+            deny.iter().map(|network| network.parse().unwrap()).collect(),
+        )
+    }
+
+    #[test]
+    fn test_ip_network_parsing() {
+        assert!("10.0.0.0/8".parse::<IpNetwork>().is_ok());
+        assert!("fe80::/10".parse::<IpNetwork>().is_ok());
+        assert!("127.0.0.1".parse::<IpNetwork>().is_ok());
+        assert!("10.0.0.0/33".parse::<IpNetwork>().is_err());
+        assert!("10.0.0/8".parse::<IpNetwork>().is_err());
+        assert!("10.0.0.0/x".parse::<IpNetwork>().is_err());
+    }
+
+    #[test]
+    fn test_access_control() {
+        struct Test {
+            name: &'static str,
+            allow: Vec<&'static str>,
+            deny: Vec<&'static str>,
+            allowed: Vec<&'static str>,
+            denied: Vec<&'static str>,
+        }
+        let tests = vec![
+            Test {
+                name: "empty lists allow all",
+                allow: vec![],
+                deny: vec![],
+                allowed: vec!["1.2.3.4", "::1", "192.168.1.1"],
+                denied: vec![],
+            },
+            Test {
+                name: "allowed addresses",
+                allow: vec!["192.168.0.0/16", "::1"],
+                deny: vec![],
+                allowed: vec!["192.168.10.20", "::1", "::ffff:192.168.0.1"],
+                denied: vec!["10.0.0.1", "::2"],
+            },
+            Test {
+                name: "denied addresses",
+                allow: vec![],
+                deny: vec!["10.0.0.0/8", "2001:db8::/32"],
+                allowed: vec!["11.0.0.1", "2001:db9::1"],
+                denied: vec!["10.1.2.3", "2001:db8::1", "::ffff:10.0.0.1"],
+            },
+            Test {
+                name: "deny takes precedence over overlapping allow",
+                allow: vec!["10.0.0.0/8"],
+                deny: vec!["10.1.0.0/16", "10.2.3.4"],
+                allowed: vec!["10.0.0.1", "10.2.3.5"],
+                denied: vec!["10.1.2.3", "10.2.3.4", "172.16.0.1"],
+            },
+            Test { name: "wildcard networks", allow: vec!["0.0.0.0/0"], deny: vec![], allowed: vec!["8.8.8.8"], denied: vec!["::1"] },
+        ];
+        for test in tests {
+            let access_control = access_control(&test.allow, &test.deny);
+            for ip in test.allowed {
+                assert!(access_control.is_allowed(&ip.parse().unwrap()), "{}: {} should be allowed", test.name, ip);
+            }
+            for ip in test.denied {
+                assert!(!access_control.is_allowed(&ip.parse().unwrap()), "{}: {} should be denied", test.name, ip);
+            }
+        }
+    }
+
+    #[test]
+    fn test_welcome_message() {
+        let labels = (0..2).map(connection_label).collect::<Vec<_>>();
+        assert_ne!(labels[0], labels[1], "connections must get distinct ids");
+        assert_eq!(labels[0], connection_label(0), "a connection label must be stable");
+
+        for label in labels.iter() {
+            // JSON
+            let Message::Text(text) = Connection::create_welcome_message(Encoding::SerdeJson, label).unwrap() else {
+                panic!("a JSON welcome message should be a text message")
+            };
+            assert!(text.contains(&format!("\"connectionId\":\"{label}\"")), "welcome message {text} should echo {label}");
+
+            // Borsh
+            let Message::Binary(bytes) = Connection::create_welcome_message(Encoding::Borsh, label).unwrap() else {
+                panic!("a Borsh welcome message should be a binary message")
+            };
+            let payload = WelcomeNotification::new(label.clone()).try_to_vec().unwrap();
+            assert!(bytes.ends_with(&payload), "welcome message should carry the payload of {label}");
+        }
+    }
+
+    /// A serializer sending the hash of the added blocks only
+    #[derive(Debug)]
+    struct CompactSerializer;
+
+    impl NotificationSerializer for CompactSerializer {
+        fn name(&self) -> &str {
+            "compact"
+        }
+
+        fn serialize(&self, op: RpcApiOps, notification: &Notification) -> WrpcResult<Message> {
+            match notification {
+                Notification::BlockAdded(payload) => Ok(Message::Binary(payload.block.header.hash.as_bytes().to_vec())),
+                _ => Ok(Message::Text(format!("{op:?}"))),
+            }
+        }
+    }
+
+    /// A serializer failing on every notification
+    #[derive(Debug)]
+    struct FailingSerializer;
+
+    impl NotificationSerializer for FailingSerializer {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        fn serialize(&self, op: RpcApiOps, _: &Notification) -> WrpcResult<Message> {
+            Err(format!("{op:?} is not supported").into())
+        }
+    }
+
+    #[test]
+    fn test_custom_notification_serializer() {
+        let hash = RpcHash::from_u64_word(7);
+        let block = Block::from_precomputed_hash(hash, vec![RpcHash::from_u64_word(1)]);
+        let notification = Notification::BlockAdded(BlockAddedNotification { block: Arc::new((&block).into()) });
+
+        let serializer: DynNotificationSerializer = Arc::new(CompactSerializer);
+        let encoding = NotifyEncoding::Custom(serializer.clone());
+        assert_eq!(encoding, NotifyEncoding::Custom(Arc::new(CompactSerializer)), "serializers should be identified by name");
+        assert_ne!(encoding, NotifyEncoding::SerdeJson);
+
+        let message = Connection::into_message(&notification, &encoding);
+        assert_eq!(message.event, EventType::BlockAdded);
+        assert_eq!(message.message, Some(serializer.serialize(RpcApiOps::BlockAddedNotification, &notification).unwrap()));
+        assert_eq!(message.message, Some(Message::Binary(hash.as_bytes().to_vec())));
+
+        // The default encodings are left unchanged
+        let message = Connection::into_message(&notification, &NotifyEncoding::SerdeJson);
+        assert!(matches!(message.message, Some(Message::Text(ref text)) if text.contains(&hash.to_string())));
+
+        // A failing serializer yields no message instead of panicking
+        let message = Connection::into_message(&notification, &NotifyEncoding::Custom(Arc::new(FailingSerializer)));
+        assert_eq!(message.event, EventType::BlockAdded);
+        assert!(message.message.is_none());
+    }
+
+    #[test]
+    fn test_handshake_negotiation() {
+        // A JSON client connecting to a Borsh endpoint
+        let negotiated =
+            negotiate_protocol(Some("chat, kaspa-json, kaspa-wrpc.v1"), None, Encoding::Borsh, SUPPORTED_PROTOCOL_VERSIONS).unwrap();
+        assert_eq!(NotifyEncoding::from(negotiated.encoding), NotifyEncoding::SerdeJson);
+        assert_eq!(negotiated.protocol_version, DEFAULT_PROTOCOL_VERSION);
+        assert_eq!(negotiated.subprotocol.as_deref(), Some(JSON_SUBPROTOCOL), "the first recognized subprotocol should be selected");
+
+        // A Borsh client stating its encoding in the query string of its URL, which takes precedence
+        let negotiated =
+            negotiate_protocol(Some("kaspa-wrpc.v1"), Some("encoding=borsh"), Encoding::SerdeJson, SUPPORTED_PROTOCOL_VERSIONS)
+                .unwrap();
+        assert_eq!(NotifyEncoding::from(negotiated.encoding), NotifyEncoding::Borsh);
+        assert_eq!(negotiated.subprotocol.as_deref(), Some("kaspa-wrpc.v1"));
+
+        // A client offering nothing keeps the encoding of the endpoint
+        let negotiated = negotiate_protocol(None, None, Encoding::Borsh, SUPPORTED_PROTOCOL_VERSIONS).unwrap();
+        assert_eq!(NotifyEncoding::from(negotiated.encoding), NotifyEncoding::Borsh);
+        assert_eq!((negotiated.protocol_version, negotiated.subprotocol), (DEFAULT_PROTOCOL_VERSION, None));
+    }
+
+    #[test]
+    fn test_handshake_with_unsupported_version() {
+        let reason = negotiate_protocol(Some("kaspa-borsh, kaspa-wrpc.v9"), None, Encoding::Borsh, SUPPORTED_PROTOCOL_VERSIONS)
+            .err()
+            .expect("a connection offering only an unsupported version should be rejected");
+        assert!(
+            reason.contains(&advertise_protocol_versions(SUPPORTED_PROTOCOL_VERSIONS)),
+            "the reason {reason} should list the supported versions"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_non_reading_client() {
+        let outbound_queue = OutboundQueueConfig::new(4)
+            .with_policy(EventType::VirtualDaaScoreChanged, SheddingPolicy::DropOldest)
+            .with_policy(EventType::BlockAdded, SheddingPolicy::Disconnect);
+        let node = TestNode::start(Encoding::Borsh, Options { outbound_queue, ..Options::default() }).await;
+
+        // The client upgrades its connection, then never reads
+        let _client = RawClient::connect(node.address, "/", &[]).await.unwrap();
+        let connection = loop {
+            match node.server.connections().pop() {
+                Some(connection) => break connection,
+                None => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+
+        // Once the socket buffers are full, the notifications dropping their oldest queued value are shed
+        let notification = || Message::Binary(vec![0; 1 << 20]);
+        for _ in 0..256 {
+            if connection.dropped_notifications() > 0 {
+                break;
+            }
+            connection.enqueue(EventType::VirtualDaaScoreChanged, notification()).unwrap();
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        assert!(connection.dropped_notifications() > 0, "the outbound queue of a non-reading client should shed notifications");
+        assert!(!connection.is_closed(), "shedding should keep the connection open");
+
+        // A notification the client cannot miss disconnects it instead
+        let err = connection.enqueue(EventType::BlockAdded, notification()).unwrap_err();
+        assert!(err.to_string().contains(SLOW_CLIENT_REASON), "unexpected error {err}");
+        assert!(connection.is_closed());
+        tokio::time::timeout(CLOSE_GRACE_PERIOD * 2, connection.closed())
+            .await
+            .expect("the socket of a slow client should be released once the grace period elapsed");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(node.server.connections().is_empty(), "the slow client should be disconnected");
+    }
+}
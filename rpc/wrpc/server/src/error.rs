@@ -1,8 +1,8 @@
 use kaspa_notify::error::Error as NotifyError;
 use kaspa_rpc_core::RpcError;
-use std::sync::PoisonError;
+use std::{sync::PoisonError, time::Duration};
 use thiserror::Error;
-use workflow_rpc::server::{error::Error as RpcServerError, WebSocketError};
+use workflow_rpc::server::{error::Error as RpcServerError, prelude::ServerError, WebSocketError};
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -15,11 +15,18 @@ pub enum Error {
     #[error("Poison error")]
     PoisonError,
 
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
     #[error("RPC error: {0}")]
     RpcError(#[from] RpcError),
 
     #[error("Notify error: {0}")]
     NotifyError(#[from] NotifyError),
+
+    /// No version of the wire format is supported by both the client and the server
+    #[error("Protocol version negotiation failure: {0}")]
+    ProtocolVersion(String),
 }
 
 impl<T> From<PoisonError<T>> for Error {
@@ -27,3 +34,108 @@ impl<T> From<PoisonError<T>> for Error {
         Error::PoisonError
     }
 }
+
+/// Reason of a request failing at the wRPC router.
+///
+/// Each variant maps to a stable error code, sent to the client ahead of the message as `[<code>] <message>`
+/// so it can branch on the failure without matching the message, see [`RouterError::code_of`].
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum RouterError {
+    /// The frame sent by the client could not be decoded
+    #[error("parse error: {0}")]
+    ParseError(String),
+
+    #[error("unknown method {0}")]
+    UnknownMethod(String),
+
+    #[error("malformed params: {0}")]
+    MalformedParams(String),
+
+    #[error("method {0} is not permitted")]
+    NotPermitted(String),
+
+    #[error("rate limit exceeded for method {0}")]
+    RateLimited(String),
+
+    /// The method handler overran its deadline, see [`MethodTimeouts`](crate::timeouts::MethodTimeouts)
+    #[error("method {0} timed out after {1:?}")]
+    Timeout(String, Duration),
+
+    /// The method handler failed
+    #[error("internal error: {0}")]
+    Internal(String),
+}
+
+impl RouterError {
+    pub const PARSE_ERROR: i32 = -32700;
+    pub const UNKNOWN_METHOD: i32 = -32601;
+    pub const MALFORMED_PARAMS: i32 = -32602;
+    pub const INTERNAL: i32 = -32603;
+    pub const NOT_PERMITTED: i32 = -32001;
+    pub const RATE_LIMITED: i32 = -32002;
+    pub const TIMEOUT: i32 = -32003;
+
+    /// Stable code of the error, following the JSON-RPC 2.0 numbering where it has an equivalent
+    pub fn code(&self) -> i32 {
+        match self {
+            RouterError::ParseError(_) => Self::PARSE_ERROR,
+            RouterError::UnknownMethod(_) => Self::UNKNOWN_METHOD,
+            RouterError::MalformedParams(_) => Self::MALFORMED_PARAMS,
+            RouterError::NotPermitted(_) => Self::NOT_PERMITTED,
+            RouterError::RateLimited(_) => Self::RATE_LIMITED,
+            RouterError::Timeout(..) => Self::TIMEOUT,
+            RouterError::Internal(_) => Self::INTERNAL,
+        }
+    }
+
+    /// Error message sent to the client
+    pub fn to_wire_message(&self) -> String {
+        format!("[{}] {}", self.code(), self)
+    }
+
+    /// Extracts the code of a router error from the message of an error response, if any
+    pub fn code_of(message: &str) -> Option<i32> {
+        message.strip_prefix('[')?.split_once(']')?.0.parse().ok()
+    }
+}
+
+impl From<RouterError> for ServerError {
+    fn from(err: RouterError) -> Self {
+        ServerError::Text(err.to_wire_message())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_router_error_codes() {
+        let cases = vec![
+            (RouterError::ParseError("expected value".to_string()), -32700, "[-32700] parse error: expected value"),
+            (RouterError::UnknownMethod("getFoo".to_string()), -32601, "[-32601] unknown method getFoo"),
+            (RouterError::MalformedParams("missing hash".to_string()), -32602, "[-32602] malformed params: missing hash"),
+            (RouterError::NotPermitted("shutdown".to_string()), -32001, "[-32001] method shutdown is not permitted"),
+            (RouterError::RateLimited("getBlock".to_string()), -32002, "[-32002] rate limit exceeded for method getBlock"),
+            (
+                RouterError::Timeout("GetBlocks".to_string(), Duration::from_secs(2)),
+                -32003,
+                "[-32003] method GetBlocks timed out after 2s",
+            ),
+            (RouterError::Internal("block not found".to_string()), -32603, "[-32603] internal error: block not found"),
+        ];
+        for (err, code, message) in cases {
+            assert_eq!(err.code(), code);
+            assert_eq!(err.to_wire_message(), message);
+            match ServerError::from(err.clone()) {
+                ServerError::Text(text) => {
+                    assert_eq!(text, message);
+                    assert_eq!(RouterError::code_of(&text), Some(code), "the code of {err:?} should be recovered from the message");
+                }
+                other => panic!("unexpected server error {other:?}"),
+            }
+        }
+        assert_eq!(RouterError::code_of("some untagged error"), None);
+        assert_eq!(RouterError::code_of("[abc] not a code"), None);
+    }
+}
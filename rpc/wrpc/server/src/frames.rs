@@ -0,0 +1,164 @@
+//! Handling of the frames a client sends that fail to decode.
+//!
+//! The payload of a request is decoded as a [`Lenient`] value, so a malformed payload reaches the method handler
+//! instead of failing the request as a whole. The handler answers it with a structured malformed params error, carrying
+//! the id of the request, and the connection is kept alive. Only a run of `max_consecutive` malformed frames closes
+//! it, a buggy client thus not losing its connection, and with it its subscriptions, over a single bad frame.
+
+use crate::error::RouterError;
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{de::DeserializeOwned, ser::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::{
+    io,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Close reason sent to a client after too many consecutive malformed frames
+pub const MALFORMED_FRAMES_REASON: &str = "too many consecutive malformed frames";
+
+/// Default number of consecutive malformed frames closing a connection
+pub const DEFAULT_MAX_CONSECUTIVE_MALFORMED_FRAMES: usize = 8;
+
+/// A request payload whose decoding never fails, a malformed payload being kept as the reason of its decoding failure.
+///
+/// Borsh payloads must be consumed entirely, trailing bytes making the payload malformed.
+#[derive(Debug)]
+pub enum Lenient<T> {
+    Decoded(T),
+    Malformed(String),
+}
+
+impl<T: BorshDeserialize> BorshDeserialize for Lenient<T> {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        let decoded = match T::deserialize(buf) {
+            Ok(_) if !buf.is_empty() => Lenient::Malformed(format!("{} trailing bytes", buf.len())),
+            Ok(value) => Lenient::Decoded(value),
+            Err(err) => Lenient::Malformed(err.to_string()),
+        };
+        *buf = &[];
+        Ok(decoded)
+    }
+}
+
+impl<T: BorshSerialize> BorshSerialize for Lenient<T> {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Lenient::Decoded(value) => value.serialize(writer),
+            Lenient::Malformed(reason) => Err(io::Error::new(io::ErrorKind::InvalidData, reason.clone())),
+        }
+    }
+}
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for Lenient<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        Ok(serde_json::from_value(value).map_or_else(|err| Lenient::Malformed(err.to_string()), Lenient::Decoded))
+    }
+}
+
+impl<T: Serialize> Serialize for Lenient<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Lenient::Decoded(value) => value.serialize(serializer),
+            Lenient::Malformed(reason) => Err(S::Error::custom(reason)),
+        }
+    }
+}
+
+/// A malformed request, answered with `error`
+#[derive(Debug)]
+pub struct MalformedFrame {
+    pub error: RouterError,
+    /// The frame ends a run of too many consecutive malformed frames, so the connection must be closed
+    pub close: bool,
+}
+
+/// Count of the consecutive malformed frames received by a connection
+#[derive(Debug)]
+pub struct MalformedFrames {
+    max_consecutive: usize,
+    consecutive: AtomicUsize,
+}
+
+impl MalformedFrames {
+    pub fn new(max_consecutive: usize) -> Self {
+        Self { max_consecutive, consecutive: AtomicUsize::new(0) }
+    }
+
+    /// Records a malformed frame, returning `false` if the connection must be closed
+    pub fn record_malformed(&self) -> bool {
+        self.consecutive.fetch_add(1, Ordering::SeqCst) + 1 < self.max_consecutive
+    }
+
+    /// Records a well-formed frame, ending the run of malformed ones
+    pub fn record_valid(&self) {
+        self.consecutive.store(0, Ordering::SeqCst);
+    }
+
+    /// Unwraps a leniently decoded request, recording it as a well-formed or a malformed frame
+    pub fn accept<T>(&self, request: Lenient<T>) -> std::result::Result<T, MalformedFrame> {
+        match request {
+            Lenient::Decoded(request) => {
+                self.record_valid();
+                Ok(request)
+            }
+            Lenient::Malformed(reason) => {
+                Err(MalformedFrame { error: RouterError::MalformedParams(reason), close: !self.record_malformed() })
+            }
+        }
+    }
+
+    pub fn consecutive(&self) -> usize {
+        self.consecutive.load(Ordering::SeqCst)
+    }
+}
+
+impl Default for MalformedFrames {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONSECUTIVE_MALFORMED_FRAMES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kaspa_notify::scope::{BlockAddedScope, Scope};
+    use kaspa_rpc_core::GetInfoRequest;
+
+    #[test]
+    fn test_malformed_frame_then_valid_call() {
+        let frames = MalformedFrames::new(3);
+
+        // Borsh
+        let malformed = Lenient::<GetInfoRequest>::try_from_slice(&[0xff; 3]).expect("a lenient payload always decodes");
+        let malformed = frames.accept(malformed).unwrap_err();
+        assert_eq!(malformed.error.code(), RouterError::MALFORMED_PARAMS);
+        assert!(malformed.error.to_string().contains("trailing bytes"), "unexpected reason {}", malformed.error);
+        assert!(!malformed.close, "a single malformed frame should keep the connection alive");
+        assert_eq!(frames.consecutive(), 1);
+        let valid = Lenient::<GetInfoRequest>::try_from_slice(&GetInfoRequest {}.try_to_vec().unwrap()).unwrap();
+        assert!(frames.accept(valid).is_ok(), "a valid call should be dispatched after a malformed one");
+        assert_eq!(frames.consecutive(), 0, "a valid call on the same connection ends the run of malformed frames");
+
+        // JSON
+        let malformed: Lenient<GetInfoRequest> = serde_json::from_str("42").expect("a lenient payload always decodes");
+        assert_eq!(frames.accept(malformed).unwrap_err().error.code(), RouterError::MALFORMED_PARAMS);
+        let valid: Lenient<GetInfoRequest> = serde_json::from_str("{}").unwrap();
+        assert!(frames.accept(valid).is_ok());
+        assert_eq!(serde_json::to_string(&Lenient::Decoded(GetInfoRequest {})).unwrap(), "{}");
+
+        assert!(frames.record_malformed());
+        assert!(frames.record_malformed());
+        assert!(!frames.record_malformed(), "the connection should be closed on the third consecutive malformed frame");
+    }
+
+    #[test]
+    fn test_subscribe_scope_predating_blue_score_floor() {
+        // Subscribe payload of a client predating the blue score floor of the block added scope
+        let scope = Lenient::<Scope>::try_from_slice(&[0u8]).unwrap();
+        assert!(matches!(scope, Lenient::Decoded(Scope::BlockAdded(BlockAddedScope { min_blue_score: None }))), "{scope:?}");
+        let scope = Lenient::<Scope>::try_from_slice(&Scope::BlockAdded(BlockAddedScope::new(Some(7))).try_to_vec().unwrap()).unwrap();
+        assert!(matches!(scope, Lenient::Decoded(Scope::BlockAdded(BlockAddedScope { min_blue_score: Some(7) }))), "{scope:?}");
+    }
+}
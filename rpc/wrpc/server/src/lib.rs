@@ -1,8 +1,18 @@
 //! Kaspa wRPC Server (AsyncService) module
 pub mod collector;
+pub mod compression;
 pub mod connection;
 pub mod error;
+pub mod frames;
+pub mod limits;
+pub mod notifications;
+pub mod protocol;
 pub mod result;
 pub mod router;
 pub mod server;
 pub mod service;
+pub mod timeouts;
+pub mod websocket;
+
+#[cfg(test)]
+mod test_client;
@@ -0,0 +1,132 @@
+//! Restrictions on the requests a connection may send to the [`Router`](crate::router::Router).
+//!
+//! A node exposing its wRPC endpoint to untrusted clients may serve only part of the methods, a call to any other
+//! method failing with a [`RouterError::NotPermitted`], and bound the rate of the requests of each connection, the
+//! requests over the limit failing with a [`RouterError::RateLimited`] so a single client cannot monopolize the node.
+
+use crate::error::RouterError;
+use kaspa_rpc_core::api::ops::RpcApiOps;
+use std::{
+    collections::HashSet,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Methods the connections are permitted to call, all of them by default
+#[derive(Clone, Debug, Default)]
+pub struct MethodPermissions {
+    /// Methods permitted, all but the denied ones if `None`
+    allowed: Option<HashSet<RpcApiOps>>,
+    denied: HashSet<RpcApiOps>,
+}
+
+impl MethodPermissions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Permits only the `allowed` methods
+    pub fn allow_only(allowed: impl IntoIterator<Item = RpcApiOps>) -> Self {
+        Self { allowed: Some(allowed.into_iter().collect()), denied: HashSet::new() }
+    }
+
+    /// Denies `op`, whatever the methods permitted
+    pub fn deny(mut self, op: RpcApiOps) -> Self {
+        self.denied.insert(op);
+        self
+    }
+
+    pub fn is_permitted(&self, op: &RpcApiOps) -> bool {
+        !self.denied.contains(op) && self.allowed.as_ref().map_or(true, |allowed| allowed.contains(op))
+    }
+
+    /// Fails with [`RouterError::NotPermitted`] if `op` is not permitted
+    pub fn check(&self, op: &RpcApiOps) -> Result<(), RouterError> {
+        match self.is_permitted(op) {
+            true => Ok(()),
+            false => Err(RouterError::NotPermitted(format!("{op:?}"))),
+        }
+    }
+}
+
+/// Maximum rate of the requests of a connection: `requests` per `interval`, a burst of up to `requests` requests
+/// being accepted at once
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub interval: Duration,
+}
+
+impl RateLimit {
+    pub fn new(requests: u32, interval: Duration) -> Self {
+        Self { requests, interval }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+/// Token bucket applying a [`RateLimit`] to the requests of a connection
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        Self { limit, bucket: Mutex::new(Bucket { tokens: limit.requests as f64, refilled: Instant::now() }) }
+    }
+
+    /// Takes a token for a request, returning `false` if the limit is exceeded
+    pub fn try_acquire(&self) -> bool {
+        let mut bucket = self.bucket.lock().unwrap();
+        let now = Instant::now();
+        let capacity = self.limit.requests as f64;
+        let refill = now.duration_since(bucket.refilled).as_secs_f64() / self.limit.interval.as_secs_f64() * capacity;
+        bucket.tokens = (bucket.tokens + refill).min(capacity);
+        bucket.refilled = now;
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+
+    /// Fails with [`RouterError::RateLimited`] if a request calling `op` exceeds the limit
+    pub fn check(&self, op: &RpcApiOps) -> Result<(), RouterError> {
+        match self.try_acquire() {
+            true => Ok(()),
+            false => Err(RouterError::RateLimited(format!("{op:?}"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_method_permissions() {
+        let permissions = MethodPermissions::new().deny(RpcApiOps::Shutdown);
+        assert!(permissions.is_permitted(&RpcApiOps::GetInfo));
+        assert_eq!(permissions.check(&RpcApiOps::Shutdown).unwrap_err().code(), RouterError::NOT_PERMITTED);
+
+        let permissions = MethodPermissions::allow_only([RpcApiOps::GetInfo, RpcApiOps::Ping]).deny(RpcApiOps::Ping);
+        assert!(permissions.is_permitted(&RpcApiOps::GetInfo));
+        assert!(!permissions.is_permitted(&RpcApiOps::Ping), "a denied method should not be permitted");
+        assert!(!permissions.is_permitted(&RpcApiOps::GetBlock), "only the allowed methods should be permitted");
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::new(RateLimit::new(3, Duration::from_millis(300)));
+        assert!((0..3).all(|_| limiter.try_acquire()), "a burst up to the limit should be accepted");
+        assert_eq!(limiter.check(&RpcApiOps::Ping).unwrap_err().code(), RouterError::RATE_LIMITED);
+        std::thread::sleep(Duration::from_millis(150));
+        assert!(limiter.try_acquire(), "the tokens should refill over time");
+    }
+}
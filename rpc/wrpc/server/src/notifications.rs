@@ -0,0 +1,423 @@
+use kaspa_notify::events::EventType;
+use kaspa_rpc_core::{api::ops::RpcApiOps, Notification};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::{Debug, Display},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::Notify;
+use workflow_log::log_trace;
+use workflow_rpc::server::{prelude::Message, result::Result as WrpcResult};
+
+/// Serialization of the notifications into the messages sent to a connection, replacing the default
+/// wire format of its negotiated encoding (a compact JSON, an alternative binary format, a filtered projection...).
+pub trait NotificationSerializer: Send + Sync + Debug {
+    /// Name of the serializer. Connections using serializers of a same name share the serialized notifications.
+    fn name(&self) -> &str;
+
+    fn serialize(&self, op: RpcApiOps, notification: &Notification) -> WrpcResult<Message>;
+}
+
+pub type DynNotificationSerializer = Arc<dyn NotificationSerializer>;
+
+/// Returns `true` for the "latest value wins" events, for which only the newest notification
+/// is relevant to a client, making them eligible to throttling.
+///
+/// Notably, `BlockAdded` and `UtxosChanged` are never throttled.
+pub fn is_throttleable(event: EventType) -> bool {
+    matches!(
+        event,
+        EventType::SinkBlueScoreChanged
+            | EventType::VirtualDaaScoreChanged
+            | EventType::NewBlockTemplate
+            | EventType::MempoolSizeChanged
+    )
+}
+
+/// Minimum intervals between two notifications of the same event type sent to a connection.
+///
+/// An event type with no interval is never throttled.
+#[derive(Clone, Debug, Default)]
+pub struct NotificationThrottling {
+    intervals: HashMap<EventType, Duration>,
+}
+
+impl NotificationThrottling {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the minimum interval between two `event` notifications sent to a connection.
+    ///
+    /// Ignored for the events that cannot be throttled (see [`is_throttleable`]).
+    pub fn with_interval(mut self, event: EventType, interval: Duration) -> Self {
+        if is_throttleable(event) {
+            self.intervals.insert(event, interval);
+        }
+        self
+    }
+
+    pub fn interval(&self, event: EventType) -> Option<Duration> {
+        self.intervals.get(&event).copied()
+    }
+}
+
+#[derive(Debug)]
+struct ThrottleState<T> {
+    last_sent: Instant,
+    pending: Option<T>,
+    flush_scheduled: bool,
+}
+
+enum Admission<T> {
+    /// The message must be sent right away
+    Send(T),
+    /// The message is held back and must be flushed after the delay
+    Schedule(Duration),
+    /// The message replaces a held back one, already scheduled for a flush
+    Held,
+}
+
+/// Per connection throttling of the notification messages.
+///
+/// A message of a throttled event type arriving less than the interval after the previous one is held back.
+/// When the interval elapses, the latest held back message is sent, the others being dropped.
+#[derive(Debug)]
+pub struct NotificationThrottle<T> {
+    throttling: NotificationThrottling,
+    states: Mutex<HashMap<EventType, ThrottleState<T>>>,
+}
+
+impl<T> NotificationThrottle<T>
+where
+    T: Send + 'static,
+{
+    pub fn new(throttling: NotificationThrottling) -> Self {
+        Self { throttling, states: Mutex::new(HashMap::new()) }
+    }
+
+    /// Passes `message` to `deliver` now or, if `event` is throttled, once its interval has elapsed
+    /// unless a newer message supersedes it in the meantime.
+    ///
+    /// Only the errors of an immediate delivery are returned, the others being logged.
+    pub fn submit<F, E>(self: &Arc<Self>, event: EventType, message: T, deliver: F) -> Result<(), E>
+    where
+        F: Fn(T) -> Result<(), E> + Send + 'static,
+        E: Display,
+    {
+        match self.admit(event, message) {
+            Admission::Send(message) => deliver(message),
+            Admission::Schedule(delay) => {
+                let throttle = self.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(delay).await;
+                    if let Some(message) = throttle.flush(event) {
+                        if let Err(err) = deliver(message) {
+                            log_trace!("Error sending a throttled {:?} notification: {}", event, err);
+                        }
+                    }
+                });
+                Ok(())
+            }
+            Admission::Held => Ok(()),
+        }
+    }
+
+    fn admit(&self, event: EventType, message: T) -> Admission<T> {
+        let Some(interval) = self.throttling.interval(event) else { return Admission::Send(message) };
+        let now = Instant::now();
+        let mut states = self.states.lock().unwrap();
+        match states.get_mut(&event) {
+            Some(state) if now.duration_since(state.last_sent) < interval => {
+                state.pending = Some(message);
+                match state.flush_scheduled {
+                    true => Admission::Held,
+                    false => {
+                        state.flush_scheduled = true;
+                        Admission::Schedule(interval - now.duration_since(state.last_sent))
+                    }
+                }
+            }
+            Some(state) => {
+                // A pending message, if any, is superseded by this one
+                state.pending = None;
+                state.last_sent = now;
+                Admission::Send(message)
+            }
+            None => {
+                states.insert(event, ThrottleState { last_sent: now, pending: None, flush_scheduled: false });
+                Admission::Send(message)
+            }
+        }
+    }
+
+    fn flush(&self, event: EventType) -> Option<T> {
+        let mut states = self.states.lock().unwrap();
+        let state = states.get_mut(&event)?;
+        state.flush_scheduled = false;
+        let message = state.pending.take()?;
+        state.last_sent = Instant::now();
+        Some(message)
+    }
+}
+
+/// Default maximum number of notification messages waiting to be sent to a connection
+pub const DEFAULT_OUTBOUND_QUEUE_CAPACITY: usize = 1024;
+
+/// What to do with a notification message submitted to a full outbound queue
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SheddingPolicy {
+    /// Drop the oldest queued message of the same event type, or the submitted one if there is none
+    DropOldest,
+    /// Drop the submitted message
+    DropNewest,
+    /// Disconnect the client
+    Disconnect,
+}
+
+impl SheddingPolicy {
+    /// Returns the default policy of `event`: drop-oldest for the "latest value wins" events
+    /// (see [`is_throttleable`]) and disconnect for the others, which a client cannot afford to miss.
+    pub fn default_for(event: EventType) -> Self {
+        match is_throttleable(event) {
+            true => SheddingPolicy::DropOldest,
+            false => SheddingPolicy::Disconnect,
+        }
+    }
+}
+
+/// Bound and shedding policies of the per connection outbound notification queues
+#[derive(Clone, Debug)]
+pub struct OutboundQueueConfig {
+    capacity: usize,
+    policies: HashMap<EventType, SheddingPolicy>,
+}
+
+impl OutboundQueueConfig {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, policies: HashMap::new() }
+    }
+
+    /// Overrides the default shedding policy of `event`
+    pub fn with_policy(mut self, event: EventType, policy: SheddingPolicy) -> Self {
+        self.policies.insert(event, policy);
+        self
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn policy(&self, event: EventType) -> SheddingPolicy {
+        self.policies.get(&event).copied().unwrap_or_else(|| SheddingPolicy::default_for(event))
+    }
+}
+
+impl Default for OutboundQueueConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_OUTBOUND_QUEUE_CAPACITY)
+    }
+}
+
+/// Outcome of [`OutboundQueue::push`]
+#[derive(Debug, PartialEq, Eq)]
+pub enum Enqueued {
+    /// The message is queued
+    Queued,
+    /// The queue is full and a message, either the submitted one or an older one, was dropped
+    Shed,
+    /// The queue is full and the client must be disconnected
+    Overflow,
+}
+
+#[derive(Debug)]
+struct OutboundState<T> {
+    messages: VecDeque<(EventType, T)>,
+    closed: bool,
+}
+
+/// Bounded queue of the notification messages waiting to be sent to a connection.
+///
+/// When full, a submitted message is handled according to the [`SheddingPolicy`] of its event type,
+/// preventing a client that stops reading from making the server memory grow unbounded.
+#[derive(Debug)]
+pub struct OutboundQueue<T> {
+    config: OutboundQueueConfig,
+    state: Mutex<OutboundState<T>>,
+    notify: Notify,
+    dropped: AtomicU64,
+}
+
+impl<T> OutboundQueue<T> {
+    pub fn new(config: OutboundQueueConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(OutboundState { messages: VecDeque::new(), closed: false }),
+            notify: Notify::new(),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    pub fn push(&self, event: EventType, message: T) -> Enqueued {
+        let mut state = self.state.lock().unwrap();
+        if state.closed {
+            return Enqueued::Shed;
+        }
+        if state.messages.len() >= self.config.capacity() {
+            match self.config.policy(event) {
+                SheddingPolicy::DropOldest => {
+                    if let Some(position) = state.messages.iter().position(|(queued, _)| *queued == event) {
+                        state.messages.remove(position);
+                        state.messages.push_back((event, message));
+                    }
+                }
+                SheddingPolicy::DropNewest => {}
+                SheddingPolicy::Disconnect => return Enqueued::Overflow,
+            }
+            self.dropped.fetch_add(1, Ordering::SeqCst);
+            return Enqueued::Shed;
+        }
+        state.messages.push_back((event, message));
+        drop(state);
+        self.notify.notify_one();
+        Enqueued::Queued
+    }
+
+    /// Waits for the next message to send. Returns `None` once the queue is closed.
+    pub async fn pop(&self) -> Option<T> {
+        loop {
+            let notified = self.notify.notified();
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.closed {
+                    return None;
+                }
+                if let Some((_, message)) = state.messages.pop_front() {
+                    return Some(message);
+                }
+            }
+            notified.await;
+        }
+    }
+
+    /// Closes the queue, dropping the pending messages
+    pub fn close(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.closed = true;
+        state.messages.clear();
+        drop(state);
+        self.notify.notify_waiters();
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of messages shed so far
+    pub fn dropped_count(&self) -> u64 {
+        self.dropped.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_millis(50);
+    const COUNT: u64 = 40;
+
+    /// Submits [`COUNT`] `event` messages every 5ms and returns the delivered ones
+    async fn submit_updates(throttle: &Arc<NotificationThrottle<u64>>, event: EventType) -> Vec<u64> {
+        let delivered = Arc::new(Mutex::new(vec![]));
+        for i in 0..COUNT {
+            let delivered = delivered.clone();
+            throttle
+                .submit(event, i, move |x| {
+                    delivered.lock().unwrap().push(x);
+                    Ok::<_, String>(())
+                })
+                .unwrap();
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        // Let the last flush happen
+        tokio::time::sleep(INTERVAL * 2).await;
+        let delivered = delivered.lock().unwrap().clone();
+        delivered
+    }
+
+    #[tokio::test]
+    async fn test_notification_throttling() {
+        let throttling = NotificationThrottling::new()
+            .with_interval(EventType::VirtualDaaScoreChanged, INTERVAL)
+            .with_interval(EventType::BlockAdded, INTERVAL)
+            .with_interval(EventType::UtxosChanged, INTERVAL);
+        assert_eq!(throttling.interval(EventType::VirtualDaaScoreChanged), Some(INTERVAL));
+        assert_eq!(throttling.interval(EventType::BlockAdded), None, "BlockAdded must be exempt");
+        assert_eq!(throttling.interval(EventType::UtxosChanged), None, "UtxosChanged must be exempt");
+        let throttle = Arc::new(NotificationThrottle::new(throttling));
+
+        let delivered = submit_updates(&throttle, EventType::VirtualDaaScoreChanged).await;
+        assert!(delivered.len() < COUNT as usize / 2, "score updates should be throttled, got {delivered:?}");
+        assert!(delivered.windows(2).all(|x| x[0] < x[1]), "throttled updates should keep their order, got {delivered:?}");
+        assert_eq!(delivered.first(), Some(&0), "the first update should be sent right away");
+        assert_eq!(delivered.last(), Some(&(COUNT - 1)), "the latest update should be eventually sent");
+
+        let delivered = submit_updates(&throttle, EventType::BlockAdded).await;
+        assert_eq!(delivered, (0..COUNT).collect::<Vec<_>>(), "BlockAdded notifications must all be sent");
+    }
+
+    /// Fills a queue that no client reads with `COUNT` messages alternating `first` and `second` events
+    fn fill_unread_queue(config: OutboundQueueConfig, first: EventType, second: EventType) -> (OutboundQueue<u64>, Vec<Enqueued>) {
+        let queue = OutboundQueue::new(config);
+        let outcomes = (0..COUNT).map(|i| queue.push(if i % 2 == 0 { first } else { second }, i)).collect();
+        (queue, outcomes)
+    }
+
+    async fn drain(queue: &OutboundQueue<u64>) -> Vec<u64> {
+        let mut messages = vec![];
+        while !queue.is_empty() {
+            messages.push(queue.pop().await.unwrap());
+        }
+        messages
+    }
+
+    #[tokio::test]
+    async fn test_outbound_queue_shedding() {
+        const CAPACITY: usize = 4;
+        let config = OutboundQueueConfig::new(CAPACITY);
+        assert_eq!(config.policy(EventType::VirtualDaaScoreChanged), SheddingPolicy::DropOldest);
+        assert_eq!(config.policy(EventType::BlockAdded), SheddingPolicy::Disconnect);
+        assert_eq!(config.policy(EventType::UtxosChanged), SheddingPolicy::Disconnect);
+
+        // Drop oldest: the latest updates of each event are kept
+        let (queue, outcomes) = fill_unread_queue(config.clone(), EventType::VirtualDaaScoreChanged, EventType::SinkBlueScoreChanged);
+        assert!(outcomes[..CAPACITY].iter().all(|x| *x == Enqueued::Queued));
+        assert!(outcomes[CAPACITY..].iter().all(|x| *x == Enqueued::Shed));
+        assert_eq!(queue.dropped_count(), COUNT - CAPACITY as u64);
+        assert_eq!(drain(&queue).await, vec![COUNT - 4, COUNT - 3, COUNT - 2, COUNT - 1]);
+
+        // Drop newest: the first messages are kept
+        let config = config.with_policy(EventType::VirtualDaaScoreChanged, SheddingPolicy::DropNewest);
+        let (queue, _) = fill_unread_queue(config.clone(), EventType::VirtualDaaScoreChanged, EventType::VirtualDaaScoreChanged);
+        assert_eq!(queue.dropped_count(), COUNT - CAPACITY as u64);
+        assert_eq!(drain(&queue).await, vec![0, 1, 2, 3]);
+
+        // Disconnect: the overflow is reported and nothing is dropped
+        let (queue, outcomes) = fill_unread_queue(config, EventType::BlockAdded, EventType::UtxosChanged);
+        assert_eq!(outcomes[CAPACITY], Enqueued::Overflow);
+        assert_eq!(queue.dropped_count(), 0);
+        assert_eq!(queue.len(), CAPACITY);
+
+        // A closed queue stops its reader
+        queue.close();
+        assert_eq!(queue.pop().await, None);
+    }
+}
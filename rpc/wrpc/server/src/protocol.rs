@@ -0,0 +1,275 @@
+//! Wire format of the wRPC messages, the one spoken by the `workflow-rpc` clients.
+//!
+//! A Borsh message is a binary frame holding a header followed by the Borsh payload:
+//! - the header of a request is the optional id of the request and the op of the method called,
+//! - the header of a server message is the optional id of the request it answers, its [`ServerMessageKind`] and,
+//!   for a notification, its op.
+//!
+//! A JSON message is a text frame holding an object: `{"id", "method", "params"}` for a request, `{"id", "kind", "params"}`
+//! or `{"id", "kind", "error"}` for a response and `{"kind", "method", "params"}` for a notification, `kind` being the
+//! [`ServerMessageKind`] of a server message.
+//!
+//! Every response carries the id of the request it answers, a request sent with no id getting one assigned by the
+//! server (see [`SERVER_REQUEST_ID_FLAG`]), while a notification carries no id.
+//!
+//! An error response carries the [wire message](RouterError::to_wire_message) of the error, a JSON one carrying its code
+//! as well.
+
+use crate::error::RouterError;
+use borsh::{BorshDeserialize, BorshSerialize};
+use kaspa_rpc_core::api::ops::RpcApiOps;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tungstenite::Message;
+use workflow_rpc::server::Encoding;
+
+/// Flag set on the ids the server assigns to the requests sent with no id, telling them apart from the ids of the client
+pub const SERVER_REQUEST_ID_FLAG: u64 = 1 << 63;
+
+/// Kind of a message sent by the server
+#[derive(Clone, Copy, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServerMessageKind {
+    Success,
+    Error,
+    Notification,
+}
+
+/// Id of a request, echoed by its response.
+///
+/// Borsh ids are numbers, JSON ones being either numbers or strings.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RequestId {
+    Number(u64),
+    Text(String),
+}
+
+impl RequestId {
+    fn from_json(value: &Value) -> Option<Self> {
+        match value {
+            Value::Number(number) => number.as_u64().map(RequestId::Number),
+            Value::String(text) => Some(RequestId::Text(text.clone())),
+            _ => None,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        match self {
+            RequestId::Number(number) => Value::from(*number),
+            RequestId::Text(text) => Value::from(text.as_str()),
+        }
+    }
+
+    fn to_borsh(&self) -> Option<u64> {
+        match self {
+            RequestId::Number(number) => Some(*number),
+            RequestId::Text(_) => None,
+        }
+    }
+}
+
+/// Params of a request, or payload of a response or a notification, in the encoding of a connection
+#[derive(Clone, Debug, PartialEq)]
+pub enum Params {
+    Borsh(Vec<u8>),
+    Json(Value),
+}
+
+impl Params {
+    pub fn encoding(&self) -> Encoding {
+        match self {
+            Params::Borsh(_) => Encoding::Borsh,
+            Params::Json(_) => Encoding::SerdeJson,
+        }
+    }
+
+    pub fn encode<T: BorshSerialize + Serialize>(encoding: Encoding, value: &T) -> Result<Self, String> {
+        match encoding {
+            Encoding::Borsh => value.try_to_vec().map(Params::Borsh).map_err(|err| err.to_string()),
+            Encoding::SerdeJson => serde_json::to_value(value).map(Params::Json).map_err(|err| err.to_string()),
+        }
+    }
+
+    pub fn decode<T: BorshDeserialize + DeserializeOwned>(self) -> Result<T, String> {
+        match self {
+            Params::Borsh(bytes) => T::try_from_slice(&bytes).map_err(|err| err.to_string()),
+            Params::Json(value) => serde_json::from_value(value).map_err(|err| err.to_string()),
+        }
+    }
+}
+
+/// A request sent by a client
+#[derive(Debug)]
+pub struct Request {
+    pub id: Option<RequestId>,
+    /// Op of the method called, or the name of the method if it is unknown
+    pub op: Result<RpcApiOps, String>,
+    pub params: Params,
+}
+
+/// Decodes a request received by a connection using `encoding`.
+///
+/// Fails with a [`RouterError::ParseError`] if the frame is no request. The id of the request, when it could be read,
+/// is returned along with the error, so the error can be answered.
+pub fn decode_request(encoding: Encoding, message: Message) -> Result<Request, (Option<RequestId>, RouterError)> {
+    match encoding {
+        Encoding::Borsh => decode_borsh_request(message),
+        Encoding::SerdeJson => decode_json_request(message),
+    }
+}
+
+fn parse_error<E: ToString>(err: E) -> RouterError {
+    RouterError::ParseError(err.to_string())
+}
+
+fn decode_borsh_request(message: Message) -> Result<Request, (Option<RequestId>, RouterError)> {
+    let Message::Binary(data) = message else {
+        return Err((None, RouterError::ParseError("expected a binary frame".to_string())));
+    };
+    let mut buf = data.as_slice();
+    let id = Option::<u64>::deserialize(&mut buf).map_err(|err| (None, parse_error(err)))?.map(RequestId::Number);
+    let tag = u8::deserialize(&mut buf).map_err(|err| (id.clone(), parse_error(err)))?;
+    let op = RpcApiOps::try_from_slice(&[tag]).map_err(|_| format!("#{tag}"));
+    Ok(Request { id, op, params: Params::Borsh(buf.to_vec()) })
+}
+
+fn decode_json_request(message: Message) -> Result<Request, (Option<RequestId>, RouterError)> {
+    let text = match message {
+        Message::Text(text) => text,
+        Message::Binary(data) => String::from_utf8(data).map_err(|err| (None, parse_error(err)))?,
+        _ => return Err((None, RouterError::ParseError("expected a text frame".to_string()))),
+    };
+    let Value::Object(mut object) = serde_json::from_str::<Value>(&text).map_err(|err| (None, parse_error(err)))? else {
+        return Err((None, RouterError::ParseError("expected a JSON object".to_string())));
+    };
+    let id = match object.get("id") {
+        None | Some(Value::Null) => None,
+        Some(id) => Some(RequestId::from_json(id).ok_or_else(|| (None, RouterError::ParseError(format!("invalid request id {id}"))))?),
+    };
+    let method = match object.remove("method") {
+        Some(Value::String(method)) => method,
+        _ => return Err((id, RouterError::ParseError("missing method".to_string()))),
+    };
+    let op = serde_json::from_value::<RpcApiOps>(Value::String(method.clone())).map_err(|_| method);
+    // A method with no params is called with an empty request
+    let params = object.remove("params").filter(|params| !params.is_null()).unwrap_or_else(|| Value::Object(Map::new()));
+    Ok(Request { id, op, params: Params::Json(params) })
+}
+
+/// Encodes the response to the request `id`
+pub fn encode_response(encoding: Encoding, id: &RequestId, result: Result<Params, RouterError>) -> Result<Message, String> {
+    match encoding {
+        Encoding::Borsh => {
+            let id = id.to_borsh();
+            let (kind, payload) = match result {
+                Ok(Params::Borsh(payload)) => (ServerMessageKind::Success, payload),
+                Ok(Params::Json(_)) => return Err("a Borsh response cannot carry a JSON payload".to_string()),
+                Err(err) => (ServerMessageKind::Error, err.to_wire_message().try_to_vec().map_err(|err| err.to_string())?),
+            };
+            borsh_server_message(id, kind, None, payload)
+        }
+        Encoding::SerdeJson => {
+            let mut object = Map::new();
+            object.insert("id".to_string(), id.to_json());
+            match result {
+                Ok(Params::Json(payload)) => {
+                    object.insert("kind".to_string(), json!(ServerMessageKind::Success));
+                    object.insert("params".to_string(), payload);
+                }
+                Ok(Params::Borsh(_)) => return Err("a JSON response cannot carry a Borsh payload".to_string()),
+                Err(err) => {
+                    object.insert("kind".to_string(), json!(ServerMessageKind::Error));
+                    object.insert("error".to_string(), json!({ "code": err.code(), "message": err.to_wire_message() }));
+                }
+            };
+            serde_json::to_string(&object).map(Message::Text).map_err(|err| err.to_string())
+        }
+    }
+}
+
+/// Encodes a notification of `op` carrying `payload`
+pub fn encode_notification(op: RpcApiOps, payload: Params) -> Result<Message, String> {
+    match payload {
+        Params::Borsh(payload) => borsh_server_message(None, ServerMessageKind::Notification, Some(op), payload),
+        Params::Json(payload) => {
+            let notification = json!({ "kind": ServerMessageKind::Notification, "method": op, "params": payload });
+            serde_json::to_string(&notification).map(Message::Text).map_err(|err| err.to_string())
+        }
+    }
+}
+
+fn borsh_server_message(id: Option<u64>, kind: ServerMessageKind, op: Option<RpcApiOps>, payload: Vec<u8>) -> Result<Message, String> {
+    let mut data = (id, kind, op).try_to_vec().map_err(|err| err.to_string())?;
+    data.extend_from_slice(&payload);
+    Ok(Message::Binary(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kaspa_rpc_core::{GetBlockRequest, GetInfoRequest, RpcHash};
+
+    #[test]
+    fn test_borsh_request() {
+        let params = GetBlockRequest { hash: RpcHash::from_u64_word(3), include_transactions: true }.try_to_vec().unwrap();
+        let mut data = (Some(9u64), RpcApiOps::GetBlock).try_to_vec().unwrap();
+        data.extend_from_slice(&params);
+        let request = decode_request(Encoding::Borsh, Message::Binary(data)).unwrap();
+        assert_eq!(request.id, Some(RequestId::Number(9)));
+        assert_eq!(request.op, Ok(RpcApiOps::GetBlock));
+        let decoded: GetBlockRequest = request.params.decode().unwrap();
+        assert_eq!(decoded.hash, RpcHash::from_u64_word(3));
+
+        let request = decode_request(Encoding::Borsh, Message::Binary(vec![1, 9, 0, 0, 0, 0, 0, 0, 0, 0xee])).unwrap();
+        assert_eq!(request.op, Err("#238".to_string()), "an unknown op should be reported as such");
+        let (id, err) = decode_request(Encoding::Borsh, Message::Binary(vec![1, 9, 0, 0, 0, 0, 0, 0, 0])).unwrap_err();
+        assert_eq!((id, err.code()), (Some(RequestId::Number(9)), RouterError::PARSE_ERROR));
+        assert!(decode_request(Encoding::Borsh, Message::Text("{}".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_json_request() {
+        let request = r#"{"id":"a1","method":"getInfo","params":{}}"#.to_string();
+        let request = decode_request(Encoding::SerdeJson, Message::Text(request)).unwrap();
+        assert_eq!(request.id, Some(RequestId::Text("a1".to_string())));
+        assert_eq!(request.op, Ok(RpcApiOps::GetInfo));
+        assert!(request.params.decode::<GetInfoRequest>().is_ok());
+
+        // A request with no params gets an empty request
+        let request = decode_request(Encoding::SerdeJson, Message::Text(r#"{"id":4,"method":"ping"}"#.to_string())).unwrap();
+        assert_eq!((request.id, request.op), (Some(RequestId::Number(4)), Ok(RpcApiOps::Ping)));
+        assert_eq!(request.params, Params::Json(Value::Object(Map::new())));
+
+        let request = decode_request(Encoding::SerdeJson, Message::Text(r#"{"id":5,"method":"getFoo"}"#.to_string())).unwrap();
+        assert_eq!(request.op, Err("getFoo".to_string()));
+        let (id, err) = decode_request(Encoding::SerdeJson, Message::Text(r#"{"id":6}"#.to_string())).unwrap_err();
+        assert_eq!((id, err.code()), (Some(RequestId::Number(6)), RouterError::PARSE_ERROR));
+        let (id, _) = decode_request(Encoding::SerdeJson, Message::Text("not json".to_string())).unwrap_err();
+        assert_eq!(id, None);
+    }
+
+    #[test]
+    fn test_responses() {
+        let id = RequestId::Number(7);
+        let Message::Binary(data) = encode_response(Encoding::Borsh, &id, Ok(Params::Borsh(vec![1, 2]))).unwrap() else {
+            panic!("a Borsh response should be a binary frame")
+        };
+        let mut buf = data.as_slice();
+        let header = <(Option<u64>, ServerMessageKind, Option<RpcApiOps>)>::deserialize(&mut buf).unwrap();
+        assert_eq!(header, (Some(7), ServerMessageKind::Success, None));
+        assert_eq!(buf, &[1, 2]);
+
+        let err = RouterError::Internal("boom".to_string());
+        let Message::Text(text) = encode_response(Encoding::SerdeJson, &id, Err(err.clone())).unwrap() else {
+            panic!("a JSON response should be a text frame")
+        };
+        let value: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(value, json!({ "id": 7, "kind": "error", "error": { "code": err.code(), "message": err.to_wire_message() } }));
+
+        let Message::Text(text) = encode_notification(RpcApiOps::BlockAddedNotification, Params::Json(json!({}))).unwrap() else {
+            panic!("a JSON notification should be a text frame")
+        };
+        let expected = json!({ "kind": "notification", "method": "blockAddedNotification", "params": {} });
+        assert_eq!(serde_json::from_str::<Value>(&text).unwrap(), expected);
+    }
+}
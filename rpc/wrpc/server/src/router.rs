@@ -1,17 +1,79 @@
-use crate::{connection::*, server::*};
+use crate::{
+    connection::*,
+    error::RouterError,
+    frames::Lenient,
+    protocol::{decode_request, encode_response, Params},
+    server::*,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
 use kaspa_notify::scope::Scope;
 use kaspa_rpc_core::{api::ops::RpcApiOps, prelude::*};
 use kaspa_rpc_macros::build_wrpc_server_interface;
-use std::sync::Arc;
-use workflow_rpc::server::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{collections::HashMap, future::Future, pin::Pin, sync::Arc};
+use tungstenite::Message;
+use workflow_log::log_trace;
+
+type MethodFuture = Pin<Box<dyn Future<Output = Result<Params, RouterError>> + Send>>;
+type MethodHandler = dyn Fn(Server, Connection, Params) -> MethodFuture + Send + Sync;
+
+/// An RPC method of the [`Interface`], decoding the params of a request and encoding the response of its handler
+/// in the encoding of the params.
+pub struct Method {
+    handler: Box<MethodHandler>,
+}
+
+impl Method {
+    pub fn new<Req, Resp, F, Fut>(handler: F) -> Self
+    where
+        Req: BorshDeserialize + DeserializeOwned + Send + 'static,
+        Resp: BorshSerialize + Serialize + Send + 'static,
+        F: Fn(Server, Connection, Req) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Resp, RouterError>> + Send + 'static,
+    {
+        let handler = move |server: Server, connection: Connection, params: Params| -> MethodFuture {
+            let encoding = params.encoding();
+            let call = params.decode::<Req>().map(|request| handler(server, connection, request));
+            Box::pin(async move {
+                let response = call.map_err(RouterError::MalformedParams)?.await?;
+                Params::encode(encoding, &response).map_err(RouterError::Internal)
+            })
+        };
+        Self { handler: Box::new(handler) }
+    }
+}
+
+/// The RPC methods served to the connections, by op
+pub struct Interface {
+    server_ctx: Server,
+    methods: HashMap<RpcApiOps, Method>,
+}
+
+impl Interface {
+    pub fn new(server_ctx: Server) -> Self {
+        Self { server_ctx, methods: HashMap::new() }
+    }
+
+    pub fn method(&mut self, op: RpcApiOps, method: Method) {
+        self.methods.insert(op, method);
+    }
+
+    /// Calls the method `op` with `params`, the response being encoded as the params
+    pub async fn call(&self, op: &RpcApiOps, connection: Connection, params: Params) -> Result<Params, RouterError> {
+        let method = self.methods.get(op).ok_or_else(|| RouterError::UnknownMethod(format!("{op:?}")))?;
+        (method.handler)(self.server_ctx.clone(), connection, params).await
+    }
+}
 
 /// A wrapper that creates an [`Interface`] instance and initializes
-/// RPC methods and notifications against this interface. The interface
-/// is later given to the RpcServer.  This wrapper exists to allow
-/// a single initialization location for both the Kaspad Server and
-/// the GRPC Proxy.
+/// RPC methods against this interface, then routes the requests of
+/// the connections to it. This wrapper exists to allow a single
+/// initialization location for both the Kaspad Server and the GRPC Proxy.
+///
+/// The request payloads are decoded as [`Lenient`] values, each handler answering a malformed payload with a malformed
+/// params error through [`Connection::accept_request`] instead of failing the request as a whole.
 pub struct Router {
-    pub interface: Arc<Interface<Server, Connection, RpcApiOps>>,
+    pub interface: Arc<Interface>,
     pub server_context: Server,
 }
 
@@ -69,40 +131,162 @@ impl Router {
 
         interface.method(
             RpcApiOps::Subscribe,
-            workflow_rpc::server::Method::new(move |manager: Server, connection: Connection, scope: Scope| {
-                Box::pin(async move {
-                    let notifier = manager.notifier();
-                    let id = if let Some(listener_id) = connection.listener_id() {
-                        listener_id
-                    } else {
-                        let id = notifier.register_new_listener(connection.clone());
-                        connection.register_notification_listener(id);
-                        id
-                    };
-                    workflow_log::log_trace!("notification subscribe[0x{id:x}] {scope:?}");
-                    notifier.try_start_notify(id, scope).map_err(|err| err.to_string())?;
-                    Ok(SubscribeResponse::new(id))
-                })
+            Method::new(move |manager: Server, connection: Connection, scope: Lenient<Scope>| async move {
+                let scope = connection.accept_request(scope)?;
+                let notifier = manager.notifier();
+                let id = if let Some(listener_id) = connection.listener_id() {
+                    listener_id
+                } else {
+                    let id = notifier.register_new_listener(connection.clone());
+                    connection.register_notification_listener(id);
+                    id
+                };
+                log_trace!("notification subscribe[0x{id:x}] {scope:?}");
+                notifier.try_start_notify(id, scope).map_err(|err| RouterError::Internal(err.to_string()))?;
+                Ok::<_, RouterError>(SubscribeResponse::new(id))
             }),
         );
 
         interface.method(
             RpcApiOps::Unsubscribe,
-            workflow_rpc::server::Method::new(move |manager: Server, connection: Connection, scope: Scope| {
-                Box::pin(async move {
-                    if let Some(listener_id) = connection.listener_id() {
-                        workflow_log::log_trace!("notification unsubscribe[0x{listener_id:x}] {scope:?}");
-                        manager.notifier().try_stop_notify(listener_id, scope).unwrap_or_else(|err| {
-                            format!("wRPC -> RpcApiOps::Unsubscribe error calling try_stop_notify(): {err}");
-                        });
-                    } else {
-                        workflow_log::log_trace!("notification unsubscribe[N/A] {scope:?}");
-                    }
-                    Ok(UnsubscribeResponse {})
-                })
+            Method::new(move |manager: Server, connection: Connection, scope: Lenient<Scope>| async move {
+                let scope = connection.accept_request(scope)?;
+                if let Some(listener_id) = connection.listener_id() {
+                    log_trace!("notification unsubscribe[0x{listener_id:x}] {scope:?}");
+                    manager.notifier().try_stop_notify(listener_id, scope).unwrap_or_else(|err| {
+                        format!("wRPC -> RpcApiOps::Unsubscribe error calling try_stop_notify(): {err}");
+                    });
+                } else {
+                    log_trace!("notification unsubscribe[N/A] {scope:?}");
+                }
+                Ok::<_, RouterError>(UnsubscribeResponse {})
             }),
         );
 
         Router { interface: Arc::new(interface), server_context }
     }
+
+    /// Answers a request frame received by `connection`, in the encoding of the connection.
+    ///
+    /// The response, or the error, carries the id of the request, a request sent with no id, or whose id could not
+    /// be read, getting one assigned by the connection (see [`Connection::assign_request_id`]).
+    pub async fn route(&self, connection: Connection, message: Message) {
+        let (id, result) = match decode_request(connection.encoding(), message) {
+            Ok(request) => (request.id, self.call(&connection, request.op, request.params).await),
+            Err((id, err)) => (id, Err(err)),
+        };
+        let id = id.unwrap_or_else(|| connection.assign_request_id());
+        match encode_response(connection.encoding(), &id, result) {
+            Ok(response) => connection.post(response).unwrap_or_else(|err| {
+                log_trace!("[{}] Error sending a response to {}: {}", connection.label(), connection.peer(), err);
+            }),
+            Err(err) => log_trace!("[{}] Error encoding a response to {}: {}", connection.label(), connection.peer(), err),
+        }
+    }
+
+    /// Calls the method `op`, the request being tracked as in flight until answered so a shutdown lets it complete,
+    /// see [`Server::enter_request`]. The request is subject to the rate limit of the connection, then to the
+    /// permissions of the server.
+    async fn call(&self, connection: &Connection, op: Result<RpcApiOps, String>, params: Params) -> Result<Params, RouterError> {
+        let _in_flight = self.server_context.enter_request()?;
+        let op = op.map_err(RouterError::UnknownMethod)?;
+        connection.check_rate_limit(&op)?;
+        self.server_context.check_permission(&op)?;
+        self.interface.call(&op, connection.clone(), params).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::RouterError,
+        limits::{MethodPermissions, RateLimit},
+        protocol::SERVER_REQUEST_ID_FLAG,
+        service::Options,
+        test_client::{test_info, RawClient, TestNode},
+    };
+    use kaspa_rpc_core::{api::ops::RpcApiOps, GetInfoRequest, GetInfoResponse};
+    use serde_json::{json, Value};
+    use std::time::Duration;
+    use tungstenite::Message;
+    use workflow_rpc::server::Encoding;
+
+    async fn recv_json(client: &mut RawClient) -> Value {
+        let Some(Message::Text(text)) = client.recv().await else { panic!("a JSON message should be received") };
+        serde_json::from_str(&text).unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_request_id_correlation() {
+        let node = TestNode::start(Encoding::SerdeJson, Options::default()).await;
+        // A slow GetInfo, answered after the Ping sent after it
+        node.rpc.on(RpcApiOps::GetInfo, |_: GetInfoRequest| {
+            std::thread::sleep(Duration::from_millis(200));
+            Ok::<GetInfoResponse, _>(test_info())
+        });
+        node.rpc.respond_with(RpcApiOps::Ping, kaspa_rpc_core::PingResponse {});
+
+        let mut client = RawClient::connect(node.address, "/", &[]).await.unwrap();
+        let welcome = recv_json(&mut client).await;
+        assert_eq!(welcome["kind"], "notification", "a notification should be marked as such");
+        assert!(welcome.get("id").is_none(), "a notification should carry no id");
+
+        client.send(Message::Text(r#"{"id":"info-1","method":"getInfo","params":{}}"#.to_string())).await;
+        client.send(Message::Text(r#"{"id":42,"method":"ping","params":{}}"#.to_string())).await;
+        let (first, second) = (recv_json(&mut client).await, recv_json(&mut client).await);
+        assert_eq!((&first["id"], &first["kind"]), (&json!(42), &json!("success")), "the ping should be answered first");
+        assert!(first["params"].get("p2pId").is_none());
+        assert_eq!((&second["id"], &second["kind"]), (&json!("info-1"), &json!("success")));
+        assert_eq!(second["params"]["p2pId"], test_info().p2p_id);
+
+        // Errors echo the id as well
+        client.send(Message::Text(r#"{"id":7}"#.to_string())).await;
+        let error = recv_json(&mut client).await;
+        assert_eq!((&error["id"], &error["kind"]), (&json!(7), &json!("error")));
+
+        // A request sent with no id gets one assigned by the server
+        client.send(Message::Text(r#"{"method":"ping"}"#.to_string())).await;
+        let response = recv_json(&mut client).await;
+        let id = response["id"].as_u64().expect("the server should assign a numeric id");
+        assert_ne!(id & SERVER_REQUEST_ID_FLAG, 0, "an assigned id should be flagged as such");
+        assert_eq!(response["kind"], "success");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_router_errors() {
+        let options = Options {
+            method_permissions: Some(MethodPermissions::new().deny(RpcApiOps::Shutdown)),
+            rate_limit: Some(RateLimit::new(3, Duration::from_secs(60))),
+            ..Options::default()
+        };
+        let node = TestNode::start(Encoding::SerdeJson, options).await;
+        node.rpc.respond_with(RpcApiOps::Ping, kaspa_rpc_core::PingResponse {});
+
+        let cases = [
+            (r#"{"id":1,"method":"getFoo","params":{}}"#, RouterError::UNKNOWN_METHOD),
+            (r#"{"id":2,"method":"getBlock","params":{"hash":42}}"#, RouterError::MALFORMED_PARAMS),
+            (r#"{"id":3,"method":"shutdown","params":{}}"#, RouterError::NOT_PERMITTED),
+            (r#"{"id":4,"method":"ping"#, RouterError::PARSE_ERROR),
+        ];
+        for (request, code) in cases {
+            // Each request is sent on a connection of its own, so the rate limit is not reached
+            let mut client = RawClient::connect(node.address, "/", &[]).await.unwrap();
+            recv_json(&mut client).await;
+            client.send(Message::Text(request.to_string())).await;
+            let response = recv_json(&mut client).await;
+            assert_eq!(response["error"]["code"], code, "unexpected response {response} to {request}");
+        }
+
+        // The requests of a connection over its rate limit are refused
+        let mut client = RawClient::connect(node.address, "/", &[]).await.unwrap();
+        recv_json(&mut client).await;
+        for id in 0..4 {
+            client.send(Message::Text(format!(r#"{{"id":{id},"method":"ping","params":{{}}}}"#))).await;
+            let response = recv_json(&mut client).await;
+            match id {
+                3 => assert_eq!(response["error"]["code"], RouterError::RATE_LIMITED, "unexpected response {response}"),
+                _ => assert_eq!(response["kind"], "success", "unexpected response {response}"),
+            }
+        }
+    }
 }
@@ -1,30 +1,115 @@
 use crate::{
     collector::{WrpcServiceCollector, WrpcServiceConverter},
-    connection::Connection,
+    compression::{negotiate_compression, PerMessageDeflate},
+    connection::{advertise_protocol_versions, negotiate_protocol, Connection, NegotiatedProtocol},
+    error::RouterError,
     result::Result,
+    router::Router,
     service::Options,
+    websocket::{accept_response, close_message, read_upgrade_request, rejection_response, FrameReader, FrameWriter, Role},
 };
 use kaspa_notify::{
+    connection::Connection as ConnectionT,
     events::EVENT_TYPE_ARRAY,
     listener::ListenerId,
     notifier::Notifier,
     subscriber::{DynSubscriptionManager, Subscriber},
 };
-use kaspa_rpc_core::{api::rpc::RpcApi, notify::connection::ChannelConnection, Notification};
-use kaspa_utils::channel::Channel;
+use kaspa_rpc_core::{
+    api::{ops::RpcApiOps, rpc::RpcApi},
+    notify::connection::ChannelConnection,
+    Notification,
+};
+use kaspa_utils::{channel::Channel, triggers::SingleTrigger};
 use std::{
     collections::HashMap,
+    future::Future,
+    net::SocketAddr,
     sync::{
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
         Arc, Mutex,
     },
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, Notify},
 };
+use tungstenite::{protocol::frame::coding::CloseCode, Message};
 use workflow_log::*;
-use workflow_rpc::server::prelude::*;
+use workflow_rpc::server::Encoding;
 
 pub type DynRpcService = Arc<dyn RpcApi<ChannelConnection>>;
 pub type NotificationChannel = Channel<Notification>;
 
+/// Close reason sent to the clients when the server is shutting down
+pub const SHUTDOWN_REASON: &str = "server shutting down";
+
+/// Maximum time given to a client to send its upgrade request once connected
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Book-keeping of the RPC requests currently being processed, allowing
+/// a shutdown to wait for them to complete.
+#[derive(Debug, Default)]
+pub struct InFlightRequests {
+    count: AtomicUsize,
+    draining: AtomicBool,
+    notify: Notify,
+}
+
+impl InFlightRequests {
+    /// Register a new request. Returns `None` if the server is draining.
+    pub fn enter(self: &Arc<Self>) -> Option<InFlightGuard> {
+        if self.is_draining() {
+            return None;
+        }
+        self.count.fetch_add(1, Ordering::SeqCst);
+        Some(InFlightGuard { requests: self.clone() })
+    }
+
+    pub fn len(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// Stop accepting new requests and wait up to `timeout` for the pending ones
+    /// to complete. Returns `true` if all requests completed in time.
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        self.draining.store(true, Ordering::SeqCst);
+        let wait = async {
+            loop {
+                let notified = self.notify.notified();
+                if self.is_empty() {
+                    break;
+                }
+                notified.await;
+            }
+        };
+        tokio::time::timeout(timeout, wait).await.is_ok()
+    }
+}
+
+/// Guard marking a request as in flight until dropped
+pub struct InFlightGuard {
+    requests: Arc<InFlightRequests>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if self.requests.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.requests.notify.notify_waiters();
+        }
+    }
+}
+
 pub struct ServerInner {
     pub next_connection_id: AtomicU64,
     pub encoding: Encoding,
@@ -34,6 +119,9 @@ pub struct ServerInner {
     pub rpc_listener_id: ListenerId,
     pub notifier: Arc<Notifier<Notification, Connection>>,
     pub options: Arc<Options>,
+    pub requests: Arc<InFlightRequests>,
+    /// Triggered to stop accepting new connections, see [`Server::serve`]
+    pub listening: SingleTrigger,
 }
 
 #[derive(Clone)]
@@ -73,6 +161,8 @@ impl Server {
                 rpc_listener_id,
                 notifier,
                 options,
+                requests: Arc::new(InFlightRequests::default()),
+                listening: SingleTrigger::new(),
             }),
         }
     }
@@ -82,26 +172,195 @@ impl Server {
         self.notifier().start();
     }
 
-    pub fn connect(&self, peer: &SocketAddr, messenger: Arc<Messenger>) -> Result<Connection> {
-        log_info!("WebSocket connected: {}", peer);
+    /// Listen on `address`, a `wrpc://` or `ws://` URL or a bare socket address, until [`Server::stop_listening`]
+    /// is called
+    pub async fn listen(&self, router: Arc<Router>, address: &str) -> Result<()> {
+        let address = address.strip_prefix("wrpc://").or_else(|| address.strip_prefix("ws://")).unwrap_or(address);
+        let listener = TcpListener::bind(address).await?;
+        self.serve(router, listener).await
+    }
+
+    /// Accept the connections of `listener` until [`Server::stop_listening`] is called, each connection being
+    /// served by a task of its own
+    pub async fn serve(&self, router: Arc<Router>, listener: TcpListener) -> Result<()> {
+        let stop = self.inner.listening.listener.clone();
+        loop {
+            let accepted = tokio::select! {
+                accepted = listener.accept() => accepted,
+                _ = stop.clone() => break,
+            };
+            match accepted {
+                Ok((stream, peer)) => {
+                    let server = self.clone();
+                    let router = router.clone();
+                    tokio::spawn(async move { server.handle_socket(router, stream, peer).await });
+                }
+                Err(err) => log_trace!("wRPC server: error accepting a connection: {err}"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Stop accepting new connections, the established ones being kept
+    pub fn stop_listening(&self) {
+        self.inner.listening.trigger.trigger();
+    }
+
+    /// Upgrade the socket of `peer` to a WebSocket, negotiating the encoding and the protocol version of the
+    /// connection from the upgrade request, then serve the connection until it is closed
+    async fn handle_socket(&self, router: Arc<Router>, stream: TcpStream, peer: SocketAddr) {
+        if !self.inner.options.access_control.is_allowed(&peer.ip()) {
+            log_trace!("WebSocket connection from {} rejected by access control", peer);
+            return;
+        }
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        let request = match tokio::time::timeout(HANDSHAKE_TIMEOUT, read_upgrade_request(&mut reader)).await {
+            Ok(Ok(request)) => request,
+            Ok(Err(err)) => {
+                log_trace!("WebSocket connection from {} rejected: {}", peer, err);
+                writer.write_all(rejection_response("400 Bad Request", &[], &err.to_string()).as_bytes()).await.ok();
+                return;
+            }
+            Err(_) => {
+                log_trace!("WebSocket connection from {} timed out before upgrading", peer);
+                return;
+            }
+        };
+        if self.is_draining() {
+            writer.write_all(rejection_response("503 Service Unavailable", &[], SHUTDOWN_REASON).as_bytes()).await.ok();
+            return;
+        }
+
+        // The encoding and the protocol version are negotiated from the subprotocols offered by the client and the
+        // query string of its URL, the selected subprotocol being stated in the upgrade response
+        let supported = &self.inner.options.protocol_versions;
+        let subprotocols = request.subprotocols();
+        let negotiated = match negotiate_protocol(subprotocols.as_deref(), request.query(), self.inner.encoding, supported) {
+            Ok(negotiated) => negotiated,
+            Err(reason) => {
+                // Let the client know which versions are supported before the socket is dropped
+                log_info!("WebSocket connection from {} rejected: {}", peer, reason);
+                let advertised = advertise_protocol_versions(supported);
+                let response = rejection_response("400 Bad Request", &[("Sec-WebSocket-Protocol", &advertised)], &reason);
+                writer.write_all(response.as_bytes()).await.ok();
+                return;
+            }
+        };
+        // Messages are compressed only if the client offered `permessage-deflate` and the server accepts it
+        let deflate = negotiate_compression(request.extensions().as_deref(), &self.inner.options.compression);
+        if let Some(deflate) = &deflate {
+            log_trace!("WebSocket connection from {} compressed at level {}", peer, deflate.level());
+        }
+        let extensions = deflate.as_ref().map(PerMessageDeflate::response_header);
+        let response = match accept_response(&request, negotiated.subprotocol.as_deref(), extensions.as_deref()) {
+            Ok(response) => response,
+            Err(reason) => {
+                log_trace!("WebSocket connection from {} rejected: {}", peer, reason);
+                writer.write_all(rejection_response("400 Bad Request", &[], &reason).as_bytes()).await.ok();
+                return;
+            }
+        };
+        if writer.write_all(response.as_bytes()).await.is_err() {
+            return;
+        }
+        let writer = FrameWriter::new(writer, Role::Server).with_deflate(deflate.clone());
+
+        let (frames, receiver) = mpsc::unbounded_channel();
+        let connection = match self.connect(&peer, &negotiated, frames) {
+            Ok(connection) => connection,
+            Err(err) => {
+                log_trace!("WebSocket connection from {} failed: {}", peer, err);
+                return;
+            }
+        };
+        connection.start_writer(writer, receiver);
+
+        let mut reader = FrameReader::new(reader, Role::Server).with_deflate(deflate);
+        loop {
+            let message = tokio::select! {
+                message = reader.read_message() => message,
+                _ = connection.closed() => break,
+            };
+            match message {
+                Ok(Some(message @ (Message::Text(_) | Message::Binary(_)))) => {
+                    let router = router.clone();
+                    let connection = connection.clone();
+                    tokio::spawn(async move { router.route(connection, message).await });
+                }
+                Ok(Some(Message::Ping(payload))) => {
+                    connection.post(Message::Pong(payload)).ok();
+                }
+                Ok(Some(Message::Close(_))) => {
+                    // Answer the close handshake of the client
+                    ConnectionT::close(&connection);
+                    break;
+                }
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                Err(err) => {
+                    log_trace!("[{}] WebSocket error reading from {}: {}", connection.label(), peer, err);
+                    connection.post(close_message(CloseCode::Protocol, &err.to_string())).ok();
+                    break;
+                }
+            }
+        }
+        self.disconnect(connection);
+    }
+
+    /// Register a new connection using the `negotiated` encoding and protocol version (see [`negotiate_protocol`]),
+    /// its frames being posted to `frames`
+    pub fn connect(
+        &self,
+        peer: &SocketAddr,
+        negotiated: &NegotiatedProtocol,
+        frames: mpsc::UnboundedSender<Message>,
+    ) -> Result<Connection> {
+        let NegotiatedProtocol { encoding, protocol_version, .. } = *negotiated;
         let id = self.inner.next_connection_id.fetch_add(1, Ordering::SeqCst);
-        let connection = Connection::new(id, peer, messenger);
+        let connection = Connection::new(
+            id,
+            peer,
+            frames,
+            encoding,
+            protocol_version,
+            self.inner.options.throttling.clone(),
+            self.inner.options.outbound_queue.clone(),
+            self.inner.options.notification_serializer.clone(),
+            self.inner.options.max_consecutive_malformed_frames,
+            self.inner.options.rate_limit,
+        );
+        log_info!("[{}] WebSocket connected: {} ({}, protocol v{})", connection.label(), peer, encoding, protocol_version);
+        connection.send_welcome()?;
         self.inner.sockets.lock()?.insert(id, connection.clone());
         Ok(connection)
     }
 
     pub fn disconnect(&self, connection: Connection) {
-        log_info!("WebSocket disconnected: {}", connection.peer());
+        log_info!("[{}] WebSocket disconnected: {}", connection.label(), connection.peer());
 
         if let Some(listener_id) = connection.listener_id() {
             self.notifier().unregister_listener(listener_id).unwrap_or_else(|err| {
-                format!("WebSocket {} (disconnected) error unregistering the notification listener: {err}", connection.peer());
+                log_trace!(
+                    "[{}] WebSocket {} (disconnected) error unregistering the notification listener: {err}",
+                    connection.label(),
+                    connection.peer()
+                );
             })
         }
+        connection.close_outbound();
+        if connection.dropped_notifications() > 0 {
+            log_info!("[{}] {} notification(s) shed during the connection", connection.label(), connection.dropped_notifications());
+        }
         self.inner.sockets.lock().unwrap().remove(&connection.id());
 
-        // TODO: determine if messenger should be closed explicitly
-        // connection.close();
+        // Release the writer of the socket, the frames already posted being written first
+        connection.mark_closed();
+    }
+
+    /// The connections currently established
+    pub fn connections(&self) -> Vec<Connection> {
+        self.inner.sockets.lock().unwrap().values().cloned().collect()
     }
 
     #[inline(always)]
@@ -113,10 +372,51 @@ impl Server {
         self.inner.rpc_service.clone()
     }
 
+    /// Mark a request as in flight for the lifetime of the returned guard.
+    /// Fails if the server is shutting down.
+    pub fn enter_request(&self) -> std::result::Result<InFlightGuard, RouterError> {
+        self.inner.requests.enter().ok_or_else(|| RouterError::Internal(SHUTDOWN_REASON.to_string()))
+    }
+
+    /// Fails with [`RouterError::NotPermitted`] if the clients may not call `op`, see [`Options::method_permissions`]
+    pub fn check_permission(&self, op: &RpcApiOps) -> std::result::Result<(), RouterError> {
+        self.inner.options.method_permissions.as_ref().map_or(Ok(()), |permissions| permissions.check(op))
+    }
+
+    /// Returns `true` once a shutdown drain has been initiated
+    pub fn is_draining(&self) -> bool {
+        self.inner.requests.is_draining()
+    }
+
+    /// Runs the handler `future` of `op` within the deadline of `op`, see [`Options::method_timeouts`]
+    pub async fn with_method_timeout<F, T>(&self, op: RpcApiOps, future: F) -> std::result::Result<T, RouterError>
+    where
+        F: Future<Output = T>,
+    {
+        self.inner.options.method_timeouts.run(op, future).await
+    }
+
     pub fn verbose(&self) -> bool {
         self.inner.options.verbose
     }
 
+    /// Gracefully shut down the connections: new requests are rejected, pending
+    /// requests are given up to `timeout` to complete, then every connection is
+    /// closed with a [`SHUTDOWN_REASON`] close frame, remaining requests included.
+    pub async fn shutdown_with_drain(&self, timeout: Duration) -> Result<()> {
+        if !self.inner.requests.drain(timeout).await {
+            log_warning!(
+                "wRPC server: {} request(s) still pending after {:?}, forcing connections to close",
+                self.inner.requests.len(),
+                timeout
+            );
+        }
+        for connection in self.connections() {
+            connection.close_with_reason(SHUTDOWN_REASON);
+        }
+        Ok(())
+    }
+
     pub async fn stop(&self) -> Result<()> {
         // Unsubscribe from all notification types
         let listener_id = self.inner.rpc_listener_id;
@@ -134,3 +434,164 @@ impl Server {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compression::CompressionOptions,
+        connection::SUPPORTED_PROTOCOL_VERSIONS,
+        test_client::{test_info, RawClient, TestNode},
+    };
+    use kaspa_wrpc_client::{KaspaRpcClient, WrpcEncoding};
+
+    #[tokio::test]
+    async fn test_drain_waits_for_pending_request() {
+        kaspa_core::log::try_init_logger("info");
+        let requests = Arc::new(InFlightRequests::default());
+        let completed = Arc::new(AtomicBool::new(false));
+
+        let guard = requests.enter().unwrap();
+        let slow_request = {
+            let completed = completed.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                completed.store(true, Ordering::SeqCst);
+                drop(guard);
+            })
+        };
+
+        assert!(requests.drain(Duration::from_secs(5)).await, "drain should complete before timeout");
+        assert!(completed.load(Ordering::SeqCst), "the slow request must complete before the drain returns");
+        assert!(requests.is_empty());
+        assert!(requests.enter().is_none(), "no request should be accepted once draining");
+        slow_request.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_drain_timeout() {
+        let requests = Arc::new(InFlightRequests::default());
+        let _guard = requests.enter().unwrap();
+        assert!(!requests.drain(Duration::from_millis(50)).await, "drain should time out");
+        assert_eq!(requests.len(), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_get_info_round_trip() {
+        let node = TestNode::start(Encoding::Borsh, Options::default()).await;
+        node.rpc.respond_with(RpcApiOps::GetInfo, test_info());
+
+        // A Borsh client with the default encoding of the server, then a JSON one negotiating its encoding in its URL
+        for (encoding, path) in [(WrpcEncoding::Borsh, ""), (WrpcEncoding::SerdeJson, "/?encoding=json")] {
+            let client = KaspaRpcClient::new(encoding, &node.url(path)).unwrap();
+            client.connect(true).await.unwrap();
+            let info = client.get_info().await.unwrap();
+            assert_eq!(info.p2p_id, test_info().p2p_id, "wrong response over {encoding}");
+            assert_eq!(info.mempool_size, test_info().mempool_size, "wrong response over {encoding}");
+            client.shutdown().await.unwrap();
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_handshake_negotiation() {
+        let node = TestNode::start(Encoding::Borsh, Options::default()).await;
+        node.rpc.respond_with(RpcApiOps::GetInfo, test_info());
+
+        // A client offering only unsupported versions is refused before the upgrade, the supported versions being listed
+        let response = RawClient::connect(node.address, "/", &[("Sec-WebSocket-Protocol", "kaspa-wrpc.v9")])
+            .await
+            .err()
+            .expect("a client offering only an unsupported version should be refused");
+        assert!(response.starts_with("HTTP/1.1 400 "), "unexpected response {response}");
+        assert!(response.contains(&advertise_protocol_versions(SUPPORTED_PROTOCOL_VERSIONS)), "unexpected response {response}");
+
+        // A JSON client connecting to a Borsh endpoint
+        let mut client =
+            RawClient::connect(node.address, "/", &[("Sec-WebSocket-Protocol", "kaspa-json, kaspa-wrpc.v1")]).await.unwrap();
+        assert!(client.response.contains("Sec-WebSocket-Protocol: kaspa-json\r\n"), "unexpected response {}", client.response);
+        assert!(matches!(client.recv().await, Some(Message::Text(_))), "the welcome should be sent in JSON");
+        client.send(Message::Text(r#"{"id":1,"method":"getInfo","params":{}}"#.to_string())).await;
+        let Some(Message::Text(text)) = client.recv().await else { panic!("the response should be sent in JSON") };
+        let response: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(response["params"]["p2pId"], test_info().p2p_id);
+
+        // A client offering nothing gets the default encoding and version
+        let mut client = RawClient::connect(node.address, "/", &[]).await.unwrap();
+        assert!(!client.response.to_lowercase().contains("sec-websocket-protocol"), "no subprotocol should be selected");
+        assert!(matches!(client.recv().await, Some(Message::Binary(_))), "the welcome should be sent in Borsh");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_compression_negotiation() {
+        let options = Options { compression: CompressionOptions { enabled: true, level: 9 }, ..Default::default() };
+        let node = TestNode::start(Encoding::SerdeJson, options).await;
+        node.rpc.respond_with(RpcApiOps::GetInfo, test_info());
+        let offer = [("Sec-WebSocket-Extensions", "permessage-deflate; client_max_window_bits")];
+
+        // A client offering compression exchanges compressed messages
+        let mut client = RawClient::connect(node.address, "/", &offer).await.unwrap();
+        assert!(client.response.contains("Sec-WebSocket-Extensions: permessage-deflate"), "unexpected response {}", client.response);
+        assert!(matches!(client.recv().await, Some(Message::Text(_))), "the welcome should be decompressed");
+        client.send(Message::Text(r#"{"id":1,"method":"getInfo","params":{}}"#.to_string())).await;
+        let Some(Message::Text(text)) = client.recv().await else { panic!("the response should be decompressed") };
+        let response: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(response["params"]["p2pId"], test_info().p2p_id);
+
+        // A client offering nothing exchanges uncompressed messages
+        let mut client = RawClient::connect(node.address, "/", &[]).await.unwrap();
+        assert!(!client.response.to_lowercase().contains("sec-websocket-extensions"), "no extension should be negotiated");
+        assert!(matches!(client.recv().await, Some(Message::Text(_))));
+
+        // A server not accepting compression ignores the offer
+        let node = TestNode::start(Encoding::SerdeJson, Options::default()).await;
+        node.rpc.respond_with(RpcApiOps::GetInfo, test_info());
+        let mut client = RawClient::connect(node.address, "/", &offer).await.unwrap();
+        assert!(!client.response.to_lowercase().contains("sec-websocket-extensions"), "compression is disabled by default");
+        client.recv().await.expect("the welcome should be received");
+        client.send(Message::Text(r#"{"id":1,"method":"getInfo","params":{}}"#.to_string())).await;
+        let Some(Message::Text(text)) = client.recv().await else { panic!("the response should be sent uncompressed") };
+        let response: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(response["params"]["p2pId"], test_info().p2p_id);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_in_flight_request_across_shutdown() {
+        let node = TestNode::start(Encoding::Borsh, Options::default()).await;
+        node.rpc.on(RpcApiOps::GetInfo, |_: kaspa_rpc_core::GetInfoRequest| {
+            std::thread::sleep(Duration::from_millis(300));
+            Ok::<kaspa_rpc_core::GetInfoResponse, _>(test_info())
+        });
+
+        let client = Arc::new(KaspaRpcClient::new(WrpcEncoding::Borsh, &node.url("")).unwrap());
+        client.connect(true).await.unwrap();
+        let mut subscriber = RawClient::connect(node.address, "/?encoding=json", &[]).await.unwrap();
+        subscriber.recv().await.expect("the welcome should be received");
+
+        let in_flight = {
+            let client = client.clone();
+            tokio::spawn(async move { client.get_info().await })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let shutdown = {
+            let server = node.server.clone();
+            tokio::spawn(async move { server.shutdown_with_drain(Duration::from_secs(5)).await })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // A subscription is a request like any other, refused once the server is draining
+        subscriber.send(Message::Text(r#"{"id":1,"method":"subscribe","params":{"BlockAdded":{}}}"#.to_string())).await;
+        let Some(Message::Text(text)) = subscriber.recv().await else { panic!("the subscription should be answered") };
+        let response: serde_json::Value = serde_json::from_str(&text).unwrap();
+        let message = response["error"]["message"].as_str().expect("the subscription should fail");
+        assert!(message.contains(SHUTDOWN_REASON), "unexpected error {message}");
+
+        // The request in flight when the shutdown started completes
+        let info = in_flight.await.unwrap().expect("the request in flight should complete");
+        assert_eq!(info.p2p_id, test_info().p2p_id);
+        shutdown.await.unwrap().unwrap();
+        match subscriber.recv().await {
+            Some(Message::Close(Some(frame))) => assert_eq!(frame.reason, SHUTDOWN_REASON),
+            message => panic!("the connection should be closed with the shutdown reason, got {message:?}"),
+        }
+    }
+}
@@ -1,12 +1,19 @@
-use crate::{connection::*, result::Result, router::*, server::*};
-use async_trait::async_trait;
+use crate::{
+    compression::CompressionOptions,
+    connection::*,
+    frames::DEFAULT_MAX_CONSECUTIVE_MALFORMED_FRAMES,
+    limits::{MethodPermissions, RateLimit},
+    notifications::{DynNotificationSerializer, NotificationThrottling, OutboundQueueConfig},
+    result::Result,
+    router::*,
+    server::*,
+    timeouts::MethodTimeouts,
+};
 use kaspa_core::task::service::{AsyncService, AsyncServiceError, AsyncServiceFuture};
-use kaspa_notify::subscriber::DynSubscriptionManager;
-use kaspa_rpc_core::api::ops::RpcApiOps;
 use kaspa_rpc_service::service::RpcCoreService;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use workflow_log::*;
-use workflow_rpc::server::prelude::*;
+use workflow_rpc::server::Encoding;
 pub use workflow_rpc::server::Encoding as WrpcEncoding;
 
 /// Options for configuring the wRPC server
@@ -14,124 +21,92 @@ pub struct Options {
     pub listen_address: String,
     pub grpc_proxy_address: Option<String>,
     pub verbose: bool,
+    /// Peer address filtering applied before any RPC dispatch
+    pub access_control: AccessControl,
+    /// Minimum intervals between two notifications of a same event type sent to a connection
+    pub throttling: NotificationThrottling,
+    /// Bound and shedding policies of the queue of notifications waiting to be sent to a connection
+    pub outbound_queue: OutboundQueueConfig,
+    /// Custom serializer of the notifications sent to the connections, replacing their negotiated encoding
+    pub notification_serializer: Option<DynNotificationSerializer>,
+    /// Number of consecutive malformed frames after which a connection is closed, each one being answered with a malformed params error
+    pub max_consecutive_malformed_frames: usize,
+    /// Versions of the wire format accepted from the clients, see [`negotiate_protocol_version`]
+    pub protocol_versions: Vec<u32>,
+    /// Deadlines of the method handlers, a handler overrunning its deadline failing with a timeout error
+    pub method_timeouts: MethodTimeouts,
+    /// Methods the clients may call, all of them if `None`
+    pub method_permissions: Option<MethodPermissions>,
+    /// Maximum rate of the requests of each connection, unlimited if `None`
+    pub rate_limit: Option<RateLimit>,
+    /// Acceptance and level of the `permessage-deflate` compression offered by the clients, disabled by default
+    pub compression: CompressionOptions,
 }
 
 impl Default for Options {
     fn default() -> Self {
-        Options { listen_address: "127.0.0.1:17110".to_owned(), verbose: false, grpc_proxy_address: None }
+        Options {
+            listen_address: "127.0.0.1:17110".to_owned(),
+            verbose: false,
+            grpc_proxy_address: None,
+            access_control: AccessControl::default(),
+            throttling: NotificationThrottling::default(),
+            outbound_queue: OutboundQueueConfig::default(),
+            notification_serializer: None,
+            max_consecutive_malformed_frames: DEFAULT_MAX_CONSECUTIVE_MALFORMED_FRAMES,
+            protocol_versions: SUPPORTED_PROTOCOL_VERSIONS.to_vec(),
+            method_timeouts: MethodTimeouts::default(),
+            method_permissions: None,
+            rate_limit: None,
+            compression: CompressionOptions::default(),
+        }
     }
 }
 
-/// ### KaspaRpcHandler
 ///
-/// [`KaspaRpcHandler`] is a handler struct that implements the [`RpcHandler`] trait
-/// allowing it to receive [`connect()`](RpcHandler::connect),
-/// [`disconnect()`](RpcHandler::disconnect) and [`handshake()`](RpcHandler::handshake)
-/// calls invoked by the [`RpcServer`].
-///
-/// [`RpcHandler::handshake`] is called by the [`RpcServer`] supplying the [`Messenger`]
-/// and expecting user to return a `ServerContext` struct (or an `Arc` of) where
-/// this struct will be supplied to each RPC method call.  Each RPC method call receives
-/// 3 arguments - `ServerContext`, `ConnectionContext` and `Request`. Upon completion
-/// the method should return a `Result`.
-///
-/// RPC method handling is implemented in the [`Router`].
-///
-pub struct KaspaRpcHandler {
-    pub server: Server,
-    pub options: Arc<Options>,
-}
-
-impl KaspaRpcHandler {
-    pub fn new(
-        tasks: usize,
-        encoding: WrpcEncoding,
-        rpc_service: DynRpcService,
-        subscription_manager: DynSubscriptionManager,
-        options: Arc<Options>,
-    ) -> KaspaRpcHandler {
-        KaspaRpcHandler { server: Server::new(tasks, encoding, rpc_service, subscription_manager, options.clone()), options }
-    }
-}
-
-#[async_trait]
-impl RpcHandler for KaspaRpcHandler {
-    type Context = Connection;
-
-    async fn connect(self: Arc<Self>, _peer: &SocketAddr) -> WebSocketResult<()> {
-        Ok(())
-    }
-
-    async fn handshake(
-        self: Arc<Self>,
-        peer: &SocketAddr,
-        _sender: &mut WebSocketSender,
-        _receiver: &mut WebSocketReceiver,
-        messenger: Arc<Messenger>,
-    ) -> WebSocketResult<Connection> {
-        // TODO - discuss and implement handshake
-        // handshake::greeting(
-        //     std::time::Duration::from_millis(3000),
-        //     sender,
-        //     receiver,
-        //     Box::pin(|msg| if msg != "kaspa" { Err(WebSocketError::NegotiationFailure) } else { Ok(()) }),
-        // )
-        // .await
-
-        let connection = self.server.connect(peer, messenger).map_err(|err| err.to_string())?;
-        Ok(connection)
-    }
-
-    /// Disconnect the websocket. Receives `Connection` (a.k.a `Self::Context`)
-    /// before dropping it. This is the last chance to cleanup and resources owned by
-    /// this connection. Delegate to Server.
-    async fn disconnect(self: Arc<Self>, ctx: Self::Context, _result: WebSocketResult<()>) {
-        self.server.disconnect(ctx);
-    }
-}
-
-///
-///  wRPC Server - A wrapper around and an initializer of the RpcServer
+///  wRPC Server - A wrapper around and an initializer of the wRPC [`Server`]
 ///
 pub struct WrpcService {
     options: Arc<Options>,
-    server: RpcServer,
-    rpc_handler: Arc<KaspaRpcHandler>,
+    server: Server,
+    router: Arc<Router>,
 }
 
 impl WrpcService {
-    /// Create and initialize RpcServer
+    /// Create and initialize the server
     pub fn new(tasks: usize, core_service: Arc<RpcCoreService>, encoding: &Encoding, options: Options) -> Self {
         let options = Arc::new(options);
-        // Create handle to manage connections
-        let rpc_handler =
-            Arc::new(KaspaRpcHandler::new(tasks, *encoding, core_service.clone(), core_service.notifier(), options.clone()));
+        // Create the server managing the connections, the default encoding applying to the clients negotiating none
+        let server = Server::new(tasks, *encoding, core_service.clone(), core_service.notifier(), options.clone());
 
         // Create router (initializes Interface registering RPC method and notification handlers)
-        let router = Arc::new(Router::new(rpc_handler.server.clone()));
-        // Create a server
-        // let server = RpcServer::new_with_encoding::<KaspaRpcHandlerReference, Connection, RpcApiOps, Id64>(
-        let server = RpcServer::new_with_encoding::<Server, Connection, RpcApiOps, Id64>(
-            *encoding,
-            rpc_handler.clone(),
-            router.interface.clone(),
-        );
+        let router = Arc::new(Router::new(server.clone()));
 
-        WrpcService { options, server, rpc_handler }
+        WrpcService { options, server, router }
     }
 
     /// Start listening on the configured address (will yield an error if the the socket listen() fails)
     async fn run(self: Arc<Self>) -> Result<()> {
-        self.rpc_handler.server.start();
+        self.server.start();
         let addr = &self.options.listen_address;
         log_info!("wRPC server is listening on {}", addr);
-        self.server.listen(addr).await?;
+        self.server.listen(self.router.clone(), addr).await?;
         Ok(())
     }
+
+    /// Stop accepting connections, let pending requests complete for up to `timeout`
+    /// and close all connections notifying the clients that the server is shutting down.
+    pub async fn shutdown_with_drain(&self, timeout: Duration) -> Result<()> {
+        self.server.stop_listening();
+        self.server.shutdown_with_drain(timeout).await
+    }
 }
 
 const WRPC_SERVER: &str = "WRPC_SERVER";
 
+/// Maximum time given to pending requests to complete when the service stops
+const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
 impl AsyncService for WrpcService {
     fn ident(self: Arc<Self>) -> &'static str {
         WRPC_SERVER
@@ -142,17 +117,15 @@ impl AsyncService for WrpcService {
     }
 
     fn signal_exit(self: Arc<Self>) {
-        self.server.stop().unwrap_or_else(|err| log_trace!("wRPC unable to signal shutdown: `{err}`"));
+        self.server.stop_listening();
     }
 
     fn stop(self: Arc<Self>) -> AsyncServiceFuture {
         Box::pin(async move {
-            self.rpc_handler
-                .server
-                .stop()
+            self.shutdown_with_drain(SHUTDOWN_DRAIN_TIMEOUT)
                 .await
-                .map_err(|err| AsyncServiceError::Service(format!("Notification system error: `{err}`")))?;
-            self.server.join().await.map_err(|err| AsyncServiceError::Service(format!("wRPC error: `{err}`")))?;
+                .map_err(|err| AsyncServiceError::Service(format!("wRPC shutdown error: `{err}`")))?;
+            self.server.stop().await.map_err(|err| AsyncServiceError::Service(format!("Notification system error: `{err}`")))?;
             Ok(())
         })
     }
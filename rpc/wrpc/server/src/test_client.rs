@@ -0,0 +1,124 @@
+//! A wRPC server backed by a programmable RPC service, for the tests of the server
+
+use crate::{
+    compression::{negotiate_compression, CompressionOptions},
+    router::Router,
+    server::Server,
+    service::Options,
+    websocket::{FrameReader, FrameWriter, Role},
+};
+use kaspa_grpc_client::mock::MockGrpcClient;
+use kaspa_rpc_core::GetInfoResponse;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpListener, TcpStream,
+    },
+};
+use tungstenite::Message;
+use workflow_rpc::server::Encoding;
+
+/// A [`Server`] serving the routes programmed on its [`MockGrpcClient`]
+pub(crate) struct TestNode {
+    pub server: Server,
+    pub rpc: Arc<MockGrpcClient>,
+    pub address: SocketAddr,
+}
+
+impl TestNode {
+    /// Serves on a free local port, `encoding` being the default encoding of the server.
+    ///
+    /// The port is bound before returning, so the server can be connected to right away.
+    pub async fn start(encoding: Encoding, options: Options) -> Self {
+        let rpc = Arc::new(MockGrpcClient::new());
+        rpc.start().await;
+        let server = Server::new(1, encoding, rpc.clone(), rpc.notifier(), Arc::new(options));
+        server.start();
+        let router = Arc::new(Router::new(server.clone()));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let serving = server.clone();
+        tokio::spawn(async move { serving.serve(router, listener).await });
+        Self { server, rpc, address }
+    }
+
+    /// URL of the server, `path` holding an optional path and query string
+    pub fn url(&self, path: &str) -> String {
+        format!("ws://{}{}", self.address, path)
+    }
+}
+
+pub(crate) fn test_info() -> GetInfoResponse {
+    GetInfoResponse {
+        p2p_id: "test-node".to_string(),
+        mempool_size: 7,
+        server_version: "0.1.0".to_string(),
+        is_utxo_indexed: true,
+        is_synced: true,
+        has_notify_command: true,
+        has_message_id: true,
+    }
+}
+
+/// A WebSocket client upgrading its connection by hand, so a test controls the headers of the upgrade request and
+/// when the frames of the server are read
+pub(crate) struct RawClient {
+    /// Head of the upgrade response
+    pub response: String,
+    reader: FrameReader<BufReader<OwnedReadHalf>>,
+    writer: FrameWriter<OwnedWriteHalf>,
+}
+
+impl RawClient {
+    /// Asks `address` to upgrade the connection to `path` with the extra `headers`, compressing the messages if the
+    /// server accepts a `permessage-deflate` offer.
+    ///
+    /// Fails with the whole response if the server refuses the upgrade.
+    pub async fn connect(address: SocketAddr, path: &str, headers: &[(&str, &str)]) -> Result<Self, String> {
+        let stream = TcpStream::connect(address).await.map_err(|err| err.to_string())?;
+        let (reader, mut writer) = stream.into_split();
+        let mut request = format!(
+            "GET {path} HTTP/1.1\r\nHost: {address}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n"
+        );
+        for (name, value) in headers {
+            request.push_str(&format!("{name}: {value}\r\n"));
+        }
+        request.push_str("\r\n");
+        writer.write_all(request.as_bytes()).await.map_err(|err| err.to_string())?;
+
+        let mut reader = BufReader::new(reader);
+        let mut response = String::new();
+        while !response.ends_with("\r\n\r\n") {
+            if reader.read_line(&mut response).await.map_err(|err| err.to_string())? == 0 {
+                return Err(response);
+            }
+        }
+        if !response.starts_with("HTTP/1.1 101 ") {
+            reader.read_to_string(&mut response).await.ok();
+            return Err(response);
+        }
+        let extensions = response.lines().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.trim().eq_ignore_ascii_case("sec-websocket-extensions").then(|| value.trim())
+        });
+        let deflate = negotiate_compression(extensions, &CompressionOptions { enabled: true, ..Default::default() });
+        let reader = FrameReader::new(reader, Role::Client).with_deflate(deflate.clone());
+        let writer = FrameWriter::new(writer, Role::Client).with_deflate(deflate);
+        Ok(Self { response, reader, writer })
+    }
+
+    pub async fn send(&mut self, message: Message) {
+        self.writer.write_message(message).await.unwrap();
+    }
+
+    /// Next message sent by the server, `None` once the socket is closed
+    pub async fn recv(&mut self) -> Option<Message> {
+        tokio::time::timeout(Duration::from_secs(5), self.reader.read_message())
+            .await
+            .expect("the server should send a message in time")
+            .unwrap_or(None)
+    }
+}
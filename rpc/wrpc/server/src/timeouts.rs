@@ -0,0 +1,103 @@
+//! Deadlines of the method handlers of the [`Router`](crate::router::Router).
+//!
+//! The cost of the methods varies widely, from a `Ping` answered right away to a `GetBlocks` walking a large part of
+//! the DAG, so each method has its own deadline. A handler overrunning its deadline is abandoned and the client gets a
+//! [`RouterError::Timeout`], a single slow handler thus never tying up a connection indefinitely.
+
+use crate::error::RouterError;
+use kaspa_rpc_core::api::ops::RpcApiOps;
+use std::{collections::HashMap, future::Future, time::Duration};
+
+/// Deadline of the cheap methods, only reading a few values of the node state
+pub const SHORT_METHOD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Deadline of the methods not listed as either cheap or long-running
+pub const DEFAULT_METHOD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Deadline of the long-running methods, walking the DAG, the UTXO index or the mempool
+pub const LONG_METHOD_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Default deadline of the handler of `op`
+pub fn default_method_timeout(op: &RpcApiOps) -> Duration {
+    match op {
+        RpcApiOps::Ping
+        | RpcApiOps::GetInfo
+        | RpcApiOps::GetCurrentNetwork
+        | RpcApiOps::GetBlockCount
+        | RpcApiOps::GetSelectedTipHash
+        | RpcApiOps::GetSinkBlueScore
+        | RpcApiOps::GetProcessMetrics
+        | RpcApiOps::GetCoinSupply => SHORT_METHOD_TIMEOUT,
+        RpcApiOps::GetBlocks
+        | RpcApiOps::GetHeaders
+        | RpcApiOps::GetVirtualChainFromBlock
+        | RpcApiOps::GetUtxosByAddresses
+        | RpcApiOps::GetBalancesByAddresses
+        | RpcApiOps::GetMempoolEntries
+        | RpcApiOps::GetMempoolEntriesByAddresses
+        | RpcApiOps::EstimateNetworkHashesPerSecond => LONG_METHOD_TIMEOUT,
+        _ => DEFAULT_METHOD_TIMEOUT,
+    }
+}
+
+/// Deadlines of the method handlers, falling back to [`default_method_timeout`] for the methods with no override
+#[derive(Clone, Debug, Default)]
+pub struct MethodTimeouts {
+    overrides: HashMap<RpcApiOps, Duration>,
+}
+
+impl MethodTimeouts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the deadline of the handler of `op`
+    pub fn with_timeout(mut self, op: RpcApiOps, timeout: Duration) -> Self {
+        self.overrides.insert(op, timeout);
+        self
+    }
+
+    pub fn timeout(&self, op: &RpcApiOps) -> Duration {
+        self.overrides.get(op).copied().unwrap_or_else(|| default_method_timeout(op))
+    }
+
+    /// Runs the handler `future` of `op`, failing with [`RouterError::Timeout`] if it overruns the deadline of `op`.
+    ///
+    /// The handler is dropped on overrun, cancelling whatever work it still had to do.
+    pub async fn run<F, T>(&self, op: RpcApiOps, future: F) -> Result<T, RouterError>
+    where
+        F: Future<Output = T>,
+    {
+        let timeout = self.timeout(&op);
+        tokio::time::timeout(timeout, future).await.map_err(|_| RouterError::Timeout(format!("{op:?}"), timeout))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use workflow_rpc::server::prelude::ServerError;
+
+    #[tokio::test]
+    async fn test_method_timeout() {
+        let timeouts = MethodTimeouts::new().with_timeout(RpcApiOps::GetBlocks, Duration::from_millis(50));
+        assert_eq!(timeouts.timeout(&RpcApiOps::GetBlocks), Duration::from_millis(50));
+        assert_eq!(timeouts.timeout(&RpcApiOps::Ping), SHORT_METHOD_TIMEOUT);
+        assert_eq!(timeouts.timeout(&RpcApiOps::SubmitBlock), DEFAULT_METHOD_TIMEOUT);
+        assert_eq!(timeouts.timeout(&RpcApiOps::GetHeaders), LONG_METHOD_TIMEOUT);
+
+        let slow_handler = async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            "blocks"
+        };
+        let err = timeouts.run(RpcApiOps::GetBlocks, slow_handler).await.unwrap_err();
+        assert_eq!(err, RouterError::Timeout("GetBlocks".to_string(), Duration::from_millis(50)));
+        match ServerError::from(err) {
+            ServerError::Text(text) => assert_eq!(RouterError::code_of(&text), Some(RouterError::TIMEOUT)),
+            other => panic!("unexpected server error {other:?}"),
+        }
+
+        // A handler completing in time is not affected
+        assert_eq!(timeouts.run(RpcApiOps::GetBlocks, async { "blocks" }).await, Ok("blocks"));
+    }
+}
@@ -0,0 +1,465 @@
+//! A minimal WebSocket transport (RFC 6455) carrying the wRPC connections.
+//!
+//! The server performs the upgrade handshake and the framing itself rather than delegating them to `workflow-rpc`,
+//! so the subprotocols and the URL of the upgrade request are available to the negotiation of a connection (see
+//! [`negotiate_protocol`](crate::connection::negotiate_protocol)) and the writing of the frames is driven by the
+//! connection. The messages are compressed when the `permessage-deflate` extension is negotiated, see
+//! [`compression`](crate::compression).
+
+use crate::compression::PerMessageDeflate;
+use std::{borrow::Cow, io};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tungstenite::{
+    handshake::derive_accept_key,
+    protocol::frame::{coding::CloseCode, CloseFrame},
+    Message,
+};
+
+/// Maximum size of the head of an upgrade request
+pub const MAX_UPGRADE_REQUEST_SIZE: usize = 16 * 1024;
+
+/// Maximum size of a message received from a peer, fragmented or not
+pub const MAX_MESSAGE_SIZE: usize = 64 << 20;
+
+/// Maximum size of the payload of a control frame
+const MAX_CONTROL_PAYLOAD_SIZE: usize = 125;
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_BINARY: u8 = 0x2;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xa;
+
+/// Reserved bit marking the first frame of a compressed message
+const RSV1: u8 = 0x40;
+
+/// The HTTP request of a client asking to upgrade its connection to a WebSocket
+#[derive(Clone, Debug)]
+pub struct UpgradeRequest {
+    /// Target of the request, the path of the URL along with its query string
+    pub target: String,
+    headers: Vec<(String, String)>,
+}
+
+impl UpgradeRequest {
+    /// Parses the head of an upgrade request, the request line and the header lines
+    pub fn parse(head: &str) -> Result<Self, String> {
+        let mut lines = head.lines();
+        let request_line = lines.next().unwrap_or_default();
+        let target = match request_line.split(' ').collect::<Vec<_>>().as_slice() {
+            ["GET", target, version] if version.starts_with("HTTP/1.") => target.to_string(),
+            _ => return Err(format!("invalid upgrade request line `{request_line}`")),
+        };
+        let headers = lines
+            .filter(|line| !line.is_empty())
+            .map(|line| match line.split_once(':') {
+                Some((name, value)) => Ok((name.trim().to_lowercase(), value.trim().to_string())),
+                None => Err(format!("invalid header line `{line}`")),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { target, headers })
+    }
+
+    /// Value of the header `name`, the values of a repeated header being joined by commas
+    pub fn header(&self, name: &str) -> Option<String> {
+        let values = self.headers.iter().filter(|(header, _)| header.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str());
+        let values = values.collect::<Vec<_>>();
+        (!values.is_empty()).then(|| values.join(", "))
+    }
+
+    /// Query string of the URL, if any
+    pub fn query(&self) -> Option<&str> {
+        self.target.split_once('?').map(|(_, query)| query)
+    }
+
+    /// Content of the `Sec-WebSocket-Protocol` header, listing the subprotocols offered by the client
+    pub fn subprotocols(&self) -> Option<String> {
+        self.header("sec-websocket-protocol")
+    }
+
+    /// Content of the `Sec-WebSocket-Extensions` header, listing the extensions offered by the client
+    pub fn extensions(&self) -> Option<String> {
+        self.header("sec-websocket-extensions")
+    }
+
+    fn has_token(&self, name: &str, token: &str) -> bool {
+        self.header(name).map_or(false, |value| value.split(',').any(|item| item.trim().eq_ignore_ascii_case(token)))
+    }
+
+    /// Checks the request is a WebSocket upgrade and returns its `Sec-WebSocket-Key`
+    fn key(&self) -> Result<String, String> {
+        if !self.has_token("upgrade", "websocket") || !self.has_token("connection", "upgrade") {
+            return Err("not a WebSocket upgrade request".to_string());
+        }
+        if self.header("sec-websocket-version").as_deref() != Some("13") {
+            return Err("unsupported WebSocket version, expecting 13".to_string());
+        }
+        self.header("sec-websocket-key").ok_or_else(|| "missing Sec-WebSocket-Key header".to_string())
+    }
+}
+
+/// Reads the head of the upgrade request opening a connection
+pub async fn read_upgrade_request<R: AsyncBufRead + Unpin>(reader: &mut R) -> io::Result<UpgradeRequest> {
+    let mut limited = reader.take(MAX_UPGRADE_REQUEST_SIZE as u64);
+    let mut head = String::new();
+    while !head.ends_with("\r\n\r\n") {
+        if limited.read_line(&mut head).await? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "incomplete or oversized upgrade request"));
+        }
+    }
+    UpgradeRequest::parse(&head).map_err(invalid_data)
+}
+
+/// Response accepting `request`, selecting `subprotocol` among the ones offered by the client and stating the accepted
+/// `extensions`
+pub fn accept_response(request: &UpgradeRequest, subprotocol: Option<&str>, extensions: Option<&str>) -> Result<String, String> {
+    let accept = derive_accept_key(request.key()?.as_bytes());
+    let mut response =
+        format!("HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {accept}\r\n");
+    if let Some(subprotocol) = subprotocol {
+        response.push_str(&format!("Sec-WebSocket-Protocol: {subprotocol}\r\n"));
+    }
+    if let Some(extensions) = extensions {
+        response.push_str(&format!("Sec-WebSocket-Extensions: {extensions}\r\n"));
+    }
+    response.push_str("\r\n");
+    Ok(response)
+}
+
+/// Response refusing an upgrade request with `status` (as in `400 Bad Request`), its body stating `reason`
+pub fn rejection_response(status: &str, headers: &[(&str, &str)], reason: &str) -> String {
+    let mut response = format!(
+        "HTTP/1.1 {status}\r\nConnection: close\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\n",
+        reason.len()
+    );
+    for (name, value) in headers {
+        response.push_str(&format!("{name}: {value}\r\n"));
+    }
+    response.push_str("\r\n");
+    response.push_str(reason);
+    response
+}
+
+/// Close frame with `code` and `reason`, the reason being truncated to fit the payload of a control frame
+pub fn close_message(code: CloseCode, reason: &str) -> Message {
+    let mut end = reason.len().min(MAX_CONTROL_PAYLOAD_SIZE - 2);
+    while !reason.is_char_boundary(end) {
+        end -= 1;
+    }
+    Message::Close(Some(CloseFrame { code, reason: reason[..end].to_string().into() }))
+}
+
+/// Side of a WebSocket, the client masking the frames it sends
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Server,
+    Client,
+}
+
+struct Frame {
+    fin: bool,
+    /// The frame starts a compressed message
+    compressed: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+/// A message being received, possibly fragmented
+struct PartialMessage {
+    opcode: u8,
+    compressed: bool,
+    data: Vec<u8>,
+}
+
+/// Reads the messages of a WebSocket, reassembling the fragmented ones
+pub struct FrameReader<R> {
+    reader: R,
+    role: Role,
+    /// Negotiated compression of the messages, if any
+    deflate: Option<PerMessageDeflate>,
+    partial: Option<PartialMessage>,
+}
+
+impl<R: AsyncRead + Unpin> FrameReader<R> {
+    pub fn new(reader: R, role: Role) -> Self {
+        Self { reader, role, deflate: None, partial: None }
+    }
+
+    /// Accepts the compressed messages of the peer, once `permessage-deflate` is negotiated
+    pub fn with_deflate(self, deflate: Option<PerMessageDeflate>) -> Self {
+        Self { deflate, ..self }
+    }
+
+    /// Reads the next message, returning `None` once the peer closed the stream.
+    ///
+    /// The control frames are returned as they arrive, even in the middle of a fragmented message.
+    pub async fn read_message(&mut self) -> io::Result<Option<Message>> {
+        loop {
+            let Some(frame) = self.read_frame().await? else {
+                return match self.partial {
+                    None => Ok(None),
+                    Some(_) => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "stream closed within a fragmented message")),
+                };
+            };
+            // Only the first frame of a data message may be marked as compressed
+            let starts_message = matches!(frame.opcode, OP_TEXT | OP_BINARY);
+            if frame.compressed && (self.deflate.is_none() || !starts_message) {
+                return Err(protocol_error("reserved bits set"));
+            }
+            match frame.opcode {
+                OP_CLOSE | OP_PING | OP_PONG => {
+                    if !frame.fin || frame.payload.len() > MAX_CONTROL_PAYLOAD_SIZE {
+                        return Err(protocol_error("fragmented or oversized control frame"));
+                    }
+                    return control_message(frame.opcode, frame.payload).map(Some);
+                }
+                OP_TEXT | OP_BINARY if self.partial.is_none() => {
+                    self.partial = Some(PartialMessage { opcode: frame.opcode, compressed: frame.compressed, data: frame.payload })
+                }
+                OP_CONTINUATION if self.partial.is_some() => {
+                    let partial = self.partial.as_mut().unwrap();
+                    if partial.data.len() + frame.payload.len() > MAX_MESSAGE_SIZE {
+                        return Err(invalid_data("message too large"));
+                    }
+                    partial.data.extend_from_slice(&frame.payload);
+                }
+                OP_TEXT | OP_BINARY | OP_CONTINUATION => return Err(protocol_error("unexpected data frame")),
+                opcode => return Err(protocol_error(&format!("unknown opcode {opcode:#x}"))),
+            }
+            if frame.fin {
+                let PartialMessage { opcode, compressed, data } = self.partial.take().unwrap();
+                let data = match (compressed, &self.deflate) {
+                    (true, Some(deflate)) => deflate.decompress(&data, MAX_MESSAGE_SIZE).map_err(invalid_data)?,
+                    _ => data,
+                };
+                return data_message(opcode, data).map(Some);
+            }
+        }
+    }
+
+    async fn read_frame(&mut self) -> io::Result<Option<Frame>> {
+        let mut head = [0u8; 2];
+        match self.reader.read_exact(&mut head[..1]).await {
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            result => result?,
+        };
+        self.reader.read_exact(&mut head[1..]).await?;
+        if head[0] & 0x70 & !RSV1 != 0 {
+            return Err(protocol_error("reserved bits set"));
+        }
+        let fin = head[0] & 0x80 != 0;
+        let compressed = head[0] & RSV1 != 0;
+        let opcode = head[0] & 0x0f;
+        let masked = head[1] & 0x80 != 0;
+        if masked != (self.role == Role::Server) {
+            return Err(protocol_error("unexpected frame masking"));
+        }
+        let len = match head[1] & 0x7f {
+            126 => self.reader.read_u16().await? as u64,
+            127 => self.reader.read_u64().await?,
+            len => len as u64,
+        };
+        if len > MAX_MESSAGE_SIZE as u64 {
+            return Err(invalid_data("message too large"));
+        }
+        let mut mask = [0u8; 4];
+        if masked {
+            self.reader.read_exact(&mut mask).await?;
+        }
+        let mut payload = vec![0u8; len as usize];
+        self.reader.read_exact(&mut payload).await?;
+        if masked {
+            apply_mask(&mut payload, mask);
+        }
+        Ok(Some(Frame { fin, compressed, opcode, payload }))
+    }
+}
+
+/// Writes the messages of a WebSocket, each in a single frame
+pub struct FrameWriter<W> {
+    writer: W,
+    role: Role,
+    /// Negotiated compression of the messages, if any
+    deflate: Option<PerMessageDeflate>,
+}
+
+impl<W: AsyncWrite + Unpin> FrameWriter<W> {
+    pub fn new(writer: W, role: Role) -> Self {
+        Self { writer, role, deflate: None }
+    }
+
+    /// Compresses the data messages, once `permessage-deflate` is negotiated
+    pub fn with_deflate(self, deflate: Option<PerMessageDeflate>) -> Self {
+        Self { deflate, ..self }
+    }
+
+    /// Writes `message` and flushes it to the stream
+    pub async fn write_message(&mut self, message: Message) -> io::Result<()> {
+        let (opcode, mut payload) = match message {
+            Message::Text(text) => (OP_TEXT, text.into_bytes()),
+            Message::Binary(data) => (OP_BINARY, data),
+            Message::Ping(data) => (OP_PING, data),
+            Message::Pong(data) => (OP_PONG, data),
+            Message::Close(frame) => (OP_CLOSE, frame.map(close_payload).unwrap_or_default()),
+            Message::Frame(_) => return Err(io::Error::new(io::ErrorKind::InvalidInput, "raw frames cannot be written")),
+        };
+        let mut compressed = false;
+        if let (Some(deflate), OP_TEXT | OP_BINARY) = (&self.deflate, opcode) {
+            payload = deflate.compress(&payload);
+            compressed = true;
+        }
+        self.write_frame(opcode, compressed, payload).await?;
+        self.writer.flush().await
+    }
+
+    async fn write_frame(&mut self, opcode: u8, compressed: bool, mut payload: Vec<u8>) -> io::Result<()> {
+        let mut head = Vec::with_capacity(14);
+        head.push(0x80 | if compressed { RSV1 } else { 0 } | opcode);
+        let mask_bit = if self.role == Role::Client { 0x80 } else { 0 };
+        match payload.len() {
+            len if len < 126 => head.push(mask_bit | len as u8),
+            len if len <= u16::MAX as usize => {
+                head.push(mask_bit | 126);
+                head.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+            len => {
+                head.push(mask_bit | 127);
+                head.extend_from_slice(&(len as u64).to_be_bytes());
+            }
+        }
+        if self.role == Role::Client {
+            let mask: [u8; 4] = rand::random();
+            head.extend_from_slice(&mask);
+            apply_mask(&mut payload, mask);
+        }
+        self.writer.write_all(&head).await?;
+        self.writer.write_all(&payload).await
+    }
+}
+
+fn apply_mask(payload: &mut [u8], mask: [u8; 4]) {
+    payload.iter_mut().enumerate().for_each(|(i, byte)| *byte ^= mask[i % 4]);
+}
+
+fn control_message(opcode: u8, payload: Vec<u8>) -> io::Result<Message> {
+    match opcode {
+        OP_PING => Ok(Message::Ping(payload)),
+        OP_PONG => Ok(Message::Pong(payload)),
+        _ => match payload.len() {
+            0 => Ok(Message::Close(None)),
+            1 => Err(protocol_error("truncated close code")),
+            _ => {
+                let code = CloseCode::from(u16::from_be_bytes([payload[0], payload[1]]));
+                let reason = String::from_utf8(payload[2..].to_vec()).map_err(invalid_data)?;
+                Ok(Message::Close(Some(CloseFrame { code, reason: Cow::Owned(reason) })))
+            }
+        },
+    }
+}
+
+fn data_message(opcode: u8, data: Vec<u8>) -> io::Result<Message> {
+    match opcode {
+        OP_TEXT => String::from_utf8(data).map(Message::Text).map_err(invalid_data),
+        _ => Ok(Message::Binary(data)),
+    }
+}
+
+fn close_payload(frame: CloseFrame<'static>) -> Vec<u8> {
+    let mut payload = u16::from(frame.code).to_be_bytes().to_vec();
+    payload.extend_from_slice(frame.reason.as_bytes());
+    payload
+}
+
+fn protocol_error(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("WebSocket protocol error: {reason}"))
+}
+
+fn invalid_data<E: ToString>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{duplex, BufReader};
+
+    #[test]
+    fn test_upgrade_request() {
+        let head = "GET /?encoding=json HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: keep-alive, Upgrade\r\n\
+                    Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\
+                    Sec-WebSocket-Protocol: kaspa-json\r\nsec-websocket-protocol: kaspa-wrpc.v1\r\n\r\n";
+        let request = UpgradeRequest::parse(head).unwrap();
+        assert_eq!(request.target, "/?encoding=json");
+        assert_eq!(request.query(), Some("encoding=json"));
+        assert_eq!(request.subprotocols().as_deref(), Some("kaspa-json, kaspa-wrpc.v1"), "repeated headers should be joined");
+
+        // The accept key of the sample handshake of RFC 6455
+        let response = accept_response(&request, Some("kaspa-json"), None).unwrap();
+        assert!(response.starts_with("HTTP/1.1 101 "), "{response}");
+        assert!(response.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"), "{response}");
+        assert!(response.contains("Sec-WebSocket-Protocol: kaspa-json\r\n"), "{response}");
+
+        let plain_http = UpgradeRequest::parse("GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        assert!(accept_response(&plain_http, None, None).is_err(), "a plain HTTP request should not be upgraded");
+        assert!(UpgradeRequest::parse("POST / HTTP/1.1\r\n\r\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_frames_round_trip() {
+        let (client, server) = duplex(1 << 20);
+        let mut writer = FrameWriter::new(client, Role::Client);
+        let mut reader = FrameReader::new(BufReader::new(server), Role::Server);
+        let messages = vec![
+            Message::Text("hello".to_string()),
+            Message::Binary(vec![7; 200]),
+            Message::Binary(vec![1; 70_000]),
+            Message::Ping(b"ping".to_vec()),
+            Message::Close(Some(CloseFrame { code: CloseCode::Away, reason: "bye".into() })),
+        ];
+        for message in messages.clone() {
+            writer.write_message(message).await.unwrap();
+        }
+        for message in messages {
+            assert_eq!(reader.read_message().await.unwrap(), Some(message));
+        }
+        drop(writer);
+        assert_eq!(reader.read_message().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_compressed_frames_round_trip() {
+        let (client, server) = duplex(1 << 20);
+        let deflate = Some(PerMessageDeflate::new(9));
+        let mut writer = FrameWriter::new(client, Role::Client).with_deflate(deflate.clone());
+        let mut reader = FrameReader::new(BufReader::new(server), Role::Server).with_deflate(deflate);
+        let messages = vec![
+            Message::Text("hello ".repeat(1000)),
+            Message::Binary(vec![7; 70_000]),
+            Message::Ping(b"ping".to_vec()),
+            Message::Binary(vec![]),
+        ];
+        for message in messages.clone() {
+            writer.write_message(message).await.unwrap();
+        }
+        for message in messages {
+            assert_eq!(reader.read_message().await.unwrap(), Some(message));
+        }
+
+        // A peer that did not negotiate compression rejects the compressed frames
+        let (client, server) = duplex(1024);
+        let mut writer = FrameWriter::new(client, Role::Client).with_deflate(Some(PerMessageDeflate::new(6)));
+        let mut reader = FrameReader::new(BufReader::new(server), Role::Server);
+        writer.write_message(Message::Text("compressed".to_string())).await.unwrap();
+        assert!(reader.read_message().await.is_err(), "a compressed frame should be rejected without negotiation");
+    }
+
+    #[tokio::test]
+    async fn test_unmasked_client_frame_is_rejected() {
+        let (client, server) = duplex(1024);
+        let mut writer = FrameWriter::new(client, Role::Server);
+        let mut reader = FrameReader::new(BufReader::new(server), Role::Server);
+        writer.write_message(Message::Text("unmasked".to_string())).await.unwrap();
+        assert!(reader.read_message().await.is_err(), "a server should reject the unmasked frames");
+    }
+}
@@ -744,6 +744,7 @@ impl KaspadGoParams {
             max_difficulty: DIFFICULTY_MAX,
             max_difficulty_f64: DIFFICULTY_MAX_AS_F64,
             difficulty_window_size: self.DifficultyAdjustmentWindowSize,
+            min_difficulty_window_size: MAINNET_PARAMS.min_difficulty_window_size,
             mergeset_size_limit: self.MergeSetSizeLimit,
             merge_depth: self.MergeDepth,
             finality_depth,
@@ -119,7 +119,7 @@ impl Wallet {
     }
 
     pub async fn new_address(self: &Arc<Wallet>) -> Result<String> {
-        let address = self.hd_wallet.receive_wallet().new_address().await?;
+        let address = self.hd_wallet.receive_wallet()?.new_address().await?;
         Ok(address.into())
         //Ok("new_address".to_string())
     }
@@ -1,13 +1,18 @@
+use async_trait::async_trait;
 use hmac::Mac;
 use kaspa_addresses::{Address, Prefix as AddressPrefix, Version};
+use kaspa_txscript::{extract_script_pub_key_address, multisig_redeem_script, pay_to_script_hash_script};
 use ripemd::Ripemd160;
 use sha2::{Digest, Sha256};
 use std::{
+    collections::HashMap,
     fmt::Debug,
+    future::{ready, Future},
+    ops::Range,
     str::FromStr,
     sync::{Arc, Mutex},
 };
-use zeroize::Zeroizing;
+use zeroize::{Zeroize, Zeroizing};
 
 use kaspa_bip32::{
     types::*, AddressType, ChildNumber, ExtendedKey, ExtendedKeyAttrs, ExtendedPrivateKey, ExtendedPublicKey, Prefix, PrivateKey,
@@ -24,8 +29,123 @@ where
     digest[..4].try_into().expect("digest truncated")
 }
 
+/// Depth of the account node `m/<Purpose>'/111111'/<Account Index>'`
+const ACCOUNT_DEPTH: Depth = 3;
+
+/// Maximum number of accounts scanned by [`HDWalletGen1::discover_accounts`]
+pub const MAX_DISCOVERED_ACCOUNTS: u64 = 1_000;
+
+/// Prefix of the extended public keys of `network`
+fn xpub_prefix(network: AddressPrefix) -> Prefix {
+    match network {
+        AddressPrefix::Mainnet => Prefix::KPUB,
+        AddressPrefix::Testnet | AddressPrefix::Simnet | AddressPrefix::Devnet => Prefix::TPUB,
+    }
+}
+
+/// Extended public key prefix of the network an extended key `prefix` belongs to,
+/// `None` for the prefixes of no Kaspa network
+fn network_xpub_prefix(prefix: Prefix) -> Option<Prefix> {
+    if prefix == Prefix::KPRV || prefix == Prefix::KPUB {
+        Some(Prefix::KPUB)
+    } else if prefix == Prefix::TPRV || prefix == Prefix::TPUB {
+        Some(Prefix::TPUB)
+    } else {
+        None
+    }
+}
+
+/// Private key and attributes of a node of a derivation path, wiped on drop.
+///
+/// Retained as the account node of wallets built from a private key, watch-only
+/// wallets holding public material only.
+struct PrivateNode {
+    private_key: PrivateKeyBytes,
+    attrs: ExtendedKeyAttrs,
+}
+
+impl PrivateNode {
+    fn new(private_key: &SecretKey, attrs: ExtendedKeyAttrs) -> Self {
+        Self { private_key: private_key.to_bytes(), attrs }
+    }
+
+    /// Runs `f` with the private key of the node, which is only lent to it
+    fn with_private_key<T>(&self, f: impl FnOnce(&SecretKey) -> Result<T>) -> Result<T> {
+        f(&SecretKey::from_bytes(&self.private_key)?)
+    }
+
+    async fn derive_child(&self, child_number: ChildNumber) -> Result<Self> {
+        self.with_private_key(|private_key| {
+            let hmac = HDWalletGen1::create_hmac(private_key, &self.attrs, child_number.is_hardened())?;
+            // The child key only lives in wiped buffers until it is moved into the child node
+            let (child_key, chain_code) = HDWalletGen1::derive_key_bytes(private_key, child_number, hmac)?;
+            let depth = self.attrs.depth.checked_add(1).ok_or(Error::Depth)?;
+            let attrs = ExtendedKeyAttrs { parent_fingerprint: get_fingerprint(private_key), child_number, chain_code, depth };
+            Ok(Self { private_key: *child_key, attrs })
+        })
+    }
+
+    fn public_key(&self) -> Result<ExtendedPublicKey<secp256k1::PublicKey>> {
+        self.with_private_key(|private_key| {
+            Ok(ExtendedPublicKey { public_key: private_key.get_public_key(), attrs: self.attrs.clone() })
+        })
+    }
+}
+
+impl Drop for PrivateNode {
+    fn drop(&mut self) {
+        self.private_key.zeroize();
+        self.attrs.chain_code.zeroize();
+    }
+}
+
+/// Kind of signature scheme an address is spendable with
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AddressKind {
+    /// P2PK address committing to the 32-byte x-only public key
+    #[default]
+    Schnorr,
+    /// P2PK ECDSA address committing to the 33-byte compressed public key
+    Ecdsa,
+}
+
+/// Default number of consecutive unused addresses ending a scan, see [`HDWalletInner::scan_range_with`]
+pub const DEFAULT_GAP_LIMIT: u32 = 20;
+
+/// Usage of an address, as reported by the checks of [`HDWalletInner::scan_range_with`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddressUsage {
+    Used,
+    Unused,
+}
+
+/// Storage backend durably recording the derivation index of a wallet branch,
+/// so that already issued addresses are not handed out again after a restart.
+#[async_trait]
+pub trait DerivationIndexStorage: Send + Sync {
+    async fn store_index(&self, index: u32) -> Result<()>;
+}
+
+/// Redeem script template of the P2SH addresses derived by a wallet branch,
+/// for instance a timelock vault spendable with the derived key
+///
+/// Implemented by closures mapping the x-only public key derived at an index to the redeem script.
+pub trait ScriptTemplate: Send + Sync {
+    fn redeem_script(&self, public_key: &[u8]) -> Result<Vec<u8>>;
+}
+
+impl<F> ScriptTemplate for F
+where
+    F: Fn(&[u8]) -> Result<Vec<u8>> + Send + Sync,
+{
+    fn redeem_script(&self, public_key: &[u8]) -> Result<Vec<u8>> {
+        self(public_key)
+    }
+}
+
+/// Key of a single-key branch
 #[derive(Clone)]
-pub struct HDWalletInner {
+struct SingleKey {
     /// Derived public key
     public_key: secp256k1::PublicKey,
 
@@ -36,8 +156,73 @@ pub struct HDWalletInner {
     fingerprint: KeyFingerprint,
 
     hmac: HmacSha512,
+}
+
+/// Keys the addresses of a branch are derived from
+#[derive(Clone)]
+enum BranchKey {
+    Single(SingleKey),
+    /// Cosigner branches of a multisig branch, which has no key of its own
+    Multisig(Arc<MultisigBranch>),
+}
+
+#[derive(Clone)]
+pub struct HDWalletInner {
+    key: BranchKey,
 
     index: Arc<Mutex<u32>>,
+
+    /// Redeem script template when this branch derives P2SH addresses of a single key
+    script_template: Option<Arc<dyn ScriptTemplate>>,
+
+    /// Optional backend persisting the derivation index
+    storage: Option<Arc<dyn DerivationIndexStorage>>,
+
+    /// Addresses of the indexes up to the current one, for reverse lookups
+    addresses: Arc<Mutex<AddressIndexMap>>,
+
+    /// Number of consecutive unused addresses ending a scan
+    gap_limit: u32,
+
+    /// Network of the derived addresses
+    network: AddressPrefix,
+}
+
+/// Reverse lookup map of the addresses of a branch, filled up to the current index only
+#[derive(Default)]
+struct AddressIndexMap {
+    indexes: HashMap<Address, u32>,
+    /// The indexes below this one, and only them, are in `indexes`
+    scanned: u32,
+}
+
+impl AddressIndexMap {
+    /// Forgets the addresses past `index`, once the current index is moved back to it
+    fn truncate(&mut self, index: u32) {
+        if self.scanned > index.saturating_add(1) {
+            self.indexes.retain(|_, address_index| *address_index <= index);
+            self.scanned = index.saturating_add(1);
+        }
+    }
+}
+
+#[derive(Clone)]
+struct MultisigBranch {
+    required: usize,
+    cosigners: Vec<HDWalletInner>,
+}
+
+impl MultisigBranch {
+    /// Derive the P2SH address of a `required`-of-n redeem script built from the
+    /// cosigner keys at `index`. Keys are sorted so the address does not depend
+    /// on the order in which cosigners were provided.
+    fn derive_address(&self, index: u32, network: AddressPrefix) -> Result<Address> {
+        let mut pub_keys = self.cosigners.iter().map(|x| x.derive_public_key_bytes(index)).collect::<Result<Vec<_>>>()?;
+        pub_keys.sort();
+        let redeem_script = multisig_redeem_script(&pub_keys, self.required).map_err(|err| Error::String(err.to_string()))?;
+        extract_script_pub_key_address(&pay_to_script_hash_script(&redeem_script), network)
+            .map_err(|err| Error::String(err.to_string()))
+    }
 }
 
 impl HDWalletInner {
@@ -48,13 +233,90 @@ impl HDWalletInner {
         hmac: HmacSha512,
         index: u32,
     ) -> Result<Self> {
-        let wallet = Self { public_key, attrs, fingerprint, hmac, index: Arc::new(Mutex::new(index)) };
+        Ok(Self::with_key(BranchKey::Single(SingleKey { public_key, attrs, fingerprint, hmac }), index))
+    }
 
-        Ok(wallet)
+    /// Build a branch deriving the P2SH addresses of `required`-of-n redeem scripts built from the
+    /// keys of the `cosigners` branches at each index
+    ///
+    /// The branch has its own index, the indexes of the cosigner branches being ignored, and no key
+    /// of its own, so the accessors of the branch key fail.
+    fn new_multisig(required: usize, cosigners: Vec<HDWalletInner>) -> Self {
+        Self::with_key(BranchKey::Multisig(Arc::new(MultisigBranch { required, cosigners })), 0)
+    }
+
+    fn with_key(key: BranchKey, index: u32) -> Self {
+        Self {
+            key,
+            index: Arc::new(Mutex::new(index)),
+            script_template: None,
+            storage: None,
+            addresses: Arc::new(Mutex::new(AddressIndexMap::default())),
+            gap_limit: DEFAULT_GAP_LIMIT,
+            network: AddressPrefix::Mainnet,
+        }
+    }
+
+    /// Returns `true` if this branch derives multisig addresses
+    pub fn is_multisig(&self) -> bool {
+        matches!(self.key, BranchKey::Multisig(_))
     }
 
+    fn single_key(&self) -> Result<&SingleKey> {
+        match self.key {
+            BranchKey::Single(ref key) => Ok(key),
+            BranchKey::Multisig(_) => Err(Error::String("multisig branches have no single public key".to_string())),
+        }
+    }
+
+    /// Attach a storage backend to this branch, restoring `stored_index` as the
+    /// current index. `stored_index` is expected to be the last value recorded
+    /// by `storage`; every later call to [`Self::new_address`] persists the new index.
+    pub fn with_index_storage(mut self, storage: Arc<dyn DerivationIndexStorage>, stored_index: u32) -> Self {
+        self.index = Arc::new(Mutex::new(stored_index));
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Set the number of consecutive unused addresses ending a scan of [`Self::scan_range_with`]
+    pub fn with_gap_limit(mut self, gap_limit: u32) -> Self {
+        self.gap_limit = gap_limit;
+        self
+    }
+
+    pub fn gap_limit(&self) -> u32 {
+        self.gap_limit
+    }
+
+    /// Derive the addresses of `network`, mainnet addresses being derived by default
+    pub fn with_network(mut self, network: AddressPrefix) -> Self {
+        self.network = network;
+        self.addresses = Arc::new(Mutex::new(AddressIndexMap::default()));
+        self
+    }
+
+    pub fn network(&self) -> AddressPrefix {
+        self.network
+    }
+
+    /// Durably record the current index. This is a no-op when no storage backend is attached.
+    pub async fn flush_index(&self) -> Result<()> {
+        match self.storage {
+            Some(ref storage) => storage.store_index(self.index()?).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Advance the current index and return its address
+    ///
+    /// The new index is persisted before being committed in memory, so a failing storage
+    /// backend leaves the branch at its previous index.
     pub async fn new_address(&self) -> Result<Address> {
-        self.set_index(self.index()? + 1)?;
+        let index = self.index()? + 1;
+        if let Some(ref storage) = self.storage {
+            storage.store_index(index).await?;
+        }
+        self.set_index(index)?;
         self.current_address().await
     }
 
@@ -64,6 +326,7 @@ impl HDWalletInner {
 
     pub fn set_index(&self, index: u32) -> Result<()> {
         *self.index.lock()? = index;
+        self.addresses.lock()?.truncate(index);
         Ok(())
     }
 
@@ -74,76 +337,305 @@ impl HDWalletInner {
         Ok(address)
     }
     pub async fn derive_address(&self, index: u32) -> Result<Address> {
-        let (key, _chain_code) = HDWalletGen1::derive_public_key_child(&self.public_key, index, self.hmac.clone())?;
+        self.derive_address_at(index)
+    }
 
-        let pubkey = &key.to_bytes()[1..];
-        let address = Address::new(AddressPrefix::Mainnet, Version::PubKey, pubkey);
+    pub async fn derive_address_with_kind(&self, index: u32, kind: AddressKind) -> Result<Address> {
+        self.derive_address_at_with_kind(index, kind)
+    }
+
+    fn derive_address_at(&self, index: u32) -> Result<Address> {
+        self.derive_address_at_with_kind(index, AddressKind::Schnorr)
+    }
+
+    /// Returns the index at which this branch derives `address`, looking up
+    /// the indexes up to the current one. Returns `None` for an unknown address.
+    ///
+    /// The addresses of the indexes not looked up yet are derived, outside of the lock, and recorded by this call only,
+    /// so the lookup map never grows past the current index, whatever the ranges derived by the scans.
+    pub async fn index_of(&self, address: &Address) -> Result<Option<u32>> {
+        let current = self.index()?;
+        let scanned = self.addresses.lock()?.scanned;
+        let derived = (scanned..=current).map(|index| Ok((self.derive_address_at(index)?, index))).collect::<Result<Vec<_>>>()?;
+        let mut addresses = self.addresses.lock()?;
+        // Another lookup may have recorded some of these indexes meanwhile, or the current index may have been moved back
+        let current = current.min(self.index()?);
+        for (address, index) in derived.into_iter().filter(|(_, index)| *index >= addresses.scanned && *index <= current) {
+            addresses.indexes.entry(address).or_insert(index);
+            addresses.scanned = index.saturating_add(1);
+        }
+        Ok(addresses.indexes.get(address).copied())
+    }
+
+    fn derive_address_at_with_kind(&self, index: u32, kind: AddressKind) -> Result<Address> {
+        if let BranchKey::Multisig(ref multisig) = self.key {
+            if kind != AddressKind::Schnorr {
+                return Err(Error::String("multisig addresses only support schnorr keys".to_string()));
+            }
+            return multisig.derive_address(index, self.network);
+        }
+
+        if let Some(ref script_template) = self.script_template {
+            if kind != AddressKind::Schnorr {
+                return Err(Error::String("script template addresses only support schnorr keys".to_string()));
+            }
+            let redeem_script = script_template.redeem_script(&self.derive_public_key_bytes(index)?)?;
+            return extract_script_pub_key_address(&pay_to_script_hash_script(&redeem_script), self.network)
+                .map_err(|err| Error::String(err.to_string()));
+        }
+
+        let address = match kind {
+            AddressKind::Schnorr => Address::new(self.network, Version::PubKey, &self.derive_public_key_bytes(index)?),
+            AddressKind::Ecdsa => Address::new(self.network, Version::PubKeyECDSA, &self.derive_child_public_key(index)?.to_bytes()),
+        };
 
         Ok(address)
     }
 
-    pub fn public_key(&self) -> ExtendedPublicKey<secp256k1::PublicKey> {
-        self.into()
+    fn derive_child_public_key(&self, index: u32) -> Result<secp256k1::PublicKey> {
+        let single_key = self.single_key()?;
+        let (key, _chain_code) = HDWalletGen1::derive_public_key_child(&single_key.public_key, index, single_key.hmac.clone())?;
+        Ok(key)
+    }
+
+    fn derive_public_key_bytes(&self, index: u32) -> Result<[u8; 32]> {
+        Ok(self.derive_child_public_key(index)?.to_bytes()[1..].try_into()?)
+    }
+
+    /// Derive the addresses of a range of indexes, in index order
+    pub async fn get_range(&self, range: Range<u32>) -> Result<Vec<Address>> {
+        range.map(|index| self.derive_address_at(index)).collect()
+    }
+
+    /// Derive the addresses of a range of indexes, splitting the work across at most
+    /// `concurrency` blocking tasks, so the executor threads are never blocked by the
+    /// derivation. Addresses are returned in index order.
+    ///
+    /// On wasm32 targets, where threads are not available, this falls back to [`Self::get_range`].
+    pub async fn get_range_parallel(&self, range: Range<u32>, concurrency: usize) -> Result<Vec<Address>> {
+        #[cfg(target_arch = "wasm32")]
+        {
+            let _ = concurrency;
+            self.get_range(range).await
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let chunk_size = (range.len().div_ceil(concurrency.max(1)) as u32).max(1);
+            let tasks = (range.start..range.end)
+                .step_by(chunk_size as usize)
+                .map(|start| {
+                    let branch = self.clone();
+                    let chunk = start..start.saturating_add(chunk_size).min(range.end);
+                    tokio::task::spawn_blocking(move || chunk.map(|index| branch.derive_address_at(index)).collect::<Result<Vec<_>>>())
+                })
+                .collect::<Vec<_>>();
+            let mut addresses = Vec::with_capacity(range.len());
+            for task in tasks {
+                let chunk = task.await.map_err(|err| Error::String(format!("address derivation task failed: {err}")))?;
+                addresses.extend(chunk?);
+            }
+
+            Ok(addresses)
+        }
+    }
+
+    /// Derive addresses starting at index 0 until `gap_limit` consecutive addresses
+    /// are reported unused by `is_used`. The returned addresses cover every index
+    /// up to the last used one, followed by the trailing gap of unused addresses.
+    pub async fn scan_addresses(&self, gap_limit: u32, is_used: impl Fn(&Address) -> bool) -> Result<Vec<Address>> {
+        self.scan_with_gap(0, gap_limit, |_, address| {
+            ready(Ok(if is_used(&address) { AddressUsage::Used } else { AddressUsage::Unused }))
+        })
+        .await
+    }
+
+    /// Derive addresses starting at index `start`, awaiting for each one the usage reported by `check`,
+    /// until [`Self::gap_limit`] consecutive addresses are reported unused.
+    ///
+    /// Unlike [`Self::scan_addresses`], `check` may perform some I/O, such as a lookup of the history of an address.
+    /// The returned addresses cover every index from `start` up to the last used one, followed by the trailing gap of
+    /// unused addresses. The first failing check aborts the scan.
+    pub async fn scan_range_with<F, Fut>(&self, start: u32, check: F) -> Result<Vec<Address>>
+    where
+        F: FnMut(u32, Address) -> Fut,
+        Fut: Future<Output = Result<AddressUsage>>,
+    {
+        self.scan_with_gap(start, self.gap_limit, check).await
+    }
+
+    /// Scan from `start` until `gap_limit` consecutive addresses are reported unused by `check`
+    async fn scan_with_gap<F, Fut>(&self, start: u32, gap_limit: u32, mut check: F) -> Result<Vec<Address>>
+    where
+        F: FnMut(u32, Address) -> Fut,
+        Fut: Future<Output = Result<AddressUsage>>,
+    {
+        let mut addresses = Vec::new();
+        let mut gap = 0;
+        let mut index = start;
+        while gap < gap_limit {
+            let address = self.derive_address_at(index)?;
+            gap = match check(index, address.clone()).await? {
+                AddressUsage::Used => 0,
+                AddressUsage::Unused => gap + 1,
+            };
+            addresses.push(address);
+            index = index.checked_add(1).ok_or_else(|| Error::String("address index overflow".to_string()))?;
+        }
+
+        Ok(addresses)
+    }
+
+    /// Extended public key of the branch, an error being returned for multisig branches
+    pub fn public_key(&self) -> Result<ExtendedPublicKey<secp256k1::PublicKey>> {
+        self.try_into()
     }
 
-    pub fn attrs(&self) -> &ExtendedKeyAttrs {
-        &self.attrs
+    pub fn attrs(&self) -> Result<&ExtendedKeyAttrs> {
+        Ok(&self.single_key()?.attrs)
     }
 
     /// Serialize the raw public key as a byte array.
-    pub fn to_bytes(&self) -> PublicKeyBytes {
-        self.public_key().to_bytes()
+    pub fn to_bytes(&self) -> Result<PublicKeyBytes> {
+        Ok(self.public_key()?.to_bytes())
     }
 
     /// Serialize this key as an [`ExtendedKey`].
-    pub fn to_extended_key(&self, prefix: Prefix) -> ExtendedKey {
+    pub fn to_extended_key(&self, prefix: Prefix) -> Result<ExtendedKey> {
         let mut key_bytes = [0u8; KEY_SIZE + 1];
-        key_bytes[..].copy_from_slice(&self.to_bytes());
-        ExtendedKey { prefix, attrs: self.attrs.clone(), key_bytes }
+        key_bytes[..].copy_from_slice(&self.to_bytes()?);
+        Ok(ExtendedKey { prefix, attrs: self.attrs()?.clone(), key_bytes })
     }
 
-    pub fn to_string(&self) -> Zeroizing<String> {
-        Zeroizing::new(self.to_extended_key(Prefix::KPUB).to_string())
+    pub fn to_string(&self) -> Result<Zeroizing<String>> {
+        Ok(Zeroizing::new(self.to_extended_key(Prefix::KPUB)?.to_string()))
     }
 }
 
-impl From<&HDWalletInner> for ExtendedPublicKey<secp256k1::PublicKey> {
-    fn from(inner: &HDWalletInner) -> ExtendedPublicKey<secp256k1::PublicKey> {
-        ExtendedPublicKey { public_key: inner.public_key, attrs: inner.attrs().clone() }
+impl TryFrom<&HDWalletInner> for ExtendedPublicKey<secp256k1::PublicKey> {
+    type Error = Error;
+
+    fn try_from(inner: &HDWalletInner) -> Result<ExtendedPublicKey<secp256k1::PublicKey>> {
+        let key = inner.single_key()?;
+        Ok(ExtendedPublicKey { public_key: key.public_key, attrs: key.attrs.clone() })
     }
 }
 
 #[derive(Clone)]
 pub struct HDWalletGen1 {
-    /// extended public key derived upto `m/<Purpose>'/111111'/<Account Index>'`
-    extended_public_key: ExtendedPublicKey<secp256k1::PublicKey>,
+    /// extended public key derived upto `m/<Purpose>'/111111'/<Account Index>'`,
+    /// `None` for multisig wallets which have no single account key
+    extended_public_key: Option<ExtendedPublicKey<secp256k1::PublicKey>>,
 
-    /// receive address wallet
-    receive_wallet: HDWalletInner,
+    /// receive address wallet, `None` for change-only wallets
+    receive_wallet: Option<HDWalletInner>,
 
     /// change address wallet
     change_wallet: HDWalletInner,
+
+    /// private account node, `None` for watch-only wallets
+    account_private_node: Option<Arc<PrivateNode>>,
+
+    /// BIP44 purpose of the account node
+    purpose: u32,
+
+    /// Network the derived addresses are expected to belong to
+    network: AddressPrefix,
+
+    /// Fail the derivation of any address not belonging to `network`, see [`Self::with_network_validation`]
+    validate_network: bool,
+
+    /// Extended public key prefix of the network of the key string the wallet was built from,
+    /// `None` if the wallet was built from a decoded key
+    key_prefix: Option<Prefix>,
 }
 
 impl HDWalletGen1 {
     /// build wallet from root/master private key
     pub async fn from_master_xprv(xprv: &str, is_multisig: bool, account_index: u64) -> Result<Self> {
-        let xprv_key = ExtendedPrivateKey::<SecretKey>::from_str(xprv)?;
-        let attrs = xprv_key.attrs();
+        let extended_key = ExtendedKey::from_str(xprv)?;
+        let key_prefix = network_xpub_prefix(extended_key.prefix);
+        let xprv_key = ExtendedPrivateKey::<SecretKey>::try_from(extended_key)?;
+        let master_node = PrivateNode::new(xprv_key.private_key(), xprv_key.attrs().clone());
 
-        let (extended_private_key, attrs) =
-            Self::create_extended_key(*xprv_key.private_key(), attrs.clone(), is_multisig, account_index).await?;
+        let account_node = Self::create_extended_key(master_node, is_multisig, account_index).await?;
 
-        let extended_public_key = ExtendedPublicKey { public_key: extended_private_key.get_public_key(), attrs };
+        let extended_public_key = account_node.public_key()?;
 
-        let wallet = Self::from_extended_public_key(extended_public_key).await?;
+        let mut wallet = Self::from_extended_public_key(extended_public_key).await?;
+        wallet.account_private_node = Some(Arc::new(account_node));
+        wallet.purpose = Self::purpose(is_multisig);
+        wallet.key_prefix = key_prefix;
 
         Ok(wallet)
     }
 
+    /// Discover the accounts of the root/master private key, following the BIP44 account discovery.
+    ///
+    /// The receive branch of each account, from index 0 on, is scanned with a gap of `address_gap` addresses, the
+    /// account being used if any of them is reported used by `is_used`. The discovery stops after `account_gap`
+    /// consecutive unused accounts. The returned indexes cover every account up to the last used one, the unused
+    /// accounts in between included, and are empty if no account is used.
+    ///
+    /// The discovery fails if it has not stopped after scanning [`MAX_DISCOVERED_ACCOUNTS`] accounts.
+    pub async fn discover_accounts(
+        master_xprv: &str,
+        account_gap: u64,
+        address_gap: u32,
+        is_used: impl Fn(&Address) -> bool,
+    ) -> Result<Vec<u64>> {
+        let mut accounts = Vec::new();
+        let mut gap = 0;
+        let mut account_index = 0;
+        while gap < account_gap {
+            if account_index >= MAX_DISCOVERED_ACCOUNTS {
+                return Err(Error::String(format!("account discovery did not stop within {MAX_DISCOVERED_ACCOUNTS} accounts")));
+            }
+            let wallet = Self::from_master_xprv(master_xprv, false, account_index).await?;
+            // A scan only goes past the gap of addresses if one of them is used
+            let scanned = wallet.scan_receive_addresses(address_gap, &is_used).await?;
+            if scanned.len() > address_gap as usize {
+                accounts.extend(accounts.len() as u64..=account_index);
+                gap = 0;
+            } else {
+                gap += 1;
+            }
+            account_index += 1;
+        }
+
+        Ok(accounts)
+    }
+
+    /// build a watch-only wallet from the `kpub` string of a mainnet account node,
+    /// see [`Self::from_extended_public_key_str_for_network`]
     pub async fn from_extended_public_key_str(xpub: &str) -> Result<Self> {
-        let extended_public_key = ExtendedPublicKey::<secp256k1::PublicKey>::from_str(xpub)?;
-        let wallet = Self::from_extended_public_key(extended_public_key).await?;
+        Self::from_extended_public_key_str_for_network(xpub, AddressPrefix::Mainnet).await
+    }
+
+    /// build a watch-only wallet from the extended public key string of an account node of `network`,
+    /// deriving the addresses of `network`
+    ///
+    /// The key is rejected if its prefix belongs to another network or if it is not at the
+    /// account node depth (`m/<Purpose>'/111111'/<Account Index>'`).
+    pub async fn from_extended_public_key_str_for_network(xpub: &str, network: AddressPrefix) -> Result<Self> {
+        let extended_key = ExtendedKey::from_str(xpub)?;
+        let expected_prefix = xpub_prefix(network);
+        if extended_key.prefix != expected_prefix {
+            return Err(Error::String(format!(
+                "extended public key prefix `{}` does not match the {network} network, `{expected_prefix}` expected",
+                extended_key.prefix
+            )));
+        }
+        if extended_key.attrs.depth != ACCOUNT_DEPTH {
+            return Err(Error::String(format!(
+                "extended public key at depth {} is not an account key, depth {ACCOUNT_DEPTH} expected",
+                extended_key.attrs.depth
+            )));
+        }
+
+        let extended_public_key = ExtendedPublicKey::<secp256k1::PublicKey>::try_from(extended_key)?;
+        let mut wallet = Self::from_extended_public_key(extended_public_key).await?.with_network(network);
+        wallet.key_prefix = Some(expected_prefix);
         Ok(wallet)
     }
 
@@ -152,29 +644,261 @@ impl HDWalletGen1 {
 
         let change_wallet = Self::derive_wallet(extended_public_key.clone(), AddressType::Change).await?;
 
-        let wallet = Self { extended_public_key, receive_wallet, change_wallet };
+        let wallet = Self {
+            extended_public_key: Some(extended_public_key),
+            receive_wallet: Some(receive_wallet),
+            change_wallet,
+            account_private_node: None,
+            purpose: Self::purpose(false),
+            network: AddressPrefix::Mainnet,
+            validate_network: false,
+            key_prefix: None,
+        };
 
         Ok(wallet)
     }
 
-    async fn create_extended_key(
-        mut private_key: SecretKey,
-        mut attrs: ExtendedKeyAttrs,
-        is_multisig: bool,
+    /// build a watch-only wallet deriving only the change branch of an account extended public key
+    ///
+    /// Meant for tools only dealing with change outputs, this skips the derivation of the receive
+    /// branch, any receive branch operation then failing with an error, see [`Self::is_change_only`].
+    pub async fn change_only_from_extended_public_key(extended_public_key: ExtendedPublicKey<secp256k1::PublicKey>) -> Result<Self> {
+        let change_wallet = Self::derive_wallet(extended_public_key.clone(), AddressType::Change).await?;
+
+        let wallet = Self {
+            extended_public_key: Some(extended_public_key),
+            receive_wallet: None,
+            change_wallet,
+            account_private_node: None,
+            purpose: Self::purpose(false),
+            network: AddressPrefix::Mainnet,
+            validate_network: false,
+            key_prefix: None,
+        };
+
+        Ok(wallet)
+    }
+
+    /// build a `required`-of-n multisig wallet from the cosigners extended public keys of account `account_index`
+    ///
+    /// Each cosigner key must be the account node `m/45'/111111'/<account_index>'` and is
+    /// derived at the receive and change branches only, so the cosigner keys of the addresses
+    /// are at `m/45'/111111'/<account_index>'/<Branch>/<Index>`. Derived addresses are P2SH
+    /// addresses which do not depend on the order of `cosigner_xpubs`.
+    pub async fn from_multisig(
+        cosigner_xpubs: Vec<ExtendedPublicKey<secp256k1::PublicKey>>,
+        required: usize,
         account_index: u64,
-    ) -> Result<(SecretKey, ExtendedKeyAttrs)> {
-        let purpose = if is_multisig { 45 } else { 44 };
-        let address_path = format!("{purpose}'/111111'/{account_index}'");
-        let children = address_path.split('/');
+    ) -> Result<Self> {
+        if required == 0 || required > cosigner_xpubs.len() {
+            return Err(Error::String(format!(
+                "invalid multisig: {required} required signatures out of {} cosigners",
+                cosigner_xpubs.len()
+            )));
+        }
+        if let Some(xpub) = cosigner_xpubs.iter().find(|xpub| xpub.attrs().depth != ACCOUNT_DEPTH) {
+            return Err(Error::String(format!(
+                "cosigner extended public key at depth {} is not an account key, depth {ACCOUNT_DEPTH} expected",
+                xpub.attrs().depth
+            )));
+        }
+        let account_child = Self::account_child_number(account_index)?;
+        if let Some(xpub) = cosigner_xpubs.iter().find(|xpub| xpub.attrs().child_number != account_child) {
+            return Err(Error::String(format!(
+                "cosigner extended public key of account {} does not match account {account_child}",
+                xpub.attrs().child_number
+            )));
+        }
+
+        let mut account_xpubs = cosigner_xpubs;
+        account_xpubs.sort();
+
+        let mut receive_wallets = Vec::with_capacity(account_xpubs.len());
+        let mut change_wallets = Vec::with_capacity(account_xpubs.len());
+        for xpub in account_xpubs.iter() {
+            receive_wallets.push(Self::derive_wallet(xpub.clone(), AddressType::Receive).await?);
+            change_wallets.push(Self::derive_wallet(xpub.clone(), AddressType::Change).await?);
+        }
+
+        let wallet = Self {
+            extended_public_key: None,
+            receive_wallet: Some(HDWalletInner::new_multisig(required, receive_wallets)),
+            change_wallet: HDWalletInner::new_multisig(required, change_wallets),
+            account_private_node: None,
+            purpose: Self::purpose(true),
+            network: AddressPrefix::Mainnet,
+            validate_network: false,
+            key_prefix: None,
+        };
+
+        Ok(wallet)
+    }
+
+    /// build a wallet deriving P2SH addresses from an account extended public key
+    ///
+    /// At each index of the receive and change branches, the derived key is composed into
+    /// `template` and the address pays to the hash of the resulting redeem script.
+    pub async fn from_script_template(
+        extended_public_key: ExtendedPublicKey<secp256k1::PublicKey>,
+        template: Arc<dyn ScriptTemplate>,
+    ) -> Result<Self> {
+        let mut wallet = Self::from_extended_public_key(extended_public_key).await?;
+        if let Some(receive_wallet) = wallet.receive_wallet.as_mut() {
+            receive_wallet.script_template = Some(template.clone());
+        }
+        wallet.change_wallet.script_template = Some(template);
+        Ok(wallet)
+    }
+
+    /// Derive the addresses of `network` on both branches, mainnet addresses being derived by default
+    pub fn with_network(mut self, network: AddressPrefix) -> Self {
+        self.receive_wallet = self.receive_wallet.map(|receive_wallet| receive_wallet.with_network(network));
+        self.change_wallet = self.change_wallet.with_network(network);
+        self.network = network;
+        self
+    }
+
+    pub fn network(&self) -> AddressPrefix {
+        self.network
+    }
+
+    /// Check at runtime that every derived address belongs to the network of the wallet, failing the derivation of
+    /// a wrong-network address with an error instead of returning it.
+    ///
+    /// The network of the wallet must also be the one of the `kprv`/`kpub` or `tprv`/`tpub` key string the wallet
+    /// was built from, so that a testnet key configured for mainnet, or the other way around, is caught.
+    /// Disabled by default, the check then only being debug-asserted.
+    pub fn with_network_validation(mut self, validate_network: bool) -> Self {
+        self.validate_network = validate_network;
+        self
+    }
+
+    /// Ensures `address` belongs to the network of the wallet, see [`Self::with_network_validation`]
+    fn check_network(&self, address: Address) -> Result<Address> {
+        let mismatch = if address.prefix != self.network {
+            Some(format!("derived address {address} does not belong to the {} network", self.network))
+        } else {
+            self.key_prefix.filter(|prefix| *prefix != xpub_prefix(self.network)).map(|prefix| {
+                format!("the `{prefix}` key of the wallet does not belong to the {} network of address {address}", self.network)
+            })
+        };
+        match mismatch {
+            Some(mismatch) if self.validate_network => Err(Error::String(mismatch)),
+            mismatch => {
+                debug_assert!(mismatch.is_none(), "{}", mismatch.unwrap_or_default());
+                Ok(address)
+            }
+        }
+    }
+
+    /// Returns `true` if this wallet derives multisig addresses
+    pub fn is_multisig(&self) -> bool {
+        self.change_wallet.is_multisig()
+    }
+
+    /// Returns `true` if this wallet only derives the change branch,
+    /// see [`Self::change_only_from_extended_public_key`]
+    pub fn is_change_only(&self) -> bool {
+        self.receive_wallet.is_none()
+    }
+
+    /// Returns `true` if this wallet holds no private key, as when built from an extended
+    /// public key exported by a hardware wallet
+    ///
+    /// A watch-only wallet derives the same addresses as its signing counterpart but
+    /// fails any operation requiring a private key, see [`Self::derive_signing_key`].
+    pub fn is_watch_only(&self) -> bool {
+        self.account_private_node.is_none()
+    }
+
+    /// Derive the private key of the address at `index` of the `address_type` branch
+    ///
+    /// An error is returned for watch-only wallets, the key then being held by the signing device.
+    pub async fn derive_signing_key(&self, address_type: AddressType, index: u32) -> Result<SecretKey> {
+        let Some(account_node) = self.account_private_node.as_ref() else {
+            return Err(Error::String(
+                "watch-only wallet: deriving a signing key requires the private key held by the signing device".to_string(),
+            ));
+        };
+        let branch_node = account_node.derive_child(ChildNumber::new(address_type.index(), false)?).await?;
+        let node = branch_node.derive_child(ChildNumber::new(index, false)?).await?;
+        node.with_private_key(|private_key| Ok(*private_key))
+    }
+
+    fn purpose(is_multisig: bool) -> u32 {
+        if is_multisig {
+            45
+        } else {
+            44
+        }
+    }
+
+    async fn create_extended_key(mut node: PrivateNode, is_multisig: bool, account_index: u64) -> Result<PrivateNode> {
+        let purpose = Self::purpose(is_multisig);
+        let children = [ChildNumber::new(purpose, true)?, ChildNumber::new(111111, true)?, Self::account_child_number(account_index)?];
         for child in children {
-            (private_key, attrs) = Self::derive_private_key(&private_key, &attrs, child.parse::<ChildNumber>()?).await?;
+            // The parent node is wiped as soon as it is replaced
+            node = node.derive_child(child).await?;
         }
 
-        Ok((private_key, attrs))
+        Ok(node)
     }
 
-    pub fn receive_wallet(&self) -> &HDWalletInner {
-        &self.receive_wallet
+    /// Hardened child number of the account node of `account_index`
+    fn account_child_number(account_index: u64) -> Result<ChildNumber> {
+        // Account indexes are hardened, so they must fit below the hardened flag
+        let index = u32::try_from(account_index).ok().filter(|index| *index < ChildNumber::HARDENED_FLAG).ok_or_else(|| {
+            Error::String(format!(
+                "account index {account_index} is out of range, it must be lower than {}",
+                ChildNumber::HARDENED_FLAG
+            ))
+        })?;
+        ChildNumber::new(index, true)
+    }
+
+    /// Extended public key of the account node (`m/<Purpose>'/111111'/<Account Index>'`),
+    /// suitable for building a watch-only wallet with [`Self::from_extended_public_key`]
+    ///
+    /// Multisig wallets have no single account key, so an error is returned for them.
+    pub fn account_xpub(&self) -> Result<ExtendedPublicKey<secp256k1::PublicKey>> {
+        self.extended_public_key
+            .clone()
+            .ok_or_else(|| Error::String("multisig wallets have no single account extended public key".to_string()))
+    }
+
+    /// `kpub` string form of [`Self::account_xpub`], accepted by [`Self::from_extended_public_key_str`]
+    pub fn account_xpub_str(&self) -> Result<String> {
+        Ok(self.account_xpub()?.to_string(Some(Prefix::KPUB)))
+    }
+
+    /// Full derivation path of the address at `index` of the receive or change branch,
+    /// such as `m/44'/111111'/0'/0/5`
+    ///
+    /// An extended public key does not record its purpose, so wallets built from one are assumed
+    /// to follow the single-signature purpose 44. Multisig wallets derive from several cosigner
+    /// keys and have no single path, so an error is returned for them.
+    pub fn derivation_path(&self, index: u32, change: bool) -> Result<String> {
+        if self.is_multisig() {
+            return Err(Error::String("multisig wallets have no single derivation path".to_string()));
+        }
+        let account_xpub = self.account_xpub()?;
+        let attrs = account_xpub.attrs();
+        if attrs.depth != ACCOUNT_DEPTH || !attrs.child_number.is_hardened() {
+            return Err(Error::String(format!(
+                "extended public key at depth {} is not an account key, no derivation path can be formatted",
+                attrs.depth
+            )));
+        }
+        let address_type = if change { AddressType::Change } else { AddressType::Receive };
+        let child_number = ChildNumber::new(index, false)?;
+        Ok(format!("m/{}'/111111'/{}/{}/{child_number}", self.purpose, attrs.child_number, address_type.index()))
+    }
+
+    /// Receive branch of the wallet, an error being returned for change-only wallets
+    pub fn receive_wallet(&self) -> Result<&HDWalletInner> {
+        self.receive_wallet.as_ref().ok_or_else(|| {
+            Error::String("change-only wallet: the receive branch is not derived, build a full wallet to use it".to_string())
+        })
     }
     pub fn change_wallet(&self) -> &HDWalletInner {
         &self.change_wallet
@@ -183,24 +907,55 @@ impl HDWalletGen1 {
     #[allow(dead_code)]
     pub async fn derive_address(&self, address_type: AddressType, index: u32) -> Result<Address> {
         let address = match address_type {
-            AddressType::Receive => self.receive_wallet.derive_address(index),
+            AddressType::Receive => self.receive_wallet()?.derive_address(index),
             AddressType::Change => self.change_wallet.derive_address(index),
         }
         .await?;
 
-        Ok(address)
+        self.check_network(address)
+    }
+
+    /// Returns the branch and the index at which this wallet derives `address`,
+    /// see [`HDWalletInner::index_of`]
+    pub async fn index_of(&self, address: &Address) -> Result<Option<(AddressType, u32)>> {
+        if let Some(receive_wallet) = self.receive_wallet.as_ref() {
+            if let Some(index) = receive_wallet.index_of(address).await? {
+                return Ok(Some((AddressType::Receive, index)));
+            }
+        }
+        Ok(self.change_wallet.index_of(address).await?.map(|index| (AddressType::Change, index)))
     }
 
     #[inline(always)]
     pub async fn derive_receive_address(&self, index: u32) -> Result<Address> {
-        let address = self.receive_wallet.derive_address(index).await?;
-        Ok(address)
+        let address = self.receive_wallet()?.derive_address(index).await?;
+        self.check_network(address)
     }
 
     #[inline(always)]
     pub async fn derive_change_address(&self, index: u32) -> Result<Address> {
         let address = self.change_wallet.derive_address(index).await?;
-        Ok(address)
+        self.check_network(address)
+    }
+
+    pub async fn derive_receive_address_with_kind(&self, index: u32, kind: AddressKind) -> Result<Address> {
+        let address = self.receive_wallet()?.derive_address_with_kind(index, kind).await?;
+        self.check_network(address)
+    }
+
+    pub async fn derive_change_address_with_kind(&self, index: u32, kind: AddressKind) -> Result<Address> {
+        let address = self.change_wallet.derive_address_with_kind(index, kind).await?;
+        self.check_network(address)
+    }
+
+    /// Scan the receive branch, see [`HDWalletInner::scan_addresses`]
+    pub async fn scan_receive_addresses(&self, gap_limit: u32, is_used: impl Fn(&Address) -> bool) -> Result<Vec<Address>> {
+        self.receive_wallet()?.scan_addresses(gap_limit, is_used).await
+    }
+
+    /// Scan the change branch, see [`HDWalletInner::scan_addresses`]
+    pub async fn scan_change_addresses(&self, gap_limit: u32, is_used: impl Fn(&Address) -> bool) -> Result<Vec<Address>> {
+        self.change_wallet.scan_addresses(gap_limit, is_used).await
     }
 
     pub async fn derive_wallet(
@@ -278,11 +1033,25 @@ impl HDWalletGen1 {
         Ok((private_key, attrs))
     }
 
-    fn derive_key(private_key: &SecretKey, child_number: ChildNumber, mut hmac: HmacSha512) -> Result<(SecretKey, ChainCode)> {
+    fn derive_key(private_key: &SecretKey, child_number: ChildNumber, hmac: HmacSha512) -> Result<(SecretKey, ChainCode)> {
+        let (child_key, chain_code) = Self::derive_key_bytes(private_key, child_number, hmac)?;
+        Ok((SecretKey::from_bytes(&child_key)?, chain_code))
+    }
+
+    /// Derive the child private key bytes, wiped on drop like the HMAC output they are derived from
+    fn derive_key_bytes(
+        private_key: &SecretKey,
+        child_number: ChildNumber,
+        mut hmac: HmacSha512,
+    ) -> Result<(Zeroizing<PrivateKeyBytes>, ChainCode)> {
         hmac.update(&child_number.to_bytes());
 
-        let result = hmac.finalize().into_bytes();
+        // The HMAC output is the tweak of the child key followed by the chain code
+        let mut output = hmac.finalize().into_bytes();
+        let result = Zeroizing::new(<[u8; 2 * KEY_SIZE]>::try_from(output.as_slice())?);
+        output.as_mut_slice().zeroize();
         let (child_key, chain_code) = result.split_at(KEY_SIZE);
+        let tweak = Zeroizing::new(PrivateKeyBytes::try_from(child_key)?);
 
         // We should technically loop here if a `secret_key` is zero or overflows
         // the order of the underlying elliptic curve group, incrementing the
@@ -293,9 +1062,9 @@ impl HDWalletGen1 {
         //
         // ...so instead, we simply return an error if this were ever to happen,
         // as the chances of it happening are vanishingly small.
-        let private_key = private_key.derive_child(child_key.try_into()?)?;
+        let child_key = Zeroizing::new(private_key.derive_child(*tweak)?.to_bytes());
 
-        Ok((private_key, chain_code.try_into()?))
+        Ok((child_key, chain_code.try_into()?))
     }
 
     pub fn create_hmac<K>(private_key: &K, attrs: &ExtendedKeyAttrs, hardened: bool) -> Result<HmacSha512>
@@ -313,30 +1082,38 @@ impl HDWalletGen1 {
         Ok(hmac)
     }
 
-    /// Serialize the raw public key as a byte array.
-    pub fn to_bytes(&self) -> PublicKeyBytes {
-        self.extended_public_key.to_bytes()
+    /// Serialize the raw public key as a byte array, an error being returned for multisig wallets.
+    pub fn to_bytes(&self) -> Result<PublicKeyBytes> {
+        Ok(self.account_xpub()?.to_bytes())
     }
 
-    pub fn attrs(&self) -> &ExtendedKeyAttrs {
-        self.extended_public_key.attrs()
+    pub fn attrs(&self) -> Result<&ExtendedKeyAttrs> {
+        self.extended_public_key
+            .as_ref()
+            .map(|xpub| xpub.attrs())
+            .ok_or_else(|| Error::String("multisig wallets have no single account extended public key".to_string()))
     }
 
-    /// Serialize this key as a self-[`Zeroizing`] `String`.
-    pub fn to_string(&self) -> Zeroizing<String> {
-        let key = self.extended_public_key.to_string(Some(Prefix::KPUB));
-        Zeroizing::new(key)
+    /// Serialize this key as a self-[`Zeroizing`] `String`, an error being returned for multisig wallets.
+    pub fn to_string(&self) -> Result<Zeroizing<String>> {
+        let key = self.account_xpub()?.to_string(Some(Prefix::KPUB));
+        Ok(Zeroizing::new(key))
     }
 }
 
 impl Debug for HDWalletGen1 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some(ref extended_public_key) = self.extended_public_key else {
+            // None of the cosigner keys is the key of the wallet
+            return f.debug_struct("HDWallet").field("multisig", &true).field("purpose", &self.purpose).finish();
+        };
+        let attrs = extended_public_key.attrs();
         f.debug_struct("HDWallet")
-            .field("depth", &self.attrs().depth)
-            .field("child_number", &self.attrs().child_number)
-            .field("chain_code", &faster_hex::hex_string(&self.attrs().chain_code))
-            .field("public_key", &faster_hex::hex_string(&self.to_bytes()))
-            .field("parent_fingerprint", &self.attrs().parent_fingerprint)
+            .field("depth", &attrs.depth)
+            .field("child_number", &attrs.child_number)
+            .field("chain_code", &faster_hex::hex_string(&attrs.chain_code))
+            .field("public_key", &faster_hex::hex_string(&extended_public_key.to_bytes()))
+            .field("parent_fingerprint", &attrs.parent_fingerprint)
             .finish()
     }
 }
@@ -344,7 +1121,26 @@ impl Debug for HDWalletGen1 {
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(test)]
 mod tests {
-    use super::HDWalletGen1;
+    use super::{
+        AddressKind, AddressUsage, DerivationIndexStorage, HDWalletGen1, PrivateNode, ACCOUNT_DEPTH, DEFAULT_GAP_LIMIT,
+        MAX_DISCOVERED_ACCOUNTS,
+    };
+    use async_trait::async_trait;
+    use kaspa_addresses::{Address, Prefix as AddressPrefix, Version};
+    use kaspa_bip32::{types::KEY_SIZE, AddressType, ChildNumber, ExtendedPrivateKey, Prefix, Result, SecretKey, SecretKeyExt};
+    use kaspa_txscript::{
+        extract_script_pub_key_address,
+        opcodes::codes::{OpCheckSig, OpData32},
+        pay_to_script_hash_script,
+    };
+    use std::{
+        mem::MaybeUninit,
+        str::FromStr,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+    };
 
     fn gen1_receive_addresses() -> Vec<String> {
         vec![
@@ -416,4 +1212,576 @@ mod tests {
             assert_eq!(change_addresses[index as usize], address, "change address at {index} failed");
         }
     }
+
+    #[tokio::test]
+    async fn hd_wallet_gen1_gap_limit_scan() {
+        let master_xprv =
+            "kprv5y2qurMHCsXYrNfU3GCihuwG3vMqFji7PZXajMEqyBkNh9UZUJgoHYBLTKu1eM4MvUtomcXPQ3Sw9HZ5ebbM4byoUciHo1zrPJBQfqpLorQ";
+        let hd_wallet = HDWalletGen1::from_master_xprv(master_xprv, false, 0).await.unwrap();
+
+        let receive_addresses = gen1_receive_addresses();
+        let used = [receive_addresses[1].clone(), receive_addresses[4].clone()];
+        let scanned = hd_wallet.scan_receive_addresses(5, |address| used.contains(&String::from(address))).await.unwrap();
+        assert_eq!(scanned.len(), 10, "scan should stop 5 addresses after the last used index");
+        for (index, address) in scanned.iter().enumerate() {
+            assert_eq!(receive_addresses[index], String::from(address), "scanned receive address at {index} failed");
+        }
+
+        let change_addresses = gen1_change_addresses();
+        let scanned = hd_wallet.scan_change_addresses(3, |_| false).await.unwrap();
+        assert_eq!(scanned.iter().map(String::from).collect::<Vec<_>>(), change_addresses[..3]);
+
+        assert!(hd_wallet.scan_change_addresses(0, |_| true).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn hd_wallet_gen1_discover_accounts() {
+        let master_xprv =
+            "kprv5y2qurMHCsXYrNfU3GCihuwG3vMqFji7PZXajMEqyBkNh9UZUJgoHYBLTKu1eM4MvUtomcXPQ3Sw9HZ5ebbM4byoUciHo1zrPJBQfqpLorQ";
+        let account_address = |account_index, index| async move {
+            let hd_wallet = HDWalletGen1::from_master_xprv(master_xprv, false, account_index).await.unwrap();
+            hd_wallet.derive_receive_address(index).await.unwrap()
+        };
+
+        // Accounts 0 and 2 have some activity, account 1 has none
+        let used = [account_address(0, 1).await, account_address(2, 3).await];
+        let accounts = HDWalletGen1::discover_accounts(master_xprv, 2, 5, |address| used.contains(address)).await.unwrap();
+        assert_eq!(accounts, vec![0, 1, 2]);
+
+        // A used account beyond the gap of accounts is not discovered
+        let discovered = HDWalletGen1::discover_accounts(master_xprv, 1, 5, |address| used.contains(address)).await.unwrap();
+        assert_eq!(discovered, vec![0]);
+        // Neither is a used address beyond the gap of addresses
+        let discovered = HDWalletGen1::discover_accounts(master_xprv, 2, 3, |address| used.contains(address)).await.unwrap();
+        assert_eq!(discovered, vec![0]);
+
+        assert!(HDWalletGen1::discover_accounts(master_xprv, 3, 5, |_| false).await.unwrap().is_empty());
+
+        // Every account being used, the discovery fails once the maximum number of accounts is scanned
+        let scanned = AtomicU64::new(0);
+        let first_address_used = |_: &Address| scanned.fetch_add(1, Ordering::Relaxed) % 2 == 0;
+        assert!(HDWalletGen1::discover_accounts(master_xprv, 1, 1, first_address_used).await.is_err());
+        assert_eq!(scanned.load(Ordering::Relaxed), 2 * MAX_DISCOVERED_ACCOUNTS);
+    }
+
+    #[tokio::test]
+    async fn hd_wallet_gen1_async_gap_scan() {
+        let master_xprv =
+            "kprv5y2qurMHCsXYrNfU3GCihuwG3vMqFji7PZXajMEqyBkNh9UZUJgoHYBLTKu1eM4MvUtomcXPQ3Sw9HZ5ebbM4byoUciHo1zrPJBQfqpLorQ";
+        let hd_wallet = HDWalletGen1::from_master_xprv(master_xprv, false, 0).await.unwrap();
+        let receive_addresses = gen1_receive_addresses();
+        assert_eq!(hd_wallet.receive_wallet().unwrap().gap_limit(), DEFAULT_GAP_LIMIT);
+        let receive_wallet = hd_wallet.receive_wallet().unwrap().clone().with_gap_limit(4);
+
+        // Simulates a lookup of the history of the addresses
+        let used = [2u32, 7];
+        let check = |index: u32, address: Address| {
+            let expected = receive_addresses[index as usize].clone();
+            async move {
+                tokio::task::yield_now().await;
+                assert_eq!(String::from(&address), expected, "the check of index {index} got a wrong address");
+                Ok(if used.contains(&index) { AddressUsage::Used } else { AddressUsage::Unused })
+            }
+        };
+
+        let scanned = receive_wallet.scan_range_with(0, check).await.unwrap();
+        assert_eq!(scanned.len(), 12, "scan should stop 4 addresses after the last used index");
+        assert_eq!(scanned.iter().map(String::from).collect::<Vec<_>>(), receive_addresses[..12]);
+
+        let scanned = receive_wallet.scan_range_with(5, check).await.unwrap();
+        assert_eq!(scanned.iter().map(String::from).collect::<Vec<_>>(), receive_addresses[5..12]);
+
+        let scanned = receive_wallet.scan_range_with(8, check).await.unwrap();
+        assert_eq!(scanned.iter().map(String::from).collect::<Vec<_>>(), receive_addresses[8..12], "no used address after 8");
+
+        // A failing lookup aborts the scan
+        let failing = |index: u32, _| async move {
+            match index {
+                3 => Err(kaspa_bip32::Error::String("lookup failed".to_string())),
+                _ => Ok(AddressUsage::Used),
+            }
+        };
+        assert!(receive_wallet.scan_range_with(0, failing).await.is_err());
+
+        assert!(receive_wallet.clone().with_gap_limit(0).scan_range_with(0, check).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn hd_wallet_gen1_parallel_range() {
+        let master_xprv =
+            "kprv5y2qurMHCsXYrNfU3GCihuwG3vMqFji7PZXajMEqyBkNh9UZUJgoHYBLTKu1eM4MvUtomcXPQ3Sw9HZ5ebbM4byoUciHo1zrPJBQfqpLorQ";
+        let hd_wallet = HDWalletGen1::from_master_xprv(master_xprv, false, 0).await.unwrap();
+        let receive_wallet = hd_wallet.receive_wallet().unwrap();
+
+        let serial = receive_wallet.get_range(0..1000).await.unwrap();
+        for concurrency in [0, 1, 3, 8] {
+            let parallel = receive_wallet.get_range_parallel(0..1000, concurrency).await.unwrap();
+            assert_eq!(serial, parallel, "parallel derivation with concurrency {concurrency} failed");
+        }
+
+        let receive_addresses = gen1_receive_addresses();
+        let parallel = receive_wallet.get_range_parallel(0..20, 4).await.unwrap();
+        assert_eq!(parallel.iter().map(String::from).collect::<Vec<_>>(), receive_addresses[..20]);
+        assert!(receive_wallet.get_range_parallel(5..5, 4).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn hd_wallet_gen1_account_xpub() {
+        let master_xprv =
+            "kprv5y2qurMHCsXYrNfU3GCihuwG3vMqFji7PZXajMEqyBkNh9UZUJgoHYBLTKu1eM4MvUtomcXPQ3Sw9HZ5ebbM4byoUciHo1zrPJBQfqpLorQ";
+        let hd_wallet = HDWalletGen1::from_master_xprv(master_xprv, false, 0).await.unwrap();
+
+        let account_xpub = hd_wallet.account_xpub().unwrap();
+        assert_eq!(account_xpub.attrs().depth, 3, "xpub should be at the account node");
+        assert_ne!(account_xpub, hd_wallet.receive_wallet().unwrap().public_key().unwrap());
+        assert_ne!(account_xpub, hd_wallet.change_wallet().public_key().unwrap());
+
+        let xpub_str = hd_wallet.account_xpub_str().unwrap();
+        assert!(xpub_str.starts_with("kpub"));
+        let watch_only = HDWalletGen1::from_extended_public_key_str(&xpub_str).await.unwrap();
+        assert_eq!(watch_only.account_xpub().unwrap(), account_xpub);
+
+        let receive_addresses = gen1_receive_addresses();
+        let change_addresses = gen1_change_addresses();
+        for index in 0..20 {
+            let address: String = watch_only.derive_receive_address(index).await.unwrap().into();
+            assert_eq!(receive_addresses[index as usize], address, "watch-only receive address at {index} failed");
+            let address: String = watch_only.derive_change_address(index).await.unwrap().into();
+            assert_eq!(change_addresses[index as usize], address, "watch-only change address at {index} failed");
+        }
+    }
+
+    #[tokio::test]
+    async fn hd_wallet_gen1_watch_only() {
+        let master_xprv =
+            "kprv5y2qurMHCsXYrNfU3GCihuwG3vMqFji7PZXajMEqyBkNh9UZUJgoHYBLTKu1eM4MvUtomcXPQ3Sw9HZ5ebbM4byoUciHo1zrPJBQfqpLorQ";
+        let hd_wallet = HDWalletGen1::from_master_xprv(master_xprv, false, 0).await.unwrap();
+        assert!(!hd_wallet.is_watch_only());
+
+        let watch_only = HDWalletGen1::from_extended_public_key_str(&hd_wallet.account_xpub_str().unwrap()).await.unwrap();
+        assert!(watch_only.is_watch_only());
+        assert!(watch_only.clone().is_watch_only());
+
+        let receive_addresses = gen1_receive_addresses();
+        for index in 0..5 {
+            let address: String = watch_only.derive_receive_address(index).await.unwrap().into();
+            assert_eq!(receive_addresses[index as usize], address, "watch-only receive address at {index} failed");
+        }
+
+        let err = watch_only.derive_signing_key(AddressType::Receive, 0).await.unwrap_err();
+        assert!(err.to_string().contains("watch-only"), "unexpected error: {err}");
+
+        for index in [0, 3] {
+            let signing_key = hd_wallet.derive_signing_key(AddressType::Receive, index).await.unwrap();
+            let address = watch_only.derive_receive_address(index).await.unwrap();
+            let (x_only_public_key, _) = signing_key.get_public_key().x_only_public_key();
+            assert_eq!(address.payload[..], x_only_public_key.serialize(), "receive signing key at {index} mismatch");
+
+            let signing_key = hd_wallet.derive_signing_key(AddressType::Change, index).await.unwrap();
+            let address = watch_only.derive_change_address(index).await.unwrap();
+            let (x_only_public_key, _) = signing_key.get_public_key().x_only_public_key();
+            assert_eq!(address.payload[..], x_only_public_key.serialize(), "change signing key at {index} mismatch");
+        }
+    }
+
+    #[tokio::test]
+    async fn hd_wallet_gen1_network_validation() {
+        let master_xprv =
+            "kprv5y2qurMHCsXYrNfU3GCihuwG3vMqFji7PZXajMEqyBkNh9UZUJgoHYBLTKu1eM4MvUtomcXPQ3Sw9HZ5ebbM4byoUciHo1zrPJBQfqpLorQ";
+        let hd_wallet = HDWalletGen1::from_master_xprv(master_xprv, false, 0).await.unwrap().with_network_validation(true);
+        let mainnet_address = hd_wallet.derive_receive_address(0).await.unwrap();
+        assert_eq!(mainnet_address.prefix, AddressPrefix::Mainnet);
+
+        // A mainnet key deliberately configured for testnet
+        let mismatched = hd_wallet.clone().with_network(AddressPrefix::Testnet);
+        let err = mismatched.derive_receive_address(0).await.unwrap_err();
+        let expected = format!("does not belong to the {} network", AddressPrefix::Testnet);
+        assert!(err.to_string().contains(&expected), "unclear error: {err}");
+        assert!(mismatched.derive_receive_address_with_kind(0, AddressKind::Ecdsa).await.is_err());
+        assert!(mismatched.derive_change_address(0).await.is_err());
+
+        // The same key material as a testnet key
+        let testnet_xprv = ExtendedPrivateKey::<SecretKey>::from_str(master_xprv).unwrap().to_string(Prefix::TPRV);
+        let testnet = HDWalletGen1::from_master_xprv(&testnet_xprv, false, 0)
+            .await
+            .unwrap()
+            .with_network(AddressPrefix::Testnet)
+            .with_network_validation(true);
+        let address = testnet.derive_receive_address(0).await.unwrap();
+        assert_eq!(address.prefix, AddressPrefix::Testnet);
+        assert_eq!(address.payload, mainnet_address.payload, "the network should only change the prefix");
+        assert!(testnet.clone().with_network(AddressPrefix::Mainnet).derive_receive_address(0).await.is_err());
+
+        // A watch-only wallet of a testnet account key configured for mainnet
+        let tpub = testnet.account_xpub().unwrap().to_string(Some(Prefix::TPUB));
+        let watch_only = HDWalletGen1::from_extended_public_key_str_for_network(&tpub, AddressPrefix::Testnet).await.unwrap();
+        assert_eq!(watch_only.clone().with_network_validation(true).derive_receive_address(0).await.unwrap(), address);
+        let watch_only = watch_only.with_network(AddressPrefix::Mainnet).with_network_validation(true);
+        assert!(watch_only.derive_change_address(0).await.is_err());
+
+        // A decoded key records no network, any network being accepted
+        let decoded = HDWalletGen1::from_extended_public_key(hd_wallet.account_xpub().unwrap()).await.unwrap();
+        let address =
+            decoded.with_network(AddressPrefix::Devnet).with_network_validation(true).derive_receive_address(0).await.unwrap();
+        assert_eq!(address.prefix, AddressPrefix::Devnet);
+    }
+
+    #[tokio::test]
+    async fn hd_wallet_gen1_change_only() {
+        let master_xprv =
+            "kprv5y2qurMHCsXYrNfU3GCihuwG3vMqFji7PZXajMEqyBkNh9UZUJgoHYBLTKu1eM4MvUtomcXPQ3Sw9HZ5ebbM4byoUciHo1zrPJBQfqpLorQ";
+        let hd_wallet = HDWalletGen1::from_master_xprv(master_xprv, false, 0).await.unwrap();
+        assert!(!hd_wallet.is_change_only());
+
+        let change_only = HDWalletGen1::change_only_from_extended_public_key(hd_wallet.account_xpub().unwrap()).await.unwrap();
+        assert!(change_only.is_change_only());
+        assert!(change_only.is_watch_only());
+
+        let change_addresses = gen1_change_addresses();
+        for index in 0..20 {
+            let address: String = change_only.derive_change_address(index).await.unwrap().into();
+            assert_eq!(change_addresses[index as usize], address, "change-only change address at {index} failed");
+        }
+        let address = change_only.derive_address(AddressType::Change, 3).await.unwrap();
+        assert_eq!(change_only.index_of(&address).await.unwrap(), Some((AddressType::Change, 3)));
+
+        let receive_address = hd_wallet.derive_receive_address(0).await.unwrap();
+        assert_eq!(change_only.index_of(&receive_address).await.unwrap(), None);
+        for err in [
+            change_only.receive_wallet().err(),
+            change_only.derive_receive_address(0).await.err(),
+            change_only.derive_address(AddressType::Receive, 0).await.err(),
+            change_only.scan_receive_addresses(5, |_| false).await.err(),
+        ] {
+            let err = err.expect("receive branch operations should fail on a change-only wallet");
+            assert!(err.to_string().contains("change-only wallet"), "unclear error: {err}");
+        }
+    }
+
+    #[tokio::test]
+    async fn hd_wallet_gen1_derivation_path() {
+        let master_xprv =
+            "kprv5y2qurMHCsXYrNfU3GCihuwG3vMqFji7PZXajMEqyBkNh9UZUJgoHYBLTKu1eM4MvUtomcXPQ3Sw9HZ5ebbM4byoUciHo1zrPJBQfqpLorQ";
+        let hd_wallet = HDWalletGen1::from_master_xprv(master_xprv, false, 0).await.unwrap();
+        assert_eq!(hd_wallet.derivation_path(5, false).unwrap(), "m/44'/111111'/0'/0/5");
+        assert_eq!(hd_wallet.derivation_path(5, true).unwrap(), "m/44'/111111'/0'/1/5");
+
+        let watch_only = HDWalletGen1::from_extended_public_key_str(&hd_wallet.account_xpub_str().unwrap()).await.unwrap();
+        assert_eq!(watch_only.derivation_path(7, false).unwrap(), "m/44'/111111'/0'/0/7");
+
+        let hd_wallet = HDWalletGen1::from_master_xprv(master_xprv, false, 3).await.unwrap();
+        assert_eq!(hd_wallet.derivation_path(0, true).unwrap(), "m/44'/111111'/3'/1/0");
+        assert!(hd_wallet.derivation_path(ChildNumber::HARDENED_FLAG, false).is_err(), "hardened address indexes should be rejected");
+
+        let hd_wallet = HDWalletGen1::from_master_xprv(master_xprv, true, 1).await.unwrap();
+        assert_eq!(hd_wallet.derivation_path(2, false).unwrap(), "m/45'/111111'/1'/0/2");
+
+        let xpub = hd_wallet.account_xpub().unwrap();
+        let multisig = HDWalletGen1::from_multisig(vec![xpub.clone(), xpub], 1, 1).await.unwrap();
+        assert!(multisig.derivation_path(0, false).is_err());
+    }
+
+    #[tokio::test]
+    async fn hd_wallet_gen1_xpub_validation() {
+        let master_xprv =
+            "kprv5y2qurMHCsXYrNfU3GCihuwG3vMqFji7PZXajMEqyBkNh9UZUJgoHYBLTKu1eM4MvUtomcXPQ3Sw9HZ5ebbM4byoUciHo1zrPJBQfqpLorQ";
+        let hd_wallet = HDWalletGen1::from_master_xprv(master_xprv, false, 0).await.unwrap();
+        let account_xpub = hd_wallet.account_xpub().unwrap();
+
+        // Correct network and depth
+        let kpub = account_xpub.to_string(Some(Prefix::KPUB));
+        let watch_only = HDWalletGen1::from_extended_public_key_str_for_network(&kpub, AddressPrefix::Mainnet).await.unwrap();
+        assert_eq!(watch_only.account_xpub().unwrap(), account_xpub);
+        let tpub = account_xpub.to_string(Some(Prefix::TPUB));
+        assert!(HDWalletGen1::from_extended_public_key_str_for_network(&tpub, AddressPrefix::Testnet).await.is_ok());
+
+        // Wrong network
+        assert!(HDWalletGen1::from_extended_public_key_str(&tpub).await.is_err(), "a testnet key should be rejected on mainnet");
+        for network in [AddressPrefix::Testnet, AddressPrefix::Simnet, AddressPrefix::Devnet] {
+            let result = HDWalletGen1::from_extended_public_key_str_for_network(&kpub, network).await;
+            assert!(result.is_err(), "a mainnet key should be rejected on {network}");
+        }
+
+        // Wrong depth
+        let receive_kpub = hd_wallet.receive_wallet().unwrap().public_key().unwrap().to_string(Some(Prefix::KPUB));
+        assert!(HDWalletGen1::from_extended_public_key_str(&receive_kpub).await.is_err(), "a branch key should be rejected");
+    }
+
+    #[tokio::test]
+    async fn hd_wallet_gen1_script_template() {
+        let master_xprv =
+            "kprv5y2qurMHCsXYrNfU3GCihuwG3vMqFji7PZXajMEqyBkNh9UZUJgoHYBLTKu1eM4MvUtomcXPQ3Sw9HZ5ebbM4byoUciHo1zrPJBQfqpLorQ";
+        let hd_wallet = HDWalletGen1::from_master_xprv(master_xprv, false, 0).await.unwrap();
+
+        // <pubkey> OP_CHECKSIG
+        let template = |public_key: &[u8]| -> Result<Vec<u8>> { Ok([&[OpData32][..], public_key, &[OpCheckSig]].concat()) };
+        let vault = HDWalletGen1::from_script_template(hd_wallet.account_xpub().unwrap(), Arc::new(template)).await.unwrap();
+
+        for index in 0..2 {
+            let address = vault.derive_receive_address(index).await.unwrap();
+            assert_eq!(address.version, Version::ScriptHash);
+
+            // The redeem script embeds the key of the standard address at the same index
+            let derived_key = hd_wallet.derive_receive_address(index).await.unwrap().payload;
+            let redeem_script = template(&derived_key).unwrap();
+            let expected = extract_script_pub_key_address(&pay_to_script_hash_script(&redeem_script), AddressPrefix::Mainnet).unwrap();
+            assert_eq!(address, expected, "receive address at {index} does not embed the derived key");
+            assert_ne!(address, vault.derive_change_address(index).await.unwrap());
+        }
+        assert_ne!(vault.derive_receive_address(0).await.unwrap(), vault.derive_receive_address(1).await.unwrap());
+        assert!(vault.derive_receive_address_with_kind(0, AddressKind::Ecdsa).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn hd_wallet_gen1_index_of() {
+        let master_xprv =
+            "kprv5y2qurMHCsXYrNfU3GCihuwG3vMqFji7PZXajMEqyBkNh9UZUJgoHYBLTKu1eM4MvUtomcXPQ3Sw9HZ5ebbM4byoUciHo1zrPJBQfqpLorQ";
+        let hd_wallet = HDWalletGen1::from_master_xprv(master_xprv, false, 0).await.unwrap();
+        let receive_addresses = gen1_receive_addresses();
+        let change_addresses = gen1_change_addresses();
+        hd_wallet.receive_wallet().unwrap().set_index(5).unwrap();
+        hd_wallet.change_wallet().set_index(2).unwrap();
+
+        let receive_wallet = hd_wallet.receive_wallet().unwrap();
+        for (index, address) in receive_addresses.iter().enumerate().take(6) {
+            let address = Address::try_from(address.as_str()).unwrap();
+            assert_eq!(receive_wallet.index_of(&address).await.unwrap(), Some(index as u32), "address at {index} not resolved");
+        }
+
+        // Beyond the current index
+        let address = Address::try_from(receive_addresses[6].as_str()).unwrap();
+        assert_eq!(receive_wallet.index_of(&address).await.unwrap(), None);
+        receive_wallet.set_index(6).unwrap();
+        assert_eq!(receive_wallet.index_of(&address).await.unwrap(), Some(6));
+
+        // Only the indexes up to the current one are recorded, whatever the derived ranges
+        receive_wallet.get_range(0..100).await.unwrap();
+        receive_wallet.scan_addresses(50, |_| false).await.unwrap();
+        assert_eq!(receive_wallet.index_of(&address).await.unwrap(), Some(6));
+        assert_eq!(receive_wallet.addresses.lock().unwrap().indexes.len(), 7);
+        receive_wallet.set_index(3).unwrap();
+        assert_eq!(receive_wallet.addresses.lock().unwrap().indexes.len(), 4, "the addresses past the index should be forgotten");
+        assert_eq!(receive_wallet.index_of(&address).await.unwrap(), None);
+        receive_wallet.set_index(6).unwrap();
+
+        // Addresses of the other branch
+        let address = Address::try_from(change_addresses[2].as_str()).unwrap();
+        assert_eq!(receive_wallet.index_of(&address).await.unwrap(), None);
+        assert!(matches!(hd_wallet.index_of(&address).await.unwrap(), Some((AddressType::Change, 2))));
+
+        // Unknown address
+        let address = Address::try_from("kaspa:qp0l70zd5x85ttwd6jv7g3s3a8llzj96d8dncn4zmhv4tlzx5k2jyqh70xmfj").unwrap();
+        assert!(hd_wallet.index_of(&address).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn hd_wallet_gen1_multisig() {
+        let master_xprvs = [
+            "kprv5y2qurMHCsXYrNfU3GCihuwG3vMqFji7PZXajMEqyBkNh9UZUJgoHYBLTKu1eM4MvUtomcXPQ3Sw9HZ5ebbM4byoUciHo1zrPJBQfqpLorQ"
+                .to_string(),
+            ExtendedPrivateKey::<SecretKey>::new([1u8; 32]).unwrap().to_string(Prefix::KPRV).to_string(),
+            ExtendedPrivateKey::<SecretKey>::new([2u8; 32]).unwrap().to_string(Prefix::KPRV).to_string(),
+        ];
+        let mut cosigner_xpubs = vec![];
+        let mut other_account_xpubs = vec![];
+        for master_xprv in master_xprvs.iter() {
+            let hd_wallet = HDWalletGen1::from_master_xprv(master_xprv, true, 0).await.unwrap();
+            cosigner_xpubs.push(hd_wallet.account_xpub().unwrap());
+            let hd_wallet = HDWalletGen1::from_master_xprv(master_xprv, true, 1).await.unwrap();
+            other_account_xpubs.push(hd_wallet.account_xpub().unwrap());
+        }
+
+        let wallet = HDWalletGen1::from_multisig(cosigner_xpubs.clone(), 2, 0).await.unwrap();
+        assert!(wallet.is_multisig());
+        assert!(wallet.account_xpub().is_err());
+        assert!(wallet.to_bytes().is_err());
+        assert!(wallet.change_wallet().public_key().is_err(), "multisig branches should have no key of their own");
+        let debug = format!("{wallet:?}");
+        for xpub in cosigner_xpubs.iter() {
+            assert!(!debug.contains(&faster_hex::hex_string(&xpub.to_bytes())), "cosigner key exposed by the wallet: {debug}");
+        }
+
+        // The multisig branches have their own index, the cosigner branches being ignored
+        let receive_wallet = wallet.receive_wallet().unwrap();
+        receive_wallet.set_index(3).unwrap();
+        assert_eq!(receive_wallet.current_address().await.unwrap(), wallet.derive_receive_address(3).await.unwrap());
+        assert_eq!(wallet.change_wallet().index().unwrap(), 0);
+        receive_wallet.set_index(0).unwrap();
+
+        let receive_address = wallet.derive_receive_address(0).await.unwrap();
+        let change_address = wallet.derive_change_address(0).await.unwrap();
+        assert_eq!(receive_address.version, Version::ScriptHash);
+        assert_eq!(change_address.version, Version::ScriptHash);
+        assert_ne!(receive_address, change_address);
+        assert_ne!(receive_address, wallet.derive_receive_address(1).await.unwrap());
+
+        let reordered = vec![cosigner_xpubs[2].clone(), cosigner_xpubs[0].clone(), cosigner_xpubs[1].clone()];
+        let reordered_wallet = HDWalletGen1::from_multisig(reordered, 2, 0).await.unwrap();
+        for index in 0..5 {
+            assert_eq!(
+                wallet.derive_receive_address(index).await.unwrap(),
+                reordered_wallet.derive_receive_address(index).await.unwrap(),
+                "receive address at {index} depends on cosigner ordering"
+            );
+            assert_eq!(
+                wallet.derive_change_address(index).await.unwrap(),
+                reordered_wallet.derive_change_address(index).await.unwrap(),
+                "change address at {index} depends on cosigner ordering"
+            );
+        }
+
+        let three_of_three = HDWalletGen1::from_multisig(cosigner_xpubs.clone(), 3, 0).await.unwrap();
+        assert_ne!(receive_address, three_of_three.derive_receive_address(0).await.unwrap());
+        let other_account = HDWalletGen1::from_multisig(other_account_xpubs.clone(), 2, 1).await.unwrap();
+        assert_ne!(receive_address, other_account.derive_receive_address(0).await.unwrap());
+
+        // The cosigner keys must all be the keys of the requested account
+        assert!(
+            HDWalletGen1::from_multisig(other_account_xpubs.clone(), 2, 0).await.is_err(),
+            "keys of another account should be rejected"
+        );
+        let mut mixed_xpubs = cosigner_xpubs.clone();
+        mixed_xpubs[0] = other_account_xpubs[0].clone();
+        assert!(HDWalletGen1::from_multisig(mixed_xpubs, 2, 0).await.is_err(), "keys of mixed accounts should be rejected");
+
+        // The cosigner keys are the branch parents of the address keys, at depth 5 once derived
+        let receive_xpub = cosigner_xpubs[0].derive_child(ChildNumber::new(0, false).unwrap()).unwrap();
+        let address_xpub = receive_xpub.derive_child(ChildNumber::new(0, false).unwrap()).unwrap();
+        assert_eq!(address_xpub.attrs().depth, ACCOUNT_DEPTH + 2);
+
+        // Only account keys are accepted
+        let mut branch_xpubs = cosigner_xpubs.clone();
+        branch_xpubs[1] = receive_xpub;
+        assert!(HDWalletGen1::from_multisig(branch_xpubs, 2, 0).await.is_err(), "non-account cosigner keys should be rejected");
+
+        assert!(HDWalletGen1::from_multisig(cosigner_xpubs.clone(), 0, 0).await.is_err());
+        assert!(HDWalletGen1::from_multisig(cosigner_xpubs, 4, 0).await.is_err());
+    }
+
+    #[derive(Default)]
+    struct MockIndexStorage {
+        index: Mutex<Option<u32>>,
+        unavailable: AtomicBool,
+    }
+
+    #[async_trait]
+    impl DerivationIndexStorage for MockIndexStorage {
+        async fn store_index(&self, index: u32) -> Result<()> {
+            if self.unavailable.load(Ordering::SeqCst) {
+                return Err(kaspa_bip32::Error::String("storage unavailable".to_string()));
+            }
+            *self.index.lock()? = Some(index);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn hd_wallet_gen1_index_storage() {
+        let master_xprv =
+            "kprv5y2qurMHCsXYrNfU3GCihuwG3vMqFji7PZXajMEqyBkNh9UZUJgoHYBLTKu1eM4MvUtomcXPQ3Sw9HZ5ebbM4byoUciHo1zrPJBQfqpLorQ";
+        let hd_wallet = HDWalletGen1::from_master_xprv(master_xprv, false, 0).await.unwrap();
+        let receive_addresses = gen1_receive_addresses();
+
+        // Without storage, flushing is a no-op
+        hd_wallet.receive_wallet().unwrap().flush_index().await.unwrap();
+
+        let storage = Arc::new(MockIndexStorage::default());
+        let receive_wallet = hd_wallet.receive_wallet().unwrap().clone().with_index_storage(storage.clone(), 0);
+        assert_eq!(*storage.index.lock().unwrap(), None);
+        for expected_index in 1..=3 {
+            let address: String = receive_wallet.new_address().await.unwrap().into();
+            assert_eq!(*storage.index.lock().unwrap(), Some(expected_index), "index {expected_index} was not persisted");
+            assert_eq!(receive_addresses[expected_index as usize], address);
+        }
+
+        // Simulate a restart restoring the persisted index
+        let stored_index = storage.index.lock().unwrap().unwrap();
+        let restored = hd_wallet.receive_wallet().unwrap().clone().with_index_storage(storage.clone(), stored_index);
+        let address: String = restored.current_address().await.unwrap().into();
+        assert_eq!(receive_addresses[3], address);
+        let address: String = restored.new_address().await.unwrap().into();
+        assert_eq!(receive_addresses[4], address);
+        assert_eq!(*storage.index.lock().unwrap(), Some(4));
+
+        // An index failing to be persisted is not handed out
+        storage.unavailable.store(true, Ordering::SeqCst);
+        assert!(restored.new_address().await.is_err());
+        assert_eq!(restored.index().unwrap(), 4);
+        assert_eq!(*storage.index.lock().unwrap(), Some(4));
+        storage.unavailable.store(false, Ordering::SeqCst);
+        let address: String = restored.new_address().await.unwrap().into();
+        assert_eq!(receive_addresses[5], address);
+    }
+
+    #[tokio::test]
+    async fn hd_wallet_gen1_address_kind() {
+        let master_xprv =
+            "kprv5y2qurMHCsXYrNfU3GCihuwG3vMqFji7PZXajMEqyBkNh9UZUJgoHYBLTKu1eM4MvUtomcXPQ3Sw9HZ5ebbM4byoUciHo1zrPJBQfqpLorQ";
+        let hd_wallet = HDWalletGen1::from_master_xprv(master_xprv, false, 0).await.unwrap();
+        let receive_addresses = gen1_receive_addresses();
+
+        for index in 0..5 {
+            let schnorr = hd_wallet.derive_receive_address_with_kind(index, AddressKind::Schnorr).await.unwrap();
+            let ecdsa = hd_wallet.derive_receive_address_with_kind(index, AddressKind::Ecdsa).await.unwrap();
+            assert_eq!(schnorr, hd_wallet.derive_receive_address(index).await.unwrap(), "schnorr should be the default kind");
+            assert_eq!(receive_addresses[index as usize], String::from(&schnorr));
+            assert_ne!(schnorr, ecdsa);
+            assert_eq!(schnorr.version, Version::PubKey);
+            assert_eq!(ecdsa.version, Version::PubKeyECDSA);
+            // Both kinds commit to the same derived key
+            assert_eq!(schnorr.payload[..], ecdsa.payload[1..]);
+
+            for address in [schnorr, ecdsa] {
+                let decoded = Address::try_from(String::from(&address)).unwrap();
+                assert_eq!(decoded, address);
+                assert_eq!(decoded.payload.len(), decoded.version.public_key_len());
+            }
+        }
+
+        let change_ecdsa = hd_wallet.derive_change_address_with_kind(0, AddressKind::Ecdsa).await.unwrap();
+        assert_ne!(change_ecdsa, hd_wallet.derive_receive_address_with_kind(0, AddressKind::Ecdsa).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn hd_wallet_gen1_account_index_validation() {
+        let master_xprv =
+            "kprv5y2qurMHCsXYrNfU3GCihuwG3vMqFji7PZXajMEqyBkNh9UZUJgoHYBLTKu1eM4MvUtomcXPQ3Sw9HZ5ebbM4byoUciHo1zrPJBQfqpLorQ";
+        let max_account_index = (ChildNumber::HARDENED_FLAG - 1) as u64;
+
+        for account_index in [0, 1, max_account_index] {
+            let hd_wallet = HDWalletGen1::from_master_xprv(master_xprv, false, account_index).await.unwrap();
+            let child_number = hd_wallet.attrs().unwrap().child_number;
+            assert!(child_number.is_hardened(), "account {account_index} should be derived as a hardened child");
+            assert_eq!(child_number.index() as u64, account_index);
+            assert_eq!(hd_wallet.attrs().unwrap().depth, 3);
+        }
+
+        let first = HDWalletGen1::from_master_xprv(master_xprv, false, 0).await.unwrap();
+        let last = HDWalletGen1::from_master_xprv(master_xprv, false, max_account_index).await.unwrap();
+        assert_ne!(first.derive_receive_address(0).await.unwrap(), last.derive_receive_address(0).await.unwrap());
+
+        for account_index in [max_account_index + 1, u32::MAX as u64, u32::MAX as u64 + 1, u64::MAX] {
+            let result = HDWalletGen1::from_master_xprv(master_xprv, false, account_index).await;
+            assert!(result.is_err(), "account index {account_index} should be rejected");
+        }
+    }
+
+    #[test]
+    fn hd_wallet_gen1_private_node_zeroized_on_drop() {
+        let master_xprv =
+            "kprv5y2qurMHCsXYrNfU3GCihuwG3vMqFji7PZXajMEqyBkNh9UZUJgoHYBLTKu1eM4MvUtomcXPQ3Sw9HZ5ebbM4byoUciHo1zrPJBQfqpLorQ";
+        let xprv_key = ExtendedPrivateKey::<SecretKey>::from_str(master_xprv).unwrap();
+
+        // The node lives in a buffer that outlives it, so its memory can be inspected once dropped
+        let mut buffer = MaybeUninit::new(PrivateNode::new(xprv_key.private_key(), xprv_key.attrs().clone()));
+        let node = buffer.as_ptr();
+        unsafe {
+            assert_ne!((*node).private_key, [0u8; KEY_SIZE]);
+            assert_ne!((*node).attrs.chain_code, [0u8; KEY_SIZE]);
+            buffer.assume_init_drop();
+            let node = buffer.as_ptr();
+            assert_eq!((*node).private_key, [0u8; KEY_SIZE], "the private key should be wiped on drop");
+            assert_eq!((*node).attrs.chain_code, [0u8; KEY_SIZE], "the chain code should be wiped on drop");
+        }
+    }
 }